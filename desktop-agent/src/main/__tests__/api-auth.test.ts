@@ -0,0 +1,95 @@
+/**
+ * Token checks: full access on a mutation endpoint (POST /api/earnings/quarantine),
+ * and the read scope on /metrics and /api/ws under authRequiredForReads.
+ */
+import { describe, it, expect, beforeAll, afterAll } from "vitest";
+import * as fs from "fs";
+import * as path from "path";
+import request from "supertest";
+import WebSocket from "ws";
+import { createTestAgent, TestAgent } from "./test-agent";
+
+let agent: TestAgent;
+
+beforeAll(async () => {
+  agent = await createTestAgent();
+}, 30000);
+
+afterAll(async () => {
+  await agent?.close();
+});
+
+describe("POST /api/earnings/quarantine", () => {
+  it("accepts the API token", async () => {
+    const res = await request(agent.url)
+      .post("/api/earnings/quarantine")
+      .set("Authorization", `Bearer ${agent.token}`)
+      .send({ action: "discard" });
+    expect(res.status).toBe(200);
+    expect(res.body.success).toBe(true);
+    expect(res.body.affected).toBe(0);
+  });
+
+  it("rejects a request without a token", async () => {
+    const res = await request(agent.url).post("/api/earnings/quarantine").send({ action: "discard" });
+    expect(res.status).toBe(401);
+  });
+
+  it("rejects a wrong token", async () => {
+    const res = await request(agent.url)
+      .post("/api/earnings/quarantine")
+      .set("Authorization", `Bearer ${"0".repeat(64)}`)
+      .send({ action: "discard" });
+    expect(res.status).toBe(401);
+  });
+});
+
+describe("API token", () => {
+  it("is the one persisted in ~/.spk-ipfs/api-token", () => {
+    const persisted = fs.readFileSync(path.join(agent.home, ".spk-ipfs", "api-token"), "utf-8").trim();
+    expect(persisted).toBe(agent.token);
+  });
+
+  it("cannot be derived from what /api/identity publishes", async () => {
+    const res = await request(agent.url).get("/api/identity");
+    expect(res.status).toBe(200);
+    expect(res.body).not.toHaveProperty("signingKeyHash");
+    expect(JSON.stringify(res.body)).not.toContain(agent.token);
+  });
+});
+
+/** HTTP status of a /api/ws upgrade attempt (101 when it succeeds). */
+function upgradeStatus(url: string, headers: Record<string, string> = {}): Promise<number> {
+  return new Promise((resolve, reject) => {
+    const ws = new WebSocket(url, { headers });
+    ws.on("open", () => {
+      ws.close();
+      resolve(101);
+    });
+    ws.on("unexpected-response", (_req, res) => resolve(res.statusCode || 0));
+    ws.on("error", reject);
+  });
+}
+
+describe("authRequiredForReads", () => {
+  beforeAll(() => agent.config.setConfig({ authRequiredForReads: true }));
+  afterAll(() => agent.config.setConfig({ authRequiredForReads: false }));
+
+  it("requires a token for /metrics", async () => {
+    expect((await request(agent.url).get("/metrics")).status).toBe(401);
+    const res = await request(agent.url).get("/metrics").set("Authorization", `Bearer ${agent.token}`);
+    expect(res.status).toBe(200);
+  });
+
+  it("requires a token to open /api/ws", async () => {
+    const wsUrl = agent.url.replace("http", "ws") + "/api/ws";
+    expect(await upgradeStatus(wsUrl)).toBe(401);
+    expect(await upgradeStatus(`${wsUrl}?token=${"0".repeat(64)}`)).toBe(401);
+    expect(await upgradeStatus(`${wsUrl}?token=${agent.token}`)).toBe(101);
+    expect(await upgradeStatus(wsUrl, { Authorization: `Bearer ${agent.token}` })).toBe(101);
+  });
+
+  it("leaves /api/health open", async () => {
+    expect((await request(agent.url).get("/api/health")).status).not.toBe(401);
+  });
+});
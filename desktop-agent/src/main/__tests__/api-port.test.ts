@@ -0,0 +1,29 @@
+/**
+ * API bind plumbing: apiPort 0 binds an ephemeral port, and a configured port
+ * that is taken fails with a clear error.
+ */
+import { describe, it, expect, beforeAll, afterAll } from "vitest";
+import request from "supertest";
+import { createTestAgent, TestAgent } from "./test-agent";
+
+describe("ApiServer port", () => {
+  let agent: TestAgent;
+
+  beforeAll(async () => {
+    agent = await createTestAgent({ apiPort: 0 });
+  }, 30000);
+
+  afterAll(async () => {
+    await agent?.close();
+  });
+
+  it("binds an ephemeral port with apiPort 0 and serves /api/status", async () => {
+    expect(agent.server.getPort()).toBeGreaterThan(0);
+    const res = await request(agent.url).get("/api/status");
+    expect(res.status).toBe(200);
+  });
+
+  it("refuses a configured port that is already in use", async () => {
+    await expect(createTestAgent({ apiPort: agent.server.getPort() })).rejects.toThrow(/already in use/);
+  }, 30000);
+});
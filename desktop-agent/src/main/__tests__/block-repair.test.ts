@@ -0,0 +1,58 @@
+/**
+ * Block repair: pinned DAGs are unpinned around block/rm and always re-pinned.
+ */
+import { describe, it, expect } from "vitest";
+import { repairBlocks } from "../block-repair";
+import type { KuboManager } from "../kubo";
+
+function fakeKubo(options: { removeError?: string; pinError?: string } = {}) {
+  const calls: string[] = [];
+  const kubo = {
+    unpin: async (cid: string) => { calls.push(`unpin ${cid}`); },
+    removeBlock: async (cid: string) => {
+      calls.push(`rm ${cid}`);
+      if (options.removeError) throw new Error(options.removeError);
+    },
+    getBlock: async (cid: string) => { calls.push(`get ${cid}`); return Buffer.from("block"); },
+    pinWithProgress: async (cid: string) => {
+      calls.push(`pin ${cid}`);
+      if (options.pinError) throw new Error(options.pinError);
+    },
+    invalidatePinSize: () => {},
+  };
+  return { kubo: kubo as unknown as KuboManager, calls };
+}
+
+describe("repairBlocks", () => {
+  it("unpins, removes, re-fetches and re-pins a corrupt block", async () => {
+    const { kubo, calls } = fakeKubo();
+    const result = await repairBlocks(kubo, "root", [{ blockCid: "b1", reason: "corrupt" }], 1000);
+    expect(calls).toEqual(["unpin root", "rm b1", "get b1", "pin root"]);
+    expect(result).toEqual({ repaired: ["b1"], failed: [] });
+  });
+
+  it("only fetches a missing block and leaves the pin alone", async () => {
+    const { kubo, calls } = fakeKubo();
+    const result = await repairBlocks(kubo, "root", [{ blockCid: "b1", reason: "missing" }], 1000);
+    expect(calls).toEqual(["get b1"]);
+    expect(result.repaired).toEqual(["b1"]);
+  });
+
+  it("reports a block/rm failure and still re-pins", async () => {
+    const { kubo, calls } = fakeKubo({ removeError: "pinned: recursive" });
+    const result = await repairBlocks(kubo, "root", [{ blockCid: "b1", reason: "corrupt" }], 1000);
+    expect(calls).toEqual(["unpin root", "rm b1", "pin root"]);
+    expect(result.failed).toEqual([{ blockCid: "b1", error: "pinned: recursive" }]);
+  });
+
+  it("fails every block when the re-pin fails", async () => {
+    const { kubo } = fakeKubo({ pinError: "stalled" });
+    const result = await repairBlocks(kubo, "root", [
+      { blockCid: "b1", reason: "corrupt" },
+      { blockCid: "b2", reason: "missing" },
+    ], 1000);
+    expect(result.repaired).toEqual([]);
+    expect(result.failed.map((f) => f.blockCid)).toEqual(["b1", "b2"]);
+    expect(result.failed[0].error).toMatch(/re-pinning failed: stalled/);
+  });
+});
@@ -0,0 +1,36 @@
+/**
+ * POST /api/config hiveUsername: names are lowercased, and only a changed name
+ * is looked up on Hive (every Hive node here is unreachable).
+ */
+import { describe, it, expect, beforeAll, afterAll } from "vitest";
+import request from "supertest";
+import { createTestAgent, TestAgent } from "./test-agent";
+
+describe("hiveUsername config", () => {
+  let agent: TestAgent;
+
+  beforeAll(async () => {
+    agent = await createTestAgent({ hiveUsername: "alice", hiveApiNodes: ["http://127.0.0.1:1"] });
+  }, 30000);
+
+  afterAll(async () => {
+    await agent?.close();
+  });
+
+  const post = (body: Record<string, any>) =>
+    request(agent.url).post("/api/config").set("Authorization", `Bearer ${agent.token}`).send(body);
+
+  it("re-saves the current name without reaching Hive, in any case", async () => {
+    const res = await post({ hiveUsername: "Alice" });
+    expect(res.status).toBe(200);
+    expect(agent.config.getConfig().hiveUsername).toBe("alice");
+  });
+
+  it("looks up a changed name", async () => {
+    const res = await post({ hiveUsername: "Bob" });
+    expect(res.status).toBe(503);
+    expect(res.body.error).toBe("HIVE_UNREACHABLE");
+    expect(res.body.message).toContain("@bob");
+    expect(agent.config.getConfig().hiveUsername).toBe("alice");
+  }, 30000);
+});
@@ -0,0 +1,158 @@
+/**
+ * HTTP block challenges against a fake Kubo: salt replay, index range,
+ * single-block pins and the per-challenge block cap.
+ */
+import { describe, it, expect, beforeAll, afterAll } from "vitest";
+import * as fs from "fs";
+import * as os from "os";
+import * as path from "path";
+import type { AgentConfig } from "../config";
+import type { CliConfigStore } from "../config-cli";
+import type { HttpChallengeService } from "../http-challenge";
+
+const CID = "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi";
+
+/** Kubo with `count` local child blocks; block reads fail while `failReads` is set. */
+function fakeKubo(count: number) {
+  const blocks = Array.from({ length: count }, (_, i) => `block-${i}`);
+  const kubo = {
+    failReads: false,
+    getBlockRefs: async () => blocks,
+    getLocalBlockMask: async (cids: string[]) => cids.map(() => true),
+    warmBlocks: () => {},
+    getBlock: async (blockCid: string) => {
+      if (kubo.failReads) throw new Error("disk read failed");
+      return Buffer.from(blockCid);
+    },
+  };
+  return kubo;
+}
+
+describe("HttpChallengeService", () => {
+  let home: string;
+  let config: CliConfigStore;
+  let kubo: ReturnType<typeof fakeKubo>;
+  let service: HttpChallengeService;
+  let saltCounter = 0;
+  const freshSalt = () => `salt-${Date.now()}-${saltCounter++}`;
+
+  async function makeService(blockCount: number, overrides: Partial<AgentConfig> = {}): Promise<void> {
+    const { HttpChallengeService } = await import("../http-challenge");
+    const { AgentMetrics } = await import("../metrics");
+    config.setConfig({ challengeReadahead: 0, challengeRetries: 0, ...overrides });
+    kubo = fakeKubo(blockCount);
+    service = new HttpChallengeService(kubo as any, config as any, new AgentMetrics());
+  }
+
+  beforeAll(async () => {
+    home = fs.mkdtempSync(path.join(os.tmpdir(), "spk-challenge-test-"));
+    process.env.HOME = home;
+    process.env.USERPROFILE = home;
+    const { CliConfigStore } = await import("../config-cli");
+    config = new CliConfigStore();
+  });
+
+  afterAll(() => {
+    fs.rmSync(home, { recursive: true, force: true });
+  });
+
+  describe("salt replay", () => {
+    beforeAll(() => makeService(4));
+
+    it("rejects a salt that was already answered", async () => {
+      const salt = freshSalt();
+      const first = await service.run({ cid: CID, salt, blockIndex: 0 });
+      expect(first.status).toBe(200);
+      const again = await service.run({ cid: CID, salt, blockIndex: 1 });
+      expect(again.status).toBe(409);
+    });
+
+    it("gives the salt back when the block read fails", async () => {
+      const salt = freshSalt();
+      kubo.failReads = true;
+      const failed = await service.run({ cid: CID, salt, blockIndex: 0 });
+      kubo.failReads = false;
+      expect(failed.status).toBe(500);
+      const retried = await service.run({ cid: CID, salt, blockIndex: 0 });
+      expect(retried.status).toBe(200);
+      expect(retried.body.proof).toMatch(/^[0-9a-f]{64}$/);
+    });
+  });
+
+  describe("block index range", () => {
+    beforeAll(() => makeService(5));
+
+    it("accepts the last valid index", async () => {
+      const res = await service.run({ cid: CID, salt: freshSalt(), blockIndex: 4 });
+      expect(res.status).toBe(200);
+      expect(res.body.blockCid).toBe("block-4");
+    });
+
+    it("rejects the index equal to the block count", async () => {
+      const res = await service.run({ cid: CID, salt: freshSalt(), blockIndex: 5 });
+      expect(res.status).toBe(400);
+      expect(res.body.error).toBe("INDEX_OUT_OF_RANGE");
+      expect(res.body.outOfRangeIndices).toEqual([5]);
+      expect(res.body.blockCount).toBe(5);
+      expect(res.body.validRange).toEqual([0, 4]);
+    });
+
+    it("rejects one past the block count", async () => {
+      const res = await service.run({ cid: CID, salt: freshSalt(), blockIndices: [0, 6] });
+      expect(res.status).toBe(400);
+      expect(res.body.error).toBe("INDEX_OUT_OF_RANGE");
+      expect(res.body.outOfRangeIndices).toEqual([6]);
+    });
+  });
+
+  describe("single-block pin", () => {
+    beforeAll(() => makeService(0));
+
+    it("answers index 0 from the root block", async () => {
+      const res = await service.run({ cid: CID, salt: freshSalt(), blockIndex: 0 });
+      expect(res.status).toBe(200);
+      expect(res.body.blockCids).toEqual([CID]);
+    });
+
+    it("rejects index 1", async () => {
+      const res = await service.run({ cid: CID, salt: freshSalt(), blockIndex: 1 });
+      expect(res.status).toBe(400);
+      expect(res.body.error).toBe("INDEX_OUT_OF_RANGE");
+      expect(res.body.validRange).toEqual([0, 0]);
+    });
+  });
+
+  describe("maxBlocksPerChallenge", () => {
+    beforeAll(() => makeService(10, { maxBlocksPerChallenge: 3 }));
+
+    it("answers a challenge at the cap", async () => {
+      const res = await service.run({ cid: CID, salt: freshSalt(), blockIndices: [0, 1, 2] });
+      expect(res.status).toBe(200);
+      expect(res.body.blockCids).toHaveLength(3);
+    });
+
+    it("rejects one block over the cap", async () => {
+      const res = await service.run({ cid: CID, salt: freshSalt(), blockIndices: [0, 1, 2, 3] });
+      expect(res.status).toBe(400);
+      expect(res.body.error).toBe("TOO_MANY_BLOCKS");
+      expect(res.body.requested).toBe(4);
+      expect(res.body.maxBlocksPerChallenge).toBe(3);
+    });
+  });
+
+  describe("hmacKeyId", () => {
+    beforeAll(() => makeService(4, { challengeHmacKeys: { pool: "secret" } }));
+
+    it("signs with a configured key", async () => {
+      const res = await service.run({ cid: CID, salt: freshSalt(), blockIndex: 0, hmacKeyId: "pool" });
+      expect(res.status).toBe(200);
+      expect(res.body.hmacKeyId).toBe("pool");
+    });
+
+    it.each(["__proto__", "constructor", "toString"])("rejects the inherited property %s", async (hmacKeyId) => {
+      const res = await service.run({ cid: CID, salt: freshSalt(), blockIndex: 0, hmacKeyId });
+      expect(res.status).toBe(400);
+      expect(res.body.error).toBe(`Unknown hmacKeyId: ${hmacKeyId}`);
+    });
+  });
+});
@@ -0,0 +1,58 @@
+/**
+ * kuboCommandTimeoutSec: hung `ipfs` CLI commands and RPC calls are cut off.
+ */
+import { describe, it, expect, beforeAll, afterAll } from "vitest";
+import * as fs from "fs";
+import * as http from "http";
+import type { AddressInfo } from "net";
+import * as os from "os";
+import * as path from "path";
+import type { KuboManager } from "../kubo";
+
+describe("kuboCommandTimeoutSec", () => {
+  let home: string;
+  let hung: http.Server;
+  let kubo: KuboManager;
+
+  beforeAll(async () => {
+    home = fs.mkdtempSync(path.join(os.tmpdir(), "spk-kubo-test-"));
+    process.env.HOME = home;
+    process.env.USERPROFILE = home;
+
+    // An RPC endpoint that accepts requests and never answers
+    hung = http.createServer(() => {});
+    await new Promise<void>((resolve) => hung.listen(0, "127.0.0.1", resolve));
+    const port = (hung.address() as AddressInfo).port;
+
+    const { CliConfigStore } = await import("../config-cli");
+    const { KuboManager } = await import("../kubo");
+    const config = new CliConfigStore();
+    config.setConfig({ kuboCommandTimeoutSec: 1, externalDaemonApi: `http://127.0.0.1:${port}` });
+    kubo = new KuboManager(config as any);
+  });
+
+  afterAll(async () => {
+    hung.closeAllConnections();
+    await new Promise<void>((resolve) => hung.close(() => resolve()));
+    fs.rmSync(home, { recursive: true, force: true });
+  });
+
+  it.skipIf(process.platform === "win32")("kills a CLI command that outlives the timeout", async () => {
+    // Fake ipfs binary: answers `version` at once, hangs on anything else
+    const binary = path.join(home, "ipfs");
+    fs.writeFileSync(binary, '#!/bin/sh\nif [ "$1" = version ]; then echo 0.0.0; exit 0; fi\nexec sleep 30\n', { mode: 0o755 });
+    (kubo as any).ipfsPath = binary;
+
+    expect(await kubo.runCommand(["version"])).toBe("0.0.0\n");
+    const started = Date.now();
+    await expect(kubo.runCommand(["repo", "stat"])).rejects.toThrow("ipfs command timed out");
+    expect(Date.now() - started).toBeLessThan(5000);
+    expect(kubo.listProcesses().filter((p) => p.kind === "command")).toEqual([]);
+  });
+
+  it("gives up on an RPC call that outlives the timeout", async () => {
+    const started = Date.now();
+    await expect(kubo.unpin("bafkqaaa")).rejects.toThrow(/timeout/i);
+    expect(Date.now() - started).toBeLessThan(5000);
+  });
+});
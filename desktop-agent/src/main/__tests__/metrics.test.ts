@@ -0,0 +1,65 @@
+/**
+ * /metrics exposition: every family has its # HELP and # TYPE lines.
+ */
+import { describe, it, expect } from "vitest";
+import { AgentMetrics, NodeGauges } from "../metrics";
+
+const node: NodeGauges = {
+  repoSizeBytes: 1024,
+  pinnedFiles: 3,
+  totalEarnedHbd: 0.5,
+  daemonUp: true,
+  daemonRestarts: 1,
+  bandwidthInBytes: 10,
+  bandwidthOutBytes: 20,
+  swarmPeers: 4,
+};
+
+/** family name → its TYPE, checking HELP and TYPE come once each, before any sample. */
+function parseFamilies(text: string): Map<string, string> {
+  const help = new Set<string>();
+  const types = new Map<string, string>();
+  for (const line of text.trim().split("\n")) {
+    const helpMatch = /^# HELP (\w+) \S/.exec(line);
+    const typeMatch = /^# TYPE (\w+) (counter|gauge|histogram)$/.exec(line);
+    if (helpMatch) {
+      expect(help.has(helpMatch[1]), `duplicate HELP for ${helpMatch[1]}`).toBe(false);
+      help.add(helpMatch[1]);
+    } else if (typeMatch) {
+      expect(help.has(typeMatch[1]), `TYPE before HELP for ${typeMatch[1]}`).toBe(true);
+      expect(types.has(typeMatch[1]), `duplicate TYPE for ${typeMatch[1]}`).toBe(false);
+      types.set(typeMatch[1], typeMatch[2]);
+    } else {
+      const name = /^(\w+)/.exec(line)![1];
+      const family = types.has(name) ? name : name.replace(/_(bucket|sum|count)$/, "");
+      expect(types.has(family), `sample ${name} has no TYPE`).toBe(true);
+    }
+  }
+  return types;
+}
+
+describe("AgentMetrics.render", () => {
+  it("declares HELP and TYPE for every family", () => {
+    const metrics = new AgentMetrics();
+    metrics.recordChallenge("bafy-a", true, 120);
+    metrics.recordChallenge("bafy-b", false);
+    const types = parseFamilies(metrics.render(node));
+
+    expect(types.get("hivepoa_repo_size_bytes")).toBe("gauge");
+    expect(types.get("hivepoa_daemon_up")).toBe("gauge");
+    expect(types.get("hivepoa_daemon_restarts_total")).toBe("counter");
+    expect(types.get("hivepoa_bandwidth_in_bytes_total")).toBe("counter");
+    expect(types.get("hivepoa_uptime_seconds")).toBe("gauge");
+    expect(types.get("hivepoa_challenges_total")).toBe("counter");
+    expect(types.get("hivepoa_challenge_latency_seconds")).toBe("histogram");
+  });
+
+  it("renders the histogram buckets cumulatively", () => {
+    const metrics = new AgentMetrics();
+    metrics.recordChallenge("bafy-a", true, 120);
+    const text = metrics.render();
+    expect(text).toContain('hivepoa_challenge_latency_seconds_bucket{le="+Inf"} 1');
+    expect(text).toContain("hivepoa_challenge_latency_seconds_count 1");
+    expect(text).not.toContain("hivepoa_repo_size_bytes");
+  });
+});
@@ -0,0 +1,29 @@
+/**
+ * Pin metadata sidecar: a corrupt file is moved aside, not overwritten.
+ */
+import { describe, it, expect, afterAll } from "vitest";
+import * as fs from "fs";
+import * as os from "os";
+import * as path from "path";
+import { PinMetadataStore } from "../pin-metadata";
+
+describe("PinMetadataStore", () => {
+  const dir = fs.mkdtempSync(path.join(os.tmpdir(), "spk-pin-metadata-test-"));
+
+  afterAll(() => {
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+
+  it("moves a corrupt sidecar to .bak before writing", () => {
+    const file = path.join(dir, "pin-metadata.json");
+    fs.writeFileSync(file, '{"bafy": {"name": "trunc');
+
+    const store = new PinMetadataStore(file);
+    expect(store.getAll()).toEqual({});
+    expect(fs.readFileSync(`${file}.bak`, "utf-8")).toBe('{"bafy": {"name": "trunc');
+
+    store.updateMany({ bafkqaaa: { name: "after" } });
+    expect(store.get("bafkqaaa")?.name).toBe("after");
+    expect(fs.readFileSync(`${file}.bak`, "utf-8")).toBe('{"bafy": {"name": "trunc');
+  });
+});
@@ -0,0 +1,50 @@
+/**
+ * Pin quota: counted per authenticated token, refunded when a request is rejected.
+ */
+import { describe, it, expect, beforeAll, afterAll } from "vitest";
+import request from "supertest";
+import { PinQuota } from "../pin-quota";
+import { createTestAgent, TestAgent } from "./test-agent";
+
+describe("PinQuota", () => {
+  it("refuses past the limit and frees a slot on refund", () => {
+    const quota = new PinQuota();
+    expect(quota.consume("a", 2, 60000)).toBeNull();
+    expect(quota.consume("a", 2, 60000)).toBeNull();
+    expect(quota.consume("a", 2, 60000)?.used).toBe(2);
+    quota.refund("a");
+    expect(quota.consume("a", 2, 60000)).toBeNull();
+  });
+
+  it("keeps identities apart", () => {
+    const quota = new PinQuota();
+    expect(quota.consume("a", 1, 60000)).toBeNull();
+    expect(quota.consume("b", 1, 60000)).toBeNull();
+    expect(quota.consume("a", 1, 60000)).not.toBeNull();
+  });
+});
+
+describe("POST /api/pin quota", () => {
+  let agent: TestAgent;
+
+  beforeAll(async () => {
+    agent = await createTestAgent({ pinQuotaPerWindow: 1, pinQuotaWindowMin: 60 });
+  }, 30000);
+
+  afterAll(async () => {
+    await agent?.close();
+  });
+
+  it("doesn't charge requests rejected during validation", async () => {
+    for (let i = 0; i < 3; i++) {
+      const res = await request(agent.url)
+        .post("/api/pin")
+        .set("Authorization", `Bearer ${agent.token}`)
+        .set("X-Coordinator-Id", `coordinator-${i}`)
+        .send({ cid: "not-a-cid" });
+      expect(res.status).toBe(400);
+    }
+    const status = await request(agent.url).get("/api/status");
+    expect(status.body.pinQuota.usage).toEqual([]);
+  });
+});
@@ -0,0 +1,133 @@
+/**
+ * Pin reconciler: allow/denylist filtering, the storage projection that counts
+ * a pin's size and the bytes still queued, and the disk safety floor.
+ */
+import { describe, it, expect, beforeAll, afterAll } from "vitest";
+import * as fs from "fs";
+import * as http from "http";
+import type { AddressInfo } from "net";
+import * as os from "os";
+import * as path from "path";
+import { PinReconciler } from "../pin-reconciler";
+
+const cid = (c: string) => "bafybei" + c.repeat(52);
+const [A, B, C, X, Y] = ["a", "b", "c", "x", "y"].map(cid);
+
+describe("PinReconciler", () => {
+  let diskError: string | null = null;
+  const storageMonitor = { checkPin: () => diskError };
+  let home: string;
+  let server: http.Server;
+  let url: string;
+  const added: string[] = [];
+  const removed: string[] = [];
+
+  beforeAll(async () => {
+    home = fs.mkdtempSync(path.join(os.tmpdir(), "spk-reconcile-test-"));
+    process.env.HOME = home;
+    process.env.USERPROFILE = home;
+    fs.mkdirSync(path.join(home, ".spk-ipfs"));
+    // X and Y were pinned by an earlier pass and are no longer desired
+    fs.writeFileSync(path.join(home, ".spk-ipfs", "reconciled-pins.json"), JSON.stringify([X, Y]));
+
+    // The coordinator's desired set
+    server = http.createServer((_req, res) => {
+      res.setHeader("Content-Type", "application/json");
+      res.end(JSON.stringify({ pins: [A, B, C] }));
+    });
+    await new Promise<void>((resolve) => server.listen(0, "127.0.0.1", resolve));
+    url = `http://127.0.0.1:${(server.address() as AddressInfo).port}`;
+  });
+
+  afterAll(async () => {
+    await new Promise<void>((resolve) => server.close(() => resolve()));
+    fs.rmSync(home, { recursive: true, force: true });
+  });
+
+  it("skips CIDs outside the allowlist or on the denylist", async () => {
+    const kubo = {
+      isRunning: () => true,
+      getPeerId: async () => null,
+      getStorageInfo: async () => ({ usedBytes: 0, maxBytes: 0 }),
+      getObjectSize: async () => 1024,
+      listPins: async () => [X, Y],
+      unpin: async (c: string) => { removed.push(c); },
+    };
+    const pinJobs = {
+      pendingBytes: () => 0,
+      enqueue: (c: string, _name: string | null, _bytes: number | null, source: string) => {
+        expect(source).toBe("reconciler");
+        added.push(c);
+      },
+    };
+    const config = {
+      getConfig: () => ({
+        pinReconcileUrl: `${url}/desired`,
+        pinReconcileUnpinExtras: true,
+        pinReconcileAllowlist: [A, B, X, Y],
+        pinReconcileDenylist: [B, Y],
+        hiveUsername: "",
+      }),
+    };
+    const report = await new PinReconciler(kubo as any, config as any, pinJobs as any, storageMonitor as any).reconcile();
+
+    expect(report!.error).toBeNull();
+    expect(added).toEqual([A]);
+    expect(removed).toEqual([X]);
+    expect(report!.skippedByFilter).toBe(3); // B denied, C not allowed, Y denied
+  });
+
+  it("defers pins whose size plus pending bytes would pass the storage limit", async () => {
+    const queued: string[] = [];
+    const kubo = {
+      isRunning: () => true,
+      getPeerId: async () => null,
+      getStorageInfo: async () => ({ usedBytes: 5000, maxBytes: 10000 }),
+      getObjectSize: async () => 2000,
+      listPins: async () => [],
+    };
+    const pinJobs = { pendingBytes: () => 4000, enqueue: (c: string) => { queued.push(c); } };
+    const config = {
+      getConfig: () => ({
+        pinReconcileUrl: `${url}/desired`,
+        pinReconcileUnpinExtras: false,
+        pinReconcileAllowlist: [],
+        pinReconcileDenylist: [],
+        hiveUsername: "",
+      }),
+    };
+    const report = await new PinReconciler(kubo as any, config as any, pinJobs as any, storageMonitor as any).reconcile();
+
+    expect(queued).toEqual([]);
+    expect(report!.skippedForQuota).toBe(3);
+  });
+
+  it("defers pins that would break the disk safety floor", async () => {
+    const queued: string[] = [];
+    const kubo = {
+      isRunning: () => true,
+      getPeerId: async () => null,
+      getStorageInfo: async () => ({ usedBytes: 0, maxBytes: 0 }),
+      getObjectSize: async () => 2000,
+      listPins: async () => [],
+    };
+    const pinJobs = { pendingBytes: () => 0, enqueue: (c: string) => { queued.push(c); } };
+    const config = {
+      getConfig: () => ({
+        pinReconcileUrl: `${url}/desired`,
+        pinReconcileUnpinExtras: false,
+        pinReconcileAllowlist: [],
+        pinReconcileDenylist: [],
+        hiveUsername: "",
+      }),
+    };
+    diskError = "Pinning would leave less than 5 GB free";
+    try {
+      const report = await new PinReconciler(kubo as any, config as any, pinJobs as any, storageMonitor as any).reconcile();
+      expect(queued).toEqual([]);
+      expect(report!.skippedForQuota).toBe(3);
+    } finally {
+      diskError = null;
+    }
+  });
+});
@@ -0,0 +1,26 @@
+/**
+ * Multi-algorithm HTTP proofs: own-key validation and blake3 support.
+ */
+import { describe, it, expect } from "vitest";
+import { httpProofFromBlocks, httpProofsFromBlocks, isProofHashAlgo } from "../poa-crypto";
+
+describe("httpProofsFromBlocks", () => {
+  const blocks = [Buffer.from("block-0"), Buffer.from("block-1")];
+
+  it("matches httpProofFromBlocks for sha256", () => {
+    expect(httpProofsFromBlocks("salt", blocks, ["sha256"]).sha256).toBe(httpProofFromBlocks("salt", blocks));
+  });
+
+  it("computes blake3", () => {
+    // BLAKE3 of the empty input
+    expect(httpProofsFromBlocks("", [], ["blake3"]).blake3)
+      .toBe("af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262");
+  });
+
+  it("rejects inherited property names", () => {
+    expect(isProofHashAlgo("constructor")).toBe(false);
+    expect(isProofHashAlgo("__proto__")).toBe(false);
+    expect(isProofHashAlgo("blake3")).toBe(true);
+    expect(() => httpProofsFromBlocks("salt", blocks, ["toString"])).toThrow(/Unsupported/);
+  });
+});
@@ -0,0 +1,56 @@
+/**
+ * Signed receipts: what signReceipt produces, verifyReceipt accepts — and nothing else.
+ */
+import { describe, it, expect } from "vitest";
+import { PrivateKey, cryptoUtils } from "@hiveio/dhive";
+import { signReceipt, verifyReceipt } from "../receipt";
+import { proofHashFromBlocks } from "../poa-crypto";
+
+const key = PrivateKey.fromSeed("hivepoa-receipt-test");
+const otherKey = PrivateKey.fromSeed("someone-else");
+const signer = (payload: string) => key.sign(cryptoUtils.sha256(payload)).toString();
+const blocks = [Buffer.from("block-a"), Buffer.from("block-b")];
+
+const response = {
+  type: "response",
+  targetPeer: "storer",
+  validatorPeer: "validator",
+  cid: "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi",
+  salt: "salt-1",
+  proofHash: proofHashFromBlocks("salt-1", blocks),
+  nonce: "n1",
+};
+
+describe("signReceipt", () => {
+  it("names the challenged node as the signer of a response", () => {
+    expect(signReceipt(response, signer).signerUsername).toBe("storer");
+    expect(signReceipt({ ...response, type: "challenge" }, signer).signerUsername).toBe("validator");
+  });
+});
+
+describe("verifyReceipt", () => {
+  const publicKey = key.createPublic().toString();
+
+  it("accepts the wire envelope and the parsed form", () => {
+    const envelope = signReceipt(response, signer);
+    expect(verifyReceipt(envelope, null, [publicKey]).valid).toBe(true);
+    const parsed = { ...JSON.parse(envelope.payload), __signature: envelope.signature, __signerUsername: "storer" };
+    expect(verifyReceipt(parsed, null, [publicKey]).valid).toBe(true);
+  });
+
+  it("rejects a tampered payload, a foreign key and a missing signature", () => {
+    const envelope = signReceipt(response, signer);
+    const tampered = { ...envelope, payload: envelope.payload.replace("salt-1", "salt-2") };
+    expect(verifyReceipt(tampered, null, [publicKey]).signatureValid).toBe(false);
+    expect(verifyReceipt(envelope, null, [otherKey.createPublic().toString()]).valid).toBe(false);
+    expect(verifyReceipt(signReceipt(response), null, [publicKey]).errors).toContain("Receipt is unsigned");
+  });
+
+  it("checks proofHash against the expected blocks", () => {
+    const envelope = signReceipt(response, signer);
+    expect(verifyReceipt(envelope, { blocks }, [publicKey]).proofValid).toBe(true);
+    const wrong = verifyReceipt(envelope, { blocks: [Buffer.from("other")] }, [publicKey]);
+    expect(wrong.valid).toBe(false);
+    expect(wrong.errors).toContain("proofHash does not match expected blocks");
+  });
+});
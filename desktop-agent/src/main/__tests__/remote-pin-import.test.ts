@@ -0,0 +1,68 @@
+/**
+ * Remote pin import: paging across shared `created` timestamps and routing the
+ * pins through the PinJobQueue.
+ */
+import { describe, it, expect, beforeAll, afterAll } from "vitest";
+import * as http from "http";
+import type { AddressInfo } from "net";
+import { RemotePinImporter } from "../remote-pin-import";
+
+const BASE32 = "abcdefghijklmnopqrstuvwxyz234567";
+
+function cidFor(i: number): string {
+  let code = "";
+  for (let n = i, k = 0; k < 4; k++, n = Math.floor(n / 32)) code = BASE32[n % 32] + code;
+  return "bafybei" + "a".repeat(49) + code;
+}
+
+// 1500 pins, newest first; 990..1009 share one timestamp across the first page boundary
+const PINS = Array.from({ length: 1500 }, (_, i) => {
+  const at = i < 990 ? 2_000_000 - i : i < 1010 ? 2_000_000 - 990 : 2_000_000 - i;
+  return { requestid: `req-${i}`, created: new Date(at * 1000).toISOString(), pin: { cid: cidFor(i) } };
+});
+
+describe("RemotePinImporter", () => {
+  let server: http.Server;
+  let url: string;
+
+  beforeAll(async () => {
+    server = http.createServer((req, res) => {
+      const u = new URL(req.url!, "http://localhost");
+      res.setHeader("Content-Type", "application/json");
+      const before = u.searchParams.get("before");
+      const limit = Number(u.searchParams.get("limit"));
+      const results = PINS.filter((p) => !before || p.created < before).slice(0, limit);
+      res.end(JSON.stringify({ count: PINS.length, results }));
+    });
+    await new Promise<void>((resolve) => server.listen(0, "127.0.0.1", resolve));
+    url = `http://127.0.0.1:${(server.address() as AddressInfo).port}`;
+  });
+
+  afterAll(async () => {
+    await new Promise<void>((resolve) => server.close(() => resolve()));
+  });
+
+  it("imports every remote pin once, through the pin queue", async () => {
+    const enqueued: { cid: string; source: string }[] = [];
+    const pinJobs = {
+      enqueue: (cid: string, _name: string | null, _bytes: number | null, source: string) => {
+        enqueued.push({ cid, source });
+        return { cid, state: "pinned", error: null };
+      },
+      whenFinished: async (job: any) => job,
+    };
+    const importer = new RemotePinImporter({ listPins: async () => [cidFor(0)] } as any, pinJobs as any);
+
+    const job = importer.start(url, "token");
+    while (job.status === "listing" || job.status === "pinning") {
+      await new Promise((resolve) => setTimeout(resolve, 10));
+    }
+
+    expect(job.status).toBe("done");
+    expect(job.listed).toBe(1500);
+    expect(job.alreadyLocal).toBe(1);
+    expect(job.imported).toBe(1499);
+    expect(new Set(enqueued.map((e) => e.cid)).size).toBe(1499);
+    expect(enqueued.every((e) => e.source === "import")).toBe(true);
+  });
+});
@@ -0,0 +1,50 @@
+/**
+ * Test Agent Factory
+ * Starts a real ApiServer on an ephemeral port with its own ~/.spk-ipfs in a
+ * temp dir. There is no IPFS daemon: Kubo points at an external API nobody
+ * listens on, so anything that needs the repo fails the way it would offline.
+ */
+import * as fs from "fs";
+import * as os from "os";
+import * as path from "path";
+import type { AgentConfig } from "../config";
+import type { CliConfigStore } from "../config-cli";
+import type { ApiServer } from "../api";
+
+export interface TestAgent {
+  server: ApiServer;
+  config: CliConfigStore;
+  home: string;
+  url: string;
+  token: string;
+  close(): Promise<void>;
+}
+
+export async function createTestAgent(overrides: Partial<AgentConfig> = {}): Promise<TestAgent> {
+  const home = fs.mkdtempSync(path.join(os.tmpdir(), "spk-agent-test-"));
+  // os.homedir() reads these, and every store resolves ~/.spk-ipfs when it is constructed
+  process.env.HOME = home;
+  process.env.USERPROFILE = home;
+
+  const { CliConfigStore } = await import("../config-cli");
+  const { KuboManager } = await import("../kubo");
+  const { ApiServer } = await import("../api");
+
+  const config = new CliConfigStore();
+  config.setConfig({ apiPort: 0, externalDaemonApi: "http://127.0.0.1:1", ...overrides });
+  const kubo = new KuboManager(config as any);
+  const server = new ApiServer(kubo, config as any);
+  await server.start();
+
+  return {
+    server,
+    config,
+    home,
+    url: `http://127.0.0.1:${server.getPort()}`,
+    token: server.getAuthToken(),
+    async close() {
+      await server.stop();
+      fs.rmSync(home, { recursive: true, force: true });
+    },
+  };
+}
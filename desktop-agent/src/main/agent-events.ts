@@ -0,0 +1,76 @@
+/**
+ * agent-events.ts — In-process event bus behind GET /api/events (SSE) and /api/ws
+ *
+ * Producers (daemon start/stop, pin/unpin, challenges, earnings, quota) publish small
+ * deltas here; each SSE or WebSocket client subscribes for as long as its
+ * connection is open. Publishing never waits on consumers: a client whose socket buffer
+ * backs up past MAX_BUFFERED_BYTES is disconnected and can simply reconnect
+ * and reload GET /api/status. Past events are kept by event-history.ts.
+ */
+
+import { EventEmitter } from 'events';
+
+export type AgentEventType =
+  | 'daemon_ready'
+  | 'daemon_stopped'
+  | 'daemon_restart_failing'
+  | 'pin_added'
+  | 'pin_removed'
+  | 'challenge_received'
+  | 'proof_submitted'
+  | 'challenge_completed'
+  | 'earnings_updated'
+  | 'earnings_milestone'
+  | 'storage_quota_warning'
+  | 'disk_space_warning'
+  | 'gc_completed'
+  | 'config_changed'
+  | 'chain_challenge'
+  | 'chain_contract'
+  | 'payout_received'
+  | 'payout_discrepancy'
+  | 'daily_summary'
+  | 'error';
+
+export interface AgentEvent {
+  id: number;
+  type: AgentEventType;
+  at: string;
+  data: Record<string, any>;
+}
+
+/** Per-client write backlog after which a slow SSE/WebSocket consumer is dropped. */
+export const MAX_BUFFERED_BYTES = 1024 * 1024;
+
+export class AgentEventBus {
+  private emitter = new EventEmitter();
+  private nextId = 1;
+
+  constructor() {
+    // One listener per SSE/WebSocket client — no meaningful cap
+    this.emitter.setMaxListeners(0);
+  }
+
+  publish(type: AgentEventType, data: Record<string, any> = {}): void {
+    const event: AgentEvent = { id: this.nextId++, type, at: new Date().toISOString(), data };
+    this.emitter.emit('event', event);
+  }
+
+  /** Continue numbering after `lastId` (the last persisted event), if that is ahead. */
+  resumeIdsAfter(lastId: number): void {
+    this.nextId = Math.max(this.nextId, lastId + 1);
+  }
+
+  /** Returns an unsubscribe function. */
+  subscribe(listener: (event: AgentEvent) => void): () => void {
+    this.emitter.on('event', listener);
+    return () => this.emitter.off('event', listener);
+  }
+
+  subscriberCount(): number {
+    return this.emitter.listenerCount('event');
+  }
+}
+
+/** Process-wide bus, shared by KuboManager, the config stores and ApiServer. */
+export const agentEvents = new AgentEventBus();
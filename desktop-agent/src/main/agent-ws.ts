@@ -2,9 +2,12 @@ import WebSocket from 'ws';
 import axios from 'axios';
 import { EventEmitter } from 'events';
 import { KuboManager } from './kubo';
-import { ConfigStore } from './config';
-import { computeProofHash, getBlockCids, computeBlockListHash, hashFile, hashString, getIntFromHash } from './poa-crypto';
+import { ConfigStore, resolveNodeId } from './config';
+import { computeProofHash, computeSealedProofHash, getBlockCids, computeBlockListHash, hashFile, hashString, getIntFromHash, proofBlockCount } from './poa-crypto';
 import { TreasurySigner } from './treasury-signer';
+import type { AgentMetrics } from './metrics';
+import type { SealStore } from './seal-store';
+import { agentEvents } from './agent-events';
 
 export class AgentWSClient extends EventEmitter {
   private ws: WebSocket | null = null;
@@ -19,6 +22,8 @@ export class AgentWSClient extends EventEmitter {
   private reconnectAttempts = 0;
   private activeChallenges = 0;
   private treasurySigner: TreasurySigner | null = null;
+  private metrics: AgentMetrics | null = null;
+  private sealStore: SealStore | null = null;
 
   constructor(kubo: KuboManager, config: ConfigStore) {
     super();
@@ -81,9 +86,11 @@ export class AgentWSClient extends EventEmitter {
       const registerMsg = {
         type: 'register',
         peerId,
+        nodeId: resolveNodeId(cfg, peerId),
         hiveUsername: cfg.hiveUsername,
         version: require('electron').app.getVersion(),
         storageMaxGB: cfg.storageMaxGB,
+        ...this.sealRegistration(),
       };
 
       this.ws?.send(JSON.stringify(registerMsg));
@@ -147,11 +154,17 @@ export class AgentWSClient extends EventEmitter {
     }
   }
 
-  private async handleChallenge(challenge: { Hash: string; CID: string; User: string }): Promise<void> {
-    const { Hash: salt, CID: cid, User: validator } = challenge;
+  private async handleChallenge(challenge: { Hash: string; CID: string; User: string; Sealed?: boolean }): Promise<void> {
+    const { Hash: salt, CID: cid, User: validator, Sealed: sealed } = challenge;
     console.log(`[AgentWS] Challenge received: CID=${cid}, validator=${validator}`);
+    if (this.config.getConfig().challengesPaused) {
+      this.ws?.send(JSON.stringify({ type: 'ProofResponse', Hash: salt, CID: cid, Status: 'Fail', error: 'CHALLENGES_PAUSED', elapsed: 0 }));
+      return;
+    }
+    agentEvents.publish('challenge_received', { source: 'validator-ws', cid, validator });
 
     const startTime = Date.now();
+    let blocks: number | undefined;
     const CHALLENGE_TIMEOUT = 24_000; // Must respond within 25s server-side; give 1s network buffer
     this.activeChallenges++;
 
@@ -160,7 +173,19 @@ export class AgentWSClient extends EventEmitter {
       const kuboApiUrl = this.kubo.getApiUrl();
       const proofPromise = (async () => {
         const blockCids = await getBlockCids(kuboApiUrl, cid);
-        return await computeProofHash(kuboApiUrl, salt, cid, blockCids);
+        // Proof-of-replication: answer from our sealed replica, never by sealing on demand
+        let proof: string;
+        if (sealed) {
+          const store = this.sealStore;
+          if (!store?.isSealed(cid)) throw new Error('NOT_SEALED');
+          proof = await computeSealedProofHash(salt, cid, blockCids, (blockCid) => store.readSealed(blockCid));
+        } else {
+          const { challengeMemoryCeilingMB } = this.config.getConfig();
+          proof = await computeProofHash(kuboApiUrl, salt, cid, blockCids, challengeMemoryCeilingMB * 1024 * 1024);
+        }
+        blocks = proofBlockCount(salt, blockCids.length);
+        this.metrics?.recordBlocksHashed(blocks);
+        return proof;
       })();
 
       const timeoutPromise = new Promise<never>((_, reject) => {
@@ -180,8 +205,11 @@ export class AgentWSClient extends EventEmitter {
         proofHash,
         elapsed,
       }));
+      agentEvents.publish('proof_submitted', { source: 'validator-ws', cid, salt, proof: proofHash, latencyMs: elapsed });
+      agentEvents.publish('challenge_completed', { source: 'validator-ws', cid, validator, success: true, latencyMs: elapsed, proof: proofHash });
 
-      this.config.recordChallenge(true, 0.001);
+      this.config.recordChallenge(true, 0.001, cid, { validator, latencyMs: elapsed, blocks });
+      this.metrics?.recordChallenge(cid, true, elapsed);
 
     } catch (err: any) {
       const elapsed = Date.now() - startTime;
@@ -196,7 +224,11 @@ export class AgentWSClient extends EventEmitter {
         elapsed,
       }));
 
-      this.config.recordChallenge(false, 0);
+      agentEvents.publish('challenge_completed', {
+        source: 'validator-ws', cid, validator, success: false, latencyMs: elapsed, proof: null, error: err.message,
+      });
+      this.config.recordChallenge(false, 0, cid, { validator, latencyMs: elapsed, blocks, error: err.message });
+      this.metrics?.recordChallenge(cid, false, elapsed);
     } finally {
       this.activeChallenges = Math.max(0, this.activeChallenges - 1);
     }
@@ -269,6 +301,26 @@ export class AgentWSClient extends EventEmitter {
     this.treasurySigner = signer;
   }
 
+  /**
+   * Sealing fields of the register message. The replica key goes to the
+   * coordinator only — it re-derives sealed proofs with it and checks it
+   * against the commitment it pinned for this node.
+   */
+  private sealRegistration(): { sealing: boolean; sealKey?: string } {
+    const key = this.sealStore?.getReplicaKey();
+    return key ? { sealing: true, sealKey: key.toString('hex') } : { sealing: false };
+  }
+
+  /** Attach the sealed replica store for `Sealed` challenges (called by ApiServer.setAgentWS). */
+  setSealStore(store: SealStore): void {
+    this.sealStore = store;
+  }
+
+  /** Attach the shared metrics registry (called by ApiServer.setAgentWS). */
+  setMetrics(metrics: AgentMetrics): void {
+    this.metrics = metrics;
+  }
+
   hasActiveChallenges(): boolean {
     return this.activeChallenges > 0;
   }
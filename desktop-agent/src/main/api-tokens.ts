@@ -0,0 +1,119 @@
+/**
+ * api-tokens.ts — Scoped API tokens
+ *
+ * The local auth token (~/.spk-ipfs/api-token) can do everything. A scoped
+ * token can only do what its scopes allow, so the web dashboard can be handed
+ * a read-only token and a validator one that can only answer challenges:
+ *
+ *   read       GET endpoints (only enforced with authRequiredForReads)
+ *   pin        pin, unpin, upload, import/export, reconcile, scrub, GC, /pins
+ *   config     /api/config, HMAC keys, toggles, Kubo process control
+ *   earnings   earnings endpoints, including quarantine decisions
+ *   challenge  /api/challenge, batch, benchmark and self-challenge runs
+ *
+ * Wallet and key endpoints always need a full-access token. Only a SHA256 of
+ * each token is kept in ~/.spk-ipfs/api-tokens.json; the token itself is
+ * shown once, when it is created.
+ *
+ * Session tokens from pairing (pairing.ts) carry the web app's origin and are
+ * only accepted on requests from that origin.
+ */
+
+import * as crypto from 'crypto';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { JsonFileWriter } from './json-writer';
+
+export type ApiScope = 'read' | 'pin' | 'config' | 'earnings' | 'challenge';
+export const API_SCOPES: ApiScope[] = ['read', 'pin', 'config', 'earnings', 'challenge'];
+
+export interface ApiToken {
+  id: string;
+  name: string;
+  scopes: ApiScope[];
+  origin: string | null; // set for paired sessions
+  createdAt: string;
+  lastUsedAt: string | null;
+}
+
+interface StoredToken extends ApiToken {
+  tokenHash: string;
+}
+
+const TOKEN_PREFIX = 'spk_';
+// lastUsedAt is informational — don't rewrite the file on every request
+const LAST_USED_RESOLUTION_MS = 60000;
+
+function hashToken(token: string): string {
+  return crypto.createHash('sha256').update(token).digest('hex');
+}
+
+export class ApiTokenStore {
+  private tokens: StoredToken[] = [];
+  private writer: JsonFileWriter;
+
+  constructor(filePath: string = path.join(os.homedir(), '.spk-ipfs', 'api-tokens.json')) {
+    this.writer = new JsonFileWriter(filePath, () => ({ tokens: this.tokens }));
+    try {
+      const parsed = JSON.parse(fs.readFileSync(filePath, 'utf-8'));
+      if (Array.isArray(parsed?.tokens)) this.tokens = parsed.tokens;
+    } catch {}
+  }
+
+  /** Create a token. The plaintext is returned here and never again. */
+  create(name: string, scopes: ApiScope[], origin: string | null = null): { token: string; info: ApiToken } {
+    const token = TOKEN_PREFIX + crypto.randomBytes(32).toString('hex');
+    const stored: StoredToken = {
+      id: crypto.randomBytes(8).toString('hex'),
+      name,
+      scopes: Array.from(new Set(scopes)),
+      origin,
+      createdAt: new Date().toISOString(),
+      lastUsedAt: null,
+      tokenHash: hashToken(token),
+    };
+    this.tokens.push(stored);
+    this.writer.schedule();
+    return { token, info: this.toInfo(stored) };
+  }
+
+  list(): ApiToken[] {
+    return this.tokens.map((t) => this.toInfo(t));
+  }
+
+  revoke(id: string): boolean {
+    const before = this.tokens.length;
+    this.tokens = this.tokens.filter((t) => t.id !== id);
+    if (this.tokens.length === before) return false;
+    this.writer.schedule();
+    return true;
+  }
+
+  /** True if a paired session is bound to `origin` (it may then make CORS requests). */
+  hasOrigin(origin: string): boolean {
+    return this.tokens.some((t) => t.origin === origin);
+  }
+
+  /** The token's record if it is known (and, for a session, `origin` matches), else null. */
+  verify(token: string, origin?: string): ApiToken | null {
+    if (!token.startsWith(TOKEN_PREFIX)) return null;
+    const hash = Buffer.from(hashToken(token), 'hex');
+    const match = this.tokens.find((t) => crypto.timingSafeEqual(Buffer.from(t.tokenHash, 'hex'), hash));
+    if (!match || (match.origin && match.origin !== origin)) return null;
+    const now = Date.now();
+    if (!match.lastUsedAt || now - Date.parse(match.lastUsedAt) > LAST_USED_RESOLUTION_MS) {
+      match.lastUsedAt = new Date(now).toISOString();
+      this.writer.schedule();
+    }
+    return this.toInfo(match);
+  }
+
+  flush(): void {
+    this.writer.flush();
+  }
+
+  private toInfo({ tokenHash: _hash, ...info }: StoredToken): ApiToken {
+    return { ...info, scopes: [...info.scopes], origin: info.origin ?? null };
+  }
+}
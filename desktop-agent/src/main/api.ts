@@ -1,7 +1,13 @@
 import express, { Express, Request, Response } from 'express';
 import * as http from 'http';
+import * as net from 'net';
 import * as crypto from 'crypto';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { Readable } from 'stream';
 import axios from 'axios';
+import { WebSocketServer, WebSocket } from 'ws';
 // Electron is optional — CLI mode runs without it
 let electronApp: { getVersion(): string; setLoginItemSettings(opts: any): void } | null = null;
 try {
@@ -10,14 +16,47 @@ try {
   electronApp = null;
 }
 import { KuboManager } from './kubo';
-import { ConfigStore, AgentConfig } from './config';
+import { ConfigStore, AgentConfig, NODE_ID_PATTERN, resolveNodeId } from './config';
 import { AgentHiveClient } from './hive';
-import { isValidCid } from './poa-crypto';
+import { isValidCid, hashString } from './poa-crypto';
 import type { AgentWSClient } from './agent-ws';
 import type { PeerDiscovery } from './peer-discovery';
 import type { LocalValidator } from './validator';
 import type { ChallengeHandler } from './challenge-handler';
+import type { GpuContributionManager } from './gpu-contribution';
+import type { AutoUpdater, UpdateMode } from './updater';
 import { WalletManager } from './wallet-manager';
+import { AgentMetrics, NodeGauges } from './metrics';
+import { runPreflight } from './preflight';
+import { runTestVectors } from './selftest-vectors';
+import { HttpChallengeService, HttpChallengeRequest, HttpChallengeResult } from './http-challenge';
+import { RemotePinImporter } from './remote-pin-import';
+import { PinMetadataStore, PinMetadataUpdate, PIN_SOURCES, PinSource } from './pin-metadata';
+import { PinReconciler } from './pin-reconciler';
+import { GcScheduler, GcRun } from './gc-scheduler';
+import { SealStore } from './seal-store';
+import { PinJobQueue } from './pin-jobs';
+import { PinRequestStore, createPinningServiceRoutes } from './pinning-service';
+import { ContractSync } from './contracts';
+import { PinQuota } from './pin-quota';
+import { IntegrityScrubber } from './integrity-scrubber';
+import { SelfChallenger } from './self-challenge';
+import { WebhookNotifier, WebhookConfig, WEBHOOK_EVENTS, redactWebhookUrl } from './webhooks';
+import { bootCooldown } from './boot-cooldown';
+import { BlockSaltVerifier } from './challenge-freshness';
+import { agentEvents, AgentEventType, MAX_BUFFERED_BYTES } from './agent-events';
+import { EARNINGS_GRANULARITIES, EarningsGranularity } from './earnings-ledger';
+import { ApiTokenStore, ApiScope, API_SCOPES } from './api-tokens';
+import { PairingManager } from './pairing';
+import { EventHistory } from './event-history';
+import { PriceFeed, toFiat } from './price-feed';
+import { ChainWatcher, ChainContractAssignment } from './chain-watcher';
+import { EarningsReconciler } from './earnings-reconciler';
+import { DailySummary } from './daily-summary';
+import { StorageMonitor } from './storage-monitor';
+import {
+  AutostartMode, supportedAutostartModes, installAutostartService, uninstallAutostartService, autostartServiceStatus,
+} from './autostart';
 
 // Self-contained Keychain auth page served to the user's browser
 const AUTH_PAGE_HTML = `<!DOCTYPE html>
@@ -171,14 +210,60 @@ const AUTH_PAGE_HTML = `<!DOCTYPE html>
 </body>
 </html>`;
 
+/** Entries of each history list included in GET /api/snapshot. */
+const SNAPSHOT_HISTORY_LIMIT = 50;
+
+/** Origins always allowed by CORS and the /api/ws origin check. */
+const BUILTIN_ORIGINS = [
+  'http://localhost:3000',
+  'http://localhost:5000',
+  'http://localhost:8080',
+  'http://127.0.0.1:3000',
+  'http://127.0.0.1:5000',
+  'http://127.0.0.1:8080',
+  'https://dhenz14.github.io',
+];
+
+/**
+ * HTTP status + JSON body of an operation shared by a route and the renderer's
+ * IPC commands, so both go through the same validation.
+ */
+export interface ApiResult {
+  status: number;
+  body: Record<string, any>;
+}
+
 export class ApiServer {
   private app: Express;
   private server: http.Server | null = null;
+  private wss: WebSocketServer | null = null;
   private kubo: KuboManager;
   private config: ConfigStore;
   private wallet: WalletManager;
   private port: number;
   private agentWS: AgentWSClient | null = null;
+  private gpuManager: GpuContributionManager | null = null;
+  private updater: AutoUpdater | null = null;
+  private metrics: AgentMetrics;
+  private challenges: HttpChallengeService;
+  private pinImporter: RemotePinImporter;
+  private pinMetadata: PinMetadataStore;
+  private pinReconciler: PinReconciler;
+  private pinJobs: PinJobQueue;
+  private pinRequests: PinRequestStore;
+  private contractSync: ContractSync;
+  private pinQuota: PinQuota = new PinQuota();
+  private scrubber: IntegrityScrubber;
+  private selfChallenger: SelfChallenger;
+  private gcScheduler: GcScheduler;
+  private sealStore: SealStore;
+  private webhooks: WebhookNotifier;
+  private eventHistory: EventHistory;
+  private priceFeed: PriceFeed;
+  private chainWatcher: ChainWatcher;
+  private reconciler: EarningsReconciler;
+  private dailySummary: DailySummary;
+  private storageMonitor: StorageMonitor;
 
   // P2P modules
   private peerDiscovery: PeerDiscovery | null = null;
@@ -192,15 +277,58 @@ export class ApiServer {
   private static readonly MAX_VERIFY_ATTEMPTS = 5;
   private static readonly VERIFY_WINDOW_MS = 60000;
   private static readonly CHALLENGE_TTL_MS = 60000;
+  private static readonly MAX_BATCH_CHALLENGES = 500;
 
-  // Local auth token — generated at startup, required for mutation endpoints
-  private localAuthToken: string = crypto.randomBytes(32).toString('hex');
+  // Repo size / pin count for /metrics, refreshed at most once per TTL so a
+  // 15s scrape interval doesn't mean a repo stat + pin ls every 15s
+  private repoGaugeCache: { repoSizeBytes: number; pinnedFiles: number; at: number } | null = null;
+  private static readonly REPO_GAUGE_TTL_MS = 60000;
+
+  // Local auth token — required for mutation endpoints. Generated on first run and
+  // kept in ~/.spk-ipfs/api-token (owner-only) so the web app can be given it once.
+  private localAuthToken: string = ApiServer.loadOrCreateAuthToken();
+  // Scoped tokens (dashboard, validator) — see api-tokens.ts
+  private apiTokens = new ApiTokenStore();
+  private pairing = new PairingManager();
 
   constructor(kubo: KuboManager, config: ConfigStore, wallet?: WalletManager) {
     this.kubo = kubo;
     this.config = config;
     this.wallet = wallet || new WalletManager();
     this.port = config.getConfig().apiPort;
+    this.metrics = new AgentMetrics(config.getConfig().metricsTopCids);
+    this.challenges = new HttpChallengeService(kubo, config, this.metrics);
+    this.challenges.setProofSigner((digest) => this.wallet.signDigestWithPostingKey(digest));
+    // Read-only chain access for requireBlockSalts — no key needed
+    this.challenges.setSaltVerifier(new BlockSaltVerifier(new AgentHiveClient({
+      username: config.getConfig().hiveUsername || '',
+      nodes: config.getConfig().hiveApiNodes,
+    })));
+    this.pinMetadata = new PinMetadataStore();
+    this.pinJobs = new PinJobQueue(kubo, this.pinMetadata);
+    this.pinImporter = new RemotePinImporter(kubo, this.pinJobs);
+    this.pinRequests = new PinRequestStore(kubo, this.pinJobs);
+    this.storageMonitor = new StorageMonitor(kubo, config);
+    this.pinReconciler = new PinReconciler(kubo, config, this.pinJobs, this.storageMonitor);
+    this.contractSync = new ContractSync(kubo, config, this.pinJobs, this.storageMonitor);
+    this.scrubber = new IntegrityScrubber(kubo, config);
+    this.selfChallenger = new SelfChallenger(kubo, config);
+    this.gcScheduler = new GcScheduler(kubo, config);
+    this.sealStore = new SealStore(kubo, config);
+    this.webhooks = new WebhookNotifier(kubo, config);
+    this.priceFeed = new PriceFeed(config);
+    this.reconciler = new EarningsReconciler(config);
+    this.chainWatcher = new ChainWatcher(config, {
+      onChallenge: async (request) => {
+        agentEvents.publish('challenge_received', { source: 'chain', cid: request.cid });
+        this.publishChallenge(request, await this.challenges.run(request));
+      },
+      onContract: (assignment) => this.acceptChainContract(assignment),
+    });
+    // Recording starts now rather than in start(), so daemon_ready is kept too
+    this.eventHistory = new EventHistory();
+    this.eventHistory.start();
+    this.dailySummary = new DailySummary(kubo, config, this.pinMetadata, this.eventHistory);
     this.app = express();
     this.setupMiddleware();
     this.setupRoutes();
@@ -211,33 +339,265 @@ export class ApiServer {
     return this.localAuthToken;
   }
 
-  /** Middleware: require local auth token for mutation endpoints */
+  /** A ?from=/?to= value: epoch ms or ISO 8601, `fallback` when absent, null when unparseable. */
+  private static parseTimeQuery(value: any, fallback: number): number | null {
+    if (value === undefined || value === '') return fallback;
+    const n = /^\d+$/.test(String(value)) ? Number(value) : Date.parse(String(value));
+    return Number.isFinite(n) ? n : null;
+  }
+
+  /**
+   * One CSV field, quoted when it holds a comma, quote or newline. Null is empty;
+   * text starting with = + - @ gets a leading ' so spreadsheets don't run it as a formula.
+   */
+  private static csvField(value: unknown): string {
+    if (value === null || value === undefined) return '';
+    const text = typeof value === 'string' && /^[=+\-@]/.test(value) ? `'${value}` : String(value);
+    return /[",\r\n]/.test(text) ? `"${text.replace(/"/g, '""')}"` : text;
+  }
+
+  private static loadOrCreateAuthToken(): string {
+    const tokenPath = path.join(os.homedir(), '.spk-ipfs', 'api-token');
+    try {
+      const existing = fs.readFileSync(tokenPath, 'utf-8').trim();
+      if (/^[0-9a-f]{64}$/.test(existing)) return existing;
+    } catch {}
+    const token = crypto.randomBytes(32).toString('hex');
+    try {
+      fs.mkdirSync(path.dirname(tokenPath), { recursive: true });
+      fs.writeFileSync(tokenPath, token + '\n', { mode: 0o600 });
+      console.log(`[API] Generated API token at ${tokenPath}`);
+    } catch (error) {
+      console.error('[API] Failed to persist API token — using a per-run token:', error);
+    }
+    return token;
+  }
+
+  /** Background scrubber — listen for 'pin-damage' to surface notifications. */
+  getScrubber(): IntegrityScrubber {
+    return this.scrubber;
+  }
+
+  /** Web app pairing — listen for 'pairing-code' to show the code to the user. */
+  getPairing(): PairingManager {
+    return this.pairing;
+  }
+
+  /** Self-challenge scheduler — listen for 'self-challenge-failed' to surface notifications. */
+  getSelfChallenger(): SelfChallenger {
+    return this.selfChallenger;
+  }
+
+  getDailySummary(): DailySummary {
+    return this.dailySummary;
+  }
+
+  getStorageMonitor(): StorageMonitor {
+    return this.storageMonitor;
+  }
+
+  /**
+   * Middleware: require the full-access bearer token from ~/.spk-ipfs/api-token.
+   * Used for the wallet, key and token-management endpoints.
+   */
   private requireLocalAuth = (req: Request, res: Response, next: express.NextFunction): void => {
-    const auth = req.headers.authorization;
-    if (!auth || auth !== `Bearer ${this.localAuthToken}`) {
+    const token = ApiServer.bearerToken(req);
+    if (!token || !this.isFullAccessToken(token)) {
       res.status(401).json({ error: 'Unauthorized — local auth token required' });
       return;
     }
     next();
   };
 
+  /** Middleware: require a full-access token or a scoped token carrying `scope`. */
+  private requireScope(scope: ApiScope): express.RequestHandler {
+    return (req, res, next) => {
+      const token = ApiServer.bearerToken(req);
+      if (!token) {
+        res.status(401).json({ error: 'Unauthorized — auth token required' });
+        return;
+      }
+      if (this.isFullAccessToken(token)) return next();
+      const scoped = this.apiTokens.verify(token, req.headers.origin);
+      if (!scoped) {
+        res.status(401).json({ error: 'Unauthorized — unknown or revoked token' });
+        return;
+      }
+      if (!scoped.scopes.includes(scope)) {
+        res.status(403).json({ error: `Forbidden — token lacks the "${scope}" scope`, scopes: scoped.scopes });
+        return;
+      }
+      next();
+    };
+  }
+
+  private isFullAccessToken(token: string): boolean {
+    return ApiServer.tokensEqual(token, this.localAuthToken);
+  }
+
+  /** Same check as requireScope, for callers outside Express (the /api/ws upgrade). */
+  private tokenAllows(token: string | null, scope: ApiScope, origin: string | undefined): boolean {
+    if (!token) return false;
+    return this.isFullAccessToken(token) || !!this.apiTokens.verify(token, origin)?.scopes.includes(scope);
+  }
+
+  private static bearerToken(req: http.IncomingMessage): string | null {
+    const auth = req.headers.authorization;
+    return auth?.startsWith('Bearer ') ? auth.slice(7) : null;
+  }
+
+  private static send(res: Response, result: ApiResult): void {
+    res.status(result.status).json(result.body);
+  }
+
+  private static tokensEqual(a: string, b: string): boolean {
+    const bufA = Buffer.from(a);
+    const bufB = Buffer.from(b);
+    return bufA.length === bufB.length && crypto.timingSafeEqual(bufA, bufB);
+  }
+
+  /**
+   * Middleware: per-caller quota on pin operations (storage safety valve).
+   * Runs after requireScope, so callers are told apart by the token they
+   * authenticated with — a header the caller picks would let it mint fresh
+   * quotas. Requests that end in an error (bad input, no space) are refunded.
+   */
+  private enforcePinQuota = (req: Request, res: Response, next: express.NextFunction): void => {
+    const { pinQuotaPerWindow, pinQuotaWindowMin } = this.config.getConfig();
+    const identity = this.quotaIdentity(req);
+    const exceeded = this.pinQuota.consume(identity, pinQuotaPerWindow, pinQuotaWindowMin * 60000);
+    if (exceeded) {
+      console.warn(`[API] Pin quota exceeded for ${identity} (${exceeded.used}/${exceeded.limit} per ${pinQuotaWindowMin} min)`);
+      res.status(429).json({
+        error: 'QUOTA_EXCEEDED',
+        message: `Pin quota of ${exceeded.limit} operations per ${pinQuotaWindowMin} min reached`,
+        ...exceeded,
+      });
+      return;
+    }
+    res.on('finish', () => {
+      if (res.statusCode >= 400) this.pinQuota.refund(identity);
+    });
+    next();
+  };
+
+  private quotaIdentity(req: Request): string {
+    const token = ApiServer.bearerToken(req);
+    if (token && this.isFullAccessToken(token)) return 'api-token';
+    const scoped = token ? this.apiTokens.verify(token, req.headers.origin) : null;
+    return scoped ? `token:${scoped.id}` : `ip:${req.ip || 'unknown'}`;
+  }
+
+  /**
+   * Whether pinning `cid` fits under storageMaxGB, counting pins still
+   * fetching. Shared by POST /api/pin and the pinning service (/pins).
+   */
+  private async projectPin(cid: string): Promise<{
+    fits: boolean; storage: Awaited<ReturnType<KuboManager['getStorageInfo']>>; sizeBytes: number | null; pendingBytes: number;
+    diskError: string | null;
+  }> {
+    const storage = await this.kubo.getStorageInfo();
+    const sizeBytes = await this.kubo.getObjectSize(cid);
+    const pendingBytes = this.pinJobs.pendingBytes();
+    const projectedBytes = storage.usedBytes + pendingBytes + (sizeBytes || 0);
+    return {
+      fits: projectedBytes <= storage.maxBytes && storage.usedBytes < storage.maxBytes,
+      storage,
+      sizeBytes,
+      pendingBytes,
+      diskError: this.storageMonitor.checkPin(sizeBytes || 0, pendingBytes),
+    };
+  }
+
+  /** Everything GET /api/status reports (also embedded in GET /api/snapshot and the renderer's status command). */
+  async buildStatus(): Promise<Record<string, any>> {
+    const peerId = await this.kubo.getPeerId();
+    const stats = await this.kubo.getStats();
+    const storageInfo = await this.kubo.getStorageInfo();
+    const configData = this.config.getConfig();
+    const earnings = this.config.getEarnings();
+
+    return {
+      running: this.kubo.isRunning(),
+      kubo: this.kubo.getMode(),
+      // Crash restarts — a climbing restartCount means the daemon is flapping
+      kuboWatchdog: this.kubo.getWatchdogState(),
+      peerId,
+      nodeId: resolveNodeId(configData, peerId),
+      stats,
+      // headroomBytes = storageMaxGB minus repo size; pendingPinBytes is already spoken for
+      storageInfo: { ...storageInfo, pendingPinBytes: this.pinJobs.pendingBytes() },
+      config: {
+        hiveUsername: configData.hiveUsername,
+        autoStart: configData.autoStart,
+        bandwidthLimitUp: configData.bandwidthLimitUp,
+        bandwidthLimitDown: configData.bandwidthLimitDown,
+        storageMaxGB: configData.storageMaxGB,
+        serverUrl: configData.serverUrl,
+        p2pMode: configData.p2pMode,
+        validatorEnabled: configData.validatorEnabled,
+        challengeIntervalMs: configData.challengeIntervalMs,
+      },
+      // P2P network status
+      network: {
+        p2pMode: configData.p2pMode,
+        peerCount: this.peerDiscovery?.getPeerCount() || 0,
+        validatorEnabled: configData.validatorEnabled,
+        validationStats: this.validator?.getStats() || { issued: 0, passed: 0, failed: 0, timeouts: 0 },
+        challengesInFlight: this.challengeHandler?.getActiveChallengeCount() || 0,
+        challengesAbandoned: this.challengeHandler?.getAbandonedCount() || 0,
+        hasPostingKey: this.wallet.hasPostingKey() || this.config.hasPostingKey(),
+      },
+      // Integrity scrubber summary (per-pin detail at GET /api/scrub)
+      scrub: (({ pins, ...summary }) => ({
+        ...summary,
+        damagedPins: Object.values(pins).filter((p) => p.damaged.length > 0).length,
+      }))(this.scrubber.getStatus()),
+      // Pin quota usage per caller (only callers with operations in the current window)
+      pinQuota: {
+        limit: configData.pinQuotaPerWindow,
+        windowMin: configData.pinQuotaWindowMin,
+        usage: this.pinQuota.getUsage(configData.pinQuotaPerWindow, configData.pinQuotaWindowMin * 60000),
+      },
+      // Legacy server connection (for backward compatibility)
+      serverConnection: this.agentWS?.getConnectionStatus() || { connected: false, reconnectAttempts: 0 },
+      // Coordinator heartbeat backoff (GPU contribution)
+      coordinatorHeartbeat: this.gpuManager?.getHeartbeatState() || null,
+      earnings,
+      // Treasury signer status
+      treasury: {
+        signerEnabled: configData.treasurySignerEnabled,
+        hasActiveKey: this.wallet.hasActiveKey() || this.config.hasActiveKey(),
+        walletInitialized: this.wallet.isInitialized(),
+      },
+      // Deferred background work after an autostart launch
+      bootCooldown: bootCooldown.getState(),
+      version: electronApp?.getVersion() || process.env.SPK_VERSION || '1.2.0',
+    };
+  }
+
+  /** Config as exposed over HTTP — HMAC secrets are replaced by their key ids, webhook URLs by their origins. */
+  private publicConfig(): Omit<AgentConfig, 'challengeHmacKeys' | 'webhooks'> & {
+    challengeHmacKeyIds: string[];
+    webhookTargets: WebhookConfig[];
+  } {
+    const { challengeHmacKeys, webhooks, ...rest } = this.config.getConfig();
+    return {
+      ...rest,
+      challengeHmacKeyIds: Object.keys(challengeHmacKeys),
+      webhookTargets: webhooks.map((h) => ({ url: redactWebhookUrl(h.url), events: h.events })),
+    };
+  }
+
   private setupMiddleware(): void {
     this.app.use(express.json());
 
-    // CORS — allow localhost origins and GitHub Pages only
-    // SECURITY: reject 'null' origin (sent by file:// and sandboxed iframes — CSRF vector)
-    const allowedOrigins = [
-      'http://localhost:3000',
-      'http://localhost:5000',
-      'http://localhost:8080',
-      'http://127.0.0.1:3000',
-      'http://127.0.0.1:5000',
-      'http://127.0.0.1:8080',
-      'https://dhenz14.github.io',
-    ];
+    // CORS — allow localhost origins, GitHub Pages and corsAllowedOrigins only
     this.app.use((req, res, next) => {
       const origin = req.headers.origin;
-      if (origin && allowedOrigins.includes(origin)) {
+      // Any real origin may try to pair — the one-time code is what guards it
+      const pairingRoute = req.path === '/api/pair' || req.path === '/api/pair/request';
+      if (origin && (this.isAllowedOrigin(origin) || (pairingRoute && origin !== 'null'))) {
         // Known origin — allow with credentials
         res.header('Access-Control-Allow-Origin', origin);
         res.header('Access-Control-Allow-Credentials', 'true');
@@ -251,9 +611,37 @@ export class ApiServer {
       }
       next();
     });
+
+    // With authRequiredForReads, GETs need the read scope (earnings reads need earnings),
+    // /metrics included. /api/health stays open so the web app can still detect the agent.
+    const requireRead = this.requireScope('read');
+    const requireEarnings = this.requireScope('earnings');
+    this.app.use((req, res, next) => {
+      if (req.method !== 'GET' || !this.config.getConfig().authRequiredForReads) return next();
+      if (!(req.path.startsWith('/api/') || req.path === '/metrics') || req.path === '/api/health') return next();
+      return req.path.startsWith('/api/earnings') ? requireEarnings(req, res, next) : requireRead(req, res, next);
+    });
+  }
+
+  /**
+   * SECURITY: 'null' (file:// and sandboxed iframes — CSRF vector) is never allowed.
+   * corsAllowedOrigins is re-read per call so changes apply without a restart.
+   * Origins with a paired session are allowed until the session is revoked.
+   */
+  private isAllowedOrigin(origin: string): boolean {
+    return BUILTIN_ORIGINS.includes(origin) || this.config.getConfig().corsAllowedOrigins.includes(origin) ||
+      this.apiTokens.hasOrigin(origin);
   }
 
   private setupRoutes(): void {
+    // IPFS Pinning Service API (spec paths at /pins) — see pinning-service.ts
+    this.app.use('/pins', this.requireScope('pin'),
+      (req, res, next) => (req.method === 'POST' ? this.enforcePinQuota(req, res, next) : next()),
+      createPinningServiceRoutes(this.pinRequests, async (cid) => {
+        const { fits, storage, diskError } = await this.projectPin(cid);
+        return fits ? diskError : `Pinning ${cid} would exceed the storage limit of ${storage.maxFormatted}`;
+      }));
+
     // Lightweight health check — used by static site to detect desktop agent
     // Must respond instantly (no async I/O) so the 2s probe timeout never fires
     this.app.get('/api/health', (_req: Request, res: Response) => {
@@ -261,163 +649,325 @@ export class ApiServer {
     });
 
     // Full status (heavy — calls Kubo APIs, may be slow)
-    this.app.get('/api/status', async (req: Request, res: Response) => {
+    this.app.get('/api/status', async (_req: Request, res: Response) => {
+      res.json(await this.buildStatus());
+    });
+
+    // Live deltas (SSE) so dashboards can load /api/status once and then listen.
+    // Same events as the /api/ws WebSocket (see AgentEventType). Without
+    // `Accept: text/event-stream` this returns the stored history instead:
+    // ?since (ISO time or ms epoch), ?type (comma-separated), ?limit (1-5000).
+    this.app.get('/api/events', (req: Request, res: Response) => {
+      if (!req.get('accept')?.includes('text/event-stream')) {
+        const { since, type, limit } = req.query;
+        let sinceMs: number | undefined;
+        if (since !== undefined) {
+          sinceMs = /^\d+$/.test(String(since)) ? Number(since) : Date.parse(String(since));
+          if (!Number.isFinite(sinceMs)) {
+            return res.status(400).json({ error: 'since must be an ISO timestamp or ms since epoch' });
+          }
+        }
+        const types = type !== undefined ? String(type).split(',').filter(Boolean) as AgentEventType[] : undefined;
+        const max = limit !== undefined ? Number(limit) : 200;
+        if (!Number.isInteger(max) || max < 1 || max > 5000) {
+          return res.status(400).json({ error: 'limit must be 1-5000' });
+        }
+        return res.json({ events: this.eventHistory.query({ since: sinceMs, types, limit: max }) });
+      }
+
+      res.status(200);
+      res.setHeader('Content-Type', 'text/event-stream');
+      res.setHeader('Cache-Control', 'no-cache');
+      res.setHeader('Connection', 'keep-alive');
+      res.flushHeaders();
+      res.write(': connected\n\n');
+
+      const close = () => {
+        unsubscribe();
+        clearInterval(keepalive);
+        if (!res.writableEnded) res.end();
+      };
+      const unsubscribe = agentEvents.subscribe((event) => {
+        // Never let a stalled client back up the producers — drop it instead
+        if (res.writableLength > MAX_BUFFERED_BYTES) {
+          console.warn('[API] Dropping slow /api/events client');
+          close();
+          return;
+        }
+        res.write(`id: ${event.id}\nevent: ${event.type}\ndata: ${JSON.stringify({ at: event.at, ...event.data })}\n\n`);
+      });
+      const keepalive = setInterval(() => res.write(': keepalive\n\n'), 30000);
+      req.on('close', close);
+    });
+
+    // Coordinator-facing identity (stable nodeId, independent of the IPFS peer ID)
+    this.app.get('/api/identity', async (_req: Request, res: Response) => {
       const peerId = await this.kubo.getPeerId();
-      const stats = await this.kubo.getStats();
-      const storageInfo = await this.kubo.getStorageInfo();
-      const configData = this.config.getConfig();
-      const earnings = this.config.getEarnings();
+      const cfg = this.config.getConfig();
+      res.json({
+        nodeId: resolveNodeId(cfg, peerId),
+        nodeIdSource: cfg.nodeId ? 'config' : 'peerId',
+        poolPrefix: cfg.poolPrefix,
+        peerId,
+        hiveUsername: cfg.hiveUsername,
+        signingPublicKey: this.wallet.getPostingPublicKey() || this.config.getPostingPublicKey(),
+        sealCommitment: this.sealStore.getCommitment(),
+      });
+    });
 
+    // Liveness for coordinators — identity plus whether the node can take work right now
+    this.app.get('/api/heartbeat', async (_req: Request, res: Response) => {
+      const peerId = await this.kubo.getPeerId();
+      const cfg = this.config.getConfig();
       res.json({
-        running: this.kubo.isRunning(),
+        nodeId: resolveNodeId(cfg, peerId),
         peerId,
-        stats,
-        storageInfo,
-        config: {
-          hiveUsername: configData.hiveUsername,
-          autoStart: configData.autoStart,
-          bandwidthLimitUp: configData.bandwidthLimitUp,
-          bandwidthLimitDown: configData.bandwidthLimitDown,
-          storageMaxGB: configData.storageMaxGB,
-          serverUrl: configData.serverUrl,
-          p2pMode: configData.p2pMode,
-          validatorEnabled: configData.validatorEnabled,
-          challengeIntervalMs: configData.challengeIntervalMs,
-        },
-        // P2P network status
-        network: {
-          p2pMode: configData.p2pMode,
-          peerCount: this.peerDiscovery?.getPeerCount() || 0,
-          validatorEnabled: configData.validatorEnabled,
-          validationStats: this.validator?.getStats() || { issued: 0, passed: 0, failed: 0, timeouts: 0 },
-          hasPostingKey: this.wallet.hasPostingKey() || this.config.hasPostingKey(),
-        },
-        // Legacy server connection (for backward compatibility)
-        serverConnection: this.agentWS?.getConnectionStatus() || { connected: false, reconnectAttempts: 0 },
-        earnings,
-        // Treasury signer status
-        treasury: {
-          signerEnabled: configData.treasurySignerEnabled,
-          hasActiveKey: this.wallet.hasActiveKey() || this.config.hasActiveKey(),
-          walletInitialized: this.wallet.isInitialized(),
-        },
-        version: electronApp?.getVersion() || process.env.SPK_VERSION || '1.2.0',
+        hiveUsername: cfg.hiveUsername,
+        ipfsRunning: this.kubo.isRunning(),
+        acceptingChallenges: !this.challengeHandler || this.challengeHandler.hasCapacity(),
+        uptimeSeconds: Math.floor(process.uptime()),
+        timestamp: new Date().toISOString(),
       });
     });
 
-    // Get/Set configuration
-    this.app.get('/api/config', (req: Request, res: Response) => {
-      res.json(this.config.getConfig());
+    // Prometheus scrape endpoint (text exposition format)
+    // Bandwidth served/received and swarm peer count (cached a few seconds)
+    this.app.get('/api/bandwidth', async (_req: Request, res: Response) => {
+      try {
+        res.json(await this.kubo.getBandwidth());
+      } catch (error: any) {
+        res.status(500).json({ error: error.message });
+      }
     });
 
-    this.app.post('/api/config', this.requireLocalAuth, async (req: Request, res: Response) => {
-      const {
-        hiveUsername, autoStart, bandwidthLimitUp, bandwidthLimitDown,
-        storageMaxGB, serverUrl, p2pMode, validatorEnabled, challengeIntervalMs,
-      } = req.body;
+    this.app.get('/metrics', async (_req: Request, res: Response) => {
+      res.type('text/plain; version=0.0.4').send(this.metrics.render(await this.nodeGauges()));
+    });
 
-      // Input validation for numeric fields
-      if (bandwidthLimitUp !== undefined) {
-        const val = Number(bandwidthLimitUp);
-        if (!Number.isFinite(val) || val < 0 || val > 1000000) {
-          return res.status(400).json({ error: 'bandwidthLimitUp must be 0-1000000 KB/s' });
-        }
+    // Preflight — read-only check that this machine can run a node
+    this.app.get('/api/preflight', async (_req: Request, res: Response) => {
+      try {
+        res.json(await runPreflight(this.kubo, this.config.getConfig()));
+      } catch (error: any) {
+        res.status(500).json({ error: error.message });
       }
-      if (bandwidthLimitDown !== undefined) {
-        const val = Number(bandwidthLimitDown);
-        if (!Number.isFinite(val) || val < 0 || val > 1000000) {
-          return res.status(400).json({ error: 'bandwidthLimitDown must be 0-1000000 KB/s' });
+    });
+
+    // Point-in-time bundle for bug reports: summaries only, secrets redacted
+    this.app.get('/api/snapshot', async (_req: Request, res: Response) => {
+      try {
+        const status = await this.buildStatus();
+        let pinCount: number | null = null;
+        if (this.kubo.isRunning()) {
+          try {
+            pinCount = (await this.kubo.listPins(10000)).length;
+          } catch {
+            // leave null — the daemon's state is in status.running
+          }
         }
+        const scrub = this.scrubber.getStatus();
+        const scrubbedPins = Object.values(scrub.pins);
+        const reconcileReports = this.pinReconciler.getReports();
+
+        res.json({
+          takenAt: new Date().toISOString(),
+          build: {
+            version: status.version,
+            platform: `${process.platform}-${process.arch}`,
+            node: process.versions.node,
+            electron: process.versions.electron || null,
+            kubo: await this.kubo.getBinaryVersion(),
+            kuboMode: this.kubo.getMode(),
+            uptimeSeconds: Math.floor(process.uptime()),
+          },
+          status,
+          config: this.publicConfig(),
+          earnings: {
+            ...this.config.getEarnings(),
+            milestones: this.config.getEarningsEvents().slice(-SNAPSHOT_HISTORY_LIMIT),
+            quarantined: this.config.getQuarantinedEarnings().length,
+          },
+          recentChallenges: this.metrics.getRecentChallenges(SNAPSHOT_HISTORY_LIMIT),
+          pins: {
+            pinned: pinCount,
+            withMetadata: Object.keys(this.pinMetadata.getAll()).length,
+            scrubbed: scrubbedPins.filter((p) => p.lastScrubAt).length,
+            damaged: scrubbedPins.filter((p) => p.damaged.length > 0).length,
+          },
+          diagnostics: {
+            kuboProcesses: this.kubo.listProcesses(),
+            pinImports: this.pinImporter.listJobs().slice(-5),
+            lastReconcile: reconcileReports[reconcileReports.length - 1] || null,
+            scrub: { ...scrub, pins: undefined },
+          },
+        });
+      } catch (error: any) {
+        res.status(500).json({ error: error.message });
       }
-      if (storageMaxGB !== undefined) {
-        const val = Number(storageMaxGB);
-        if (!Number.isFinite(val) || val < 0 || val > 10000) {
-          return res.status(400).json({ error: 'storageMaxGB must be 0-10000' });
-        }
+    });
+
+    // Coordinator-shared HMAC secrets for keyed challenge proofs (secrets are never returned)
+    this.app.get('/api/challenge/hmac-keys', this.requireScope('config'), (_req: Request, res: Response) => {
+      res.json({ keyIds: Object.keys(this.config.getConfig().challengeHmacKeys) });
+    });
+
+    this.app.post('/api/challenge/hmac-keys', this.requireScope('config'), (req: Request, res: Response) => {
+      const { keyId, secret } = req.body || {};
+      if (typeof keyId !== 'string' || !NODE_ID_PATTERN.test(keyId)) {
+        return res.status(400).json({ error: "keyId must be 1-64 characters of A-Z, a-z, 0-9, '.', '_' or '-'" });
+      }
+      if (typeof secret !== 'string' || secret.length < 32) {
+        return res.status(400).json({ error: 'secret must be at least 32 characters' });
       }
+      const keys = { ...this.config.getConfig().challengeHmacKeys, [keyId]: secret };
+      this.config.setConfig({ challengeHmacKeys: keys });
+      res.json({ success: true, keyIds: Object.keys(keys) });
+    });
 
-      const updates: Partial<AgentConfig> = {};
-      if (hiveUsername !== undefined) updates.hiveUsername = hiveUsername;
-      if (autoStart !== undefined) updates.autoStart = autoStart;
-      if (bandwidthLimitUp !== undefined) updates.bandwidthLimitUp = Number(bandwidthLimitUp);
-      if (bandwidthLimitDown !== undefined) updates.bandwidthLimitDown = Number(bandwidthLimitDown);
-      if (storageMaxGB !== undefined) updates.storageMaxGB = Number(storageMaxGB);
-      if (serverUrl !== undefined) updates.serverUrl = serverUrl;
-      if (p2pMode !== undefined) updates.p2pMode = p2pMode;
-      if (validatorEnabled !== undefined) updates.validatorEnabled = validatorEnabled;
-      if (challengeIntervalMs !== undefined) updates.challengeIntervalMs = Number(challengeIntervalMs);
+    this.app.delete('/api/challenge/hmac-keys/:keyId', this.requireScope('config'), (req: Request, res: Response) => {
+      const keys = { ...this.config.getConfig().challengeHmacKeys };
+      if (!(req.params.keyId in keys)) {
+        return res.status(404).json({ error: 'Unknown keyId' });
+      }
+      delete keys[req.params.keyId];
+      this.config.setConfig({ challengeHmacKeys: keys });
+      res.json({ success: true, keyIds: Object.keys(keys) });
+    });
 
-      this.config.setConfig(updates);
+    // Scoped API tokens — full-access token only. The token is returned once, on creation.
+    this.app.get('/api/tokens', this.requireLocalAuth, (_req: Request, res: Response) => {
+      res.json({ tokens: this.apiTokens.list(), scopes: API_SCOPES });
+    });
 
-      // Apply IPFS config changes if needed
-      let needsRestart = false;
-      if (bandwidthLimitUp !== undefined || bandwidthLimitDown !== undefined) {
-        const cfg = this.config.getConfig();
-        needsRestart = this.kubo.applyBandwidthConfig(cfg.bandwidthLimitUp, cfg.bandwidthLimitDown);
+    this.app.post('/api/tokens', this.requireLocalAuth, (req: Request, res: Response) => {
+      const { name, scopes } = req.body || {};
+      if (typeof name !== 'string' || !name.trim() || name.length > 64) {
+        return res.status(400).json({ error: 'name must be 1-64 characters' });
       }
-      if (storageMaxGB !== undefined) {
-        needsRestart = this.kubo.applyStorageQuota(storageMaxGB) || needsRestart;
+      if (!Array.isArray(scopes) || scopes.length === 0 || !scopes.every((s: any) => API_SCOPES.includes(s))) {
+        return res.status(400).json({ error: `scopes must be a non-empty list of: ${API_SCOPES.join(', ')}` });
       }
-      if (needsRestart && this.kubo.isRunning()) {
-        // Defer restart if challenges are in-flight
-        const hasActive = this.agentWS?.hasActiveChallenges() ||
-          (this.challengeHandler && !this.challengeHandler.hasCapacity());
-        if (hasActive) {
-          console.log('[API] Config saved, but deferring IPFS restart — challenge in progress');
-          res.json({ success: true, config: this.config.getConfig(), restartDeferred: true });
-          return;
-        }
-        try {
-          await this.kubo.restart();
-        } catch (error: any) {
-          return res.status(500).json({ error: 'Failed to restart IPFS: ' + error.message });
-        }
+      const { token, info } = this.apiTokens.create(name.trim(), scopes);
+      res.status(201).json({ token, ...info });
+    });
+
+    this.app.delete('/api/tokens/:id', this.requireLocalAuth, (req: Request, res: Response) => {
+      if (!this.apiTokens.revoke(req.params.id)) {
+        return res.status(404).json({ error: 'Unknown token id' });
       }
+      res.json({ success: true });
+    });
 
-      // Legacy: Reconnect WebSocket if server URL or username changed
-      if ((serverUrl !== undefined || hiveUsername !== undefined) && this.agentWS) {
-        this.agentWS.reconnectToServer();
+    // Pairing: the web app asks for a code, the user reads it off the agent, the
+    // app trades it for a session token bound to its origin. See pairing.ts.
+    this.app.post('/api/pair/request', (req: Request, res: Response) => {
+      const origin = req.headers.origin;
+      if (!origin || origin === 'null') {
+        return res.status(400).json({ error: 'Pairing must come from a browser origin' });
+      }
+      const { scopes = ['read', 'pin', 'earnings'], name = origin } = req.body || {};
+      if (!Array.isArray(scopes) || scopes.length === 0 || !scopes.every((s: any) => API_SCOPES.includes(s))) {
+        return res.status(400).json({ error: `scopes must be a non-empty list of: ${API_SCOPES.join(', ')}` });
+      }
+      if (typeof name !== 'string' || !name.trim() || name.length > 64) {
+        return res.status(400).json({ error: 'name must be 1-64 characters' });
       }
+      const started = this.pairing.request(origin, scopes, name.trim());
+      if (!started) {
+        return res.status(429).json({ error: 'Pairing was requested moments ago — try again shortly' });
+      }
+      res.status(202).json({ pending: true, ...started });
+    });
 
-      res.json({ success: true, config: this.config.getConfig() });
+    this.app.post('/api/pair', (req: Request, res: Response) => {
+      const origin = req.headers.origin;
+      const { code } = req.body || {};
+      if (!origin || typeof code !== 'string' || !/^\d{6}$/.test(code)) {
+        return res.status(400).json({ error: 'A 6-digit code and a browser origin are required' });
+      }
+      const claim = this.pairing.claim(code, origin);
+      if (!claim.ok) {
+        return res.status(claim.error === 'INVALID_CODE' ? 401 : 409).json({ error: claim.error });
+      }
+      const { name, scopes } = claim.request;
+      const { token, info } = this.apiTokens.create(name, scopes, origin);
+      console.log(`[API] Paired ${origin} (${scopes.join(', ')})`);
+      res.status(201).json({ token, ...info });
     });
 
-    // Pin content
-    this.app.post('/api/pin', this.requireLocalAuth, async (req: Request, res: Response) => {
-      const { cid } = req.body;
-      if (!cid || !isValidCid(cid)) {
-        return res.status(400).json({ error: 'Valid CID required' });
+    // Paired sessions — listed and revoked with the full-access token
+    this.app.get('/api/sessions', this.requireLocalAuth, (_req: Request, res: Response) => {
+      res.json({ sessions: this.apiTokens.list().filter((t) => t.origin) });
+    });
+
+    this.app.delete('/api/sessions/:id', this.requireLocalAuth, (req: Request, res: Response) => {
+      const session = this.apiTokens.list().find((t) => t.id === req.params.id && t.origin);
+      if (!session || !this.apiTokens.revoke(session.id)) {
+        return res.status(404).json({ error: 'Unknown session id' });
       }
+      res.json({ success: true });
+    });
 
+    // Protocol conformance — run the embedded test vectors for every challenge mode.
+    // 200 if all pass, 422 with per-mode results if any mode mismatches.
+    this.app.post('/api/selftest/vectors', this.requireScope('challenge'), async (_req: Request, res: Response) => {
       try {
-        const response = await axios.post(
-          `${this.kubo.getApiUrl()}/api/v0/pin/add?arg=${cid}`,
-          null,
-          { timeout: 300000 }
-        );
-        res.json({ success: true, pins: response.data.Pins });
+        const report = await runTestVectors(this.kubo);
+        res.status(report.passed ? 200 : 422).json(report);
       } catch (error: any) {
         res.status(500).json({ error: error.message });
       }
     });
 
-    // Unpin content
-    this.app.post('/api/unpin', this.requireLocalAuth, async (req: Request, res: Response) => {
-      const { cid } = req.body;
-      if (!cid || !isValidCid(cid)) {
-        return res.status(400).json({ error: 'Valid CID required' });
-      }
+    // ipfs subprocesses spawned by the agent (daemon + one-shot CLI commands)
+    this.app.get('/api/kubo/processes', (_req: Request, res: Response) => {
+      res.json({ processes: this.kubo.listProcesses() });
+    });
 
-      try {
-        await axios.post(`${this.kubo.getApiUrl()}/api/v0/pin/rm?arg=${cid}`);
-        res.json({ success: true });
-      } catch (error: any) {
-        res.status(500).json({ error: error.message });
+    // Kill CLI commands older than olderThanSec (default: kuboProcessTimeoutSec, or 300)
+    this.app.post('/api/kubo/processes/kill-stuck', this.requireScope('config'), (req: Request, res: Response) => {
+      const configured = this.config.getConfig().kuboProcessTimeoutSec;
+      const olderThanSec = req.body?.olderThanSec !== undefined
+        ? Number(req.body.olderThanSec)
+        : configured > 0 ? configured : 300;
+      if (!Number.isFinite(olderThanSec) || olderThanSec < 0) {
+        return res.status(400).json({ error: 'olderThanSec must be a non-negative number' });
       }
+      const killed = this.kubo.killStuckProcesses(olderThanSec * 1000);
+      res.json({ success: true, killed });
+    });
+
+    // Get/Set configuration
+    this.app.get('/api/config', (req: Request, res: Response) => {
+      res.json(this.publicConfig());
+    });
+
+    this.app.post('/api/config', this.requireScope('config'), async (req: Request, res: Response) => {
+      ApiServer.send(res, await this.applyConfig(req.body));
+    });
+
+    // Pin content
+    this.app.post('/api/pin', this.requireScope('pin'), this.enforcePinQuota, async (req: Request, res: Response) => {
+      ApiServer.send(res, await this.queuePin(req.body));
+    });
+
+    this.app.get('/api/jobs', (_req: Request, res: Response) => {
+      res.json({ jobs: this.pinJobs.listJobs() });
+    });
+
+    this.app.get('/api/jobs/:id', (req: Request, res: Response) => {
+      const job = this.pinJobs.getJob(req.params.id);
+      if (!job) return res.status(404).json({ error: 'Job not found' });
+      res.json({ job });
+    });
+
+    // Unpin content
+    this.app.post('/api/unpin', this.requireScope('pin'), async (req: Request, res: Response) => {
+      ApiServer.send(res, await this.unpin(req.body));
     });
 
     // Upload file directly to IPFS (add + pin in one step)
-    this.app.post('/api/upload', this.requireLocalAuth, express.raw({ type: '*/*', limit: '500mb' }), async (req: Request, res: Response) => {
+    this.app.post('/api/upload', this.requireScope('pin'), this.enforcePinQuota, express.raw({ type: '*/*', limit: '500mb' }), async (req: Request, res: Response) => {
       const fileBuffer = req.body as Buffer;
       if (!fileBuffer || fileBuffer.length === 0) {
         return res.status(400).json({ error: 'No file data provided' });
@@ -447,84 +997,780 @@ export class ApiServer {
           }
         );
 
-        const cid = response.data.Hash;
-        console.log(`[API] Uploaded file "${fileName}" → ${cid}`);
-        res.json({ success: true, cid, name: fileName, size: fileBuffer.length });
+        const cid = response.data.Hash;
+        console.log(`[API] Uploaded file "${fileName}" → ${cid}`);
+        agentEvents.publish('pin_added', { cid, name: fileName, source: 'upload' });
+        res.json({ success: true, cid, name: fileName, size: fileBuffer.length });
+      } catch (error: any) {
+        console.error('[API] Upload failed:', error.message);
+        res.status(500).json({ error: error.message });
+      }
+    });
+
+    // Streamed add — multipart/form-data is piped to Kubo as-is (any other body
+    // is sent as a single file named by X-File-Name), never buffered in memory.
+    // ?chunker=size-N|rabin[-min-avg-max]|buzhash &cidVersion=0|1 &rawLeaves &pin (default true) &name
+    this.app.post('/api/add', this.requireScope('pin'), this.enforcePinQuota, async (req: Request, res: Response) => {
+      const q = req.query as Record<string, string | undefined>;
+      const parseBool = (v: string | undefined): boolean | undefined | null =>
+        v === undefined ? undefined : v === 'true' || v === '1' ? true : v === 'false' || v === '0' ? false : null;
+      const rawLeaves = parseBool(q.rawLeaves);
+      const pin = parseBool(q.pin);
+      if (q.chunker !== undefined && !/^(size-\d{1,7}|rabin(-\d{1,7}-\d{1,7}-\d{1,7})?|buzhash)$/.test(q.chunker)) {
+        return res.status(400).json({ error: 'chunker must be size-<bytes>, rabin[-<min>-<avg>-<max>] or buzhash' });
+      }
+      if (q.cidVersion !== undefined && q.cidVersion !== '0' && q.cidVersion !== '1') {
+        return res.status(400).json({ error: 'cidVersion must be 0 or 1' });
+      }
+      if (rawLeaves === null || pin === null) {
+        return res.status(400).json({ error: 'rawLeaves and pin must be true or false' });
+      }
+      if (q.name !== undefined && q.name.length > 256) {
+        return res.status(400).json({ error: 'name must be at most 256 characters' });
+      }
+
+      try {
+        // Content-Length (when sent) bounds what the upload adds to the repo
+        const sizeBytes = Number(req.headers['content-length']) || 0;
+        const storage = await this.kubo.getStorageInfo();
+        const pendingBytes = this.pinJobs.pendingBytes();
+        if (storage.usedBytes >= storage.maxBytes || storage.usedBytes + pendingBytes + sizeBytes > storage.maxBytes) {
+          return res.status(507).json({
+            error: 'INSUFFICIENT_STORAGE',
+            message: `Adding this upload would exceed the storage limit of ${storage.maxFormatted}`,
+            usedBytes: storage.usedBytes,
+            pendingBytes,
+            maxBytes: storage.maxBytes,
+            sizeBytes,
+          });
+        }
+        const diskError = this.storageMonitor.checkPin(sizeBytes, pendingBytes);
+        if (diskError) {
+          return res.status(507).json({ error: 'INSUFFICIENT_DISK_SPACE', message: diskError, pendingBytes, sizeBytes });
+        }
+
+        let contentType = req.headers['content-type'] || '';
+        let body: Readable = req;
+        if (!/^multipart\/form-data;\s*boundary=/i.test(contentType)) {
+          const boundary = '----IPFSAdd' + Date.now();
+          const fileName = String(req.headers['x-file-name'] || 'upload').replace(/["\r\n]/g, '');
+          const upload = req;
+          body = Readable.from((async function* () {
+            yield Buffer.from(
+              `--${boundary}\r\n` +
+              `Content-Disposition: form-data; name="file"; filename="${fileName}"\r\n` +
+              `Content-Type: application/octet-stream\r\n\r\n`
+            );
+            yield* upload;
+            yield Buffer.from(`\r\n--${boundary}--\r\n`);
+          })());
+          contentType = `multipart/form-data; boundary=${boundary}`;
+        }
+
+        const entries = await this.kubo.addStream(body, contentType, {
+          chunker: q.chunker,
+          cidVersion: q.cidVersion === undefined ? undefined : (Number(q.cidVersion) as 0 | 1),
+          rawLeaves,
+          pin,
+        });
+        const root = entries[entries.length - 1];
+        if (!root) return res.status(400).json({ error: 'No file data provided' });
+
+        const pinned = pin ?? true;
+        const name = q.name || root.name;
+        if (pinned) {
+          if (q.name) this.pinMetadata.updateMany({ [root.cid]: { name: q.name } });
+          agentEvents.publish('pin_added', { cid: root.cid, name, source: 'upload' });
+        }
+        console.log(`[API] Added "${name}" → ${root.cid}${pinned ? ' (pinned)' : ''}`);
+        res.json({ success: true, cid: root.cid, name, size: root.size, pinned, entries });
+      } catch (error: any) {
+        const message = error.response?.data?.Message || error.message;
+        console.error('[API] Add failed:', message);
+        res.status(500).json({ error: message });
+      }
+    });
+
+    // Export a pinned DAG as a CAR file for offline backup
+    // { cid, path? } — path must be absolute; default ~/.spk-ipfs/exports/<cid>.car
+    this.app.post('/api/export', this.requireScope('pin'), async (req: Request, res: Response) => {
+      const { cid, path: outPath } = req.body;
+      if (!cid || !isValidCid(cid)) {
+        return res.status(400).json({ error: 'Valid CID required' });
+      }
+      if (outPath !== undefined && (typeof outPath !== 'string' || !path.isAbsolute(outPath))) {
+        return res.status(400).json({ error: 'path must be an absolute file path' });
+      }
+      const filePath = outPath || path.join(os.homedir(), '.spk-ipfs', 'exports', `${cid}.car`);
+
+      try {
+        const { bytes } = await this.kubo.exportCar(cid, filePath);
+        console.log(`[API] Exported ${cid} → ${filePath} (${bytes} bytes)`);
+        res.json({ success: true, cid, path: filePath, bytes });
+      } catch (error: any) {
+        const message = error.response?.data?.Message || error.message;
+        console.error(`[API] CAR export of ${cid} failed:`, message);
+        res.status(500).json({ error: message });
+      }
+    });
+
+    // Import a CAR file from disk and pin its roots (seed a new agent without re-fetching)
+    // { path, name? } — name is recorded as pin metadata for every imported root
+    this.app.post('/api/import', this.requireScope('pin'), this.enforcePinQuota, async (req: Request, res: Response) => {
+      const { path: carPath, name } = req.body;
+      if (typeof carPath !== 'string' || !path.isAbsolute(carPath)) {
+        return res.status(400).json({ error: 'path must be an absolute file path' });
+      }
+      if (name !== undefined && (typeof name !== 'string' || name.length > 256)) {
+        return res.status(400).json({ error: 'name must be a string of at most 256 characters' });
+      }
+      let sizeBytes: number;
+      try {
+        const stat = fs.statSync(carPath);
+        if (!stat.isFile()) return res.status(400).json({ error: `${carPath} is not a file` });
+        sizeBytes = stat.size;
+      } catch {
+        return res.status(404).json({ error: `${carPath} not found` });
+      }
+
+      try {
+        // A CAR's size is an upper bound on what it adds to the repo
+        const storage = await this.kubo.getStorageInfo();
+        const pendingBytes = this.pinJobs.pendingBytes();
+        if (storage.usedBytes + pendingBytes + sizeBytes > storage.maxBytes) {
+          return res.status(507).json({
+            error: 'INSUFFICIENT_STORAGE',
+            message: `Importing ${path.basename(carPath)} would exceed the storage limit of ${storage.maxFormatted}`,
+            usedBytes: storage.usedBytes,
+            pendingBytes,
+            maxBytes: storage.maxBytes,
+            sizeBytes,
+          });
+        }
+        const diskError = this.storageMonitor.checkPin(sizeBytes, pendingBytes);
+        if (diskError) {
+          return res.status(507).json({ error: 'INSUFFICIENT_DISK_SPACE', message: diskError, pendingBytes, sizeBytes });
+        }
+
+        const roots = await this.kubo.importCar(carPath);
+        const pinned = roots.filter(r => !r.error).map(r => r.cid);
+        if (name && pinned.length > 0) {
+          this.pinMetadata.updateMany(Object.fromEntries(pinned.map(cid => [cid, { name }])));
+        }
+        for (const cid of pinned) {
+          agentEvents.publish('pin_added', { cid, name: name || '', source: 'import' });
+        }
+        console.log(`[API] Imported ${carPath}: ${pinned.length}/${roots.length} roots pinned`);
+        res.json({ success: roots.length > 0 && pinned.length === roots.length, roots, bytes: sizeBytes });
+      } catch (error: any) {
+        const message = error.response?.data?.Message || error.message;
+        console.error(`[API] CAR import of ${carPath} failed:`, message);
+        res.status(500).json({ error: message });
+      }
+    });
+
+    // List pinned content, merged with its metadata (name, tags, source, pinnedAt, lastChallengedAt)
+    // ?offset&limit page the list (no limit = everything); ?q filters on CID, name or tag; ?source filters on provenance;
+    // ?sort=cid|name|size&order=asc|desc. Sizes are only stat'ed for the returned
+    // page, except with sort=size, which needs all of them (cached for a few minutes).
+    this.app.get('/api/pins', async (req: Request, res: Response) => {
+      ApiServer.send(res, await this.listPins(req.query));
+    });
+
+    // Browse inside pinned content: UnixFS listing of ?cid (names, sizes, sub-CIDs).
+    // ?depth (1-5, default 1) expands sub-directories; ?limit (default 1000) caps total entries.
+    this.app.get('/api/ls', async (req: Request, res: Response) => {
+      const cid = String(req.query.cid || '');
+      const depth = req.query.depth === undefined ? 1 : Number(req.query.depth);
+      const limit = req.query.limit === undefined ? 1000 : Number(req.query.limit);
+      if (!isValidCid(cid)) {
+        return res.status(400).json({ error: 'Valid CID required' });
+      }
+      if (!Number.isInteger(depth) || depth < 1 || depth > 5) {
+        return res.status(400).json({ error: 'depth must be an integer from 1 to 5' });
+      }
+      if (!Number.isInteger(limit) || limit < 1 || limit > 10000) {
+        return res.status(400).json({ error: 'limit must be an integer from 1 to 10000' });
+      }
+
+      try {
+        const listing = await this.kubo.listUnixfs(cid, depth, limit);
+        res.json({ cid, ...listing });
+      } catch (error: any) {
+        const message = error.response?.data?.Message || error.message;
+        // Offline listing: a block we don't have means the content isn't stored here
+        if (/not found|no link named/i.test(message)) {
+          return res.status(404).json({ error: `${cid} is not available locally: ${message}` });
+        }
+        res.status(500).json({ error: message });
+      }
+    });
+
+    // Pin metadata sidecar (name, tags, priority)
+    this.app.get('/api/pins/metadata', (_req: Request, res: Response) => {
+      res.json({ metadata: this.pinMetadata.getAll() });
+    });
+
+    // Bulk update: { updates: { [cid]: { name?, tags?, priority? } } } — one atomic write
+    this.app.post('/api/pins/metadata/bulk', this.requireScope('pin'), async (req: Request, res: Response) => {
+      const updates = req.body?.updates;
+      if (!updates || typeof updates !== 'object' || Array.isArray(updates)) {
+        return res.status(400).json({ error: 'updates must be an object of CID -> { name?, tags?, priority? }' });
+      }
+
+      const valid: Record<string, PinMetadataUpdate> = {};
+      for (const [cid, entry] of Object.entries<any>(updates)) {
+        if (!isValidCid(cid)) {
+          return res.status(400).json({ error: `Invalid CID: ${cid}` });
+        }
+        if (!entry || typeof entry !== 'object') {
+          return res.status(400).json({ error: `Metadata for ${cid} must be an object` });
+        }
+        const { name, tags, priority } = entry;
+        if (name !== undefined && (typeof name !== 'string' || name.length > 256)) {
+          return res.status(400).json({ error: `name for ${cid} must be a string of at most 256 characters` });
+        }
+        if (tags !== undefined && (!Array.isArray(tags) || !tags.every((t: any) => typeof t === 'string'))) {
+          return res.status(400).json({ error: `tags for ${cid} must be an array of strings` });
+        }
+        if (priority !== undefined && !Number.isInteger(priority)) {
+          return res.status(400).json({ error: `priority for ${cid} must be an integer` });
+        }
+        valid[cid] = {
+          ...(name !== undefined && { name }),
+          ...(tags !== undefined && { tags }),
+          ...(priority !== undefined && { priority }),
+        };
+      }
+
+      try {
+        const pinned = new Set(await this.kubo.listPins());
+        const unknown = Object.keys(valid).filter((cid) => !pinned.has(cid));
+        for (const cid of unknown) delete valid[cid];
+
+        const updated = this.pinMetadata.updateMany(valid);
+        res.json({ success: true, updated: updated.length, unknown });
+      } catch (error: any) {
+        res.status(500).json({ error: error.message });
+      }
+    });
+
+    // Bulk import from a remote pinning service (IPFS Pinning Service API) — runs in background
+    this.app.post('/api/pins/import/remote', this.requireScope('pin'), (req: Request, res: Response) => {
+      const { endpoint, token } = req.body || {};
+      if (typeof endpoint !== 'string' || !/^https?:\/\//.test(endpoint)) {
+        return res.status(400).json({ error: 'endpoint must be an http(s) URL' });
+      }
+      if (typeof token !== 'string' || !token) {
+        return res.status(400).json({ error: 'token required' });
+      }
+      try {
+        const job = this.pinImporter.start(endpoint, token);
+        res.status(202).json({ success: true, job });
+      } catch (error: any) {
+        res.status(409).json({ error: error.message });
+      }
+    });
+
+    this.app.get('/api/pins/import', (_req: Request, res: Response) => {
+      res.json({ jobs: this.pinImporter.listJobs() });
+    });
+
+    this.app.get('/api/pins/import/:id', (req: Request, res: Response) => {
+      const job = this.pinImporter.getJob(req.params.id);
+      if (!job) return res.status(404).json({ error: 'Import not found' });
+      res.json({ job });
+    });
+
+    this.app.post('/api/pins/import/:id/cancel', this.requireScope('pin'), (req: Request, res: Response) => {
+      if (!this.pinImporter.cancel(req.params.id)) {
+        return res.status(404).json({ error: 'No running import with that id' });
+      }
+      res.json({ success: true });
+    });
+
+    // Pin reconciliation against the coordinator's desired set
+    this.app.get('/api/pins/reconcile', (_req: Request, res: Response) => {
+      const cfg = this.config.getConfig();
+      res.json({
+        enabled: cfg.pinReconcileEnabled,
+        unpinExtras: cfg.pinReconcileUnpinExtras,
+        allowlist: cfg.pinReconcileAllowlist,
+        denylist: cfg.pinReconcileDenylist,
+        intervalMin: cfg.pinReconcileIntervalMin,
+        reports: this.pinReconciler.getReports(),
+      });
+    });
+
+    this.app.post('/api/pins/reconcile/run', this.requireScope('pin'), async (_req: Request, res: Response) => {
+      if (!this.kubo.isRunning()) {
+        return res.status(503).json({ error: 'IPFS daemon is not running' });
+      }
+      const report = await this.pinReconciler.reconcile();
+      if (!report) return res.status(409).json({ error: 'A reconciliation pass is already running' });
+      res.json({ success: report.error === null, report });
+    });
+
+    // Storage contract sync — auto-pins CIDs of contracts assigned to hiveUsername
+    this.app.get('/api/contracts/sync', (_req: Request, res: Response) => {
+      const cfg = this.config.getConfig();
+      res.json({
+        enabled: cfg.contractSyncEnabled,
+        unpinExpired: cfg.contractUnpinExpired,
+        intervalMin: cfg.contractSyncIntervalMin,
+        reports: this.contractSync.getReports(),
+      });
+    });
+
+    this.app.post('/api/contracts/sync/run', this.requireScope('pin'), async (_req: Request, res: Response) => {
+      if (!this.kubo.isRunning()) {
+        return res.status(503).json({ error: 'IPFS daemon is not running' });
+      }
+      const report = await this.contractSync.sync();
+      if (!report) return res.status(409).json({ error: 'A contract sync pass is already running' });
+      res.json({ success: report.error === null, report });
+    });
+
+    // Integrity scrubber — progress, last-scrub time and damage per pin
+    this.app.get('/api/scrub', (_req: Request, res: Response) => {
+      res.json(this.scrubber.getStatus());
+    });
+
+    this.app.post('/api/scrub/run', this.requireScope('pin'), async (_req: Request, res: Response) => {
+      if (!this.kubo.isRunning()) {
+        return res.status(503).json({ error: 'IPFS daemon is not running' });
+      }
+      if (!await this.scrubber.scrub()) {
+        return res.status(409).json({ error: 'A scrub pass is already running' });
+      }
+      res.json({ success: true, status: this.scrubber.getStatus() });
+    });
+
+    // Repo garbage collection — schedule status and recent runs; POST runs one now
+    this.app.get('/api/gc', (_req: Request, res: Response) => {
+      res.json(this.gcScheduler.getStatus());
+    });
+
+    this.app.post('/api/gc', this.requireScope('pin'), async (_req: Request, res: Response) => {
+      if (!this.kubo.isRunning()) {
+        return res.status(503).json({ error: 'IPFS daemon is not running' });
+      }
+      const run = await this.gcScheduler.run('manual');
+      if (!run) return res.status(409).json({ error: 'A GC run is already in progress' });
+      res.status(run.error ? 500 : 200).json({ success: !run.error, run });
+    });
+
+    // Proof-of-replication — sealed replica status (account, pins sealed, queue)
+    this.app.get('/api/seal', (_req: Request, res: Response) => {
+      res.json(this.sealStore.getStatus());
+    });
+
+    // Self-challenges — validator-style spot checks of random local pins
+    this.app.get('/api/self-challenge', (_req: Request, res: Response) => {
+      const cfg = this.config.getConfig();
+      res.json({
+        enabled: cfg.selfChallengeEnabled,
+        intervalMin: cfg.selfChallengeIntervalMin,
+        results: this.selfChallenger.getResults(),
+      });
+    });
+
+    this.app.post('/api/self-challenge/run', this.requireScope('challenge'), async (_req: Request, res: Response) => {
+      if (!this.kubo.isRunning()) {
+        return res.status(503).json({ error: 'IPFS daemon is not running' });
+      }
+      const results = await this.selfChallenger.runPass();
+      if (!results) return res.status(409).json({ error: 'A self-challenge pass is already running' });
+      res.json({ success: results.every((r) => r.passed), results });
+    });
+
+    // Webhooks — configured targets (origin only) and recent deliveries
+    this.app.get('/api/webhooks', (_req: Request, res: Response) => {
+      res.json({
+        webhooks: this.publicConfig().webhookTargets,
+        deliveries: this.webhooks.getDeliveries(),
+      });
+    });
+
+    // Pin manifest — block count and how much of it is local (pins may still be fetching)
+    this.app.get('/api/pins/:cid/manifest', async (req: Request, res: Response) => {
+      const { cid } = req.params;
+      if (!isValidCid(cid)) {
+        return res.status(400).json({ error: 'Valid CID required' });
+      }
+
+      try {
+        res.json(await this.kubo.getPinManifest(cid));
+      } catch (error: any) {
+        res.status(500).json({ error: error.message });
+      }
+    });
+
+    // Why a single CID would pass or fail a challenge right now. Read-only: combines
+    // pin state, the manifest, scrub results and this node's challenge history.
+    this.app.get('/api/pins/:cid/status', async (req: Request, res: Response) => {
+      const { cid } = req.params;
+      if (!isValidCid(cid)) {
+        return res.status(400).json({ error: 'Valid CID required' });
+      }
+      if (!this.kubo.isRunning()) {
+        return res.json({ cid, pinned: null, challengeable: false, reason: 'IPFS daemon is not running — every challenge fails until it is started.' });
+      }
+
+      const pinned = await this.kubo.isPinned(cid);
+
+      let manifest: Awaited<ReturnType<KuboManager['getPinManifest']>> | null = null;
+      let manifestError: string | null = null;
+      try {
+        manifest = await this.kubo.getPinManifest(cid);
+      } catch (error: any) {
+        manifestError = error.message;
+      }
+
+      const scrub = this.scrubber.getStatus().pins[cid] || null;
+      const history = this.metrics.getCidStats(cid);
+      const missingBlocks = manifest ? manifest.blockCount - manifest.localBlocks : null;
+      const damagedBlocks = scrub?.damaged.length || 0;
+
+      let reason: string;
+      if (!pinned) {
+        reason = 'Not pinned on this node — challenges for it fail.';
+      } else if (!manifest) {
+        reason = `Could not enumerate the DAG (${manifestError}) — blocks are probably missing locally, so challenges would time out.`;
+      } else if (missingBlocks! > 0) {
+        reason = `${missingBlocks} of ${manifest.blockCount} blocks are not local — challenges touching them fail (pin may still be fetching).`;
+      } else if (damagedBlocks > 0) {
+        reason = `The scrubber found ${damagedBlocks} corrupt or missing block(s) — challenges touching them fail until repaired.`;
+      } else {
+        reason = 'Pinned and fully local — challenges should pass.';
+      }
+
+      res.json({
+        cid,
+        pinned,
+        challengeable: pinned && !!manifest && missingBlocks === 0 && damagedBlocks === 0,
+        reason,
+        blockCount: manifest?.blockCount ?? null,
+        localBlocks: manifest?.localBlocks ?? null,
+        missingBlocks,
+        scrub: scrub && { lastScrubAt: scrub.lastScrubAt, lastCompletedAt: scrub.lastCompletedAt, damagedBlocks },
+        lastChallenge: history && {
+          passed: history.lastPassed,
+          at: new Date(history.lastChallengedAt).toISOString(),
+          challenges: history.challenges,
+          passedTotal: history.passed,
+        },
+        metadata: this.pinMetadata.get(cid),
+      });
+    });
+
+    // PoA Challenge endpoint - validators call this (legacy HTTP mode).
+    // Body may set offline: true (+ deadlineMs) for a strict offline timing proof.
+    this.app.post('/api/challenge', this.requireScope('challenge'), async (req: Request, res: Response) => {
+      agentEvents.publish('challenge_received', { source: 'http', cid: req.body?.cid });
+      const result = await this.challenges.run(req.body);
+      this.publishChallenge(req.body, result);
+      res.status(result.status).json(result.body);
+    });
+
+    // Batch challenges. With `stream: "ndjson" | "sse"` each result is written as
+    // soon as it completes; a client disconnect cancels the remaining work.
+    this.app.post('/api/challenge/batch', this.requireScope('challenge'), async (req: Request, res: Response) => {
+      const { challenges, stream } = req.body;
+      if (!Array.isArray(challenges) || challenges.length === 0) {
+        return res.status(400).json({ error: 'challenges must be a non-empty array' });
+      }
+      if (challenges.length > ApiServer.MAX_BATCH_CHALLENGES) {
+        return res.status(400).json({ error: `At most ${ApiServer.MAX_BATCH_CHALLENGES} challenges per batch` });
+      }
+      if (stream !== undefined && stream !== 'ndjson' && stream !== 'sse') {
+        return res.status(400).json({ error: 'stream must be "ndjson" or "sse"' });
+      }
+
+      const abort = new AbortController();
+      res.on('close', () => {
+        if (!res.writableEnded) abort.abort();
+      });
+
+      if (!stream) {
+        const results: any[] = new Array(challenges.length);
+        await this.challenges.runBatch(challenges, abort.signal, (index, result) => {
+          this.publishChallenge(challenges[index], result);
+          results[index] = { index, cid: challenges[index]?.cid, status: result.status, ...result.body };
+        });
+        if (!abort.signal.aborted) res.json({ results });
+        return;
+      }
+
+      res.status(200);
+      res.setHeader('Content-Type', stream === 'sse' ? 'text/event-stream' : 'application/x-ndjson');
+      res.setHeader('Cache-Control', 'no-cache');
+      res.flushHeaders();
+
+      const write = (event: string, payload: object) => {
+        if (stream === 'sse') res.write(`event: ${event}\ndata: ${JSON.stringify(payload)}\n\n`);
+        else res.write(JSON.stringify(payload) + '\n');
+      };
+
+      const completed = await this.challenges.runBatch(challenges, abort.signal, (index, result) => {
+        this.publishChallenge(challenges[index], result);
+        write('result', { index, cid: challenges[index]?.cid, status: result.status, ...result.body });
+      });
+      if (!abort.signal.aborted) {
+        write('done', { done: true, completed, total: challenges.length });
+        res.end();
+      }
+    });
+
+    // Readahead benchmark — reads two disjoint runs of blocks, one cold and one
+    // with readahead, so operators can see whether it helps on their datastore.
+    this.app.post('/api/challenge/benchmark', this.requireScope('challenge'), async (req: Request, res: Response) => {
+      const { cid } = req.body;
+      if (!cid || !isValidCid(cid)) {
+        return res.status(400).json({ error: 'Valid CID required' });
+      }
+      const readahead = Math.min(Math.max(Number(req.body.readahead) || this.config.getConfig().challengeReadahead || 4, 1), 16);
+      const sampleSize = Math.min(Math.max(Number(req.body.blocks) || 32, 1), 256);
+
+      try {
+        const blocks = await this.kubo.getBlockRefs(cid, 10000);
+        const count = Math.min(sampleSize, Math.floor(blocks.length / 2));
+        if (count === 0) {
+          return res.status(400).json({ error: 'CID needs at least 2 blocks to benchmark' });
+        }
+
+        const timeRun = async (run: string[], k: number) => {
+          const latencies: number[] = [];
+          const warmed = new Set<number>();
+          const runStart = Date.now();
+          for (let i = 0; i < run.length; i++) {
+            const t = Date.now();
+            await this.challenges.readBlock(run, i, k, warmed);
+            latencies.push(Date.now() - t);
+          }
+          latencies.sort((a, b) => a - b);
+          return {
+            totalMs: Date.now() - runStart,
+            avgMs: Math.round(latencies.reduce((a, b) => a + b, 0) / latencies.length),
+            p95Ms: latencies[Math.min(latencies.length - 1, Math.floor(latencies.length * 0.95))],
+            maxMs: latencies[latencies.length - 1],
+          };
+        };
+
+        const without = await timeRun(blocks.slice(0, count), 0);
+        const withReadahead = await timeRun(blocks.slice(count, count * 2), readahead);
+
+        res.json({
+          cid,
+          datastore: this.kubo.getDatastoreType(),
+          blocksPerRun: count,
+          readahead,
+          without,
+          with: withReadahead,
+        });
+      } catch (error: any) {
+        res.status(500).json({ error: error.message });
+      }
+    });
+
+    // Challenge history (from the ledger): totals, success rate and latency percentiles.
+    // ?from= (epoch ms or ISO 8601) limits the window; default is all time.
+    this.app.get('/api/challenges/stats', (req: Request, res: Response) => {
+      const raw = req.query.from;
+      let from = 0;
+      if (raw !== undefined && raw !== '') {
+        from = /^\d+$/.test(String(raw)) ? Number(raw) : Date.parse(String(raw));
+        if (!Number.isFinite(from)) return res.status(400).json({ error: 'from must be epoch ms or an ISO timestamp' });
+      }
+      res.json({ from: new Date(from).toISOString(), ...this.config.getChallengeStats(from) });
+    });
+
+    // Most recent challenges, newest first: ?limit= (1-500, default 50)
+    this.app.get('/api/challenges/recent', (req: Request, res: Response) => {
+      const limit = req.query.limit === undefined ? 50 : Number(req.query.limit);
+      if (!Number.isInteger(limit) || limit < 1 || limit > 500) {
+        return res.status(400).json({ error: 'limit must be 1-500' });
+      }
+      res.json({ challenges: this.config.getRecentChallenges(limit) });
+    });
+
+    // Get earnings, with fiat totals once the price feed has a rate
+    this.app.get('/api/earnings', (req: Request, res: Response) => {
+      const earnings = this.config.getEarnings();
+      const rates = this.priceFeed.getRates();
+      res.json({
+        ...earnings,
+        totalEarnedUsd: rates ? toFiat(earnings.totalHbd, rates.hbdUsd) : null,
+        fiat: rates && {
+          currency: rates.currency,
+          totalEarned: rates.hbd === null ? null : toFiat(earnings.totalHbd, rates.hbd),
+          hbdRate: rates.hbd,
+          hiveRate: rates.hive,
+          source: rates.source,
+          updatedAt: rates.updatedAt,
+          stale: rates.stale,
+        },
+      });
+    });
+
+    // Chain watcher — cursor, counters and last error
+    this.app.get('/api/chain', (_req: Request, res: Response) => {
+      res.json(this.chainWatcher.getStatus());
+    });
+
+    // Price feed status and cached rates
+    this.app.get('/api/price', (_req: Request, res: Response) => {
+      res.json(this.priceFeed.getStatus());
+    });
+
+    // Credited challenges in a time range: ?from=&to= (epoch ms or ISO 8601, default all time).
+    // With ?granularity=day|week|month, per-bucket HBD and challenge counts instead of records.
+    this.app.get('/api/earnings/history', (req: Request, res: Response) => {
+      const from = ApiServer.parseTimeQuery(req.query.from, 0);
+      const to = ApiServer.parseTimeQuery(req.query.to, Date.now());
+      if (from === null || to === null || from > to) {
+        return res.status(400).json({ error: 'from/to must be epoch ms or ISO timestamps with from <= to' });
+      }
+      const granularity = req.query.granularity as EarningsGranularity | undefined;
+      if (granularity !== undefined) {
+        if (!EARNINGS_GRANULARITIES.includes(granularity)) {
+          return res.status(400).json({ error: `granularity must be one of: ${EARNINGS_GRANULARITIES.join(', ')}` });
+        }
+        const buckets = this.config.getEarningsBuckets(from, to, granularity);
+        return res.json({
+          from: new Date(from).toISOString(),
+          to: new Date(to).toISOString(),
+          granularity,
+          buckets,
+          totalHbd: buckets.reduce((sum, b) => sum + b.hbd, 0),
+          challenges: buckets.reduce((sum, b) => sum + b.challenges, 0),
+        });
+      }
+      // Fiat at today's rate — the feed doesn't keep historical prices
+      const history = this.config.getEarningsHistory(from, to);
+      const hbdRate = this.priceFeed.getRates()?.hbd ?? null;
+      res.json({
+        from: new Date(from).toISOString(),
+        to: new Date(to).toISOString(),
+        ...history,
+        records: history.records.map((r) => ({ ...r, fiatValue: hbdRate === null ? null : toFiat(r.amountHbd, hbdRate) })),
+        currency: this.config.getConfig().displayCurrency,
+        totalFiat: hbdRate === null ? null : toFiat(history.totalHbd, hbdRate),
+      });
+    });
+
+    // The whole ledger (passed and failed challenges) for bookkeeping: ?format=csv|json&from=&to=.
+    // Streamed row by row, so a multi-year ledger never sits in memory.
+    this.app.get('/api/earnings/export', async (req: Request, res: Response) => {
+      const format = String(req.query.format || 'csv');
+      if (format !== 'csv' && format !== 'json') {
+        return res.status(400).json({ error: 'format must be csv or json' });
+      }
+      const from = ApiServer.parseTimeQuery(req.query.from, 0);
+      const to = ApiServer.parseTimeQuery(req.query.to, Date.now());
+      if (from === null || to === null || from > to) {
+        return res.status(400).json({ error: 'from/to must be epoch ms or ISO timestamps with from <= to' });
+      }
+
+      const day = (ms: number) => new Date(ms).toISOString().slice(0, 10);
+      res.setHeader('Content-Type', format === 'csv' ? 'text/csv; charset=utf-8' : 'application/json');
+      res.setHeader('Content-Disposition', `attachment; filename="hivepoa-earnings-${day(from)}-to-${day(to)}.${format}"`);
+      const write = async (chunk: string) => {
+        if (!res.write(chunk)) await new Promise((resolve) => res.once('drain', resolve));
+      };
+      let closed = false;
+      req.on('close', () => { closed = true; });
+
+      try {
+        if (format === 'csv') await write('timestamp,cid,passed,amount_hbd,validator,latency_ms,blocks,error\n');
+        else await write('[');
+        let first = true;
+        for (const row of this.config.iterateChallenges(from, to)) {
+          if (closed) return;
+          if (format === 'csv') {
+            await write([row.at, row.cid, row.passed, row.amountHbd, row.validator, row.latencyMs, row.blocks, row.error]
+              .map(ApiServer.csvField).join(',') + '\n');
+          } else {
+            await write(`${first ? '\n' : ',\n'}${JSON.stringify(row)}`);
+          }
+          first = false;
+        }
+        res.end(format === 'json' ? '\n]\n' : undefined);
       } catch (error: any) {
-        console.error('[API] Upload failed:', error.message);
-        res.status(500).json({ error: error.message });
+        // Headers are gone — cut the download short rather than hand over a truncated file as complete
+        console.error(`[API] Earnings export failed: ${error.message}`);
+        res.destroy();
       }
     });
 
-    // List pinned content
-    this.app.get('/api/pins', async (req: Request, res: Response) => {
+    // Repo volume free space, repo growth rate and warning level (diskWarnFreePercent, diskMinFreeGB)
+    this.app.get('/api/storage/disk', async (_req: Request, res: Response) => {
       try {
-        const response = await axios.post(`${this.kubo.getApiUrl()}/api/v0/pin/ls?type=recursive`);
-        const pins = Object.keys(response.data.Keys || {});
-        res.json({ pins });
+        res.json(this.storageMonitor.getStatus() ?? await this.storageMonitor.check());
       } catch (error: any) {
         res.status(500).json({ error: error.message });
       }
     });
 
-    // PoA Challenge endpoint - validators call this (legacy HTTP mode)
-    this.app.post('/api/challenge', this.requireLocalAuth, async (req: Request, res: Response) => {
-      const { cid, blockIndex, salt, validatorId } = req.body;
-
-      if (!cid || blockIndex === undefined || !salt) {
-        return res.status(400).json({ error: 'Missing required fields: cid, blockIndex, salt' });
-      }
-
-      const startTime = Date.now();
-
-      try {
-        const blocksResponse = await axios.post(
-          `${this.kubo.getApiUrl()}/api/v0/refs?arg=${cid}`,
-          null,
-          { timeout: 2000 }
-        );
-
-        const blocks = blocksResponse.data.split('\n')
-          .filter((line: string) => line.trim())
-          .map((line: string) => {
-            try { return JSON.parse(line).Ref; } catch { return null; }
-          })
-          .filter(Boolean);
-
-        if (blockIndex >= blocks.length) {
-          return res.status(400).json({ error: 'Block index out of range' });
-        }
+    // The last 24 hours as the daily summary would report them (notifyDailySummary)
+    this.app.get('/api/summary/daily', (_req: Request, res: Response) => {
+      res.json(this.dailySummary.build());
+    });
 
-        const blockCid = blocks[blockIndex];
-        const blockResponse = await axios.post(
-          `${this.kubo.getApiUrl()}/api/v0/block/get?arg=${blockCid}`,
-          null,
-          { timeout: 2000, responseType: 'arraybuffer' }
-        );
+    // Milestone and goal history — each threshold is logged once, oldest first
+    this.app.get('/api/earnings/milestones', (_req: Request, res: Response) => {
+      const state = this.config.getMilestoneState();
+      const goalHbd = this.config.getConfig().earningsGoalHbd;
+      res.json({
+        events: this.config.getEarningsEvents(),
+        highestMilestoneHbd: state.highestMilestoneHbd ?? 0,
+        goalHbd,
+        goalReached: goalHbd !== null && state.goalReachedHbd === goalHbd,
+        totalHbd: this.config.getEarnings().totalHbd,
+      });
+    });
 
-        const hash = crypto.createHash('sha256');
-        hash.update(salt);
-        hash.update(Buffer.from(blockResponse.data));
-        const proof = hash.digest('hex');
+    // Ledger credits checked against reward transfers on Hive (payoutReconcileEnabled)
+    this.app.get('/api/earnings/reconciliation', (_req: Request, res: Response) => {
+      const cfg = this.config.getConfig();
+      const reports = this.reconciler.getReports();
+      res.json({
+        enabled: cfg.payoutReconcileEnabled,
+        windowDays: cfg.payoutReconcileDays,
+        report: reports.length > 0 ? reports[reports.length - 1] : null,
+        reports,
+      });
+    });
 
-        const responseTime = Date.now() - startTime;
-        const hbdEarned = 0.001;
-        this.config.recordChallenge(true, hbdEarned);
+    this.app.post('/api/earnings/reconciliation/run', this.requireScope('earnings'), async (_req: Request, res: Response) => {
+      const report = await this.reconciler.reconcile();
+      if (!report) return res.status(409).json({ error: 'A reconciliation pass is already running' });
+      res.json({ success: report.error === null, report });
+    });
 
-        res.json({ success: true, proof, blockCid, responseTime });
-      } catch (error: any) {
-        this.config.recordChallenge(false, 0);
-        res.status(500).json({
-          success: false,
-          error: error.message,
-          responseTime: Date.now() - startTime,
-        });
-      }
+    // Earnings held back by offlineEarningsPolicy = 'quarantine', pending manual review
+    this.app.get('/api/earnings/quarantine', (_req: Request, res: Response) => {
+      const entries = this.config.getQuarantinedEarnings();
+      res.json({
+        entries,
+        totalHbd: entries.reduce((sum, e) => sum + e.hbdEarned, 0),
+        policy: this.config.getConfig().offlineEarningsPolicy,
+      });
     });
 
-    // Get earnings
-    this.app.get('/api/earnings', (req: Request, res: Response) => {
-      res.json(this.config.getEarnings());
+    // Body: { action: 'release' | 'discard', ids?: string[] } — omitting ids applies to all
+    this.app.post('/api/earnings/quarantine', this.requireScope('earnings'), (req: Request, res: Response) => {
+      const { action, ids } = req.body || {};
+      if (action !== 'release' && action !== 'discard') {
+        return res.status(400).json({ error: "action must be 'release' or 'discard'" });
+      }
+      if (ids !== undefined && (!Array.isArray(ids) || !ids.every((id: any) => typeof id === 'string'))) {
+        return res.status(400).json({ error: 'ids must be an array of strings' });
+      }
+      const affected = action === 'release'
+        ? this.config.releaseQuarantinedEarnings(ids)
+        : this.config.discardQuarantinedEarnings(ids);
+      res.json({ success: true, action, affected: affected.length, earnings: this.config.getEarnings() });
     });
 
     // Connection/network status
@@ -553,8 +1799,14 @@ export class ApiServer {
       res.json(this.validator?.getStats() || { issued: 0, passed: 0, failed: 0, timeouts: 0 });
     });
 
+    // Recent challenges issued by this node's validator (pass/fail per peer and CID)
+    this.app.get('/api/validation/results', (req: Request, res: Response) => {
+      const limit = Math.min(Math.max(Number(req.query.limit) || 50, 1), 100);
+      res.json({ enabled: !!this.validator, results: this.validator?.getRecentResults(limit) || [] });
+    });
+
     // Toggle validation
-    this.app.post('/api/validation/toggle', this.requireLocalAuth, (req: Request, res: Response) => {
+    this.app.post('/api/validation/toggle', this.requireScope('config'), (req: Request, res: Response) => {
       const { enabled } = req.body;
       this.config.setConfig({ validatorEnabled: !!enabled });
       res.json({ success: true, validatorEnabled: !!enabled });
@@ -588,14 +1840,32 @@ export class ApiServer {
         return res.status(400).json({ error: 'Wallet not initialized — call POST /api/wallet/init first' });
       }
       try {
-        const pubKey = this.wallet.importPostingKey(key);
-        this.config.setPostingPublicKey(pubKey);
-        res.json({ success: true, hasPostingKey: true, publicKey: pubKey });
+        const { publicKey } = this.importSigningKey(key);
+        res.json({ success: true, hasPostingKey: true, publicKey });
       } catch (err: any) {
         res.status(500).json({ error: err.message });
       }
     });
 
+    // Signing key for signed challenge responses. The key goes into the
+    // encrypted wallet only; config keeps the public key and a SHA-256 hash.
+    // The raw key is never returned by any endpoint.
+    this.app.post('/api/config/set-signing-key', this.requireLocalAuth, (req: Request, res: Response) => {
+      const { key } = req.body;
+      if (!key || typeof key !== 'string') {
+        return res.status(400).json({ error: 'Signing key required' });
+      }
+      if (!this.wallet.isInitialized()) {
+        return res.status(400).json({ error: 'Wallet not initialized — call POST /api/wallet/init first' });
+      }
+      try {
+        const { publicKey, keyHash } = this.importSigningKey(key);
+        res.json({ success: true, publicKey, keyHash });
+      } catch (err: any) {
+        res.status(400).json({ error: `Invalid signing key: ${err.message}` });
+      }
+    });
+
     this.app.delete('/api/hive/posting-key', this.requireLocalAuth, (req: Request, res: Response) => {
       if (this.wallet.isInitialized()) {
         this.wallet.removePostingKey();
@@ -640,37 +1910,72 @@ export class ApiServer {
     });
 
     // Toggle treasury signing
-    this.app.post('/api/treasury/toggle', this.requireLocalAuth, (req: Request, res: Response) => {
+    this.app.post('/api/treasury/toggle', this.requireScope('config'), (req: Request, res: Response) => {
       const { enabled } = req.body;
       this.config.setConfig({ treasurySignerEnabled: !!enabled });
       res.json({ success: true, treasurySignerEnabled: !!enabled });
     });
 
     // Autostart management
-    this.app.get('/api/autostart', (req: Request, res: Response) => {
+    this.app.get('/api/autostart', async (req: Request, res: Response) => {
       const config = this.config.getConfig();
-      res.json({ enabled: config.autoStart });
+      const service = await autostartServiceStatus(config.autoStartMode, config);
+      res.json({ enabled: config.autoStart, mode: config.autoStartMode, supportedModes: supportedAutostartModes(), service });
     });
 
-    this.app.post('/api/autostart', this.requireLocalAuth, (req: Request, res: Response) => {
+    this.app.post('/api/autostart', this.requireScope('config'), async (req: Request, res: Response) => {
       const { enabled } = req.body;
-      this.config.setConfig({ autoStart: enabled });
+      const previousMode = this.config.getConfig().autoStartMode;
+      const mode: AutostartMode = req.body.mode ?? previousMode;
+      if (!supportedAutostartModes().includes(mode)) {
+        return res.status(400).json({ error: `mode must be one of: ${supportedAutostartModes().join(', ')}` });
+      }
 
-      if (electronApp) {
-        try {
-          electronApp.setLoginItemSettings({
-            openAtLogin: !!enabled,
-            name: 'SPK Desktop Agent',
-          });
-          console.log(`[API] Autostart ${enabled ? 'enabled' : 'disabled'}`);
-        } catch (error) {
-          console.error('[API] Failed to configure autostart:', error);
+      let warnings: string[] = [];
+      try {
+        // Switching mechanisms: turn the old one off so the agent isn't launched twice
+        if (mode !== previousMode || !enabled) {
+          await uninstallAutostartService(previousMode);
+          this.setLoginItem(false);
         }
-      } else {
-        console.log(`[API] Autostart ${enabled ? 'enabled' : 'disabled'} (CLI mode — use systemd for autostart)`);
+        if (enabled && mode === 'desktop') {
+          this.setLoginItem(true);
+        } else if (enabled) {
+          ({ warnings } = await installAutostartService(mode, this.config.getConfig()));
+        }
+      } catch (error: any) {
+        console.error('[API] Failed to configure autostart:', error);
+        return res.status(500).json({ error: `Failed to configure autostart: ${error.message}` });
       }
 
-      res.json({ success: true, enabled });
+      this.config.setConfig({ autoStart: !!enabled, autoStartMode: mode });
+      console.log(`[API] Autostart ${enabled ? 'enabled' : 'disabled'} (${mode})`);
+      res.json({ success: true, enabled: !!enabled, mode, warnings });
+    });
+
+    // Self-update (desktop app; electron-updater against GitHub Releases)
+    const updaterUnavailable = { error: 'Self-update is only available in the desktop app — update the CLI build with git pull && npm run build:cli' };
+
+    this.app.get('/api/update', (_req: Request, res: Response) => {
+      if (!this.updater) return res.status(501).json(updaterUnavailable);
+      res.json(this.updater.getStatus());
+    });
+
+    this.app.post('/api/update/check', this.requireScope('config'), async (_req: Request, res: Response) => {
+      if (!this.updater) return res.status(501).json(updaterUnavailable);
+      const status = await this.updater.checkForUpdates();
+      if (status.state === 'error') return res.status(502).json({ ...status, error: `Update check failed: ${status.error}` });
+      res.json(status);
+    });
+
+    // Downloads the update if needed, then restarts into it once no challenge is in flight
+    this.app.post('/api/update/apply', this.requireScope('config'), async (_req: Request, res: Response) => {
+      if (!this.updater) return res.status(501).json(updaterUnavailable);
+      try {
+        res.json({ success: true, restarting: true, ...(await this.updater.applyUpdate()) });
+      } catch (error: any) {
+        res.status(409).json({ error: error.message });
+      }
     });
 
     // ─── 3Speak Proxy (CORS bypass for GitHub Pages) ────────────────────
@@ -961,6 +2266,21 @@ export class ApiServer {
 
   setAgentWS(agentWS: AgentWSClient): void {
     this.agentWS = agentWS;
+    agentWS.setMetrics(this.metrics);
+    agentWS.setSealStore(this.sealStore);
+  }
+
+  setGpuManager(gpuManager: GpuContributionManager): void {
+    this.gpuManager = gpuManager;
+  }
+
+  /** Desktop app only — the CLI build updates through its package manager. */
+  setUpdater(updater: AutoUpdater): void {
+    this.updater = updater;
+  }
+
+  getMetrics(): AgentMetrics {
+    return this.metrics;
   }
 
   setP2PModules(
@@ -971,34 +2291,781 @@ export class ApiServer {
     this.peerDiscovery = peerDiscovery;
     this.validator = validator;
     this.challengeHandler = challengeHandler;
+    challengeHandler.setMetrics(this.metrics);
   }
 
-  async start(): Promise<void> {
-    return new Promise((resolve, reject) => {
+  // --- Operations shared by the HTTP routes and the renderer's IPC commands ---
+
+  /** Validate and apply a config update, restarting whatever it affects. Backs POST /api/config. */
+  async applyConfig(body: Record<string, any>): Promise<ApiResult> {
+    const {
+      hiveUsername: rawHiveUsername, autoStart, bandwidthLimitUp, bandwidthLimitDown,
+      storageMaxGB, serverUrl, p2pMode, validatorEnabled, challengeIntervalMs,
+      challengeReadahead, challengeRetries, nodeId, poolPrefix, kuboProcessTimeoutSec, kuboCommandTimeoutSec,
+      kuboShutdownGraceSec, offlineEarningsPolicy, pinReconcileEnabled, pinReconcileUrl, pinReconcileIntervalMin,
+      pinReconcileUnpinExtras, pinReconcileAllowlist, pinReconcileDenylist, contractSyncEnabled, contractSyncIntervalMin, contractUnpinExpired, maxBlocksPerChallenge, earningsGoalHbd, pinQuotaPerWindow,
+      pinQuotaWindowMin, scrubEnabled, scrubIntervalMin, scrubBlocksPerPass, scrubMaxBytesPerSec,
+      scrubAutoRepair, selfChallengeEnabled, selfChallengeIntervalMin, gcScheduleEnabled, gcQuietStartHour, gcQuietEndHour,
+      gcWatermarkPercent, sealingEnabled, challengeMemoryCeilingMB, corsAllowedOrigins, authRequiredForReads, challengesPaused, apiBindAddress, apiPort, signChallengeProofs,
+      launchdKeepAlive, launchdThrottleSec, launchdAsDaemon, updateMode, priceFeedEnabled, displayCurrency, chainWatcherEnabled,
+      payoutReconcileEnabled, payoutReconcileDays, notifyDailySummary, dailySummaryHour, diskWarnFreePercent, diskMinFreeGB,
+      broadcastProofs, proofBroadcastIntervalSec, hiveApiNodes, requireBlockSalts, webhooks,
+    } = body;
+    // Hive account names are lowercase; store them that way so comparisons and lookups agree
+    const hiveUsername = typeof rawHiveUsername === 'string' ? rawHiveUsername.toLowerCase() : rawHiveUsername;
+
+    // Input validation for numeric fields
+    if (bandwidthLimitUp !== undefined) {
+      const val = Number(bandwidthLimitUp);
+      if (!Number.isFinite(val) || val < 0 || val > 1000000) {
+        return { status: 400, body: { error: 'bandwidthLimitUp must be 0-1000000 KB/s' } };
+      }
+    }
+    if (bandwidthLimitDown !== undefined) {
+      const val = Number(bandwidthLimitDown);
+      if (!Number.isFinite(val) || val < 0 || val > 1000000) {
+        return { status: 400, body: { error: 'bandwidthLimitDown must be 0-1000000 KB/s' } };
+      }
+    }
+    if (storageMaxGB !== undefined) {
+      const val = Number(storageMaxGB);
+      if (!Number.isFinite(val) || val < 0 || val > 10000) {
+        return { status: 400, body: { error: 'storageMaxGB must be 0-10000' } };
+      }
+    }
+
+    if (challengeReadahead !== undefined) {
+      const val = Number(challengeReadahead);
+      if (!Number.isInteger(val) || val < 0 || val > 16) {
+        return { status: 400, body: { error: 'challengeReadahead must be 0-16' } };
+      }
+    }
+    if (displayCurrency !== undefined && (typeof displayCurrency !== 'string' || !/^[a-z]{3}$/i.test(displayCurrency))) {
+      return { status: 400, body: { error: "displayCurrency must be a 3-letter currency code, e.g. 'usd'" } };
+    }
+    if (updateMode !== undefined && !['auto', 'prompt', 'off'].includes(updateMode)) {
+      return { status: 400, body: { error: "updateMode must be 'auto', 'prompt' or 'off'" } };
+    }
+    if (updateMode === 'auto' && this.updater && !this.updater.canVerify()) {
+      return { status: 400, body: { error: "updateMode 'auto' needs a build with a pinned update signing key" } };
+    }
+    if (offlineEarningsPolicy !== undefined && !['accept', 'reject', 'quarantine'].includes(offlineEarningsPolicy)) {
+      return { status: 400, body: { error: "offlineEarningsPolicy must be 'accept', 'reject' or 'quarantine'" } };
+    }
+    if (challengeRetries !== undefined) {
+      const val = Number(challengeRetries);
+      if (!Number.isInteger(val) || val < 0 || val > 3) {
+        return { status: 400, body: { error: 'challengeRetries must be 0-3' } };
+      }
+    }
+    if (pinReconcileIntervalMin !== undefined) {
+      const val = Number(pinReconcileIntervalMin);
+      if (!Number.isInteger(val) || val < 1 || val > 1440) {
+        return { status: 400, body: { error: 'pinReconcileIntervalMin must be 1-1440' } };
+      }
+    }
+    if (pinReconcileUrl !== undefined && pinReconcileUrl !== null && pinReconcileUrl !== '' &&
+        (typeof pinReconcileUrl !== 'string' || !/^https?:\/\//.test(pinReconcileUrl))) {
+      return { status: 400, body: { error: 'pinReconcileUrl must be an http(s) URL' } };
+    }
+    if (pinReconcileAllowlist !== undefined &&
+        (!Array.isArray(pinReconcileAllowlist) || !pinReconcileAllowlist.every((c: any) => isValidCid(c)))) {
+      return { status: 400, body: { error: 'pinReconcileAllowlist must be an array of CIDs' } };
+    }
+    if (pinReconcileDenylist !== undefined &&
+        (!Array.isArray(pinReconcileDenylist) || !pinReconcileDenylist.every((c: any) => isValidCid(c)))) {
+      return { status: 400, body: { error: 'pinReconcileDenylist must be an array of CIDs' } };
+    }
+    if (contractSyncIntervalMin !== undefined) {
+      const val = Number(contractSyncIntervalMin);
+      if (!Number.isInteger(val) || val < 1 || val > 1440) {
+        return { status: 400, body: { error: 'contractSyncIntervalMin must be 1-1440' } };
+      }
+    }
+    if (earningsGoalHbd !== undefined && earningsGoalHbd !== null) {
+      const val = Number(earningsGoalHbd);
+      if (!Number.isFinite(val) || val <= 0) {
+        return { status: 400, body: { error: 'earningsGoalHbd must be a positive number (or null to clear)' } };
+      }
+    }
+    if (pinQuotaPerWindow !== undefined) {
+      const val = Number(pinQuotaPerWindow);
+      if (!Number.isInteger(val) || val < 0 || val > 1000000) {
+        return { status: 400, body: { error: 'pinQuotaPerWindow must be 0-1000000' } };
+      }
+    }
+    if (pinQuotaWindowMin !== undefined) {
+      const val = Number(pinQuotaWindowMin);
+      if (!Number.isInteger(val) || val < 1 || val > 10080) {
+        return { status: 400, body: { error: 'pinQuotaWindowMin must be 1-10080' } };
+      }
+    }
+    for (const [name, value, min, max] of [
+      ['scrubIntervalMin', scrubIntervalMin, 1, 10080],
+      ['scrubBlocksPerPass', scrubBlocksPerPass, 1, 1000000],
+      ['scrubMaxBytesPerSec', scrubMaxBytesPerSec, 0, 1073741824],
+      ['selfChallengeIntervalMin', selfChallengeIntervalMin, 1, 10080],
+      ['gcQuietStartHour', gcQuietStartHour, 0, 23],
+      ['gcQuietEndHour', gcQuietEndHour, 0, 23],
+      ['gcWatermarkPercent', gcWatermarkPercent, 0, 100],
+      ['challengeMemoryCeilingMB', challengeMemoryCeilingMB, 0, 4096],
+      ['launchdThrottleSec', launchdThrottleSec, 10, 3600],
+      ['payoutReconcileDays', payoutReconcileDays, 1, 365],
+      ['dailySummaryHour', dailySummaryHour, 0, 23],
+      ['diskWarnFreePercent', diskWarnFreePercent, 0, 100],
+      ['diskMinFreeGB', diskMinFreeGB, 0, 100000],
+    ] as const) {
+      if (value !== undefined) {
+        const val = Number(value);
+        if (!Number.isInteger(val) || val < min || val > max) {
+          return { status: 400, body: { error: `${name} must be ${min}-${max}` } };
+        }
+      }
+    }
+    if (maxBlocksPerChallenge !== undefined) {
+      const val = Number(maxBlocksPerChallenge);
+      if (!Number.isInteger(val) || val < 1 || val > 10000) {
+        return { status: 400, body: { error: 'maxBlocksPerChallenge must be 1-10000' } };
+      }
+    }
+    if (kuboProcessTimeoutSec !== undefined) {
+      const val = Number(kuboProcessTimeoutSec);
+      if (!Number.isInteger(val) || val < 0 || val > 86400) {
+        return { status: 400, body: { error: 'kuboProcessTimeoutSec must be 0-86400' } };
+      }
+    }
+    if (kuboCommandTimeoutSec !== undefined) {
+      const val = Number(kuboCommandTimeoutSec);
+      if (!Number.isInteger(val) || val < 1 || val > 3600) {
+        return { status: 400, body: { error: 'kuboCommandTimeoutSec must be 1-3600' } };
+      }
+    }
+    if (kuboShutdownGraceSec !== undefined) {
+      const val = Number(kuboShutdownGraceSec);
+      if (!Number.isInteger(val) || val < 1 || val > 600) {
+        return { status: 400, body: { error: 'kuboShutdownGraceSec must be 1-600' } };
+      }
+    }
+    if (apiBindAddress !== undefined && (typeof apiBindAddress !== 'string' ||
+        (apiBindAddress !== 'localhost' && net.isIP(apiBindAddress) === 0))) {
+      return { status: 400, body: { error: 'apiBindAddress must be an IP address or "localhost"' } };
+    }
+    if (apiPort !== undefined) {
+      const val = Number(apiPort);
+      if (!Number.isInteger(val) || val < 0 || val > 65535) {
+        return { status: 400, body: { error: 'apiPort must be 0-65535' } };
+      }
+    }
+    if (corsAllowedOrigins !== undefined) {
+      // Exact origins only: no wildcards, paths, or the 'null' origin
+      if (!Array.isArray(corsAllowedOrigins) ||
+          !corsAllowedOrigins.every((o: any) => typeof o === 'string' && /^https?:\/\/[^/\s*]+$/.test(o))) {
+        return { status: 400, body: { error: 'corsAllowedOrigins must be an array of origins like https://example.com' } };
+      }
+    }
+    if (proofBroadcastIntervalSec !== undefined) {
+      const val = Number(proofBroadcastIntervalSec);
+      if (!Number.isInteger(val) || val < 3 || val > 86400) {
+        return { status: 400, body: { error: 'proofBroadcastIntervalSec must be 3-86400' } };
+      }
+    }
+    if (hiveApiNodes !== undefined &&
+        (!Array.isArray(hiveApiNodes) || !hiveApiNodes.every((n: any) => typeof n === 'string' && /^https?:\/\/\S+$/.test(n)))) {
+      return { status: 400, body: { error: 'hiveApiNodes must be an array of http(s) URLs' } };
+    }
+    if (webhooks !== undefined &&
+        (!Array.isArray(webhooks) || !webhooks.every((h: any) =>
+          typeof h?.url === 'string' && /^https?:\/\/\S+$/.test(h.url) &&
+          Array.isArray(h.events) && h.events.every((e: any) => (WEBHOOK_EVENTS as readonly string[]).includes(e))))) {
+      return { status: 400, body: { error: `webhooks must be an array of { url, events } with events from: ${WEBHOOK_EVENTS.join(', ')}` } };
+    }
+
+    // Only save a hiveUsername that exists on chain (and owns hivePublicKey, if sent).
+    // Re-saving the current name skips the lookup, so config saves work while Hive is unreachable.
+    const { hivePublicKey } = body;
+    if (hivePublicKey !== undefined && (typeof hivePublicKey !== 'string' || !hiveUsername)) {
+      return { status: 400, body: { error: 'hivePublicKey must be a string and needs hiveUsername' } };
+    }
+    if (hiveUsername !== undefined && hiveUsername !== null && hiveUsername !== '') {
+      if (typeof hiveUsername !== 'string') {
+        return { status: 400, body: { error: 'hiveUsername must be a string' } };
+      }
+      if (hiveUsername !== this.config.getConfig().hiveUsername || hivePublicKey !== undefined) {
+        const hive = new AgentHiveClient({ username: hiveUsername, nodes: hiveApiNodes ?? this.config.getConfig().hiveApiNodes });
+        const check = await hive.checkAccount(hiveUsername, hivePublicKey);
+        if (!check.ok) {
+          return {
+            status: check.error === 'HIVE_UNREACHABLE' ? 503 : 400,
+            body: { success: false, error: check.error, field: check.error === 'HIVE_KEY_MISMATCH' ? 'hivePublicKey' : 'hiveUsername', message: check.message },
+          };
+        }
+      }
+    }
+
+    // Identity fields: null/'' clears back to the peer-ID-derived default
+    for (const [name, value] of [['nodeId', nodeId], ['poolPrefix', poolPrefix]] as const) {
+      if (value !== undefined && value !== null && value !== '' &&
+          (typeof value !== 'string' || !NODE_ID_PATTERN.test(value))) {
+        return { status: 400, body: { error: `${name} must be 1-64 characters of A-Z, a-z, 0-9, '.', '_' or '-'` } };
+      }
+    }
+
+    const updates: Partial<AgentConfig> = {};
+    if (hiveUsername !== undefined) updates.hiveUsername = hiveUsername;
+    if (autoStart !== undefined) updates.autoStart = autoStart;
+    if (bandwidthLimitUp !== undefined) updates.bandwidthLimitUp = Number(bandwidthLimitUp);
+    if (bandwidthLimitDown !== undefined) updates.bandwidthLimitDown = Number(bandwidthLimitDown);
+    if (storageMaxGB !== undefined) updates.storageMaxGB = Number(storageMaxGB);
+    if (serverUrl !== undefined) updates.serverUrl = serverUrl;
+    if (p2pMode !== undefined) updates.p2pMode = p2pMode;
+    if (validatorEnabled !== undefined) updates.validatorEnabled = validatorEnabled;
+    if (challengeIntervalMs !== undefined) updates.challengeIntervalMs = Number(challengeIntervalMs);
+    if (challengeReadahead !== undefined) updates.challengeReadahead = Number(challengeReadahead);
+    if (challengeRetries !== undefined) updates.challengeRetries = Number(challengeRetries);
+    if (maxBlocksPerChallenge !== undefined) updates.maxBlocksPerChallenge = Number(maxBlocksPerChallenge);
+    if (challengeMemoryCeilingMB !== undefined) updates.challengeMemoryCeilingMB = Number(challengeMemoryCeilingMB);
+    if (pinQuotaPerWindow !== undefined) updates.pinQuotaPerWindow = Number(pinQuotaPerWindow);
+    if (pinQuotaWindowMin !== undefined) updates.pinQuotaWindowMin = Number(pinQuotaWindowMin);
+    if (scrubEnabled !== undefined) updates.scrubEnabled = !!scrubEnabled;
+    if (scrubIntervalMin !== undefined) updates.scrubIntervalMin = Number(scrubIntervalMin);
+    if (scrubBlocksPerPass !== undefined) updates.scrubBlocksPerPass = Number(scrubBlocksPerPass);
+    if (scrubMaxBytesPerSec !== undefined) updates.scrubMaxBytesPerSec = Number(scrubMaxBytesPerSec);
+    if (scrubAutoRepair !== undefined) updates.scrubAutoRepair = !!scrubAutoRepair;
+    if (selfChallengeEnabled !== undefined) updates.selfChallengeEnabled = !!selfChallengeEnabled;
+    if (selfChallengeIntervalMin !== undefined) updates.selfChallengeIntervalMin = Number(selfChallengeIntervalMin);
+    if (gcScheduleEnabled !== undefined) updates.gcScheduleEnabled = !!gcScheduleEnabled;
+    if (sealingEnabled !== undefined) updates.sealingEnabled = !!sealingEnabled;
+    if (priceFeedEnabled !== undefined) updates.priceFeedEnabled = !!priceFeedEnabled;
+    if (chainWatcherEnabled !== undefined) updates.chainWatcherEnabled = !!chainWatcherEnabled;
+    if (diskWarnFreePercent !== undefined) updates.diskWarnFreePercent = Number(diskWarnFreePercent);
+    if (diskMinFreeGB !== undefined) updates.diskMinFreeGB = Number(diskMinFreeGB);
+    if (notifyDailySummary !== undefined) updates.notifyDailySummary = !!notifyDailySummary;
+    if (dailySummaryHour !== undefined) updates.dailySummaryHour = Number(dailySummaryHour);
+    if (payoutReconcileEnabled !== undefined) updates.payoutReconcileEnabled = !!payoutReconcileEnabled;
+    if (payoutReconcileDays !== undefined) updates.payoutReconcileDays = Number(payoutReconcileDays);
+    if (displayCurrency !== undefined) updates.displayCurrency = displayCurrency.toLowerCase();
+    if (gcQuietStartHour !== undefined) updates.gcQuietStartHour = Number(gcQuietStartHour);
+    if (gcQuietEndHour !== undefined) updates.gcQuietEndHour = Number(gcQuietEndHour);
+    if (gcWatermarkPercent !== undefined) updates.gcWatermarkPercent = Number(gcWatermarkPercent);
+    if (signChallengeProofs !== undefined) updates.signChallengeProofs = !!signChallengeProofs;
+    if (broadcastProofs !== undefined) updates.broadcastProofs = !!broadcastProofs;
+    if (proofBroadcastIntervalSec !== undefined) updates.proofBroadcastIntervalSec = Number(proofBroadcastIntervalSec);
+    if (hiveApiNodes !== undefined) updates.hiveApiNodes = hiveApiNodes;
+    if (requireBlockSalts !== undefined) updates.requireBlockSalts = !!requireBlockSalts;
+    if (webhooks !== undefined) updates.webhooks = webhooks.map((h: any) => ({ url: h.url, events: h.events }));
+    if (offlineEarningsPolicy !== undefined) updates.offlineEarningsPolicy = offlineEarningsPolicy;
+    if (earningsGoalHbd !== undefined) updates.earningsGoalHbd = earningsGoalHbd === null ? null : Number(earningsGoalHbd);
+    if (kuboProcessTimeoutSec !== undefined) updates.kuboProcessTimeoutSec = Number(kuboProcessTimeoutSec);
+    if (kuboCommandTimeoutSec !== undefined) updates.kuboCommandTimeoutSec = Number(kuboCommandTimeoutSec);
+    if (kuboShutdownGraceSec !== undefined) updates.kuboShutdownGraceSec = Number(kuboShutdownGraceSec);
+    if (corsAllowedOrigins !== undefined) updates.corsAllowedOrigins = corsAllowedOrigins;
+    if (authRequiredForReads !== undefined) updates.authRequiredForReads = !!authRequiredForReads;
+    if (challengesPaused !== undefined) updates.challengesPaused = !!challengesPaused;
+    if (launchdKeepAlive !== undefined) updates.launchdKeepAlive = !!launchdKeepAlive;
+    if (launchdThrottleSec !== undefined) updates.launchdThrottleSec = Number(launchdThrottleSec);
+    if (launchdAsDaemon !== undefined) updates.launchdAsDaemon = !!launchdAsDaemon;
+    if (updateMode !== undefined) updates.updateMode = updateMode as UpdateMode;
+    if (apiBindAddress !== undefined) updates.apiBindAddress = apiBindAddress;
+    if (apiPort !== undefined) updates.apiPort = Number(apiPort);
+    if (nodeId !== undefined) updates.nodeId = nodeId || null;
+    if (poolPrefix !== undefined) updates.poolPrefix = poolPrefix || null;
+    if (pinReconcileEnabled !== undefined) updates.pinReconcileEnabled = !!pinReconcileEnabled;
+    if (pinReconcileUrl !== undefined) updates.pinReconcileUrl = pinReconcileUrl || null;
+    if (pinReconcileIntervalMin !== undefined) updates.pinReconcileIntervalMin = Number(pinReconcileIntervalMin);
+    if (pinReconcileUnpinExtras !== undefined) updates.pinReconcileUnpinExtras = !!pinReconcileUnpinExtras;
+    if (pinReconcileAllowlist !== undefined) updates.pinReconcileAllowlist = pinReconcileAllowlist;
+    if (pinReconcileDenylist !== undefined) updates.pinReconcileDenylist = pinReconcileDenylist;
+    if (contractSyncEnabled !== undefined) updates.contractSyncEnabled = !!contractSyncEnabled;
+    if (contractSyncIntervalMin !== undefined) updates.contractSyncIntervalMin = Number(contractSyncIntervalMin);
+    if (contractUnpinExpired !== undefined) updates.contractUnpinExpired = !!contractUnpinExpired;
+
+    this.config.setConfig(updates);
+    // Keys only — values may be secrets
+    agentEvents.publish('config_changed', { keys: Object.keys(updates) });
+
+    // Restart the reconciler so enable/interval changes take effect
+    if (pinReconcileEnabled !== undefined || pinReconcileIntervalMin !== undefined) {
+      this.pinReconciler.stop();
+      this.pinReconciler.start();
+    }
+    if (contractSyncEnabled !== undefined || contractSyncIntervalMin !== undefined) {
+      this.contractSync.stop();
+      this.contractSync.start();
+    }
+    if (scrubEnabled !== undefined || scrubIntervalMin !== undefined) {
+      this.scrubber.stop();
+      this.scrubber.start();
+    }
+    if (selfChallengeEnabled !== undefined || selfChallengeIntervalMin !== undefined) {
+      this.selfChallenger.stop();
+      this.selfChallenger.start();
+    }
+    if (gcScheduleEnabled !== undefined) {
+      this.gcScheduler.stop();
+      this.gcScheduler.start();
+    }
+    if (updateMode !== undefined) this.updater?.setMode(updateMode);
+    if (priceFeedEnabled !== undefined || displayCurrency !== undefined) {
+      this.priceFeed.stop();
+      this.priceFeed.start(); // polls right away, so the new currency shows up
+    }
+    if (notifyDailySummary !== undefined || dailySummaryHour !== undefined) {
+      this.dailySummary.stop();
+      this.dailySummary.start();
+    }
+    if (payoutReconcileEnabled !== undefined || hiveUsername !== undefined) {
+      this.reconciler.stop();
+      this.reconciler.start();
+    }
+    if (chainWatcherEnabled !== undefined || hiveUsername !== undefined || hiveApiNodes !== undefined) {
+      this.chainWatcher.stop();
+      this.chainWatcher.start();
+    }
+    if (sealingEnabled !== undefined || hiveUsername !== undefined) {
+      this.sealStore.stop();
+      this.sealStore.start();
+    }
+
+    // Apply IPFS config changes if needed
+    const ipfsSettingsChanged: string[] = [];
+    if (bandwidthLimitUp !== undefined || bandwidthLimitDown !== undefined) {
+      const cfg = this.config.getConfig();
+      ipfsSettingsChanged.push(...this.kubo.applyBandwidthConfig(cfg.bandwidthLimitUp, cfg.bandwidthLimitDown));
+    }
+    const warnings: string[] = [];
+    if (storageMaxGB !== undefined) {
+      ipfsSettingsChanged.push(...this.kubo.applyStorageQuota(Number(storageMaxGB)));
       try {
-        this.server = this.app.listen(this.port, '127.0.0.1', () => {
-          console.log(`[API] Server listening on http://127.0.0.1:${this.port}`);
-          resolve();
-        });
+        const storage = await this.kubo.getStorageInfo();
+        if (storage.usedBytes > storage.maxBytes) {
+          warnings.push(`storageMaxGB (${storage.maxFormatted}) is below current repo usage (${storage.usedFormatted}) — ` +
+            'Kubo GC will remove unpinned blocks to shrink the repo; pinned content is kept but new pins will be rejected');
+          console.warn(`[API] ${warnings[warnings.length - 1]}`);
+        }
+      } catch {}
+    }
+    if (apiBindAddress !== undefined || apiPort !== undefined) {
+      warnings.push('apiBindAddress/apiPort take effect after the agent restarts');
+      if (apiBindAddress !== undefined && !['127.0.0.1', 'localhost', '::1'].includes(apiBindAddress)) {
+        warnings.push(`Binding to ${apiBindAddress} exposes read-only endpoints to the network`);
+      }
+    }
+    if (signChallengeProofs && (!this.wallet.getPostingKey() || !this.config.getConfig().hiveUsername)) {
+      warnings.push('signChallengeProofs needs hiveUsername and an unlocked posting key — proofs stay unsigned until both are set');
+    }
+    if (broadcastProofs && (!this.wallet.getPostingKey() || !this.config.getConfig().hiveUsername)) {
+      warnings.push('broadcastProofs needs hiveUsername and an unlocked posting key — nothing is broadcast until both are set');
+    }
+    if (hiveApiNodes !== undefined) {
+      warnings.push('hiveApiNodes take effect after the agent restarts');
+    }
+    const current = this.config.getConfig();
+    if ((launchdKeepAlive !== undefined || launchdThrottleSec !== undefined || launchdAsDaemon !== undefined) &&
+        current.autoStart && current.autoStartMode === 'launchd') {
+      // Regenerate the plist so the switches apply from the next launch
+      try {
+        warnings.push(...(await installAutostartService('launchd', current)).warnings);
+      } catch (error: any) {
+        warnings.push(`launchd settings saved but the plist was not rewritten: ${error.message}`);
+      }
+    }
+    if (requireBlockSalts) {
+      warnings.push('requireBlockSalts rejects challenges from validators that do not send blockHash/saltNonce — older validators will fail');
+    }
+    const needsRestart = ipfsSettingsChanged.length > 0;
+    if (needsRestart && this.kubo.isRunning()) {
+      // Defer restart if challenges are in-flight
+      const hasActive = this.agentWS?.hasActiveChallenges() ||
+        (this.challengeHandler && !this.challengeHandler.hasCapacity());
+      if (hasActive) {
+        console.log('[API] Config saved, but deferring IPFS restart — challenge in progress');
+        return { status: 200, body: { success: true, config: this.publicConfig(), ipfsSettingsChanged, restartDeferred: true, warnings } };
+      }
+      try {
+        await this.kubo.restart();
+      } catch (error: any) {
+        return { status: 500, body: { error: 'Failed to restart IPFS: ' + error.message } };
+      }
+    }
 
-        this.server.on('error', (error: any) => {
-          if (error.code === 'EADDRINUSE') {
-            console.log(`[API] Port ${this.port} in use, trying ${this.port + 1}`);
-            this.port++;
-            this.server = this.app.listen(this.port, '127.0.0.1', () => {
-              resolve();
-            });
-          } else {
-            reject(error);
-          }
-        });
-      } catch (error) {
-        reject(error);
+    // Legacy: Reconnect WebSocket if server URL or username changed
+    if ((serverUrl !== undefined || hiveUsername !== undefined || nodeId !== undefined || poolPrefix !== undefined) && this.agentWS) {
+      this.agentWS.reconnectToServer();
+    }
+
+    return { status: 200, body: { success: true, config: this.publicConfig(), ipfsSettingsChanged, warnings } };
+  }
+
+  /** Queue a background pin job `{ cid, name? }` after the storage check. Backs POST /api/pin. */
+  async queuePin(body: Record<string, any>): Promise<ApiResult> {
+    const { cid, name } = body;
+    if (!cid || !isValidCid(cid)) {
+      return { status: 400, body: { error: 'Valid CID required' } };
+    }
+    if (name !== undefined && (typeof name !== 'string' || name.length > 256)) {
+      return { status: 400, body: { error: 'name must be a string of at most 256 characters' } };
+    }
+
+    try {
+      // Refuse pins that would push the repo past storageMaxGB, counting pins still fetching
+      const { fits, storage, sizeBytes, pendingBytes, diskError } = await this.projectPin(cid);
+      if (fits && diskError) {
+        return { status: 507, body: { error: 'INSUFFICIENT_DISK_SPACE', message: diskError, pendingBytes, sizeBytes } };
+      }
+      if (!fits) {
+        return {
+          status: 507,
+          body: {
+            error: 'INSUFFICIENT_STORAGE',
+            message: `Pinning ${cid} would exceed the storage limit of ${storage.maxFormatted}`,
+            usedBytes: storage.usedBytes,
+            pendingBytes,
+            maxBytes: storage.maxBytes,
+            sizeBytes,
+          },
+        };
+      }
+
+      // Large pins outlive any HTTP timeout — fetch in the background, poll GET /api/jobs/:id
+      const job = this.pinJobs.enqueue(cid, name || null, sizeBytes);
+      return { status: 202, body: { success: true, jobId: job.id, job } };
+    } catch (error: any) {
+      return { status: 500, body: { error: error.message } };
+    }
+  }
+
+  /** Pin a contract assigned on chain, under the same storage limit as the contract sync. */
+  private async acceptChainContract(assignment: ChainContractAssignment): Promise<void> {
+    if (!this.config.getConfig().contractSyncEnabled || !this.kubo.isRunning()) return;
+    if (assignment.expiresAt && Date.parse(assignment.expiresAt) <= Date.now()) return;
+    const { fits, storage, sizeBytes, diskError } = await this.projectPin(assignment.cid);
+    if (!fits || diskError) {
+      console.warn(`[Chain] ${diskError || `Storage limit of ${storage.maxFormatted} reached`} — contract ${assignment.contractId} not pinned`);
+      return;
+    }
+    this.pinJobs.enqueue(assignment.cid, `contract:${assignment.contractId}`, sizeBytes, 'contract');
+  }
+
+  /** Unpin `{ cid }`. Backs POST /api/unpin. */
+  async unpin(body: Record<string, any>): Promise<ApiResult> {
+    const { cid } = body;
+    if (!cid || !isValidCid(cid)) {
+      return { status: 400, body: { error: 'Valid CID required' } };
+    }
+
+    try {
+      await this.kubo.unpin(cid);
+      agentEvents.publish('pin_removed', { cid });
+      return { status: 200, body: { success: true } };
+    } catch (error: any) {
+      return { status: 500, body: { error: error.message } };
+    }
+  }
+
+  /** One page of pins with metadata — see GET /api/pins for the query parameters. */
+  async listPins(query: Record<string, any>): Promise<ApiResult> {
+    const offset = query.offset === undefined ? 0 : Number(query.offset);
+    const limit = query.limit === undefined ? null : Number(query.limit);
+    const sort = String(query.sort || 'cid');
+    const order = String(query.order || 'asc');
+    if (!Number.isInteger(offset) || offset < 0) {
+      return { status: 400, body: { error: 'offset must be a non-negative integer' } };
+    }
+    if (limit !== null && (!Number.isInteger(limit) || limit < 1)) {
+      return { status: 400, body: { error: 'limit must be a positive integer' } };
+    }
+    if (!['cid', 'name', 'size'].includes(sort) || !['asc', 'desc'].includes(order)) {
+      return { status: 400, body: { error: 'sort must be cid, name or size; order must be asc or desc' } };
+    }
+    const source = query.source === undefined ? null : String(query.source);
+    if (source !== null && !PIN_SOURCES.includes(source as PinSource)) {
+      return { status: 400, body: { error: `source must be one of ${PIN_SOURCES.join(', ')}` } };
+    }
+
+    try {
+      const metadata = this.pinMetadata.getAll();
+      const q = String(query.q || '').toLowerCase();
+      let matching = await this.kubo.listPins();
+      if (q) {
+        matching = matching.filter((cid) =>
+          cid.toLowerCase().includes(q) || (metadata[cid]?.name || '').toLowerCase().includes(q) ||
+          (metadata[cid]?.tags || []).some((tag) => tag.toLowerCase().includes(q)));
+      }
+      if (source) {
+        // Pins from before provenance tracking have no source; they count as manual
+        matching = matching.filter((cid) => (metadata[cid]?.source || 'manual') === source);
+      }
+
+      let sizes: Record<string, number> = {};
+      if (sort === 'size') {
+        sizes = await this.kubo.getPinSizes(matching);
+        matching.sort((a, b) => (sizes[a] || 0) - (sizes[b] || 0) || a.localeCompare(b));
+      } else if (sort === 'name') {
+        matching.sort((a, b) => (metadata[a]?.name || '').localeCompare(metadata[b]?.name || '') || a.localeCompare(b));
+      } else {
+        matching.sort();
+      }
+      if (order === 'desc') matching.reverse();
+
+      // `pins` stays a bare CID list for older clients; `details` adds size and metadata
+      const pins = matching.slice(offset, limit === null ? undefined : offset + limit);
+      if (sort !== 'size') sizes = await this.kubo.getPinSizes(pins);
+      const details = pins.map((cid) => ({
+        cid,
+        name: metadata[cid]?.name || '',
+        size: sizes[cid] || 0,
+        tags: metadata[cid]?.tags || [],
+        source: metadata[cid]?.source || null,
+        pinnedAt: metadata[cid]?.pinnedAt || null,
+        lastChallengedAt: metadata[cid]?.lastChallengedAt || null,
+      }));
+      return { status: 200, body: { pins, details, total: matching.length, offset, limit } };
+    } catch (error: any) {
+      return { status: 500, body: { error: error.message } };
+    }
+  }
+
+  /**
+   * Live numbers for the tray menu, from the same sources as /metrics and
+   * /api/earnings/history.
+   */
+  async getTrayStats(): Promise<{
+    daemonRunning: boolean; pinnedFiles: number; repoSizeBytes: number; todayHbd: number; challengesPaused: boolean;
+  }> {
+    await this.refreshRepoGauges();
+    const midnight = new Date();
+    midnight.setHours(0, 0, 0, 0);
+    return {
+      daemonRunning: this.kubo.isRunning(),
+      pinnedFiles: this.repoGaugeCache?.pinnedFiles || 0,
+      repoSizeBytes: this.repoGaugeCache?.repoSizeBytes || 0,
+      todayHbd: this.config.getEarningsHistory(midnight.getTime(), Date.now()).totalHbd,
+      challengesPaused: this.config.getConfig().challengesPaused,
+    };
+  }
+
+  /** Tray "Pause challenges" — same switch as challengesPaused in /api/config. */
+  setChallengesPaused(paused: boolean): void {
+    this.config.setConfig({ challengesPaused: paused });
+    agentEvents.publish('config_changed', { keys: ['challengesPaused'] });
+  }
+
+  /** Tray "Run GC now" — null if the daemon is down or a run is in progress. */
+  async runGc(): Promise<GcRun | null> {
+    if (!this.kubo.isRunning()) return null;
+    return this.gcScheduler.run('manual');
+  }
+
+  /** Desktop-mode autostart: the Electron login item (no-op in CLI mode). */
+  private setLoginItem(openAtLogin: boolean): void {
+    if (!electronApp) return;
+    electronApp.setLoginItemSettings({
+      openAtLogin,
+      name: 'SPK Desktop Agent',
+      args: ['--autostart'], // lets the next launch enter the boot cooldown
+    });
+  }
+
+  private async refreshRepoGauges(): Promise<void> {
+    const cached = this.repoGaugeCache;
+    if (this.kubo.isRunning() && (!cached || Date.now() - cached.at > ApiServer.REPO_GAUGE_TTL_MS)) {
+      try {
+        const stats = await this.kubo.getStats();
+        const pins = await this.kubo.listPins(10000);
+        this.repoGaugeCache = {
+          repoSizeBytes: stats?.repoSize || 0,
+          pinnedFiles: pins.length,
+          at: Date.now(),
+        };
+      } catch {
+        // keep serving the last known values
+      }
+    }
+  }
+
+  /** Node-level /metrics gauges; repo numbers come from a TTL cache. */
+  private async nodeGauges(): Promise<NodeGauges> {
+    const daemonUp = this.kubo.isRunning();
+    await this.refreshRepoGauges();
+    const bandwidth = await this.kubo.getBandwidth();
+    return {
+      repoSizeBytes: this.repoGaugeCache?.repoSizeBytes || 0,
+      pinnedFiles: this.repoGaugeCache?.pinnedFiles || 0,
+      totalEarnedHbd: this.config.getEarnings().totalHbd,
+      daemonUp,
+      daemonRestarts: this.kubo.getWatchdogState().restartCount,
+      bandwidthInBytes: bandwidth.totalIn,
+      bandwidthOutBytes: bandwidth.totalOut,
+      swarmPeers: bandwidth.numPeers,
+    };
+  }
+
+  /** challenge_completed (and, on success, proof_submitted) events for bus subscribers. */
+  private publishChallenge(request: HttpChallengeRequest | undefined, result: HttpChallengeResult): void {
+    const cid = request?.cid;
+    if (result.body.success === true && result.body.proof) {
+      agentEvents.publish('proof_submitted', {
+        source: 'http', cid, salt: request?.salt, proof: result.body.proof, latencyMs: result.body.responseTime ?? null,
+      });
+    }
+    agentEvents.publish('challenge_completed', {
+      source: 'http',
+      cid,
+      status: result.status,
+      success: result.body.success === true,
+      latencyMs: result.body.responseTime ?? null,
+      proof: result.body.proof ?? null,
+      error: result.body.error ?? null,
+    });
+  }
+
+  /**
+   * Import the posting key into the encrypted wallet and record its public key
+   * and hash in config. The challenge signer reads the key back from the wallet
+   * on demand, so it takes effect without a restart.
+   */
+  private importSigningKey(key: string): { publicKey: string; keyHash: string } {
+    const publicKey = this.wallet.importPostingKey(key.trim());
+    const keyHash = hashString(key.trim());
+    this.config.setPostingPublicKey(publicKey);
+    this.config.setPostingKeyHash(keyHash);
+    return { publicKey, keyHash };
+  }
+
+  /** Port actually bound (differs from apiPort after a fallback or with apiPort 0). */
+  getPort(): number {
+    return this.port;
+  }
+
+  /**
+   * Bind to apiBindAddress:apiPort. Only the default port falls back to the
+   * next one when taken (a second desktop agent); an explicitly configured
+   * port that is in use is an error. apiPort 0 binds an ephemeral port.
+   */
+  async start(): Promise<void> {
+    const host = this.config.getConfig().apiBindAddress || '127.0.0.1';
+    if (!['127.0.0.1', 'localhost', '::1'].includes(host)) {
+      console.warn(`[API] WARNING: binding to ${host} — read-only endpoints (/api/status, /api/pins, /api/earnings, /metrics) ` +
+        'are reachable from the network unless authRequiredForReads is set. Other endpoints need the API token or a ' +
+        'paired/scoped token, except pairing and Keychain/validator login.');
+    }
+
+    try {
+      await this.listen(host, this.port);
+    } catch (error: any) {
+      if (error.code !== 'EADDRINUSE' || this.port !== 5111) {
+        throw error.code === 'EADDRINUSE'
+          ? new Error(`API port ${this.port} on ${host} is already in use — set apiPort (or SPK_API_PORT) to a free port`)
+          : error;
+      }
+      console.log(`[API] Port ${this.port} in use, trying ${this.port + 1}`);
+      await this.listen(host, this.port + 1).catch((retryError: any) => {
+        throw retryError.code === 'EADDRINUSE'
+          ? new Error(`API ports ${this.port} and ${this.port + 1} on ${host} are both in use — set apiPort (or SPK_API_PORT)`)
+          : retryError;
+      });
+    }
+
+    const address = this.server!.address();
+    if (address && typeof address === 'object') this.port = address.port;
+    console.log(`[API] Server listening on http://${host}:${this.port}`);
+    this.attachEventSocket(this.server!);
+    this.pinReconciler.start();
+    this.contractSync.start();
+    this.scrubber.start();
+    this.selfChallenger.start();
+    this.gcScheduler.start();
+    this.sealStore.start();
+    this.priceFeed.start();
+    this.chainWatcher.start();
+    this.reconciler.start();
+    this.dailySummary.start();
+    this.storageMonitor.start();
+    this.webhooks.start();
+    this.pinMetadata.start();
+    this.pinRequests.resume();
+  }
+
+  /**
+   * /api/ws — the agentEvents stream as JSON text frames ({ id, type, at, data }),
+   * for dashboards that would otherwise poll /api/status. Read-only like
+   * /api/events; browsers don't apply CORS to WebSockets, so the Origin header
+   * is checked here instead (no Origin = non-browser client, allowed). With
+   * authRequiredForReads the upgrade needs a read-scoped token, as a bearer
+   * header or ?token= (browsers can't set headers on a WebSocket).
+   */
+  private attachEventSocket(server: http.Server): void {
+    this.wss = new WebSocketServer({ noServer: true });
+    server.on('upgrade', (req, socket, head) => {
+      const url = new URL(req.url || '/', 'http://localhost');
+      if (url.pathname !== '/api/ws') return; // leave other upgrade handlers alone
+
+      const origin = req.headers.origin;
+      if (origin && !this.isAllowedOrigin(origin)) {
+        socket.write('HTTP/1.1 403 Forbidden\r\n\r\n');
+        socket.destroy();
+        return;
+      }
+      if (this.config.getConfig().authRequiredForReads) {
+        const token = ApiServer.bearerToken(req) ?? url.searchParams.get('token');
+        if (!this.tokenAllows(token, 'read', origin)) {
+          socket.write('HTTP/1.1 401 Unauthorized\r\n\r\n');
+          socket.destroy();
+          return;
+        }
+      }
+      this.wss!.handleUpgrade(req, socket, head, (ws) => this.streamEvents(ws));
+    });
+  }
+
+  private streamEvents(ws: WebSocket): void {
+    const unsubscribe = agentEvents.subscribe((event) => {
+      if (ws.readyState !== WebSocket.OPEN) return;
+      // Never let a stalled client back up the producers — drop it instead
+      if (ws.bufferedAmount > MAX_BUFFERED_BYTES) {
+        console.warn('[API] Dropping slow /api/ws client');
+        ws.terminate();
+        return;
       }
+      ws.send(JSON.stringify(event));
+    });
+    ws.on('close', unsubscribe);
+    ws.on('error', () => ws.terminate());
+  }
+
+  private listen(host: string, port: number): Promise<void> {
+    return new Promise((resolve, reject) => {
+      const server = this.app.listen(port, host);
+      server.once('listening', () => {
+        this.server = server;
+        resolve();
+      });
+      server.once('error', reject);
     });
   }
 
   async stop(): Promise<void> {
+    this.pinReconciler.stop();
+    this.contractSync.stop();
+    this.scrubber.stop();
+    this.selfChallenger.stop();
+    this.gcScheduler.stop();
+    this.sealStore.stop();
+    this.priceFeed.stop();
+    this.chainWatcher.stop();
+    this.reconciler.stop();
+    this.dailySummary.stop();
+    this.storageMonitor.stop();
+    this.webhooks.stop();
+    this.pinMetadata.stop();
+    this.eventHistory.stop();
+    this.pinRequests.flush();
+    this.apiTokens.flush();
+    if (this.wss) {
+      for (const client of this.wss.clients) client.terminate();
+      this.wss.close();
+      this.wss = null;
+    }
     return new Promise((resolve) => {
       if (this.server) {
         this.server.close(() => {
@@ -6,6 +6,8 @@
  * that would otherwise only be served by transient browser peers.
  */
 import axios from 'axios';
+import { bootCooldown } from './boot-cooldown';
+import { agentEvents } from './agent-events';
 
 interface PopularCid {
   cid: string;
@@ -58,7 +60,7 @@ export class AutoPinner {
   }
 
   private async checkAndPin(): Promise<void> {
-    if (!this.enabled) return;
+    if (!this.enabled || bootCooldown.isActive()) return;
 
     try {
       const res = await axios.get(`${this.serverUrl}/api/p2p/popular`, {
@@ -83,6 +85,7 @@ export class AutoPinner {
           );
           this.pinnedCids.add(item.cid);
           this.autoPinnedCids.add(item.cid);
+          agentEvents.publish('pin_added', { cid: item.cid, name: '', source: 'auto-pin' });
           // Query object size to track storage usage
           try {
             const statRes = await axios.post(
@@ -0,0 +1,356 @@
+/**
+ * autostart.ts — Start the agent with the OS
+ *
+ * `autoStartMode` picks the mechanism behind POST /api/autostart:
+ *
+ *   desktop   Electron login item (per-user, needs a graphical login)
+ *   systemd   Linux user unit ~/.config/systemd/user/hivepoa-agent.service,
+ *             restarted on failure. Works on headless servers and Wayland
+ *             kiosks; with lingering enabled it also runs without a login.
+ *   windows-service
+ *             Windows service "HivePoA Agent" (CLI build only), so a storage
+ *             box runs before anyone logs in. Node can't answer the service
+ *             control manager itself, so node-windows wraps it in WinSW; a
+ *             service stop reaches the agent as Ctrl+C/Ctrl+Break, which the
+ *             CLI turns into its normal clean shutdown. It logs on as the
+ *             installing user (password from SPK_SERVICE_PASSWORD), never as
+ *             LocalSystem — the agent has no need for machine-wide rights.
+ *   launchd   macOS LaunchAgent ~/Library/LaunchAgents/network.spk.hivepoa-agent.plist,
+ *             or with launchdAsDaemon a LaunchDaemon in /Library/LaunchDaemons
+ *             (root to install) that runs before login. launchdKeepAlive
+ *             relaunches it after a crash, at most every launchdThrottleSec.
+ *
+ * Services launch this same binary with --autostart (boot cooldown) and
+ * --headless (no display to attach to).
+ */
+
+import { execFile } from 'child_process';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { promisify } from 'util';
+import type { AgentConfig } from './config';
+
+// Optional — only the Windows service mode needs it
+let WindowsService: any = null;
+try {
+  WindowsService = require('node-windows').Service;
+} catch {
+  WindowsService = null;
+}
+
+const execFileAsync = promisify(execFile);
+
+export type AutostartMode = 'desktop' | 'systemd' | 'windows-service' | 'launchd';
+export const AUTOSTART_MODES: AutostartMode[] = ['desktop', 'systemd', 'windows-service', 'launchd'];
+
+/** The config fields the launchd plist is generated from. */
+export type LaunchdOptions = Pick<AgentConfig, 'launchdKeepAlive' | 'launchdThrottleSec' | 'launchdAsDaemon'>;
+
+export const SYSTEMD_UNIT_NAME = 'hivepoa-agent.service';
+export const WINDOWS_SERVICE_NAME = 'HivePoA Agent';
+export const LAUNCHD_LABEL = 'network.spk.hivepoa-agent';
+const SYSTEMCTL_TIMEOUT_MS = 15000;
+// Matches TimeoutStopSec — long enough for the agent to stop Kubo cleanly
+const SERVICE_STOP_TIMEOUT_SEC = 90;
+
+export interface AutostartServiceStatus {
+  installed: boolean;
+  enabled: boolean;
+  active: boolean;
+  location: string; // unit path or service name
+}
+
+/** Modes available on this platform. */
+export function supportedAutostartModes(): AutostartMode[] {
+  if (process.platform === 'linux') return ['desktop', 'systemd'];
+  // A service runs in session 0 without a desktop, so only the CLI build qualifies
+  if (process.platform === 'win32' && !process.versions.electron) return ['desktop', 'windows-service'];
+  if (process.platform === 'darwin') return ['desktop', 'launchd'];
+  return ['desktop'];
+}
+
+/** Install (or rewrite) the service behind `mode`. 'desktop' has none — that's the login item. */
+export async function installAutostartService(mode: AutostartMode, launchd: LaunchdOptions): Promise<{ warnings: string[] }> {
+  if (mode === 'systemd') return installSystemdService();
+  if (mode === 'windows-service') return installWindowsService();
+  if (mode === 'launchd') return installLaunchdService(launchd);
+  return { warnings: [] };
+}
+
+export async function uninstallAutostartService(mode: AutostartMode): Promise<void> {
+  if (mode === 'systemd') return uninstallSystemdService();
+  if (mode === 'windows-service') return uninstallWindowsService();
+  if (mode === 'launchd') return uninstallLaunchdService();
+}
+
+export async function autostartServiceStatus(mode: AutostartMode, launchd: LaunchdOptions): Promise<AutostartServiceStatus | undefined> {
+  if (mode === 'systemd') return systemdServiceStatus();
+  if (mode === 'windows-service') return windowsServiceStatus();
+  if (mode === 'launchd') return launchdServiceStatus(launchd);
+  return undefined;
+}
+
+/**
+ * Command line that relaunches this agent unattended: the Electron binary
+ * (plus the app path when unpackaged) or node plus the CLI script.
+ */
+export function agentLaunchCommand(): string[] {
+  const packagedElectron = !!process.versions.electron && !(process as any).defaultApp;
+  const script = packagedElectron ? [] : [path.resolve(process.argv[1])];
+  return [process.execPath, ...script, '--autostart', '--headless'];
+}
+
+function systemdUnitPath(): string {
+  return path.join(os.homedir(), '.config', 'systemd', 'user', SYSTEMD_UNIT_NAME);
+}
+
+/** systemd splits ExecStart on spaces unless the word is quoted. */
+function quoteSystemdArg(arg: string): string {
+  return /^[A-Za-z0-9_@%+=:,./-]+$/.test(arg) ? arg : `"${arg.replace(/(["\\])/g, '\\$1')}"`;
+}
+
+export function buildSystemdUnit(command: string[] = agentLaunchCommand()): string {
+  return [
+    '[Unit]',
+    'Description=HivePoA storage agent',
+    'After=network-online.target',
+    'Wants=network-online.target',
+    '',
+    '[Service]',
+    'Type=simple',
+    `ExecStart=${command.map(quoteSystemdArg).join(' ')}`,
+    'Restart=on-failure',
+    'RestartSec=10',
+    // Room for the agent's own graceful Kubo shutdown before systemd kills it
+    'TimeoutStopSec=90',
+    '',
+    '[Install]',
+    'WantedBy=default.target',
+    '',
+  ].join('\n');
+}
+
+async function systemctl(...args: string[]): Promise<string> {
+  const { stdout } = await execFileAsync('systemctl', ['--user', ...args], { timeout: SYSTEMCTL_TIMEOUT_MS });
+  return stdout.trim();
+}
+
+/**
+ * Write and enable the user unit. It starts at the next login (or boot, with
+ * lingering) — not now, since this agent is already running. Returns warnings
+ * for anything that only half worked.
+ */
+export async function installSystemdService(): Promise<{ warnings: string[] }> {
+  const unitPath = systemdUnitPath();
+  fs.mkdirSync(path.dirname(unitPath), { recursive: true });
+  fs.writeFileSync(unitPath, buildSystemdUnit());
+  await systemctl('daemon-reload');
+  await systemctl('enable', SYSTEMD_UNIT_NAME);
+
+  const warnings: string[] = [];
+  try {
+    // Lets the user manager (and the unit) start at boot without a login session
+    await execFileAsync('loginctl', ['enable-linger', os.userInfo().username], { timeout: SYSTEMCTL_TIMEOUT_MS });
+  } catch (err: any) {
+    warnings.push(`Could not enable lingering (${err.message.split('\n')[0]}) — the service starts at login; ` +
+      `run "sudo loginctl enable-linger ${os.userInfo().username}" to start it at boot`);
+  }
+  console.log(`[Autostart] Installed ${unitPath}`);
+  return { warnings };
+}
+
+export async function uninstallSystemdService(): Promise<void> {
+  const unitPath = systemdUnitPath();
+  if (!fs.existsSync(unitPath)) return;
+  try {
+    await systemctl('disable', SYSTEMD_UNIT_NAME);
+  } catch {}
+  fs.unlinkSync(unitPath);
+  await systemctl('daemon-reload').catch(() => '');
+  console.log(`[Autostart] Removed ${unitPath}`);
+}
+
+export async function systemdServiceStatus(): Promise<AutostartServiceStatus> {
+  const unitPath = systemdUnitPath();
+  const installed = fs.existsSync(unitPath);
+  const query = (verb: string) => systemctl(verb, SYSTEMD_UNIT_NAME).catch(() => '');
+  return {
+    installed,
+    enabled: installed && (await query('is-enabled')) === 'enabled',
+    active: installed && (await query('is-active')) === 'active',
+    location: unitPath,
+  };
+}
+
+function windowsService(): any {
+  if (!WindowsService) throw new Error('Windows service mode needs the node-windows package (npm install node-windows)');
+  const [execPath, script, ...scriptOptions] = agentLaunchCommand();
+  if (!script || !script.endsWith('.js')) throw new Error('Windows service mode needs the CLI build (node dist-cli/cli.js)');
+  // WinSW hands scriptOptions to the wrapper as one string split on spaces
+  const spaced = scriptOptions.find((arg) => /\s/.test(arg));
+  if (spaced) throw new Error(`Service arguments can't contain spaces: "${spaced}"`);
+  return new WindowsService({
+    name: WINDOWS_SERVICE_NAME,
+    description: 'HivePoA storage agent — IPFS pinning and proof-of-access challenges',
+    execPath,
+    script,
+    scriptOptions: scriptOptions.join(' '),
+    wait: 10,
+    grow: 0.5,
+    maxRestarts: 5,
+    stoptimeout: SERVICE_STOP_TIMEOUT_SEC,
+  });
+}
+
+/** Resolve once node-windows reports `action` done (or nothing to do). Needs an elevated process. */
+function runWindowsServiceAction(action: 'install' | 'uninstall', svc: any = windowsService()): Promise<void> {
+  return new Promise((resolve, reject) => {
+    svc.once(action, () => resolve());
+    svc.once(`already${action}ed`, () => resolve());
+    svc.once('invalidinstallation', () => reject(new Error('Existing service installation is incomplete — uninstall it first')));
+    svc.once('error', (err: any) => reject(err instanceof Error ? err : new Error(String(err))));
+    svc[action]();
+  });
+}
+
+/**
+ * Register the service (automatic start). Like the systemd unit it is not
+ * started now, since this agent is already running.
+ */
+export async function installWindowsService(): Promise<{ warnings: string[] }> {
+  const password = process.env.SPK_SERVICE_PASSWORD;
+  if (!password) {
+    throw new Error('Set SPK_SERVICE_PASSWORD to your Windows password — the service logs on as you, not as LocalSystem');
+  }
+  const svc = windowsService();
+  // Your own account: it owns ~/.spk-ipfs and has no rights beyond yours
+  svc.logOnAs.domain = process.env.USERDOMAIN || os.hostname();
+  svc.logOnAs.account = os.userInfo().username;
+  svc.logOnAs.password = password;
+  await runWindowsServiceAction('install', svc);
+  console.log(`[Autostart] Installed Windows service "${WINDOWS_SERVICE_NAME}"`);
+  const warnings = process.env.SPK_WALLET_PASSWORD ? [] : [
+    'SPK_WALLET_PASSWORD is not set — the service cannot unlock the wallet for signing; ' +
+    'set it as an environment variable of your account and restart the service',
+  ];
+  return { warnings };
+}
+
+export async function uninstallWindowsService(): Promise<void> {
+  if (!WindowsService || !windowsService().exists) return;
+  await runWindowsServiceAction('uninstall');
+  console.log(`[Autostart] Removed Windows service "${WINDOWS_SERVICE_NAME}"`);
+}
+
+export async function windowsServiceStatus(): Promise<AutostartServiceStatus> {
+  const installed = !!WindowsService && !!windowsService().exists;
+  let active = false;
+  if (installed) {
+    try {
+      const { stdout } = await execFileAsync('sc.exe', ['query', windowsService().id], { timeout: SYSTEMCTL_TIMEOUT_MS });
+      active = /STATE\s+:\s+\d+\s+RUNNING/.test(stdout);
+    } catch {}
+  }
+  return { installed, enabled: installed, active, location: WINDOWS_SERVICE_NAME };
+}
+
+function launchdPlistPath(asDaemon: boolean): string {
+  return asDaemon
+    ? path.join('/Library', 'LaunchDaemons', `${LAUNCHD_LABEL}.plist`)
+    : path.join(os.homedir(), 'Library', 'LaunchAgents', `${LAUNCHD_LABEL}.plist`);
+}
+
+function plistString(value: string): string {
+  return `<string>${value.replace(/&/g, '&amp;').replace(/</g, '&lt;').replace(/>/g, '&gt;')}</string>`;
+}
+
+export function buildLaunchdPlist(options: LaunchdOptions, command: string[] = agentLaunchCommand()): string {
+  const user = os.userInfo();
+  const lines = [
+    '<?xml version="1.0" encoding="UTF-8"?>',
+    '<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">',
+    '<plist version="1.0">',
+    '<dict>',
+    `  <key>Label</key>${plistString(LAUNCHD_LABEL)}`,
+    '  <key>ProgramArguments</key>',
+    '  <array>',
+    ...command.map((arg) => `    ${plistString(arg)}`),
+    '  </array>',
+    '  <key>RunAtLoad</key><true/>',
+    // Relaunch after a crash but not after a clean quit (tray Quit, Ctrl+C)
+    '  <key>KeepAlive</key>',
+    options.launchdKeepAlive ? '  <dict><key>SuccessfulExit</key><false/></dict>' : '  <false/>',
+    `  <key>ThrottleInterval</key><integer>${options.launchdThrottleSec}</integer>`,
+    `  <key>ExitTimeOut</key><integer>${SERVICE_STOP_TIMEOUT_SEC}</integer>`,
+    '  <key>ProcessType</key>',
+    '  <string>Background</string>',
+  ];
+  if (options.launchdAsDaemon) {
+    // Daemons run as root unless told otherwise — keep the user's ~/.spk-ipfs
+    lines.push(
+      `  <key>UserName</key>${plistString(user.username)}`,
+      '  <key>EnvironmentVariables</key>',
+      `  <dict><key>HOME</key>${plistString(user.homedir)}</dict>`,
+    );
+  }
+  lines.push('</dict>', '</plist>', '');
+  return lines.join('\n');
+}
+
+/**
+ * Write the plist (LaunchAgent or LaunchDaemon) and remove the other kind.
+ * launchd picks it up at the next login or boot; the running agent is not
+ * restarted. A LaunchDaemon needs root to install.
+ */
+export async function installLaunchdService(options: LaunchdOptions): Promise<{ warnings: string[] }> {
+  const plistPath = launchdPlistPath(options.launchdAsDaemon);
+  try {
+    fs.mkdirSync(path.dirname(plistPath), { recursive: true });
+    fs.writeFileSync(plistPath, buildLaunchdPlist(options), { mode: 0o644 });
+  } catch (err: any) {
+    if (options.launchdAsDaemon && (err.code === 'EACCES' || err.code === 'EPERM')) {
+      throw new Error(`Writing ${plistPath} needs root — run the agent once with sudo to install the LaunchDaemon`);
+    }
+    throw err;
+  }
+
+  const warnings: string[] = [];
+  const otherPath = launchdPlistPath(!options.launchdAsDaemon);
+  try {
+    if (fs.existsSync(otherPath)) fs.unlinkSync(otherPath);
+  } catch (err: any) {
+    warnings.push(`Could not remove ${otherPath} (${err.code || err.message}) — delete it so the agent isn't launched twice`);
+  }
+  // Clear a previous `launchctl disable`; harmless if the job was never disabled
+  const domain = options.launchdAsDaemon ? 'system' : `gui/${os.userInfo().uid}`;
+  await execFileAsync('launchctl', ['enable', `${domain}/${LAUNCHD_LABEL}`], { timeout: SYSTEMCTL_TIMEOUT_MS }).catch(() => undefined);
+  console.log(`[Autostart] Wrote ${plistPath}`);
+  return { warnings };
+}
+
+export async function uninstallLaunchdService(): Promise<void> {
+  for (const plistPath of [launchdPlistPath(false), launchdPlistPath(true)]) {
+    if (!fs.existsSync(plistPath)) continue;
+    try {
+      fs.unlinkSync(plistPath);
+    } catch (err: any) {
+      throw new Error(`Could not remove ${plistPath} (${err.code || err.message}) — remove it with sudo`);
+    }
+    console.log(`[Autostart] Removed ${plistPath}`);
+  }
+}
+
+export async function launchdServiceStatus(options: LaunchdOptions): Promise<AutostartServiceStatus> {
+  const plistPath = launchdPlistPath(options.launchdAsDaemon);
+  const installed = fs.existsSync(plistPath);
+  let active = false;
+  if (installed) {
+    const domain = options.launchdAsDaemon ? 'system' : `gui/${os.userInfo().uid}`;
+    try {
+      const { stdout } = await execFileAsync('launchctl', ['print', `${domain}/${LAUNCHD_LABEL}`], { timeout: SYSTEMCTL_TIMEOUT_MS });
+      active = /state = running/.test(stdout);
+    } catch {}
+  }
+  return { installed, enabled: installed, active, location: plistPath };
+}
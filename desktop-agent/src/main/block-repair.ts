@@ -0,0 +1,69 @@
+/**
+ * block-repair.ts — Replace missing or corrupt blocks of a pin
+ *
+ * Shared by the integrity scrubber and self-challenges. A missing block only
+ * needs fetching. A corrupt one has to be deleted first — block/get would keep
+ * serving the bad local copy — and Kubo refuses block/rm on any block a pin
+ * references. So when a pin has corrupt blocks it is:
+ *   1. unpinned
+ *   2. stripped of each corrupt block (block/rm errors are reported, not ignored)
+ *   3. re-fetched block by block from the network and checked against the CIDs
+ *   4. pinned again — always, even if a step above failed
+ *
+ * Nothing else references the DAG while it is unpinned, so a GC running in
+ * that window could drop blocks; re-pinning fetches them back. A re-pin that
+ * fails is reported against every block, since the pin itself is then gone.
+ */
+
+import type { KuboManager } from './kubo';
+import { blockMatchesCid } from './poa-crypto';
+
+export interface DamagedBlock {
+  blockCid: string;
+  reason: 'missing' | 'corrupt';
+}
+
+export interface BlockRepairResult {
+  repaired: string[];
+  failed: { blockCid: string; error: string }[];
+}
+
+/** Repair `blocks` of `pinCid`, giving each fetch (and the re-pin) up to `timeoutMs`. */
+export async function repairBlocks(kubo: KuboManager, pinCid: string, blocks: DamagedBlock[], timeoutMs: number): Promise<BlockRepairResult> {
+  const result: BlockRepairResult = { repaired: [], failed: [] };
+  const corrupt = blocks.filter((b) => b.reason === 'corrupt');
+
+  if (corrupt.length > 0) {
+    try {
+      await kubo.unpin(pinCid);
+    } catch (err: any) {
+      for (const block of blocks) result.failed.push({ blockCid: block.blockCid, error: `unpin failed: ${err.message}` });
+      return result;
+    }
+  }
+
+  try {
+    for (const block of blocks) {
+      try {
+        if (block.reason === 'corrupt') await kubo.removeBlock(block.blockCid);
+        const data = await kubo.getBlock(block.blockCid, timeoutMs);
+        if (blockMatchesCid(block.blockCid, data) === false) throw new Error('re-fetched block still does not match its CID');
+        result.repaired.push(block.blockCid);
+      } catch (err: any) {
+        result.failed.push({ blockCid: block.blockCid, error: err.message });
+      }
+    }
+  } finally {
+    if (corrupt.length > 0) {
+      try {
+        await kubo.pinWithProgress(pinCid, () => {}, timeoutMs);
+      } catch (err: any) {
+        const error = `pin ${pinCid} was removed for the repair and re-pinning failed: ${err.message}`;
+        result.failed = blocks.map((b) => ({ blockCid: b.blockCid, error }));
+        result.repaired = [];
+      }
+    }
+    kubo.invalidatePinSize(pinCid);
+  }
+  return result;
+}
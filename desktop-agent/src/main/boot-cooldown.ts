@@ -0,0 +1,55 @@
+/**
+ * boot-cooldown.ts — Gentle ramp-up after an autostart launch
+ *
+ * When the agent is started by the OS at login (--autostart / --minimized, or
+ * macOS wasOpenedAtLogin), the user is usually still booting into their
+ * desktop. For bootCooldownMin minutes, background work that competes for disk
+ * and network (scrubbing, pin reconciliation, auto-pinning, challenge
+ * readahead) is deferred. Challenges themselves are always answered.
+ *
+ * Kubo's own reprovider runs inside the daemon on its own schedule and is not
+ * affected.
+ */
+
+export interface BootCooldownState {
+  active: boolean;
+  launchedAtLogin: boolean;
+  endsAt: string | null;
+  remainingSec: number;
+}
+
+/** Whether this process was launched by autostart rather than by the user. */
+export function wasLaunchedAtLogin(argv: string[], openedAtLogin: boolean = false): boolean {
+  return openedAtLogin || argv.includes('--autostart') || argv.includes('--minimized');
+}
+
+export class BootCooldown {
+  private launchedAtLogin = false;
+  private endsAt = 0;
+
+  /** Start the cooldown. A duration of 0 records the launch mode but defers nothing. */
+  begin(durationMs: number): void {
+    this.launchedAtLogin = true;
+    this.endsAt = Date.now() + durationMs;
+    if (durationMs <= 0) return;
+    console.log(`[Boot] Launched at login — deferring background work for ${Math.round(durationMs / 60000)} min`);
+    setTimeout(() => console.log('[Boot] Cooldown over — resuming full activity'), durationMs).unref();
+  }
+
+  isActive(): boolean {
+    return Date.now() < this.endsAt;
+  }
+
+  getState(): BootCooldownState {
+    const remainingMs = Math.max(0, this.endsAt - Date.now());
+    return {
+      active: remainingMs > 0,
+      launchedAtLogin: this.launchedAtLogin,
+      endsAt: this.endsAt > 0 ? new Date(this.endsAt).toISOString() : null,
+      remainingSec: Math.ceil(remainingMs / 1000),
+    };
+  }
+}
+
+/** Process-wide cooldown, started once by index.ts / cli.ts. */
+export const bootCooldown = new BootCooldown();
@@ -0,0 +1,225 @@
+/**
+ * chain-watcher.ts — React to Hive operations addressed to this node
+ *
+ * Opt-in (chainWatcherEnabled, needs hiveUsername). Follows irreversible blocks
+ * — last_irreversible_block_num, about 45 s behind head, so nothing acted on
+ * can be forked away — and handles, without the web app relaying them:
+ *
+ *   spk_poa_challenge     custom_json { node, cid, salt, blockIndices | blockIndex }
+ *                         → answered like POST /api/challenge (ledger, events and,
+ *                           with broadcastProofs, an on-chain spk_poa_proof)
+ *   spk_storage_contract  custom_json { type: 'assign', node, contractId, cid, expiresAt }
+ *                         → pin queued with source 'contract' if contractSyncEnabled
+ *   transfer to hiveUsername
+ *                         → payout_received event (amount, from, memo)
+ *
+ * Anyone can post a custom_json, so challenges and contracts are only acted on
+ * when authored by a top-150 witness — the same bar as validator login. Each
+ * handled operation is published as an agent event (chain_challenge,
+ * chain_contract, payout_received).
+ *
+ * The block cursor is kept in ~/.spk-ipfs/chain-cursor.json. The first start
+ * begins at the current irreversible block; after downtime the watcher catches
+ * up at most MAX_CATCHUP_BLOCKS.
+ */
+
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import type { ConfigStore } from './config';
+import type { HttpChallengeRequest } from './http-challenge';
+import { AgentHiveClient } from './hive';
+import { agentEvents } from './agent-events';
+import { isValidCid } from './poa-crypto';
+import { JsonFileWriter } from './json-writer';
+
+export interface ChainContractAssignment {
+  contractId: string;
+  cid: string;
+  expiresAt: string | null;
+  authority: string;
+  block: number;
+}
+
+export interface ChainWatcherHandlers {
+  onChallenge(request: HttpChallengeRequest): Promise<void>;
+  onContract(assignment: ChainContractAssignment): Promise<void>;
+}
+
+const CHAIN_POLL_INTERVAL_MS = 15000;
+const MAX_BLOCKS_PER_POLL = 200;
+const BLOCK_BATCH = 50;
+const MAX_CATCHUP_BLOCKS = 28800; // ~1 day
+const WITNESS_CACHE_TTL_MS = 3600000;
+const NAI_SYMBOLS: Record<string, string> = { '@@000000013': 'HBD', '@@000000021': 'HIVE', '@@000000037': 'VESTS' };
+
+/** [name, payload] for both condenser (`['transfer', {...}]`) and appbase (`{ type: 'transfer_operation', value }`) ops. */
+function opParts(op: any): [string, any] | null {
+  if (Array.isArray(op)) return [op[0], op[1]];
+  if (typeof op?.type === 'string') return [op.type.replace(/_operation$/, ''), op.value];
+  return null;
+}
+
+/** "1.000 HBD" from either asset format. */
+function formatAsset(amount: any): string {
+  if (typeof amount === 'string') return amount;
+  const precision = Number(amount?.precision ?? 3);
+  return `${(Number(amount?.amount) / 10 ** precision).toFixed(precision)} ${NAI_SYMBOLS[amount?.nai] || amount?.nai}`;
+}
+
+export class ChainWatcher {
+  private config: ConfigStore;
+  private handlers: ChainWatcherHandlers;
+  private hive: AgentHiveClient | null = null;
+  private timer: NodeJS.Timeout | null = null;
+  private polling = false;
+  private cursor: number | null = null;
+  private cursorWriter: JsonFileWriter;
+  private witnesses: { names: Set<string>; at: number } | null = null;
+  private lastPollAt: string | null = null;
+  private lastError: string | null = null;
+  private counts = { challenges: 0, contracts: 0, payouts: 0, ignored: 0 };
+
+  constructor(config: ConfigStore, handlers: ChainWatcherHandlers,
+    cursorPath: string = path.join(os.homedir(), '.spk-ipfs', 'chain-cursor.json')) {
+    this.config = config;
+    this.handlers = handlers;
+    this.cursorWriter = new JsonFileWriter(cursorPath, () => ({ block: this.cursor }));
+    try {
+      const block = JSON.parse(fs.readFileSync(cursorPath, 'utf-8'))?.block;
+      if (Number.isInteger(block)) this.cursor = block;
+    } catch {}
+  }
+
+  start(): void {
+    const { chainWatcherEnabled, hiveUsername, hiveApiNodes } = this.config.getConfig();
+    if (!chainWatcherEnabled || !hiveUsername || this.timer) return;
+    this.hive = new AgentHiveClient({ username: hiveUsername, nodes: hiveApiNodes });
+    this.timer = setInterval(() => this.poll().catch(() => {}), CHAIN_POLL_INTERVAL_MS);
+    this.poll().catch(() => {});
+    console.log(`[Chain] Watching irreversible blocks for @${hiveUsername}`);
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+    this.cursorWriter.flush();
+  }
+
+  getStatus(): Record<string, any> {
+    const { chainWatcherEnabled, hiveUsername } = this.config.getConfig();
+    return {
+      enabled: chainWatcherEnabled,
+      running: this.timer !== null,
+      account: hiveUsername,
+      cursor: this.cursor,
+      lastPollAt: this.lastPollAt,
+      lastError: this.lastError,
+      ...this.counts,
+    };
+  }
+
+  private async poll(): Promise<void> {
+    if (this.polling || !this.hive) return;
+    this.polling = true;
+    try {
+      const props = await this.hive.getDynamicGlobalProperties();
+      const irreversible: number = props.last_irreversible_block_num;
+      if (this.cursor === null || irreversible - this.cursor > MAX_CATCHUP_BLOCKS) {
+        if (this.cursor !== null) console.warn(`[Chain] ${irreversible - this.cursor} blocks behind — skipping ahead`);
+        this.cursor = this.cursor === null ? irreversible : irreversible - MAX_CATCHUP_BLOCKS;
+      }
+      const end = Math.min(irreversible, this.cursor + MAX_BLOCKS_PER_POLL);
+      while (this.cursor < end) {
+        const start = this.cursor + 1;
+        const blocks = await this.hive.getBlockRange(start, Math.min(BLOCK_BATCH, end - this.cursor));
+        if (blocks.length === 0) break;
+        for (let i = 0; i < blocks.length; i++) {
+          await this.processBlock(start + i, blocks[i]);
+          this.cursor = start + i;
+        }
+        this.cursorWriter.schedule();
+      }
+      this.lastPollAt = new Date().toISOString();
+      this.lastError = null;
+    } catch (err: any) {
+      this.lastError = err.message;
+      console.warn(`[Chain] Poll failed: ${err.message}`);
+    } finally {
+      this.polling = false;
+    }
+  }
+
+  private async processBlock(blockNum: number, block: any): Promise<void> {
+    const account = this.config.getConfig().hiveUsername;
+    for (const tx of block?.transactions || []) {
+      for (const rawOp of tx.operations || []) {
+        const parts = opParts(rawOp);
+        if (!parts) continue;
+        const [name, op] = parts;
+        try {
+          if (name === 'transfer' && op.to === account) {
+            this.counts.payouts++;
+            agentEvents.publish('payout_received', {
+              from: op.from, amount: formatAsset(op.amount), memo: op.memo || '', block: blockNum, txId: tx.transaction_id ?? null,
+            });
+          } else if (name === 'custom_json' && (op.id === 'spk_poa_challenge' || op.id === 'spk_storage_contract')) {
+            const json = JSON.parse(op.json);
+            if (json?.node !== account) continue;
+            const author: string = op.required_posting_auths?.[0] || op.required_auths?.[0] || '';
+            if (!(await this.isTrusted(author))) {
+              this.counts.ignored++;
+              console.warn(`[Chain] Ignoring ${op.id} from @${author} (not a top-150 witness) in block ${blockNum}`);
+              continue;
+            }
+            if (op.id === 'spk_poa_challenge') {
+              await this.handleChallenge(json, author, blockNum);
+            } else {
+              await this.handleContract(json, author, blockNum);
+            }
+          }
+        } catch (err: any) {
+          console.warn(`[Chain] Skipping malformed ${name} in block ${blockNum}: ${err.message}`);
+        }
+      }
+    }
+  }
+
+  private async handleChallenge(json: any, validator: string, block: number): Promise<void> {
+    if (typeof json.cid !== 'string' || typeof json.salt !== 'string') throw new Error('challenge needs cid and salt');
+    this.counts.challenges++;
+    agentEvents.publish('chain_challenge', { cid: json.cid, validator, block });
+    await this.handlers.onChallenge({
+      cid: json.cid,
+      salt: json.salt,
+      blockIndices: Array.isArray(json.blockIndices) ? json.blockIndices : undefined,
+      blockIndex: Number.isInteger(json.blockIndex) ? json.blockIndex : undefined,
+      validatorId: validator,
+    });
+  }
+
+  private async handleContract(json: any, authority: string, block: number): Promise<void> {
+    if (json.type !== 'assign') return;
+    if (typeof json.cid !== 'string' || !isValidCid(json.cid)) throw new Error('contract needs a valid cid');
+    const assignment: ChainContractAssignment = {
+      contractId: String(json.contractId ?? ''),
+      cid: json.cid,
+      expiresAt: typeof json.expiresAt === 'string' ? json.expiresAt : null,
+      authority,
+      block,
+    };
+    this.counts.contracts++;
+    agentEvents.publish('chain_contract', { ...assignment });
+    await this.handlers.onContract(assignment);
+  }
+
+  private async isTrusted(author: string): Promise<boolean> {
+    if (!author || !this.hive) return false;
+    if (!this.witnesses || Date.now() - this.witnesses.at > WITNESS_CACHE_TTL_MS) {
+      this.witnesses = { names: new Set(await this.hive.getTopWitnesses(150)), at: Date.now() };
+    }
+    return this.witnesses.names.has(author);
+  }
+}
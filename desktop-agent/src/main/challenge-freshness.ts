@@ -0,0 +1,95 @@
+/**
+ * challenge-freshness.ts — Replay protection shared by the HTTP and PubSub paths
+ *
+ * SaltReplayGuard remembers every (cid, salt) pair answered inside a window, so
+ * a relay can't resend an old challenge and collect a cached proof for it.
+ *
+ * BlockSaltVerifier backs requireBlockSalts: the challenge must carry the full
+ * Hive block id and a nonce with salt = sha256(`${blockHash}:${saltNonce}`)
+ * (deriveBlockSalt), and the block must be on chain and no more than
+ * MAX_SALT_BLOCK_AGE blocks behind head. A salt therefore can't have been
+ * chosen before that block existed.
+ */
+
+import type { AgentHiveClient } from './hive';
+import { deriveBlockSalt } from './poa-crypto';
+
+export const MAX_SALT_BLOCK_AGE = 200; // ~10 minutes of 3s blocks
+const MAX_CONFIRMED_BLOCKS = 1000;
+
+export class SaltReplayGuard {
+  private windowMs: number;
+  private maxEntries: number;
+  // `${cid}:${salt}` → first seen (ms), oldest first; pruned past the window
+  private seen: Map<string, number> = new Map();
+
+  constructor(windowMs: number, maxEntries: number) {
+    this.windowMs = windowMs;
+    this.maxEntries = maxEntries;
+  }
+
+  /** Record a (cid, salt) pair; false if it was already used inside the window. */
+  claim(cid: string, salt: string, now: number = Date.now()): boolean {
+    for (const [key, seenAt] of this.seen) {
+      if (now - seenAt < this.windowMs && this.seen.size <= this.maxEntries) break;
+      this.seen.delete(key);
+    }
+    const key = `${cid}:${salt}`;
+    if (this.seen.has(key)) return false;
+    this.seen.set(key, now);
+    return true;
+  }
+
+  /** Give back a claim whose challenge was never answered, so the validator can retry it. */
+  release(cid: string, salt: string): void {
+    this.seen.delete(`${cid}:${salt}`);
+  }
+}
+
+export type SaltCheck =
+  | { ok: true }
+  | { ok: false; reason: string; unverifiable?: boolean };
+
+export class BlockSaltVerifier {
+  private hive: AgentHiveClient;
+  // Block ids already matched against the chain, oldest first
+  private confirmed: Set<string> = new Set();
+
+  constructor(hive: AgentHiveClient) {
+    this.hive = hive;
+  }
+
+  async verify(salt: string, blockHash: unknown, saltNonce: unknown): Promise<SaltCheck> {
+    if (typeof blockHash !== 'string' || !/^[0-9a-f]{40}$/.test(blockHash)) {
+      return { ok: false, reason: 'blockHash must be the full 40-hex Hive block id' };
+    }
+    if (typeof saltNonce !== 'string' || saltNonce.length === 0 || saltNonce.length > 128) {
+      return { ok: false, reason: 'saltNonce must be a 1-128 character string' };
+    }
+    if (deriveBlockSalt(blockHash, saltNonce) !== salt) {
+      return { ok: false, reason: 'salt is not sha256(blockHash:saltNonce)' };
+    }
+
+    // A Hive block id starts with the block number (big-endian u32)
+    const blockNum = parseInt(blockHash.slice(0, 8), 16);
+    try {
+      const head = await this.hive.getHeadBlockNumber();
+      if (blockNum > head + 1 || head - blockNum > MAX_SALT_BLOCK_AGE) {
+        return { ok: false, reason: `block ${blockNum} is not within ${MAX_SALT_BLOCK_AGE} blocks of head ${head}` };
+      }
+      if (!this.confirmed.has(blockHash)) {
+        const block = await this.hive.getBlock(blockNum);
+        if (!block || block.block_id !== blockHash) {
+          return { ok: false, reason: `blockHash does not match block ${blockNum} on chain` };
+        }
+        this.confirmed.add(blockHash);
+        if (this.confirmed.size > MAX_CONFIRMED_BLOCKS) {
+          this.confirmed.delete(this.confirmed.values().next().value as string);
+        }
+      }
+    } catch (err: any) {
+      return { ok: false, reason: `Hive API unavailable: ${err.message}`, unverifiable: true };
+    }
+    return { ok: true };
+  }
+}
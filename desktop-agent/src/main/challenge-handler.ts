@@ -1,7 +1,10 @@
 import { PubSubBridge } from './pubsub';
-import { ConfigStore } from './config';
+import { ConfigStore, resolveNodeId } from './config';
 import { AgentHiveClient } from './hive';
-import { computeProofHash, getBlockCids, computeBlockListHash, isValidCid } from './poa-crypto';
+import { computeProofHash, getBlockCids, computeBlockListHash, isValidCid, retryTransient, proofBlockCount } from './poa-crypto';
+import type { AgentMetrics } from './metrics';
+import { agentEvents } from './agent-events';
+import { BlockSaltVerifier, SaltReplayGuard } from './challenge-freshness';
 
 export interface ChallengeMessage {
   type: 'challenge';
@@ -12,6 +15,8 @@ export interface ChallengeMessage {
   blockHash: string;       // First 16 chars of Hive block hash (for verification)
   timestamp: number;
   nonce: string;            // Random nonce to prevent replay
+  blockId?: string;         // Full Hive block id when salt = sha256(blockId:saltNonce)
+  saltNonce?: string;
 }
 
 export interface ChallengeResponse {
@@ -25,6 +30,8 @@ export interface ChallengeResponse {
   status: 'success' | 'fail';
   error?: string;
   nonce: string;           // Must match challenge nonce
+  nodeId?: string;         // Responder's coordinator-facing node ID (covered by the signature)
+  retriesUsed?: number;    // Transient read errors retried before answering
 }
 
 /** Protocol v2: Two-phase commitment — proves data is stored locally (not fetched on-demand). */
@@ -49,6 +56,7 @@ export interface CommitmentResponse {
   status: 'success' | 'fail';
   error?: string;
   nonce: string;
+  nodeId?: string;
   protocolVersion: 2;
 }
 
@@ -57,6 +65,19 @@ const CHALLENGE_TIMEOUT = 24000; // 24s (1s buffer for 25s server-side limit)
 const COMMITMENT_TIMEOUT = 1800; // 1.8s (200ms buffer for 2s validator-side limit)
 const MAX_CONCURRENT = 5;
 const MAX_PER_VALIDATOR_PER_30S = 1;
+// A challenge still in flight after this long is hung (e.g. a stalled Kubo call
+// that ignored its timeout) — fail it and free the slot. Well above CHALLENGE_TIMEOUT.
+const IN_FLIGHT_TTL_MS = 60000;
+const REAP_INTERVAL_MS = 10000;
+const SALT_REPLAY_WINDOW_MS = 10 * 60000;
+const MAX_SEEN_SALTS = 10000;
+
+interface InFlightChallenge {
+  kind: 'challenge' | 'commitment';
+  cid: string;
+  validatorPeer: string;
+  startedAt: number;
+}
 
 /**
  * Handles incoming challenges from other validators via IPFS PubSub.
@@ -68,10 +89,15 @@ export class ChallengeHandler {
   private myUsername: string;
   private config: ConfigStore;
   private hive: AgentHiveClient;
-  private activeChallenges = 0;
+  private inFlight: Map<string, InFlightChallenge> = new Map(); // nonce → in-flight entry
+  private abandoned = 0;
   private seenNonces: Map<string, number> = new Map(); // nonce → timestamp
+  private replayGuard = new SaltReplayGuard(SALT_REPLAY_WINDOW_MS, MAX_SEEN_SALTS);
+  private saltVerifier: BlockSaltVerifier;
   private validatorTimestamps: Map<string, number> = new Map(); // validator → last challenge timestamp
   private cleanupInterval: NodeJS.Timeout | null = null;
+  private reapInterval: NodeJS.Timeout | null = null;
+  private metrics: AgentMetrics | null = null;
 
   constructor(kuboApiUrl: string, pubsub: PubSubBridge, myUsername: string, config: ConfigStore, hive: AgentHiveClient) {
     this.kuboApiUrl = kuboApiUrl;
@@ -79,18 +105,37 @@ export class ChallengeHandler {
     this.myUsername = myUsername;
     this.config = config;
     this.hive = hive;
+    this.saltVerifier = new BlockSaltVerifier(hive);
 
     // Clean up old nonces and timestamps every 60 seconds
     this.cleanupInterval = setInterval(() => this.cleanup(), 60000);
+    this.reapInterval = setInterval(() => this.reapInFlight(), REAP_INTERVAL_MS);
   }
 
   /** Handle an incoming challenge from PubSub. */
   async handleChallenge(challenge: ChallengeMessage): Promise<void> {
     // Validation checks
     if (!this.validateChallenge(challenge)) return;
+    if (this.config.getConfig().challengesPaused) {
+      console.log(`[ChallengeHandler] Challenges paused — ignoring ${challenge.cid.slice(0, 12)}... from ${challenge.validatorPeer}`);
+      return;
+    }
+
+    // A fresh nonce doesn't make an old (cid, salt) pair new
+    if (!this.replayGuard.claim(challenge.cid, challenge.salt)) {
+      console.log(`[ChallengeHandler] Replayed salt from ${challenge.validatorPeer} for ${challenge.cid.slice(0, 12)}...`);
+      return;
+    }
+    if (this.config.getConfig().requireBlockSalts) {
+      const check = await this.saltVerifier.verify(challenge.salt, challenge.blockId, challenge.saltNonce);
+      if (!check.ok) {
+        console.log(`[ChallengeHandler] Rejected salt from ${challenge.validatorPeer}: ${check.reason}`);
+        return;
+      }
+    }
 
     // Rate limiting
-    if (this.activeChallenges >= MAX_CONCURRENT) {
+    if (this.inFlight.size >= MAX_CONCURRENT) {
       console.log(`[ChallengeHandler] Dropping challenge from ${challenge.validatorPeer} — at capacity`);
       return;
     }
@@ -105,16 +150,24 @@ export class ChallengeHandler {
     }
 
     this.validatorTimestamps.set(challenge.validatorPeer, Date.now());
-    this.activeChallenges++;
+    agentEvents.publish('challenge_received', { source: 'pubsub', cid: challenge.cid, validator: challenge.validatorPeer });
+    this.inFlight.set(challenge.nonce, {
+      kind: 'challenge', cid: challenge.cid, validatorPeer: challenge.validatorPeer, startedAt: Date.now(),
+    });
 
     const startTime = Date.now();
+    let retriesUsed = 0;
+    let blocks: number | undefined;
 
     try {
-      // Race proof computation against timeout
-      const proofPromise = (async () => {
+      // Race proof computation against timeout; transient read errors get a short retry
+      const proofPromise = retryTransient(async () => {
         const blockCids = await getBlockCids(this.kuboApiUrl, challenge.cid);
-        return await computeProofHash(this.kuboApiUrl, challenge.salt, challenge.cid, blockCids);
-      })();
+        const proof = await computeProofHash(this.kuboApiUrl, challenge.salt, challenge.cid, blockCids);
+        blocks = proofBlockCount(challenge.salt, blockCids.length);
+        this.metrics?.recordBlocksHashed(blocks);
+        return proof;
+      }, this.config.getConfig().challengeRetries, undefined, () => retriesUsed++);
 
       const timeoutPromise = new Promise<never>((_, reject) => {
         setTimeout(() => reject(new Error('PROOF_TIMEOUT')), CHALLENGE_TIMEOUT);
@@ -124,6 +177,7 @@ export class ChallengeHandler {
       const elapsed = Date.now() - startTime;
 
       console.log(`[ChallengeHandler] Proof computed in ${elapsed}ms for ${challenge.cid.slice(0, 12)}...`);
+      if (!this.inFlight.has(challenge.nonce)) return; // reaped as abandoned — already failed
 
       // Send response via PubSub
       const response: ChallengeResponse = {
@@ -136,15 +190,23 @@ export class ChallengeHandler {
         elapsed,
         status: 'success',
         nonce: challenge.nonce,
+        retriesUsed,
+        ...this.nodeIdField(),
       };
 
       const signer = (payload: string) => this.hive.signMessage(payload);
       await this.pubsub.publish(CHALLENGE_TOPIC, response, signer);
-      this.config.recordChallenge(true, 0.001);
+      agentEvents.publish('proof_submitted', { source: 'pubsub', cid: challenge.cid, salt: challenge.salt, proof: proofHash, latencyMs: elapsed });
+      agentEvents.publish('challenge_completed', {
+        source: 'pubsub', cid: challenge.cid, validator: challenge.validatorPeer, success: true, latencyMs: elapsed, proof: proofHash,
+      });
+      this.config.recordChallenge(true, 0.001, challenge.cid, { validator: challenge.validatorPeer, latencyMs: elapsed, blocks });
+      this.metrics?.recordChallenge(challenge.cid, true, elapsed);
 
     } catch (err: any) {
       const elapsed = Date.now() - startTime;
       console.error(`[ChallengeHandler] Challenge failed: ${err.message}`);
+      if (!this.inFlight.has(challenge.nonce)) return;
 
       const response: ChallengeResponse = {
         type: 'response',
@@ -157,13 +219,22 @@ export class ChallengeHandler {
         status: 'fail',
         error: err.message,
         nonce: challenge.nonce,
+        retriesUsed,
+        ...this.nodeIdField(),
       };
 
       const signer = (payload: string) => this.hive.signMessage(payload);
       await this.pubsub.publish(CHALLENGE_TOPIC, response, signer);
-      this.config.recordChallenge(false, 0);
+      agentEvents.publish('challenge_completed', {
+        source: 'pubsub', cid: challenge.cid, validator: challenge.validatorPeer, success: false, latencyMs: elapsed, proof: null,
+        error: err.message,
+      });
+      this.config.recordChallenge(false, 0, challenge.cid, {
+        validator: challenge.validatorPeer, latencyMs: elapsed, blocks, error: err.message,
+      });
+      this.metrics?.recordChallenge(challenge.cid, false, elapsed);
     } finally {
-      this.activeChallenges = Math.max(0, this.activeChallenges - 1);
+      this.inFlight.delete(challenge.nonce);
     }
   }
 
@@ -223,10 +294,12 @@ export class ChallengeHandler {
     if (this.seenNonces.has(request.nonce)) return;
     if (!request.cid || !isValidCid(request.cid)) return;
 
-    if (this.activeChallenges >= MAX_CONCURRENT) return;
+    if (this.inFlight.size >= MAX_CONCURRENT) return;
 
     this.seenNonces.set(request.nonce, Date.now());
-    this.activeChallenges++;
+    this.inFlight.set(request.nonce, {
+      kind: 'commitment', cid: request.cid, validatorPeer: request.validatorPeer, startedAt: Date.now(),
+    });
 
     const startTime = Date.now();
 
@@ -241,6 +314,7 @@ export class ChallengeHandler {
       const elapsed = Date.now() - startTime;
 
       console.log(`[ChallengeHandler] Commitment computed in ${elapsed}ms for ${request.cid.slice(0, 12)}...`);
+      if (!this.inFlight.has(request.nonce)) return; // reaped as abandoned
 
       const response: CommitmentResponse = {
         type: 'commitment-response',
@@ -252,6 +326,7 @@ export class ChallengeHandler {
         elapsed,
         status: 'success',
         nonce: request.nonce,
+        ...this.nodeIdField(),
         protocolVersion: 2,
       };
 
@@ -260,6 +335,7 @@ export class ChallengeHandler {
     } catch (err: any) {
       const elapsed = Date.now() - startTime;
       console.error(`[ChallengeHandler] Commitment failed: ${err.message}`);
+      if (!this.inFlight.has(request.nonce)) return;
 
       const response: CommitmentResponse = {
         type: 'commitment-response',
@@ -272,24 +348,57 @@ export class ChallengeHandler {
         status: 'fail',
         error: err.message,
         nonce: request.nonce,
+        ...this.nodeIdField(),
         protocolVersion: 2,
       };
 
       const signer = (payload: string) => this.hive.signMessage(payload);
       await this.pubsub.publish(CHALLENGE_TOPIC, response, signer);
     } finally {
-      this.activeChallenges = Math.max(0, this.activeChallenges - 1);
+      this.inFlight.delete(request.nonce);
     }
   }
 
+  /** Coordinator-facing node ID to stamp on signed responses (omitted if unresolvable). */
+  private nodeIdField(): { nodeId?: string } {
+    const nodeId = resolveNodeId(this.config.getConfig(), this.pubsub.getPeerId());
+    return nodeId ? { nodeId } : {};
+  }
+
+  /** Attach the shared metrics registry (called by ApiServer.setP2PModules). */
+  setMetrics(metrics: AgentMetrics): void {
+    this.metrics = metrics;
+  }
+
   /** Check if handler has capacity for more challenges. */
   hasCapacity(): boolean {
-    return this.activeChallenges < MAX_CONCURRENT;
+    return this.inFlight.size < MAX_CONCURRENT;
   }
 
   /** Get active challenge count. */
   getActiveChallengeCount(): number {
-    return this.activeChallenges;
+    return this.inFlight.size;
+  }
+
+  /** Challenges force-failed by the in-flight TTL reaper since startup. */
+  getAbandonedCount(): number {
+    return this.abandoned;
+  }
+
+  /** Fail and release any in-flight challenge older than IN_FLIGHT_TTL_MS. */
+  private reapInFlight(): void {
+    const cutoff = Date.now() - IN_FLIGHT_TTL_MS;
+    for (const [nonce, entry] of this.inFlight) {
+      if (entry.startedAt >= cutoff) continue;
+      this.inFlight.delete(nonce);
+      this.abandoned++;
+      this.metrics?.recordAbandoned();
+      if (entry.kind === 'challenge') {
+        this.config.recordChallenge(false, 0, entry.cid, { validator: entry.validatorPeer, error: 'ABANDONED' });
+        this.metrics?.recordChallenge(entry.cid, false);
+      }
+      console.warn(`[ChallengeHandler] Abandoned hung ${entry.kind} from ${entry.validatorPeer} for ${entry.cid.slice(0, 12)}... after ${Math.round((Date.now() - entry.startedAt) / 1000)}s`);
+    }
   }
 
   /** Clean up old nonces and rate limit timestamps. */
@@ -309,5 +418,9 @@ export class ChallengeHandler {
       clearInterval(this.cleanupInterval);
       this.cleanupInterval = null;
     }
+    if (this.reapInterval) {
+      clearInterval(this.reapInterval);
+      this.reapInterval = null;
+    }
   }
 }
@@ -6,10 +6,15 @@
  * Electron. Designed for Ubuntu VPS / dedicated server deployment.
  *
  * Usage:
- *   SPK_WALLET_PASSWORD=mypassword node cli.js
+ *   SPK_WALLET_PASSWORD=mypassword node cli.js [--headless] [--log-file=<path>]
+ *   node cli.js --install-service | --uninstall-service   (Windows, elevated prompt)
+ *
+ * --headless also writes the log to ~/.spk-ipfs/logs/agent.log (or --log-file)
+ * and logs the events the desktop app would show as notifications.
  *
  * Environment variables:
  *   SPK_WALLET_PASSWORD  — Wallet password (required for signing)
+ *   SPK_SERVICE_PASSWORD — Your Windows password, for --install-service (the service logs on as you)
  *   SPK_HIVE_USERNAME    — Hive username (overrides saved config)
  *   SPK_API_PORT         — API port (default: 5111)
  *   SPK_SERVER_URL       — Central server URL (default: http://localhost:5000)
@@ -27,9 +32,21 @@ import { PubSubBridge } from './pubsub';
 import { ChallengeHandler, ChallengeMessage, ChallengeResponse, CommitmentRequest, CommitmentResponse } from './challenge-handler';
 import { LocalValidator } from './validator';
 import { AutoPinner } from './auto-pinner';
+import { ProofBroadcaster } from './proof-broadcast';
 import { TreasurySigner } from './treasury-signer';
 import { AgentWSClient } from './agent-ws';
 import { initializeFullServer, shutdownFullServer } from './server-init-cli';
+import { bootCooldown, wasLaunchedAtLogin } from './boot-cooldown';
+import { isHeadless, resolveLogFile, startFileLogging, logNotification } from './headless';
+import type { PinDamageEvent } from './integrity-scrubber';
+import type { SelfChallengeResult } from './self-challenge';
+import { formatDailySummary, DailySummaryReport } from './daily-summary';
+import type { DiskStatus } from './storage-monitor';
+import type { PairingRequest } from './pairing';
+import { installWindowsService, uninstallWindowsService } from './autostart';
+import { InstanceLock } from './instance-lock';
+
+if (isHeadless(process.argv)) startFileLogging(resolveLogFile(process.argv));
 
 // ─── Global error handlers ─────────────────────────────────────────────
 process.on('uncaughtException', (error) => {
@@ -46,6 +63,7 @@ let configStore: CliConfigStore;
 let kuboManager: KuboManager;
 let apiServer: ApiServer;
 let walletManager: WalletManager;
+let instanceLock: InstanceLock | null = null;
 
 // P2P mode
 let hiveClient: AgentHiveClient | null = null;
@@ -54,6 +72,7 @@ let pubsub: PubSubBridge | null = null;
 let challengeHandler: ChallengeHandler | null = null;
 let validator: LocalValidator | null = null;
 let autoPinner: AutoPinner | null = null;
+let proofBroadcaster: ProofBroadcaster | null = null;
 
 // Legacy mode
 let agentWS: AgentWSClient | null = null;
@@ -69,10 +88,15 @@ async function initializeP2P(): Promise<void> {
   const hasKey = configStore.hasPostingKey();
 
   hiveClient = new AgentHiveClient({
+    nodes: cfg.hiveApiNodes,
     username: cfg.hiveUsername,
     getPostingKey: () => walletManager.getPostingKey(),
   });
 
+  // Passed proofs → spk_poa_proof custom_json (no-op unless broadcastProofs is on)
+  proofBroadcaster = new ProofBroadcaster(hiveClient, configStore as any);
+  proofBroadcaster.start();
+
   const peerId = await kuboManager.getPeerId();
   if (!peerId) {
     console.error('[SPK-CLI] P2P mode: no IPFS peer ID available');
@@ -108,7 +132,7 @@ async function initializeP2P(): Promise<void> {
   if (cfg.validatorEnabled) {
     validator = new LocalValidator(
       hiveClient, peerDiscovery, pubsub, kuboManager.getApiUrl(),
-      cfg.hiveUsername, cfg.challengeIntervalMs, hasKey, cfg.requireSignedMessages
+      cfg.hiveUsername, cfg.challengeIntervalMs, hasKey, cfg.requireSignedMessages, cfg.serverUrl
     );
     await validator.start();
   }
@@ -142,6 +166,25 @@ async function initializeLegacy(): Promise<void> {
   setTimeout(() => agentWS!.connect(), 2000);
 }
 
+/** --install-service / --uninstall-service: manage the Windows service, then exit. */
+async function manageWindowsService(install: boolean): Promise<void> {
+  if (process.platform !== 'win32') {
+    throw new Error('Windows service mode is Windows-only — use POST /api/autostart with mode "systemd" on Linux');
+  }
+  const store = new CliConfigStore();
+  if (install) {
+    const { warnings } = await installWindowsService();
+    warnings.forEach((w) => console.warn(`[SPK-CLI] ${w}`));
+    store.setConfig({ autoStart: true, autoStartMode: 'windows-service' });
+    console.log('[SPK-CLI] Service installed — it starts at boot, or now with: sc.exe start hivepoaagent.exe');
+  } else {
+    await uninstallWindowsService();
+    store.setConfig({ autoStart: false, autoStartMode: 'desktop' });
+    console.log('[SPK-CLI] Service removed');
+  }
+  store.flush();
+}
+
 async function main(): Promise<void> {
   console.log('╔══════════════════════════════════════════════╗');
   console.log('║  SPK Desktop Agent — CLI / Headless Mode     ║');
@@ -149,8 +192,20 @@ async function main(): Promise<void> {
   console.log('╚══════════════════════════════════════════════╝');
   console.log();
 
+  // One agent per user: a second launch would start a second Kubo on the same repo
+  instanceLock = await InstanceLock.acquire(process.argv, (argv) => {
+    console.log(`[SPK-CLI] Another launch was handed to this instance (${argv.slice(2).join(' ') || 'no arguments'})`);
+  });
+  if (!instanceLock) {
+    console.log('[SPK-CLI] The agent is already running — handed this launch over to it');
+    process.exit(0);
+  }
+
   // Initialize config
   configStore = new CliConfigStore();
+  configStore.setEarningsEventListener((event) => {
+    logNotification(event.type === 'goal' ? 'Goal reached' : 'Milestone', `${event.thresholdHbd} HBD (total ${event.totalHbd} HBD)`);
+  });
 
   // Apply env var overrides
   if (process.env.SPK_HIVE_USERNAME) {
@@ -160,10 +215,18 @@ async function main(): Promise<void> {
   if (process.env.SPK_API_PORT) {
     configStore.setConfig({ apiPort: parseInt(process.env.SPK_API_PORT, 10) });
   }
+  if (process.env.SPK_API_BIND) {
+    configStore.setConfig({ apiBindAddress: process.env.SPK_API_BIND });
+  }
   if (process.env.SPK_SERVER_URL) {
     configStore.setConfig({ serverUrl: process.env.SPK_SERVER_URL });
   }
 
+  // Started by systemd/launchd with --autostart: defer background work for a while
+  if (wasLaunchedAtLogin(process.argv)) {
+    bootCooldown.begin(configStore.getConfig().bootCooldownMin * 60000);
+  }
+
   // Initialize wallet
   walletManager = new WalletManager();
   const walletDir = path.join(os.homedir(), '.spk-ipfs', 'wallet');
@@ -182,21 +245,35 @@ async function main(): Promise<void> {
   // Initialize Kubo (IPFS)
   kuboManager = new KuboManager(configStore as any);
   apiServer = new ApiServer(kuboManager, configStore as any, walletManager);
+  apiServer.getScrubber().on('pin-damage', (event: PinDamageEvent) => {
+    logNotification('Pin damaged', `block ${event.blockIndex} of ${event.cid} is ${event.reason}`);
+  });
+  apiServer.getPairing().on('pairing-code', (request: PairingRequest) => {
+    logNotification('Pairing code', `${request.code} for ${request.origin} (${request.scopes.join(', ')}), expires ${request.expiresAt}`);
+  });
+  apiServer.getSelfChallenger().on('self-challenge-failed', (result: SelfChallengeResult) => {
+    logNotification('Self-challenge failed', `${result.cid}: ${result.error} (repaired: ${result.repaired})`);
+  });
+  apiServer.getDailySummary().on('daily-summary', (report: DailySummaryReport) => {
+    logNotification('Daily summary', formatDailySummary(report));
+  });
+  apiServer.getStorageMonitor().on('disk-space', (status: DiskStatus & { message: string }) => {
+    logNotification(status.level === 'floor' ? 'Disk full' : 'Disk space low', status.message);
+  });
 
   try {
     await kuboManager.start();
     console.log('[SPK-CLI] IPFS daemon started');
 
+    // KuboManager's watchdog restarts a crashed daemon with backoff
     kuboManager.onExit(() => {
-      console.log('[SPK-CLI] Kubo exited unexpectedly, restarting in 5s...');
-      setTimeout(async () => {
-        try {
-          await kuboManager.start();
-          console.log('[SPK-CLI] Kubo restarted');
-        } catch (err) {
-          console.error('[SPK-CLI] Kubo restart failed:', err);
-        }
-      }, 5000);
+      console.log('[SPK-CLI] Kubo exited unexpectedly, watchdog restarting...');
+    });
+    kuboManager.onRestart(() => {
+      console.log('[SPK-CLI] Kubo restarted');
+    });
+    kuboManager.onRestartFailing((attempts, error) => {
+      console.error(`[SPK-CLI] Kubo failed to restart ${attempts} times in a row (${error}) — still retrying`);
     });
   } catch (error) {
     console.error('[SPK-CLI] Failed to start IPFS:', error);
@@ -205,8 +282,8 @@ async function main(): Promise<void> {
 
   try {
     await apiServer.start();
-    const port = configStore.getConfig().apiPort;
-    console.log(`[SPK-CLI] API server started on http://127.0.0.1:${port}`);
+    const { apiBindAddress } = configStore.getConfig();
+    console.log(`[SPK-CLI] API server started on http://${apiBindAddress}:${apiServer.getPort()}`);
 
     // Initialize full server backend (SQLite)
     const httpServer = apiServer.getHttpServer();
@@ -236,10 +313,14 @@ async function main(): Promise<void> {
 }
 
 // Graceful shutdown
+let shuttingDown = false;
 async function shutdown(): Promise<void> {
+  if (shuttingDown) return;
+  shuttingDown = true;
   console.log('\n[SPK-CLI] Shutting down...');
 
   autoPinner?.stop();
+  proofBroadcaster?.stop();
   validator?.stop();
   challengeHandler?.stop();
   peerDiscovery?.stop();
@@ -250,6 +331,8 @@ async function shutdown(): Promise<void> {
 
   await kuboManager?.stop();
   await apiServer?.stop();
+  configStore?.flush();
+  instanceLock?.release();
 
   console.log('[SPK-CLI] Goodbye.');
   process.exit(0);
@@ -257,8 +340,11 @@ async function shutdown(): Promise<void> {
 
 process.on('SIGINT', shutdown);
 process.on('SIGTERM', shutdown);
+// Windows: a service stop (WinSW) arrives as Ctrl+C or Ctrl+Break
+process.on('SIGBREAK', shutdown);
 
-main().catch((err) => {
+const serviceFlag = ['--install-service', '--uninstall-service'].find((flag) => process.argv.includes(flag));
+(serviceFlag ? manageWindowsService(serviceFlag === '--install-service').then(() => process.exit(0)) : main()).catch((err) => {
   console.error('[SPK-CLI] Fatal error:', err);
   process.exit(1);
 });
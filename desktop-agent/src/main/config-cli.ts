@@ -10,11 +10,28 @@ import * as path from 'path';
 import * as os from 'os';
 import * as fs from 'fs';
 import type { AgentConfig, EarningsData } from './config';
+import type { WebhookConfig } from './webhooks';
+import type { AutostartMode } from './autostart';
+import type { UpdateMode } from './updater';
+import { EarningsQuarantine, QuarantinedEarning } from './earnings-quarantine';
+import { EarningsMilestoneTracker, EarningsEvent, MilestoneState } from './earnings-milestones';
+import {
+  EarningsLedger, EarningsHistoryRange, EarningsBucket, EarningsGranularity, ChallengeStats, ChallengeHistoryEntry,
+} from './earnings-ledger';
+import { agentEvents } from './agent-events';
+import { JsonFileWriter } from './json-writer';
 
 export class CliConfigStore {
   private configPath: string;
   private earningsPath: string;
   private data: Record<string, any>;
+  private quarantine: EarningsQuarantine;
+  private milestones: EarningsMilestoneTracker;
+  private ledger: EarningsLedger;
+  private daemonProbe: (() => boolean) | null = null;
+  private consecutivePasses: number | null = null;
+  private configWriter: JsonFileWriter;
+  private earningsWriter: JsonFileWriter;
 
   constructor() {
     const spkDir = path.join(os.homedir(), '.spk-ipfs');
@@ -24,6 +41,25 @@ export class CliConfigStore {
 
     this.configPath = path.join(spkDir, 'agent-config.json');
     this.earningsPath = path.join(spkDir, 'earnings.json');
+    this.configWriter = new JsonFileWriter(this.configPath, () => this.data);
+    this.earningsWriter = new JsonFileWriter(this.earningsPath, () => this.getEarnings());
+    this.quarantine = new EarningsQuarantine(path.join(spkDir, 'earnings-quarantine.json'));
+    this.ledger = new EarningsLedger(path.join(spkDir, 'earnings.db'), {
+      historyDir: path.join(spkDir, 'earnings-history'),
+      earningsPath: this.earningsPath,
+    });
+    this.milestones = new EarningsMilestoneTracker(
+      path.join(spkDir, 'earnings-events.jsonl'),
+      () => ({
+        highestMilestoneHbd: this.get('highestMilestoneHbd', null) as number | null,
+        goalReachedHbd: this.get('goalReachedHbd', null) as number | null,
+      }),
+      (state: MilestoneState) => {
+        this.data.highestMilestoneHbd = state.highestMilestoneHbd;
+        this.data.goalReachedHbd = state.goalReachedHbd;
+        this.save();
+      },
+    );
 
     // Load persisted config
     if (fs.existsSync(this.configPath)) {
@@ -45,8 +81,15 @@ export class CliConfigStore {
     return {
       hiveUsername: this.get('hiveUsername', null) as string | null,
       ipfsRepoPath: this.get('ipfsRepoPath', path.join(os.homedir(), '.spk-ipfs', 'repo')) as string,
+      externalDaemonApi: this.get('externalDaemonApi', null) as string | null,
       apiPort: this.get('apiPort', 5111) as number,
+      apiBindAddress: this.get('apiBindAddress', '127.0.0.1') as string,
       autoStart: this.get('autoStart', false) as boolean,
+      autoStartMode: this.get('autoStartMode', 'desktop') as AutostartMode,
+      launchdKeepAlive: this.get('launchdKeepAlive', true) as boolean,
+      launchdThrottleSec: this.get('launchdThrottleSec', 30) as number,
+      launchdAsDaemon: this.get('launchdAsDaemon', false) as boolean,
+      bootCooldownMin: this.get('bootCooldownMin', 10) as number,
       bandwidthLimitUp: this.get('bandwidthLimitUp', 0) as number,
       bandwidthLimitDown: this.get('bandwidthLimitDown', 0) as number,
       storageMaxGB: this.get('storageMaxGB', 50) as number,
@@ -58,7 +101,63 @@ export class CliConfigStore {
       requireSignedMessages: this.get('requireSignedMessages', false) as boolean,
       autoPinPopular: this.get('autoPinPopular', true) as boolean,
       autoPinMaxGB: this.get('autoPinMaxGB', 10) as number,
+      pinQuotaPerWindow: this.get('pinQuotaPerWindow', 1000) as number,
+      pinQuotaWindowMin: this.get('pinQuotaWindowMin', 60) as number,
+      pinReconcileEnabled: this.get('pinReconcileEnabled', false) as boolean,
+      pinReconcileUrl: this.get('pinReconcileUrl', null) as string | null,
+      pinReconcileIntervalMin: this.get('pinReconcileIntervalMin', 15) as number,
+      pinReconcileUnpinExtras: this.get('pinReconcileUnpinExtras', false) as boolean,
+      pinReconcileAllowlist: this.get('pinReconcileAllowlist', []) as string[],
+      pinReconcileDenylist: this.get('pinReconcileDenylist', []) as string[],
+      contractSyncEnabled: this.get('contractSyncEnabled', false) as boolean,
+      contractSyncIntervalMin: this.get('contractSyncIntervalMin', 15) as number,
+      contractUnpinExpired: this.get('contractUnpinExpired', true) as boolean,
+      scrubEnabled: this.get('scrubEnabled', false) as boolean,
+      scrubIntervalMin: this.get('scrubIntervalMin', 60) as number,
+      scrubBlocksPerPass: this.get('scrubBlocksPerPass', 500) as number,
+      scrubMaxBytesPerSec: this.get('scrubMaxBytesPerSec', 2097152) as number,
+      scrubAutoRepair: this.get('scrubAutoRepair', false) as boolean,
+      selfChallengeEnabled: this.get('selfChallengeEnabled', false) as boolean,
+      selfChallengeIntervalMin: this.get('selfChallengeIntervalMin', 30) as number,
+      sealingEnabled: this.get('sealingEnabled', false) as boolean,
+      gcScheduleEnabled: this.get('gcScheduleEnabled', false) as boolean,
+      gcQuietStartHour: this.get('gcQuietStartHour', 3) as number,
+      gcQuietEndHour: this.get('gcQuietEndHour', 5) as number,
+      gcWatermarkPercent: this.get('gcWatermarkPercent', 90) as number,
+      webhooks: this.get('webhooks', []) as WebhookConfig[],
       treasurySignerEnabled: this.get('treasurySignerEnabled', false) as boolean,
+      offlineEarningsPolicy: this.get('offlineEarningsPolicy', 'accept') as 'accept' | 'reject' | 'quarantine',
+      earningsGoalHbd: this.get('earningsGoalHbd', null) as number | null,
+      challengeReadahead: this.get('challengeReadahead', 0) as number,
+      challengeRetries: this.get('challengeRetries', 1) as number,
+      maxBlocksPerChallenge: this.get('maxBlocksPerChallenge', 256) as number,
+      challengesPaused: this.get('challengesPaused', false) as boolean,
+      updateMode: this.get('updateMode', 'prompt') as UpdateMode,
+      priceFeedEnabled: this.get('priceFeedEnabled', true) as boolean,
+      displayCurrency: this.get('displayCurrency', 'usd') as string,
+      diskWarnFreePercent: this.get('diskWarnFreePercent', 10) as number,
+      diskMinFreeGB: this.get('diskMinFreeGB', 5) as number,
+      notifyDailySummary: this.get('notifyDailySummary', false) as boolean,
+      dailySummaryHour: this.get('dailySummaryHour', 9) as number,
+      payoutReconcileEnabled: this.get('payoutReconcileEnabled', true) as boolean,
+      payoutReconcileDays: this.get('payoutReconcileDays', 30) as number,
+      chainWatcherEnabled: this.get('chainWatcherEnabled', false) as boolean,
+      challengeMemoryCeilingMB: this.get('challengeMemoryCeilingMB', 0) as number,
+      challengeHmacKeys: this.get('challengeHmacKeys', {}) as Record<string, string>,
+      signChallengeProofs: this.get('signChallengeProofs', false) as boolean,
+      broadcastProofs: this.get('broadcastProofs', false) as boolean,
+      proofBroadcastIntervalSec: this.get('proofBroadcastIntervalSec', 60) as number,
+      hiveApiNodes: this.get('hiveApiNodes', []) as string[],
+      requireBlockSalts: this.get('requireBlockSalts', false) as boolean,
+      kuboProcessTimeoutSec: this.get('kuboProcessTimeoutSec', 300) as number,
+      kuboCommandTimeoutSec: this.get('kuboCommandTimeoutSec', 15) as number,
+      kuboShutdownGraceSec: this.get('kuboShutdownGraceSec', 30) as number,
+      metricsTopCids: this.get('metricsTopCids', 50) as number,
+      corsAllowedOrigins: this.get('corsAllowedOrigins', []) as string[],
+      authRequiredForReads: this.get('authRequiredForReads', false) as boolean,
+      nodeId: this.get('nodeId', null) as string | null,
+      poolPrefix: this.get('poolPrefix', null) as string | null,
+      heartbeatBackoffMaxSec: this.get('heartbeatBackoffMaxSec', 900) as number,
     };
   }
 
@@ -115,8 +214,18 @@ export class CliConfigStore {
     return !!this.data.postingPublicKey;
   }
 
+  setPostingKeyHash(hash: string): void {
+    this.data.postingKeyHash = hash;
+    this.save();
+  }
+
+  getPostingKeyHash(): string | null {
+    return (this.data.postingKeyHash as string) || null;
+  }
+
   clearPostingPublicKey(): void {
     delete this.data.postingPublicKey;
+    delete this.data.postingKeyHash;
     this.save();
   }
 
@@ -127,49 +236,143 @@ export class CliConfigStore {
     this.save();
   }
 
+  /** Totals come from the ledger; earnings.json only contributes the pass streak. */
   getEarnings(): EarningsData {
+    if (this.consecutivePasses === null) this.consecutivePasses = this.readStreak();
+    return { ...this.ledger.totals(), consecutivePasses: this.consecutivePasses };
+  }
+
+  updateEarnings(update: Partial<EarningsData>): EarningsData {
+    const updated = { ...this.getEarnings(), ...update };
+    this.consecutivePasses = updated.consecutivePasses;
+    this.earningsWriter.schedule();
+    return updated;
+  }
+
+  /** Persist pending config and earnings writes now — call before exit. */
+  flush(): void {
+    this.configWriter.flush();
+    this.earningsWriter.flush();
+  }
+
+  private readStreak(): number {
     try {
       if (fs.existsSync(this.earningsPath)) {
-        return JSON.parse(fs.readFileSync(this.earningsPath, 'utf-8'));
+        return JSON.parse(fs.readFileSync(this.earningsPath, 'utf-8')).consecutivePasses || 0;
       }
     } catch (error) {
       console.error('[Config-CLI] Failed to read earnings:', error);
     }
+    return 0;
+  }
 
-    return {
-      totalHbd: 0,
-      challengesPassed: 0,
-      challengesFailed: 0,
-      consecutivePasses: 0,
-      lastChallengeTime: null,
-    };
+  /** Lets recordChallenge() see whether the IPFS daemon is running (wired by KuboManager). */
+  setDaemonProbe(probe: () => boolean): void {
+    this.daemonProbe = probe;
   }
 
-  updateEarnings(update: Partial<EarningsData>): EarningsData {
-    const current = this.getEarnings();
-    const updated = { ...current, ...update };
-    fs.writeFileSync(this.earningsPath, JSON.stringify(updated, null, 2));
-    return updated;
+  /** Called for each milestone/goal event as it is logged (desktop notifications). */
+  setEarningsEventListener(listener: (event: EarningsEvent) => void): void {
+    this.milestones.setListener(listener);
   }
 
-  recordChallenge(passed: boolean, hbdEarned: number): EarningsData {
-    const current = this.getEarnings();
+  getEarningsEvents(): EarningsEvent[] {
+    return this.milestones.list();
+  }
 
-    if (passed) {
-      current.challengesPassed++;
-      current.consecutivePasses++;
-      current.totalHbd += hbdEarned;
-    } else {
-      current.challengesFailed++;
-      current.consecutivePasses = 0;
+  /** Credited challenges between two epoch-ms timestamps, with their sum. */
+  getEarningsHistory(fromMs: number, toMs: number): EarningsHistoryRange {
+    return this.ledger.query(fromMs, toMs);
+  }
+
+  /** Challenge counts and HBD per day/week/month between two epoch-ms timestamps. */
+  getEarningsBuckets(fromMs: number, toMs: number, granularity: EarningsGranularity): EarningsBucket[] {
+    return this.ledger.buckets(fromMs, toMs, granularity);
+  }
+
+  getChallengeStats(fromMs: number): ChallengeStats {
+    return this.ledger.challengeStats(fromMs);
+  }
+
+  getRecentChallenges(limit: number): ChallengeHistoryEntry[] {
+    return this.ledger.recentChallenges(limit);
+  }
+
+  /** Every ledger row between two epoch-ms timestamps, oldest first — for exports. */
+  iterateChallenges(fromMs: number, toMs: number): IterableIterator<ChallengeHistoryEntry> {
+    return this.ledger.iterate(fromMs, toMs);
+  }
+
+  getMilestoneState(): MilestoneState {
+    return this.milestones.getState();
+  }
+
+  getQuarantinedEarnings(): QuarantinedEarning[] {
+    return this.quarantine.list();
+  }
+
+  /** Credit quarantined entries (all if `ids` is omitted) as passed challenges. */
+  releaseQuarantinedEarnings(ids?: string[]): QuarantinedEarning[] {
+    const released = this.quarantine.take(ids);
+    if (released.length > 0) {
+      const previousHbd = this.getEarnings().totalHbd;
+      for (const entry of released) {
+        this.ledger.record({ passed: true, amountHbd: entry.hbdEarned });
+      }
+      const current = this.getEarnings();
+      this.milestones.check(previousHbd, current.totalHbd, this.getConfig().earningsGoalHbd);
+      agentEvents.publish('earnings_updated', {
+        totalHbd: current.totalHbd,
+        challengesPassed: current.challengesPassed,
+        challengesFailed: current.challengesFailed,
+      });
     }
+    return released;
+  }
+
+  /** Drop quarantined entries (all if `ids` is omitted) without crediting them. */
+  discardQuarantinedEarnings(ids?: string[]): QuarantinedEarning[] {
+    return this.quarantine.take(ids);
+  }
+
+  recordChallenge(
+    passed: boolean,
+    hbdEarned: number,
+    cid?: string,
+    details: { validator?: string; latencyMs?: number; blocks?: number; error?: string } = {},
+  ): EarningsData {
+    let current = this.getEarnings();
 
-    current.lastChallengeTime = new Date().toISOString();
-    fs.writeFileSync(this.earningsPath, JSON.stringify(current, null, 2));
+    // Credit arriving while the daemon is down can't reflect served work — apply the offline policy
+    if (passed && this.daemonProbe && !this.daemonProbe()) {
+      const policy = this.getConfig().offlineEarningsPolicy;
+      if (policy === 'reject') {
+        console.warn(`[Config-CLI] Rejected ${hbdEarned} HBD credited while the IPFS daemon is down`);
+        return current;
+      }
+      if (policy === 'quarantine') {
+        const entry = this.quarantine.add(hbdEarned, 'daemon-not-running');
+        console.warn(`[Config-CLI] Quarantined ${hbdEarned} HBD credited while the IPFS daemon is down (${entry.id})`);
+        return current;
+      }
+    }
+
+    const previousHbd = current.totalHbd;
+    this.ledger.record({ passed, amountHbd: hbdEarned, cid, ...details });
+    const consecutivePasses = passed ? current.consecutivePasses + 1 : 0;
+    current = this.updateEarnings({ consecutivePasses });
+    if (passed && hbdEarned > 0) {
+      this.milestones.check(previousHbd, current.totalHbd, this.getConfig().earningsGoalHbd);
+    }
+    agentEvents.publish('earnings_updated', {
+      totalHbd: current.totalHbd,
+      challengesPassed: current.challengesPassed,
+      challengesFailed: current.challengesFailed,
+    });
     return current;
   }
 
   private save(): void {
-    fs.writeFileSync(this.configPath, JSON.stringify(this.data, null, 2));
+    this.configWriter.schedule();
   }
 }
@@ -1,6 +1,16 @@
 import * as path from 'path';
 import * as os from 'os';
 import * as fs from 'fs';
+import { EarningsQuarantine, QuarantinedEarning } from './earnings-quarantine';
+import { EarningsMilestoneTracker, EarningsEvent, MilestoneState } from './earnings-milestones';
+import {
+  EarningsLedger, EarningsHistoryRange, EarningsBucket, EarningsGranularity, ChallengeStats, ChallengeHistoryEntry,
+} from './earnings-ledger';
+import { agentEvents } from './agent-events';
+import { JsonFileWriter } from './json-writer';
+import type { WebhookConfig } from './webhooks';
+import type { AutostartMode } from './autostart';
+import type { UpdateMode } from './updater';
 
 // Electron modules are optional — CLI mode runs without them
 let Store: any;
@@ -15,8 +25,15 @@ try {
 export interface AgentConfig {
   hiveUsername: string | null;
   ipfsRepoPath: string;
+  externalDaemonApi: string | null; // RPC URL of a separately managed Kubo, null = spawn our own
   apiPort: number;
+  apiBindAddress: string;      // Interface the local API binds to; non-loopback exposes read-only GETs
   autoStart: boolean;
+  autoStartMode: AutostartMode; // 'desktop' login item, or a systemd / Windows service / launchd job (autostart.ts)
+  launchdKeepAlive: boolean;   // launchd mode: relaunch after a crash
+  launchdThrottleSec: number;  // launchd mode: minimum seconds between launches
+  launchdAsDaemon: boolean;    // launchd mode: LaunchDaemon (before login, root to install) instead of LaunchAgent
+  bootCooldownMin: number;     // Defer background work this long after an autostart launch, 0 = off
   bandwidthLimitUp: number;   // KB/s, 0 = unlimited
   bandwidthLimitDown: number; // KB/s, 0 = unlimited
   storageMaxGB: number;       // GB, 0 = 100GB default, default 50
@@ -30,8 +47,78 @@ export interface AgentConfig {
   // Auto-pin popular content
   autoPinPopular: boolean;    // Auto-pin popular content from network
   autoPinMaxGB: number;       // Max storage for auto-pinned content (GB)
+  // Pin quota (per coordinator / source IP)
+  pinQuotaPerWindow: number;   // Pin operations allowed per caller per window, 0 = unlimited
+  pinQuotaWindowMin: number;   // Pin quota sliding window (minutes)
+  // Coordinator pin reconciliation
+  pinReconcileEnabled: boolean; // Converge pins to the coordinator's desired set
+  pinReconcileUrl: string | null; // Desired-pins URL, null = <serverUrl>/api/pool/desired-pins
+  pinReconcileIntervalMin: number; // Minutes between reconciliation passes
+  pinReconcileUnpinExtras: boolean; // Also unpin reconciler-added pins no longer desired
+  pinReconcileAllowlist: string[]; // CIDs the reconciler may pin or unpin, empty = any
+  pinReconcileDenylist: string[]; // CIDs the reconciler never pins or unpins
+  // Storage contract sync
+  contractSyncEnabled: boolean; // Auto-pin CIDs of hiveUsername's storage contracts
+  contractSyncIntervalMin: number; // Minutes between contract sync passes
+  contractUnpinExpired: boolean; // Unpin contract pins once the contract expires
+  // Background integrity scrubber
+  scrubEnabled: boolean;       // Periodically re-verify pinned blocks against their CIDs
+  scrubIntervalMin: number;    // Minutes between scrub passes
+  scrubBlocksPerPass: number;  // Blocks verified per pass (rotates through pins)
+  scrubMaxBytesPerSec: number; // Scrub read throttle, 0 = unthrottled
+  scrubAutoRepair: boolean;    // Re-fetch damaged blocks from the network
+  // Self-challenges
+  selfChallengeEnabled: boolean; // Periodically challenge random local pins the way a validator would
+  selfChallengeIntervalMin: number; // Minutes between self-challenge passes
+  // Proof-of-replication
+  sealingEnabled: boolean;     // Keep sealed replicas of pins and answer Sealed validator challenges
+  // Scheduled garbage collection
+  gcScheduleEnabled: boolean;  // Run repo GC during quiet hours or past the watermark
+  gcQuietStartHour: number;    // Quiet hours start (local hour, 0-23)
+  gcQuietEndHour: number;      // Quiet hours end (local hour, 0-23; may wrap midnight)
+  gcWatermarkPercent: number;  // Also GC when repo usage reaches this % of storageMaxGB, 0 = off
+  // Webhooks
+  webhooks: WebhookConfig[];   // POST event payloads to these URLs (Discord, Slack, monitoring)
   // Multisig Treasury
   treasurySignerEnabled: boolean; // Whether this agent auto-signs treasury transactions
+  // Earnings
+  offlineEarningsPolicy: 'accept' | 'reject' | 'quarantine'; // Passed challenges credited while the daemon is down
+  earningsGoalHbd: number | null; // Total HBD goal, logged and notified once when reached
+  // Challenge tuning
+  challengeReadahead: number;  // Blocks to prefetch after each challenge read, 0 = off
+  challengeRetries: number;    // Retries per block on transient read errors (0-3)
+  maxBlocksPerChallenge: number; // Reject challenges asking for more blocks than this
+  challengesPaused: boolean;   // Refuse all validator challenges (tray "Pause challenges")
+  updateMode: UpdateMode;      // Self-update: 'prompt' before restarting, 'auto' restart when idle, 'off'
+  priceFeedEnabled: boolean;   // Poll HBD/HIVE prices (CoinGecko, Hive feed fallback) for fiat earnings
+  displayCurrency: string;     // ISO code fiat earnings are shown in, e.g. 'usd', 'eur'
+  diskWarnFreePercent: number; // Warn when the repo volume has less than this % free
+  diskMinFreeGB: number;       // Refuse new pins that would leave less than this free on the repo volume, 0 = off
+  notifyDailySummary: boolean; // Notify (and webhook) a digest of the last 24 h once a day
+  dailySummaryHour: number;    // Local hour (0-23) the daily summary goes out
+  payoutReconcileEnabled: boolean; // Match ledger credits against reward transfers on Hive every 6 h
+  payoutReconcileDays: number; // How far back each reconciliation pass looks
+  chainWatcherEnabled: boolean; // Follow irreversible Hive blocks for challenges, contracts and payouts to hiveUsername
+  challengeMemoryCeilingMB: number; // Stream validator challenge reads through the hasher within this much memory, 0 = buffer whole blocks
+  challengeHmacKeys: Record<string, string>; // keyId -> coordinator-shared secret for HMAC'd proofs
+  signChallengeProofs: boolean; // Sign each HTTP proof with the posting key (signature + account)
+  broadcastProofs: boolean;    // Broadcast each passed proof to Hive as spk_poa_proof custom_json
+  proofBroadcastIntervalSec: number; // Minimum seconds between proof broadcasts (RC budget)
+  hiveApiNodes: string[];      // Hive API node URLs, empty = built-in list
+  requireBlockSalts: boolean;  // Reject challenges whose salt isn't derived from a recent Hive block
+  // Kubo subprocesses
+  kuboProcessTimeoutSec: number; // Reap ipfs CLI subprocesses older than this, 0 = never
+  kuboCommandTimeoutSec: number; // Timeout for one-shot ipfs CLI/RPC calls (repo stat gets a third)
+  kuboShutdownGraceSec: number; // Wait this long for a graceful daemon shutdown before SIGKILL
+  // Observability
+  metricsTopCids: number;      // Per-CID /metrics series cap (busiest CIDs only)
+  // API access
+  corsAllowedOrigins: string[]; // Extra browser origins allowed to call the API (added to the built-in list)
+  authRequiredForReads: boolean; // GET endpoints need a token with the read (or earnings) scope
+  // Coordinator identity & heartbeat
+  nodeId: string | null;       // Coordinator-facing node ID, null = derive from peer ID
+  poolPrefix: string | null;   // Optional namespace, presented as "<prefix>:<nodeId>"
+  heartbeatBackoffMaxSec: number; // Cap on coordinator heartbeat retry backoff
   // GPU Contribution (Spirit Bomb)
   gpuContributionEnabled: boolean;
   gpuContributionMode: 'local' | 'pool' | 'cluster' | 'lend';
@@ -55,10 +142,34 @@ export interface EarningsData {
   lastChallengeTime: string | null;
 }
 
+/** Allowed characters for nodeId / poolPrefix (the ':' separator is reserved). */
+export const NODE_ID_PATTERN = /^[A-Za-z0-9._-]{1,64}$/;
+
+/**
+ * Coordinator-facing node identifier: `<poolPrefix>:<nodeId>`, or just `<nodeId>`.
+ * When nodeId is unset it derives from the IPFS peer ID, so it only stays stable
+ * across repo resets if set explicitly.
+ */
+export function resolveNodeId(config: AgentConfig, peerId: string | null): string | null {
+  const base = config.nodeId || peerId;
+  if (!base) return null;
+  return config.poolPrefix ? `${config.poolPrefix}:${base}` : base;
+}
+
 export class ConfigStore {
   private store: any;
   private configPath: string;
   private earningsPath: string;
+  private quarantine: EarningsQuarantine;
+  private milestones: EarningsMilestoneTracker;
+  private ledger: EarningsLedger;
+  private daemonProbe: (() => boolean) | null = null;
+  // electron-store re-reads its file on every get(), so getConfig() is cached
+  // until the next setConfig(); the streak is read from earnings.json once
+  private cachedConfig: AgentConfig | null = null;
+  private consecutivePasses: number | null = null;
+  private configWriter: JsonFileWriter;
+  private earningsWriter: JsonFileWriter;
 
   constructor() {
     if (!Store) {
@@ -75,14 +186,44 @@ export class ConfigStore {
 
     this.configPath = path.join(spkDir, 'agent-config.json');
     this.earningsPath = path.join(spkDir, 'earnings.json');
+    this.configWriter = new JsonFileWriter(this.configPath, () => this.getConfig());
+    this.earningsWriter = new JsonFileWriter(this.earningsPath, () => this.getEarnings());
+    this.quarantine = new EarningsQuarantine(path.join(spkDir, 'earnings-quarantine.json'));
+    this.ledger = new EarningsLedger(path.join(spkDir, 'earnings.db'), {
+      historyDir: path.join(spkDir, 'earnings-history'),
+      earningsPath: this.earningsPath,
+    });
+    this.milestones = new EarningsMilestoneTracker(
+      path.join(spkDir, 'earnings-events.jsonl'),
+      () => ({
+        highestMilestoneHbd: this.store.get('highestMilestoneHbd', null) as number | null,
+        goalReachedHbd: this.store.get('goalReachedHbd', null) as number | null,
+      }),
+      (state: MilestoneState) => {
+        this.store.set('highestMilestoneHbd', state.highestMilestoneHbd);
+        this.store.set('goalReachedHbd', state.goalReachedHbd);
+      },
+    );
   }
 
   getConfig(): AgentConfig {
+    if (!this.cachedConfig) this.cachedConfig = this.readConfig();
+    return { ...this.cachedConfig };
+  }
+
+  private readConfig(): AgentConfig {
     return {
       hiveUsername: this.store.get('hiveUsername', null) as string | null,
       ipfsRepoPath: this.store.get('ipfsRepoPath', path.join(os.homedir(), '.spk-ipfs', 'repo')) as string,
+      externalDaemonApi: this.store.get('externalDaemonApi', null) as string | null,
       apiPort: this.store.get('apiPort', 5111) as number,
+      apiBindAddress: this.store.get('apiBindAddress', '127.0.0.1') as string,
       autoStart: this.store.get('autoStart', false) as boolean,
+      autoStartMode: this.store.get('autoStartMode', 'desktop') as AutostartMode,
+      launchdKeepAlive: this.store.get('launchdKeepAlive', true) as boolean,
+      launchdThrottleSec: this.store.get('launchdThrottleSec', 30) as number,
+      launchdAsDaemon: this.store.get('launchdAsDaemon', false) as boolean,
+      bootCooldownMin: this.store.get('bootCooldownMin', 10) as number,
       bandwidthLimitUp: this.store.get('bandwidthLimitUp', 0) as number,
       bandwidthLimitDown: this.store.get('bandwidthLimitDown', 0) as number,
       storageMaxGB: this.store.get('storageMaxGB', 50) as number,
@@ -94,7 +235,63 @@ export class ConfigStore {
       requireSignedMessages: this.store.get('requireSignedMessages', false) as boolean,
       autoPinPopular: this.store.get('autoPinPopular', true) as boolean,
       autoPinMaxGB: this.store.get('autoPinMaxGB', 10) as number,
+      pinQuotaPerWindow: this.store.get('pinQuotaPerWindow', 1000) as number,
+      pinQuotaWindowMin: this.store.get('pinQuotaWindowMin', 60) as number,
+      pinReconcileEnabled: this.store.get('pinReconcileEnabled', false) as boolean,
+      pinReconcileUrl: this.store.get('pinReconcileUrl', null) as string | null,
+      pinReconcileIntervalMin: this.store.get('pinReconcileIntervalMin', 15) as number,
+      pinReconcileUnpinExtras: this.store.get('pinReconcileUnpinExtras', false) as boolean,
+      pinReconcileAllowlist: this.store.get('pinReconcileAllowlist', []) as string[],
+      pinReconcileDenylist: this.store.get('pinReconcileDenylist', []) as string[],
+      contractSyncEnabled: this.store.get('contractSyncEnabled', false) as boolean,
+      contractSyncIntervalMin: this.store.get('contractSyncIntervalMin', 15) as number,
+      contractUnpinExpired: this.store.get('contractUnpinExpired', true) as boolean,
+      scrubEnabled: this.store.get('scrubEnabled', false) as boolean,
+      scrubIntervalMin: this.store.get('scrubIntervalMin', 60) as number,
+      scrubBlocksPerPass: this.store.get('scrubBlocksPerPass', 500) as number,
+      scrubMaxBytesPerSec: this.store.get('scrubMaxBytesPerSec', 2097152) as number,
+      scrubAutoRepair: this.store.get('scrubAutoRepair', false) as boolean,
+      selfChallengeEnabled: this.store.get('selfChallengeEnabled', false) as boolean,
+      selfChallengeIntervalMin: this.store.get('selfChallengeIntervalMin', 30) as number,
+      sealingEnabled: this.store.get('sealingEnabled', false) as boolean,
+      gcScheduleEnabled: this.store.get('gcScheduleEnabled', false) as boolean,
+      gcQuietStartHour: this.store.get('gcQuietStartHour', 3) as number,
+      gcQuietEndHour: this.store.get('gcQuietEndHour', 5) as number,
+      gcWatermarkPercent: this.store.get('gcWatermarkPercent', 90) as number,
+      webhooks: this.store.get('webhooks', []) as WebhookConfig[],
       treasurySignerEnabled: this.store.get('treasurySignerEnabled', false) as boolean,
+      offlineEarningsPolicy: this.store.get('offlineEarningsPolicy', 'accept') as 'accept' | 'reject' | 'quarantine',
+      earningsGoalHbd: this.store.get('earningsGoalHbd', null) as number | null,
+      challengeReadahead: this.store.get('challengeReadahead', 0) as number,
+      challengeRetries: this.store.get('challengeRetries', 1) as number,
+      maxBlocksPerChallenge: this.store.get('maxBlocksPerChallenge', 256) as number,
+      challengesPaused: this.store.get('challengesPaused', false) as boolean,
+      updateMode: this.store.get('updateMode', 'prompt') as UpdateMode,
+      priceFeedEnabled: this.store.get('priceFeedEnabled', true) as boolean,
+      displayCurrency: this.store.get('displayCurrency', 'usd') as string,
+      diskWarnFreePercent: this.store.get('diskWarnFreePercent', 10) as number,
+      diskMinFreeGB: this.store.get('diskMinFreeGB', 5) as number,
+      notifyDailySummary: this.store.get('notifyDailySummary', false) as boolean,
+      dailySummaryHour: this.store.get('dailySummaryHour', 9) as number,
+      payoutReconcileEnabled: this.store.get('payoutReconcileEnabled', true) as boolean,
+      payoutReconcileDays: this.store.get('payoutReconcileDays', 30) as number,
+      chainWatcherEnabled: this.store.get('chainWatcherEnabled', false) as boolean,
+      challengeMemoryCeilingMB: this.store.get('challengeMemoryCeilingMB', 0) as number,
+      challengeHmacKeys: this.store.get('challengeHmacKeys', {}) as Record<string, string>,
+      signChallengeProofs: this.store.get('signChallengeProofs', false) as boolean,
+      broadcastProofs: this.store.get('broadcastProofs', false) as boolean,
+      proofBroadcastIntervalSec: this.store.get('proofBroadcastIntervalSec', 60) as number,
+      hiveApiNodes: this.store.get('hiveApiNodes', []) as string[],
+      requireBlockSalts: this.store.get('requireBlockSalts', false) as boolean,
+      kuboProcessTimeoutSec: this.store.get('kuboProcessTimeoutSec', 300) as number,
+      kuboCommandTimeoutSec: this.store.get('kuboCommandTimeoutSec', 15) as number,
+      kuboShutdownGraceSec: this.store.get('kuboShutdownGraceSec', 30) as number,
+      metricsTopCids: this.store.get('metricsTopCids', 50) as number,
+      corsAllowedOrigins: this.store.get('corsAllowedOrigins', []) as string[],
+      authRequiredForReads: this.store.get('authRequiredForReads', false) as boolean,
+      nodeId: this.store.get('nodeId', null) as string | null,
+      poolPrefix: this.store.get('poolPrefix', null) as string | null,
+      heartbeatBackoffMaxSec: this.store.get('heartbeatBackoffMaxSec', 900) as number,
       // GPU Contribution (Spirit Bomb)
       gpuContributionEnabled: this.store.get('gpuContributionEnabled', false) as boolean,
       gpuContributionMode: this.store.get('gpuContributionMode', 'pool') as 'local' | 'pool' | 'cluster' | 'lend',
@@ -182,64 +379,171 @@ export class ConfigStore {
     return !!this.store.get('postingPublicKey');
   }
 
+  /**
+   * SHA-256 of the signing (posting) key. The key itself lives only in the
+   * encrypted wallet; the hash lets an operator confirm which key is loaded.
+   */
+  setPostingKeyHash(hash: string): void {
+    this.store.set('postingKeyHash', hash);
+  }
+
+  getPostingKeyHash(): string | null {
+    return this.store.get('postingKeyHash', null) as string | null;
+  }
+
   clearPostingPublicKey(): void {
     this.store.delete('postingPublicKey');
+    this.store.delete('postingKeyHash');
   }
 
   setConfig(config: Partial<AgentConfig>): void {
     Object.entries(config).forEach(([key, value]) => {
       this.store.set(key, value);
     });
+    this.cachedConfig = null;
 
     // Also save to JSON file for external access
-    const fullConfig = this.getConfig();
-    fs.writeFileSync(this.configPath, JSON.stringify(fullConfig, null, 2));
+    this.configWriter.schedule();
   }
 
+  /** Totals come from the ledger; earnings.json only contributes the pass streak. */
   getEarnings(): EarningsData {
+    if (this.consecutivePasses === null) this.consecutivePasses = this.readStreak();
+    return { ...this.ledger.totals(), consecutivePasses: this.consecutivePasses };
+  }
+
+  updateEarnings(update: Partial<EarningsData>): EarningsData {
+    const updated = { ...this.getEarnings(), ...update };
+    this.consecutivePasses = updated.consecutivePasses;
+    this.earningsWriter.schedule();
+    return updated;
+  }
+
+  /** Persist pending config and earnings writes now — call before exit. */
+  flush(): void {
+    this.configWriter.flush();
+    this.earningsWriter.flush();
+  }
+
+  private readStreak(): number {
     try {
       if (fs.existsSync(this.earningsPath)) {
-        return JSON.parse(fs.readFileSync(this.earningsPath, 'utf-8'));
+        return JSON.parse(fs.readFileSync(this.earningsPath, 'utf-8')).consecutivePasses || 0;
       }
     } catch (error) {
       console.error('[Config] Failed to read earnings:', error);
     }
+    return 0;
+  }
 
-    return {
-      totalHbd: 0,
-      challengesPassed: 0,
-      challengesFailed: 0,
-      consecutivePasses: 0,
-      lastChallengeTime: null,
-    };
+  /** Lets recordChallenge() see whether the IPFS daemon is running (wired by KuboManager). */
+  setDaemonProbe(probe: () => boolean): void {
+    this.daemonProbe = probe;
   }
 
-  updateEarnings(update: Partial<EarningsData>): EarningsData {
-    const current = this.getEarnings();
-    const updated = { ...current, ...update };
-    fs.writeFileSync(this.earningsPath, JSON.stringify(updated, null, 2));
-    return updated;
+  /** Called for each milestone/goal event as it is logged (desktop notifications). */
+  setEarningsEventListener(listener: (event: EarningsEvent) => void): void {
+    this.milestones.setListener(listener);
+  }
+
+  getEarningsEvents(): EarningsEvent[] {
+    return this.milestones.list();
+  }
+
+  /** Credited challenges between two epoch-ms timestamps, with their sum. */
+  getEarningsHistory(fromMs: number, toMs: number): EarningsHistoryRange {
+    return this.ledger.query(fromMs, toMs);
+  }
+
+  /** Challenge counts and HBD per day/week/month between two epoch-ms timestamps. */
+  getEarningsBuckets(fromMs: number, toMs: number, granularity: EarningsGranularity): EarningsBucket[] {
+    return this.ledger.buckets(fromMs, toMs, granularity);
+  }
+
+  getChallengeStats(fromMs: number): ChallengeStats {
+    return this.ledger.challengeStats(fromMs);
+  }
+
+  getRecentChallenges(limit: number): ChallengeHistoryEntry[] {
+    return this.ledger.recentChallenges(limit);
+  }
+
+  /** Every ledger row between two epoch-ms timestamps, oldest first — for exports. */
+  iterateChallenges(fromMs: number, toMs: number): IterableIterator<ChallengeHistoryEntry> {
+    return this.ledger.iterate(fromMs, toMs);
+  }
+
+  getMilestoneState(): MilestoneState {
+    return this.milestones.getState();
+  }
+
+  getQuarantinedEarnings(): QuarantinedEarning[] {
+    return this.quarantine.list();
+  }
+
+  /** Credit quarantined entries (all if `ids` is omitted) as passed challenges. */
+  releaseQuarantinedEarnings(ids?: string[]): QuarantinedEarning[] {
+    const released = this.quarantine.take(ids);
+    if (released.length > 0) {
+      const previousHbd = this.getEarnings().totalHbd;
+      for (const entry of released) {
+        this.ledger.record({ passed: true, amountHbd: entry.hbdEarned });
+      }
+      const current = this.getEarnings();
+      this.milestones.check(previousHbd, current.totalHbd, this.getConfig().earningsGoalHbd);
+      agentEvents.publish('earnings_updated', {
+        totalHbd: current.totalHbd,
+        challengesPassed: current.challengesPassed,
+        challengesFailed: current.challengesFailed,
+      });
+    }
+    return released;
+  }
+
+  /** Drop quarantined entries (all if `ids` is omitted) without crediting them. */
+  discardQuarantinedEarnings(ids?: string[]): QuarantinedEarning[] {
+    return this.quarantine.take(ids);
   }
 
   /**
-   * Record a challenge result. This is synchronous (readFileSync + writeFileSync)
-   * which is safe in Node.js single-threaded event loop — no concurrent interleaving.
+   * Record a challenge result as a ledger row (with the validator and latency when
+   * known). Synchronous — SQLite insert plus the streak write — so there is no
+   * concurrent interleaving on Node's single-threaded event loop.
    */
-  recordChallenge(passed: boolean, hbdEarned: number): EarningsData {
-    const current = this.getEarnings();
-
-    if (passed) {
-      current.challengesPassed++;
-      current.consecutivePasses++;
-      current.totalHbd += hbdEarned;
-    } else {
-      current.challengesFailed++;
-      current.consecutivePasses = 0;
-    }
+  recordChallenge(
+    passed: boolean,
+    hbdEarned: number,
+    cid?: string,
+    details: { validator?: string; latencyMs?: number; blocks?: number; error?: string } = {},
+  ): EarningsData {
+    let current = this.getEarnings();
 
-    current.lastChallengeTime = new Date().toISOString();
+    // Credit arriving while the daemon is down can't reflect served work — apply the offline policy
+    if (passed && this.daemonProbe && !this.daemonProbe()) {
+      const policy = this.getConfig().offlineEarningsPolicy;
+      if (policy === 'reject') {
+        console.warn(`[Config] Rejected ${hbdEarned} HBD credited while the IPFS daemon is down`);
+        return current;
+      }
+      if (policy === 'quarantine') {
+        const entry = this.quarantine.add(hbdEarned, 'daemon-not-running');
+        console.warn(`[Config] Quarantined ${hbdEarned} HBD credited while the IPFS daemon is down (${entry.id})`);
+        return current;
+      }
+    }
 
-    fs.writeFileSync(this.earningsPath, JSON.stringify(current, null, 2));
+    const previousHbd = current.totalHbd;
+    this.ledger.record({ passed, amountHbd: hbdEarned, cid, ...details });
+    const consecutivePasses = passed ? current.consecutivePasses + 1 : 0;
+    current = this.updateEarnings({ consecutivePasses });
+    if (passed && hbdEarned > 0) {
+      this.milestones.check(previousHbd, current.totalHbd, this.getConfig().earningsGoalHbd);
+    }
+    agentEvents.publish('earnings_updated', {
+      totalHbd: current.totalHbd,
+      challengesPassed: current.challengesPassed,
+      challengesFailed: current.challengesFailed,
+    });
     return current;
   }
 }
@@ -0,0 +1,215 @@
+/**
+ * contracts.ts — Keep pins in step with this node's storage contracts
+ *
+ * Opt-in (contractSyncEnabled). Every contractSyncIntervalMin minutes:
+ *   1. GET <serverUrl>/api/contracts/active?storageNode=<hiveUsername>
+ *   2. Queue a pin (via PinJobQueue) for each contract CID not pinned yet,
+ *      stopping once the projected size would pass storageMaxGB or leave the
+ *      repo volume with less than diskMinFreeGB free
+ *   3. If contractUnpinExpired is set, unpin CIDs whose contract has expired
+ *      or left the active list — only pins this module added, never pins the
+ *      operator made by hand or that another contract still covers
+ *
+ * Each pass is kept as a report for GET /api/contracts/sync.
+ */
+
+import axios from 'axios';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import type { KuboManager } from './kubo';
+import type { ConfigStore } from './config';
+import type { PinJobQueue } from './pin-jobs';
+import type { StorageMonitor } from './storage-monitor';
+import { isValidCid } from './poa-crypto';
+import { bootCooldown } from './boot-cooldown';
+import { agentEvents } from './agent-events';
+
+export interface StorageContract {
+  id: string;
+  cid: string;
+  expiresAt: string;
+}
+
+export interface ContractSyncReport {
+  startedAt: string;
+  finishedAt: string;
+  contracts: number;
+  queued: string[];
+  unpinned: string[];
+  failed: { cid: string; error: string }[];
+  skippedForQuota: number;
+  error: string | null;
+}
+
+interface OwnedPin {
+  contractId: string;
+  expiresAt: string;
+}
+
+const MAX_PINS_PER_PASS = 20;
+const MAX_REPORTS = 20;
+
+export class ContractSync {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private pinJobs: PinJobQueue;
+  private storageMonitor: StorageMonitor;
+  private timer: NodeJS.Timeout | null = null;
+  private firstPass: NodeJS.Timeout | null = null;
+  private running = false;
+  private reports: ContractSyncReport[] = [];
+  private ownedPath: string;
+
+  constructor(kubo: KuboManager, config: ConfigStore, pinJobs: PinJobQueue, storageMonitor: StorageMonitor) {
+    this.kubo = kubo;
+    this.config = config;
+    this.pinJobs = pinJobs;
+    this.storageMonitor = storageMonitor;
+    this.ownedPath = path.join(os.homedir(), '.spk-ipfs', 'contract-pins.json');
+  }
+
+  start(): void {
+    const cfg = this.config.getConfig();
+    if (!cfg.contractSyncEnabled || this.timer) return;
+    const intervalMs = Math.max(1, cfg.contractSyncIntervalMin) * 60000;
+    const scheduled = () => {
+      if (!bootCooldown.isActive()) this.sync().catch(() => {});
+    };
+    this.timer = setInterval(scheduled, intervalMs);
+    // First pass after 60 seconds (let IPFS stabilize)
+    this.firstPass = setTimeout(scheduled, 60000);
+    console.log(`[Contracts] Started (every ${cfg.contractSyncIntervalMin} min, unpinExpired=${cfg.contractUnpinExpired})`);
+  }
+
+  stop(): void {
+    if (this.firstPass) {
+      clearTimeout(this.firstPass);
+      this.firstPass = null;
+    }
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  getReports(): ContractSyncReport[] {
+    return [...this.reports];
+  }
+
+  /** Run one sync pass now. Concurrent calls return null. */
+  async sync(): Promise<ContractSyncReport | null> {
+    if (this.running || !this.kubo.isRunning()) return null;
+    this.running = true;
+
+    const cfg = this.config.getConfig();
+    const report: ContractSyncReport = {
+      startedAt: new Date().toISOString(),
+      finishedAt: '',
+      contracts: 0,
+      queued: [],
+      unpinned: [],
+      failed: [],
+      skippedForQuota: 0,
+      error: null,
+    };
+
+    try {
+      if (!cfg.hiveUsername) throw new Error('hiveUsername is not configured');
+      const now = Date.now();
+      const contracts = (await this.fetchContracts(cfg.serverUrl, cfg.hiveUsername))
+        .filter((c) => Date.parse(c.expiresAt) > now);
+      const local = new Set(await this.kubo.listPins());
+      const owned = this.loadOwned();
+      report.contracts = contracts.length;
+
+      const live = new Map<string, StorageContract>();
+      for (const contract of contracts) {
+        const current = live.get(contract.cid);
+        if (!current || Date.parse(contract.expiresAt) > Date.parse(current.expiresAt)) live.set(contract.cid, contract);
+      }
+
+      // Track the latest expiry for every contract pin we own
+      for (const [cid, contract] of live) {
+        if (owned[cid]) owned[cid] = { contractId: contract.id, expiresAt: contract.expiresAt };
+      }
+
+      // Includes our own pins that failed last time; enqueue() dedupes any still fetching
+      const missing = Array.from(live.values()).filter((c) => !local.has(c.cid));
+      for (const contract of missing.slice(0, MAX_PINS_PER_PASS)) {
+        const storage = await this.kubo.getStorageInfo();
+        const sizeBytes = await this.kubo.getObjectSize(contract.cid);
+        const projectedBytes = storage.usedBytes + this.pinJobs.pendingBytes() + (sizeBytes || 0);
+        const diskError = this.storageMonitor.checkPin(sizeBytes || 0, this.pinJobs.pendingBytes());
+        if ((storage.maxBytes > 0 && projectedBytes > storage.maxBytes) || diskError) {
+          report.skippedForQuota = missing.length - report.queued.length;
+          console.warn(`[Contracts] ${diskError || 'Storage limit reached'} — ${report.skippedForQuota} contract pins deferred`);
+          break;
+        }
+        this.pinJobs.enqueue(contract.cid, `contract:${contract.id}`, sizeBytes, 'contract');
+        owned[contract.cid] = { contractId: contract.id, expiresAt: contract.expiresAt };
+        report.queued.push(contract.cid);
+      }
+
+      if (cfg.contractUnpinExpired) {
+        for (const [cid, pin] of Object.entries(owned)) {
+          if (live.has(cid)) continue;
+          try {
+            if (local.has(cid)) {
+              await this.kubo.unpin(cid);
+              agentEvents.publish('pin_removed', { cid, contractId: pin.contractId });
+            }
+            delete owned[cid];
+            report.unpinned.push(cid);
+          } catch (err: any) {
+            report.failed.push({ cid, error: err.message });
+          }
+        }
+      }
+
+      this.saveOwned(owned);
+      console.log(`[Contracts] Sync complete: ${report.contracts} contracts, +${report.queued.length} -${report.unpinned.length} (${report.failed.length} failed)`);
+    } catch (err: any) {
+      report.error = err.message;
+      console.warn(`[Contracts] Sync failed: ${err.message}`);
+    } finally {
+      report.finishedAt = new Date().toISOString();
+      this.reports.push(report);
+      if (this.reports.length > MAX_REPORTS) this.reports.shift();
+      this.running = false;
+    }
+    return report;
+  }
+
+  /** Active contracts the coordinator has assigned to this node's Hive account. */
+  private async fetchContracts(serverUrl: string, hiveUsername: string): Promise<StorageContract[]> {
+    const response = await axios.get(`${serverUrl}/api/contracts/active`, {
+      timeout: 30000,
+      params: { storageNode: hiveUsername },
+    });
+    const list: any[] = Array.isArray(response.data) ? response.data : response.data?.contracts || [];
+    const contracts: StorageContract[] = [];
+    for (const entry of list) {
+      const cid = entry?.fileCid;
+      if (typeof entry?.id !== 'string' || typeof cid !== 'string' || !isValidCid(cid)) continue;
+      if (entry.status && entry.status !== 'active') continue;
+      if (!Number.isFinite(Date.parse(entry.expiresAt))) continue;
+      contracts.push({ id: entry.id, cid, expiresAt: new Date(entry.expiresAt).toISOString() });
+    }
+    return contracts;
+  }
+
+  /** CID → contract for every pin this module added — the only ones it may unpin. */
+  private loadOwned(): Record<string, OwnedPin> {
+    try {
+      if (fs.existsSync(this.ownedPath)) {
+        return JSON.parse(fs.readFileSync(this.ownedPath, 'utf-8'));
+      }
+    } catch {}
+    return {};
+  }
+
+  private saveOwned(owned: Record<string, OwnedPin>): void {
+    fs.writeFileSync(this.ownedPath, JSON.stringify(owned, null, 2));
+  }
+}
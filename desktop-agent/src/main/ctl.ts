@@ -0,0 +1,200 @@
+#!/usr/bin/env node
+/**
+ * hivepoa-ctl — Manage a running agent through its local HTTP API (port 5111).
+ *
+ * Usage:
+ *   hivepoa-ctl status
+ *   hivepoa-ctl pins [--limit=N] [--q=text]
+ *   hivepoa-ctl pin <cid> [--name=label] [--wait]
+ *   hivepoa-ctl earnings
+ *   hivepoa-ctl challenge-test
+ *
+ * Options (any command):
+ *   --api=<url>   Agent API base URL (default $SPK_API_URL or http://127.0.0.1:$SPK_API_PORT|5111)
+ *   --json        Print the raw API response
+ *
+ * Mutating commands authenticate with $SPK_API_TOKEN or ~/.spk-ipfs/api-token.
+ * challenge-test runs a self-challenge pass (random pins, nothing credited).
+ * Exit codes: 0 ok, 1 request or check failed, 2 usage error.
+ */
+
+import axios from 'axios';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+
+const JOB_POLL_MS = 2000;
+
+class UsageError extends Error {}
+
+interface Args {
+  command: string | undefined;
+  positional: string[];
+  options: Record<string, string | true>;
+}
+
+function parseArgs(argv: string[]): Args {
+  const positional: string[] = [];
+  const options: Record<string, string | true> = {};
+  for (const arg of argv) {
+    if (arg.startsWith('--')) {
+      const eq = arg.indexOf('=');
+      if (eq === -1) options[arg.slice(2)] = true;
+      else options[arg.slice(2, eq)] = arg.slice(eq + 1);
+    } else {
+      positional.push(arg);
+    }
+  }
+  return { command: positional.shift(), positional, options };
+}
+
+function apiBase(options: Args['options']): string {
+  if (typeof options.api === 'string') return options.api.replace(/\/+$/, '');
+  if (process.env.SPK_API_URL) return process.env.SPK_API_URL.replace(/\/+$/, '');
+  return `http://127.0.0.1:${process.env.SPK_API_PORT || 5111}`;
+}
+
+function authToken(): string {
+  if (process.env.SPK_API_TOKEN) return process.env.SPK_API_TOKEN;
+  const tokenPath = path.join(os.homedir(), '.spk-ipfs', 'api-token');
+  try {
+    return fs.readFileSync(tokenPath, 'utf-8').trim();
+  } catch {
+    throw new Error(`No API token — set SPK_API_TOKEN or run as the agent's user (${tokenPath})`);
+  }
+}
+
+function formatBytes(bytes: number): string {
+  const units = ['B', 'KB', 'MB', 'GB', 'TB'];
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1024 && unit < units.length - 1) {
+    value /= 1024;
+    unit++;
+  }
+  return `${value.toFixed(unit === 0 ? 0 : 1)} ${units[unit]}`;
+}
+
+class Ctl {
+  private base: string;
+  private json: boolean;
+
+  constructor(options: Args['options']) {
+    this.base = apiBase(options);
+    this.json = options.json === true;
+  }
+
+  async get(route: string, params?: Record<string, any>): Promise<any> {
+    return (await axios.get(`${this.base}${route}`, { params, timeout: 30000 })).data;
+  }
+
+  async post(route: string, body: object = {}, timeout = 30000): Promise<any> {
+    const headers = { Authorization: `Bearer ${authToken()}` };
+    return (await axios.post(`${this.base}${route}`, body, { headers, timeout })).data;
+  }
+
+  /** Print `data` as JSON with --json, else run the human formatter. */
+  print(data: any, human: () => void): void {
+    if (this.json) console.log(JSON.stringify(data, null, 2));
+    else human();
+  }
+
+  async status(): Promise<number> {
+    const s = await this.get('/api/status');
+    this.print(s, () => {
+      console.log(`IPFS:       ${s.running ? 'running' : 'stopped'} (${s.kubo?.mode ?? 'unknown'}), restarts ${s.kuboWatchdog?.restartCount ?? 0}`);
+      console.log(`Peer ID:    ${s.peerId || '-'}`);
+      console.log(`Node ID:    ${s.nodeId || '-'}`);
+      console.log(`Account:    ${s.config?.hiveUsername || '(not set)'}`);
+      console.log(`Storage:    ${s.storageInfo?.usedFormatted} of ${s.storageInfo?.maxFormatted} (${s.storageInfo?.percentage}%)`);
+      console.log(`Mode:       ${s.network?.p2pMode ? 'P2P' : 'legacy'}, ${s.network?.peerCount ?? 0} peers, validator ${s.network?.validatorEnabled ? 'on' : 'off'}`);
+      console.log(`Earnings:   ${s.earnings?.totalHbd ?? 0} HBD (${s.earnings?.challengesPassed ?? 0} passed, ${s.earnings?.challengesFailed ?? 0} failed)`);
+    });
+    return s.running ? 0 : 1;
+  }
+
+  async pins(options: Args['options']): Promise<number> {
+    const params: Record<string, any> = { limit: typeof options.limit === 'string' ? options.limit : 100 };
+    if (typeof options.q === 'string') params.q = options.q;
+    const data = await this.get('/api/pins', params);
+    this.print(data, () => {
+      for (const pin of data.details) {
+        console.log(`${pin.cid}  ${formatBytes(pin.size).padStart(9)}  ${pin.name}`);
+      }
+      console.log(`${data.details.length} of ${data.total} pins`);
+    });
+    return 0;
+  }
+
+  async pin(cid: string | undefined, options: Args['options']): Promise<number> {
+    if (!cid) throw new UsageError('pin needs a CID');
+    const body: Record<string, string> = { cid };
+    if (typeof options.name === 'string') body.name = options.name;
+    let { job } = await this.post('/api/pin', body);
+    if (!this.json) console.log(`Queued ${cid} as job ${job.id}`);
+
+    if (options.wait === true) {
+      while (job.state === 'queued' || job.state === 'fetching') {
+        await new Promise((resolve) => setTimeout(resolve, JOB_POLL_MS));
+        job = (await this.get(`/api/jobs/${job.id}`)).job;
+        if (!this.json) process.stdout.write(`\r${job.state}: ${job.blocksFetched} blocks`);
+      }
+      if (!this.json) process.stdout.write('\n');
+    }
+    this.print(job, () => {
+      if (job.state === 'failed') console.error(`Pin failed: ${job.error}`);
+      else if (options.wait === true) console.log(`Pinned ${cid}`);
+    });
+    return job.state === 'failed' ? 1 : 0;
+  }
+
+  async earnings(): Promise<number> {
+    const e = await this.get('/api/earnings');
+    this.print(e, () => {
+      console.log(`Total:          ${e.totalHbd} HBD`);
+      console.log(`Passed/failed:  ${e.challengesPassed} / ${e.challengesFailed}`);
+      console.log(`Streak:         ${e.consecutivePasses}`);
+      console.log(`Last challenge: ${e.lastChallengeTime || 'never'}`);
+    });
+    return 0;
+  }
+
+  async challengeTest(): Promise<number> {
+    const data = await this.post('/api/self-challenge/run', {}, 300000);
+    this.print(data, () => {
+      if (data.results.length === 0) console.log('No pins to challenge');
+      for (const r of data.results) {
+        const detail = r.passed ? `${r.blockIndices.length} blocks` : r.error;
+        console.log(`${r.passed ? 'PASS' : 'FAIL'}  ${r.cid}  ${r.latencyMs}ms  ${detail}`);
+      }
+    });
+    return data.success ? 0 : 1;
+  }
+}
+
+async function main(): Promise<number> {
+  const { command, positional, options } = parseArgs(process.argv.slice(2));
+  const ctl = new Ctl(options);
+  switch (command) {
+    case 'status': return ctl.status();
+    case 'pins': return ctl.pins(options);
+    case 'pin': return ctl.pin(positional[0], options);
+    case 'earnings': return ctl.earnings();
+    case 'challenge-test': return ctl.challengeTest();
+    default: throw new UsageError(command ? `Unknown command: ${command}` : 'No command given');
+  }
+}
+
+main().then(
+  (code) => process.exit(code),
+  (err: any) => {
+    if (err instanceof UsageError) {
+      console.error(`${err.message}\nUsage: hivepoa-ctl status | pins | pin <cid> | earnings | challenge-test [--api=<url>] [--json]`);
+      process.exit(2);
+    }
+    const apiError = err.response?.data?.error;
+    console.error(apiError ? `${err.response.status}: ${apiError}` : err.code === 'ECONNREFUSED'
+      ? 'Agent API is not reachable — is the agent running?' : err.message);
+    process.exit(1);
+  },
+);
@@ -0,0 +1,135 @@
+/**
+ * daily-summary.ts — Once-a-day digest of what the node did
+ *
+ * With notifyDailySummary set, at dailySummaryHour (local time) every day it
+ * aggregates the previous 24 hours:
+ *   - challenges answered, pass rate and median latency (earnings ledger)
+ *   - HBD credited (earnings ledger)
+ *   - pins added (pin metadata's pinnedAt)
+ *   - agent and IPFS uptime — the IPFS share is worked out from the
+ *     daemon_ready / daemon_stopped events in the event history
+ *
+ * and emits 'daily-summary' for a desktop notification plus a daily_summary
+ * agent event, which webhooks subscribed to 'daily-summary' receive. A summary
+ * missed because the agent was off at that hour is not sent late. GET
+ * /api/summary/daily builds the same report on demand.
+ */
+
+import { EventEmitter } from 'events';
+import type { KuboManager } from './kubo';
+import type { ConfigStore } from './config';
+import type { PinMetadataStore } from './pin-metadata';
+import type { EventHistory } from './event-history';
+import { agentEvents } from './agent-events';
+
+export interface DailySummaryReport {
+  from: string;
+  to: string;
+  challenges: number;
+  passed: number;
+  failed: number;
+  successRate: number | null;  // 0-1, null without challenges
+  latencyP50Ms: number | null;
+  earnedHbd: number;
+  pinsAdded: number;
+  agentUptimePercent: number;
+  ipfsUptimePercent: number;
+}
+
+const DAY_MS = 86400000;
+
+/** One line, used for the notification body and the webhook text. */
+export function formatDailySummary(report: DailySummaryReport): string {
+  const rate = report.successRate === null ? '' : ` (${(report.successRate * 100).toFixed(1)}% passed)`;
+  return `Last 24h: ${report.challenges} challenges${rate}, ${report.earnedHbd.toFixed(3)} HBD earned, ` +
+    `${report.pinsAdded} pins added, IPFS up ${report.ipfsUptimePercent}% of the time`;
+}
+
+export class DailySummary extends EventEmitter {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private pinMetadata: PinMetadataStore;
+  private eventHistory: EventHistory;
+  private timer: NodeJS.Timeout | null = null;
+
+  constructor(kubo: KuboManager, config: ConfigStore, pinMetadata: PinMetadataStore, eventHistory: EventHistory) {
+    super();
+    this.kubo = kubo;
+    this.config = config;
+    this.pinMetadata = pinMetadata;
+    this.eventHistory = eventHistory;
+  }
+
+  start(): void {
+    if (!this.config.getConfig().notifyDailySummary || this.timer) return;
+    this.scheduleNext();
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearTimeout(this.timer);
+      this.timer = null;
+    }
+  }
+
+  /** The last 24 hours up to `now`. */
+  build(now: number = Date.now()): DailySummaryReport {
+    const from = now - DAY_MS;
+    const stats = this.config.getChallengeStats(from);
+    const pinsAdded = Object.values(this.pinMetadata.getAll())
+      .filter((entry) => entry.pinnedAt && Date.parse(entry.pinnedAt) > from).length;
+    const agentUpMs = Math.min(process.uptime() * 1000, DAY_MS);
+    return {
+      from: new Date(from).toISOString(),
+      to: new Date(now).toISOString(),
+      challenges: stats.total,
+      passed: stats.passed,
+      failed: stats.failed,
+      successRate: stats.successRate,
+      latencyP50Ms: stats.latencyP50Ms,
+      earnedHbd: this.config.getEarningsHistory(from, now).totalHbd,
+      pinsAdded,
+      agentUptimePercent: Math.round((agentUpMs / DAY_MS) * 1000) / 10,
+      ipfsUptimePercent: Math.round((this.ipfsUpMs(now - agentUpMs, now) / DAY_MS) * 1000) / 10,
+    };
+  }
+
+  /** Time the daemon was up between `from` and `now`, both within this agent run. */
+  private ipfsUpMs(from: number, now: number): number {
+    const transitions = this.eventHistory.query({ since: from, types: ['daemon_ready', 'daemon_stopped'], limit: 1000 });
+    // State before the first transition is the opposite of it; with none, it is the current state
+    let up = transitions.length > 0 ? transitions[0].type === 'daemon_stopped' : this.kubo.isRunning();
+    let since = from;
+    let total = 0;
+    for (const event of transitions) {
+      const at = Date.parse(event.at);
+      if (up) total += at - since;
+      up = event.type === 'daemon_ready';
+      since = at;
+    }
+    if (up) total += now - since;
+    return total;
+  }
+
+  private scheduleNext(): void {
+    const next = new Date();
+    next.setHours(this.config.getConfig().dailySummaryHour, 0, 0, 0);
+    if (next.getTime() <= Date.now()) next.setDate(next.getDate() + 1);
+    this.timer = setTimeout(() => {
+      this.timer = null;
+      this.send();
+      this.scheduleNext();
+    }, next.getTime() - Date.now());
+  }
+
+  private send(): void {
+    try {
+      const report = this.build();
+      console.log(`[Summary] ${formatDailySummary(report)}`);
+      agentEvents.publish('daily_summary', { ...report });
+      this.emit('daily-summary', report);
+    } catch (err: any) {
+      console.warn(`[Summary] Failed to build the daily summary: ${err.message}`);
+    }
+  }
+}
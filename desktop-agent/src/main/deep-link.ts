@@ -0,0 +1,43 @@
+/**
+ * deep-link.ts — Parse spk:// links
+ *
+ * The agent registers itself as the OS handler for spk://, so a link on a web
+ * page can hand it work. Supported:
+ *
+ *   spk://pin/<cid>?name=<label>   queue a pin (after the user confirms)
+ *
+ * Parsing only — index.ts receives the URL (argv, second-instance, open-url),
+ * asks for confirmation and routes it into the pin queue.
+ */
+
+import { isValidCid } from './poa-crypto';
+
+export const DEEP_LINK_PROTOCOL = 'spk';
+
+export type DeepLink = { action: 'pin'; cid: string; name: string | null };
+
+/** The first spk:// argument, if any (Windows/Linux pass the link on the command line). */
+export function findDeepLinkArg(argv: string[]): string | null {
+  return argv.find((arg) => arg.startsWith(`${DEEP_LINK_PROTOCOL}://`)) || null;
+}
+
+/** The action a link asks for, or an error message for anything malformed or unsupported. */
+export function parseDeepLink(raw: string): DeepLink | { error: string } {
+  let url: URL;
+  try {
+    url = new URL(raw);
+  } catch {
+    return { error: 'Malformed link' };
+  }
+  if (url.protocol !== `${DEEP_LINK_PROTOCOL}:`) return { error: `Not an ${DEEP_LINK_PROTOCOL}:// link` };
+
+  // spk://pin/<cid> parses with host "pin" and path "/<cid>"
+  const action = url.hostname;
+  const args = url.pathname.split('/').filter(Boolean);
+  if (action !== 'pin') return { error: `Unsupported action: ${action || '(none)'}` };
+  const cid = args[0];
+  if (args.length !== 1 || !isValidCid(cid)) return { error: 'Link does not contain a valid CID' };
+  const name = url.searchParams.get('name');
+  if (name !== null && name.length > 256) return { error: 'name must be at most 256 characters' };
+  return { action: 'pin', cid, name: name || null };
+}
@@ -0,0 +1,351 @@
+/**
+ * earnings-ledger.ts — Per-challenge earnings ledger (SQLite)
+ *
+ * Every answered challenge is one row in ~/.spk-ipfs/earnings.db: time, CID,
+ * pass/fail, HBD credited, validator, latency, blocks hashed and the error for
+ * a failure. The same rows back GET /api/challenges/stats and /recent. Totals shown by
+ * /api/earnings are summed from here, so a corrupted or reset earnings.json
+ * no longer loses anything — that file only carries the pass streak now.
+ *
+ * On first open the ledger imports what came before it: the monthly
+ * earnings-history/YYYY-MM.jsonl records, plus an opening balance for totals
+ * in earnings.json that predate any per-challenge record.
+ *
+ * WAL with synchronous=FULL: a record is on disk before record() returns.
+ */
+
+import Database from 'better-sqlite3';
+import * as fs from 'fs';
+import * as path from 'path';
+
+export interface ChallengeRecord {
+  passed: boolean;
+  amountHbd: number;
+  cid?: string;
+  validator?: string;
+  latencyMs?: number;
+  blocks?: number;
+  error?: string;
+}
+
+export interface ChallengeHistoryEntry {
+  at: string;
+  cid: string | null;
+  passed: boolean;
+  amountHbd: number;
+  validator: string | null;
+  latencyMs: number | null;
+  blocks: number | null;
+  error: string | null;
+}
+
+export interface ChallengeStats {
+  total: number;
+  passed: number;
+  failed: number;
+  successRate: number | null;  // 0-1, null before the first challenge
+  latencyP50Ms: number | null;
+  latencyP95Ms: number | null;
+}
+
+export interface EarningsRecord {
+  amountHbd: number;
+  timestamp: string;
+  cid?: string;
+  validator?: string;
+  latencyMs?: number;
+}
+
+export interface EarningsHistoryRange {
+  records: EarningsRecord[];
+  count: number;
+  totalHbd: number;
+  avgPerChallengeHbd: number;
+}
+
+export type EarningsGranularity = 'day' | 'week' | 'month';
+export const EARNINGS_GRANULARITIES: EarningsGranularity[] = ['day', 'week', 'month'];
+
+export interface EarningsBucket {
+  start: string;       // bucket start, UTC date (weeks start on Monday)
+  hbd: number;
+  challenges: number;
+  passed: number;
+  failed: number;
+}
+
+export interface LedgerTotals {
+  totalHbd: number;
+  challengesPassed: number;
+  challengesFailed: number;
+  lastChallengeTime: string | null;
+}
+
+const SCHEMA_VERSION = 2;
+const EXPORT_PAGE_SIZE = 1000;
+
+/** SQLite date() modifiers that map a row's time to its bucket start. */
+const BUCKET_START_SQL: Record<EarningsGranularity, string> = {
+  day: "date(at / 1000, 'unixepoch')",
+  week: "date(at / 1000, 'unixepoch', 'weekday 0', '-6 days')",
+  month: "date(at / 1000, 'unixepoch', 'start of month')",
+};
+
+function nextBucketStart(start: string, granularity: EarningsGranularity): string {
+  const d = new Date(`${start}T00:00:00Z`);
+  if (granularity === 'day') d.setUTCDate(d.getUTCDate() + 1);
+  else if (granularity === 'week') d.setUTCDate(d.getUTCDate() + 7);
+  else d.setUTCMonth(d.getUTCMonth() + 1);
+  return d.toISOString().slice(0, 10);
+}
+
+export class EarningsLedger {
+  private db: ReturnType<typeof Database>;
+
+  /** `legacy` locates the pre-ledger files imported on first open. */
+  constructor(dbPath: string, legacy: { historyDir: string; earningsPath: string }) {
+    fs.mkdirSync(path.dirname(dbPath), { recursive: true });
+    this.db = new Database(dbPath);
+    this.db.pragma('journal_mode = WAL');
+    this.db.pragma('synchronous = FULL');
+    this.db.pragma('busy_timeout = 5000');
+
+    const version = this.db.pragma('user_version', { simple: true }) as number;
+    if (version < 1) this.migrate(legacy);
+    if (version < 2) {
+      this.db.exec('ALTER TABLE challenges ADD COLUMN blocks INTEGER; ALTER TABLE challenges ADD COLUMN error TEXT');
+      this.db.pragma(`user_version = ${SCHEMA_VERSION}`);
+    }
+  }
+
+  record(entry: ChallengeRecord): void {
+    try {
+      this.db.prepare(
+        'INSERT INTO challenges (at, cid, passed, amount_hbd, validator, latency_ms, blocks, error) VALUES (?, ?, ?, ?, ?, ?, ?, ?)'
+      ).run(
+        Date.now(),
+        entry.cid ?? null,
+        entry.passed ? 1 : 0,
+        entry.passed ? entry.amountHbd : 0,
+        entry.validator ?? null,
+        entry.latencyMs ?? null,
+        entry.blocks ?? null,
+        entry.error ? entry.error.slice(0, 500) : null,
+      );
+    } catch (error) {
+      console.error('[Earnings] Failed to write ledger record:', error);
+    }
+  }
+
+  /** All-time totals: the opening balance plus every recorded challenge. */
+  totals(): LedgerTotals {
+    const opening = this.db.prepare('SELECT total_hbd, passed, failed FROM opening_balance WHERE id = 1').get()
+      || { total_hbd: 0, passed: 0, failed: 0 };
+    const sums = this.db.prepare(
+      'SELECT COALESCE(SUM(amount_hbd), 0) AS hbd, COALESCE(SUM(passed), 0) AS passed, ' +
+      'COUNT(*) - COALESCE(SUM(passed), 0) AS failed, MAX(at) AS last FROM challenges'
+    ).get();
+    return {
+      totalHbd: opening.total_hbd + sums.hbd,
+      challengesPassed: opening.passed + sums.passed,
+      challengesFailed: opening.failed + sums.failed,
+      lastChallengeTime: sums.last ? new Date(sums.last).toISOString() : null,
+    };
+  }
+
+  /** Credited challenges with from <= time <= to (epoch ms), oldest first. */
+  query(fromMs: number, toMs: number): EarningsHistoryRange {
+    const rows = this.db.prepare(
+      'SELECT at, cid, amount_hbd, validator, latency_ms FROM challenges ' +
+      'WHERE at >= ? AND at <= ? AND amount_hbd > 0 ORDER BY at, id'
+    ).all(fromMs, toMs);
+    const records: EarningsRecord[] = rows.map((row: any) => ({
+      amountHbd: row.amount_hbd,
+      timestamp: new Date(row.at).toISOString(),
+      ...(row.cid ? { cid: row.cid } : {}),
+      ...(row.validator ? { validator: row.validator } : {}),
+      ...(row.latency_ms !== null ? { latencyMs: row.latency_ms } : {}),
+    }));
+    const totalHbd = records.reduce((sum, r) => sum + r.amountHbd, 0);
+    return {
+      records,
+      count: records.length,
+      totalHbd,
+      avgPerChallengeHbd: records.length > 0 ? totalHbd / records.length : 0,
+    };
+  }
+
+  /**
+   * Every challenge (passed or failed) in from <= time <= to, grouped by UTC
+   * day, Monday-start week or month. Buckets run from the first one with
+   * activity to the last, with quiet ones zero-filled so charts get an even axis.
+   */
+  buckets(fromMs: number, toMs: number, granularity: EarningsGranularity): EarningsBucket[] {
+    const rows = this.db.prepare(
+      `SELECT ${BUCKET_START_SQL[granularity]} AS start, SUM(amount_hbd) AS hbd, COUNT(*) AS challenges, ` +
+      'SUM(passed) AS passed FROM challenges WHERE at >= ? AND at <= ? GROUP BY start ORDER BY start'
+    ).all(fromMs, toMs);
+    if (rows.length === 0) return [];
+
+    const byStart = new Map<string, any>(rows.map((row: any) => [row.start, row]));
+    const last = rows[rows.length - 1].start;
+    const buckets: EarningsBucket[] = [];
+    for (let start = rows[0].start; start <= last; start = nextBucketStart(start, granularity)) {
+      const row = byStart.get(start);
+      buckets.push({
+        start,
+        hbd: row ? row.hbd : 0,
+        challenges: row ? row.challenges : 0,
+        passed: row ? row.passed : 0,
+        failed: row ? row.challenges - row.passed : 0,
+      });
+    }
+    return buckets;
+  }
+
+  /** Pass/fail totals and latency percentiles for challenges at or after `fromMs`. */
+  challengeStats(fromMs: number): ChallengeStats {
+    const counts = this.db.prepare(
+      'SELECT COUNT(*) AS total, COALESCE(SUM(passed), 0) AS passed FROM challenges WHERE at >= ?'
+    ).get(fromMs);
+    const timed = this.db.prepare(
+      'SELECT COUNT(*) AS n FROM challenges WHERE at >= ? AND latency_ms IS NOT NULL'
+    ).get(fromMs).n as number;
+    const percentile = (p: number): number | null => {
+      if (timed === 0) return null;
+      const row = this.db.prepare(
+        'SELECT latency_ms FROM challenges WHERE at >= ? AND latency_ms IS NOT NULL ORDER BY latency_ms LIMIT 1 OFFSET ?'
+      ).get(fromMs, Math.min(timed - 1, Math.floor(timed * p)));
+      return row ? row.latency_ms : null;
+    };
+    return {
+      total: counts.total,
+      passed: counts.passed,
+      failed: counts.total - counts.passed,
+      successRate: counts.total > 0 ? counts.passed / counts.total : null,
+      latencyP50Ms: percentile(0.5),
+      latencyP95Ms: percentile(0.95),
+    };
+  }
+
+  /** The `limit` most recent challenges, newest first. */
+  recentChallenges(limit: number): ChallengeHistoryEntry[] {
+    return this.db.prepare(
+      'SELECT at, cid, passed, amount_hbd, validator, latency_ms, blocks, error FROM challenges ORDER BY at DESC, id DESC LIMIT ?'
+    ).all(limit).map((row: any) => ({
+      at: new Date(row.at).toISOString(),
+      cid: row.cid,
+      passed: row.passed === 1,
+      amountHbd: row.amount_hbd,
+      validator: row.validator,
+      latencyMs: row.latency_ms,
+      blocks: row.blocks,
+      error: row.error,
+    }));
+  }
+
+  /**
+   * Every challenge with from <= time <= to, oldest first, for exports. Read a
+   * page at a time so no statement stays open while the caller awaits — the
+   * connection must stay free for record().
+   */
+  *iterate(fromMs: number, toMs: number): IterableIterator<ChallengeHistoryEntry> {
+    const page = this.db.prepare(
+      'SELECT id, at, cid, passed, amount_hbd, validator, latency_ms, blocks, error FROM challenges ' +
+      'WHERE at <= ? AND (at > ? OR (at = ? AND id > ?)) ORDER BY at, id LIMIT ?'
+    );
+    let lastAt = fromMs;
+    let lastId = -1;
+    for (;;) {
+      const rows = page.all(toMs, lastAt, lastAt, lastId, EXPORT_PAGE_SIZE);
+      for (const row of rows as any[]) {
+        yield {
+          at: new Date(row.at).toISOString(),
+          cid: row.cid,
+          passed: row.passed === 1,
+          amountHbd: row.amount_hbd,
+          validator: row.validator,
+          latencyMs: row.latency_ms,
+          blocks: row.blocks,
+          error: row.error,
+        };
+      }
+      if (rows.length < EXPORT_PAGE_SIZE) return;
+      lastAt = rows[rows.length - 1].at;
+      lastId = rows[rows.length - 1].id;
+    }
+  }
+
+  close(): void {
+    this.db.close();
+  }
+
+  private migrate(legacy: { historyDir: string; earningsPath: string }): void {
+    this.db.exec('BEGIN');
+    try {
+      this.db.exec(`
+        CREATE TABLE IF NOT EXISTS challenges (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          at INTEGER NOT NULL,
+          cid TEXT,
+          passed INTEGER NOT NULL,
+          amount_hbd REAL NOT NULL DEFAULT 0,
+          validator TEXT,
+          latency_ms INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS challenges_at ON challenges (at);
+        CREATE TABLE IF NOT EXISTS opening_balance (
+          id INTEGER PRIMARY KEY CHECK (id = 1),
+          total_hbd REAL NOT NULL,
+          passed INTEGER NOT NULL,
+          failed INTEGER NOT NULL
+        );
+      `);
+
+      const imported = this.importHistory(legacy.historyDir);
+      let previous: any = {};
+      try {
+        if (fs.existsSync(legacy.earningsPath)) previous = JSON.parse(fs.readFileSync(legacy.earningsPath, 'utf-8'));
+      } catch {
+        // unreadable — nothing to carry over beyond the imported records
+      }
+      // Totals in earnings.json that no imported record accounts for
+      this.db.prepare('INSERT OR REPLACE INTO opening_balance (id, total_hbd, passed, failed) VALUES (1, ?, ?, ?)').run(
+        Math.max(0, (Number(previous.totalHbd) || 0) - imported.totalHbd),
+        Math.max(0, (Number(previous.challengesPassed) || 0) - imported.count),
+        Math.max(0, Number(previous.challengesFailed) || 0),
+      );
+
+      this.db.pragma('user_version = 1');
+      this.db.exec('COMMIT');
+      if (imported.count > 0) console.log(`[Earnings] Imported ${imported.count} records into the ledger`);
+    } catch (error) {
+      this.db.exec('ROLLBACK');
+      throw error;
+    }
+  }
+
+  /** Load the monthly JSONL files the ledger replaces. A torn last line is skipped. */
+  private importHistory(dir: string): { count: number; totalHbd: number } {
+    let count = 0;
+    let totalHbd = 0;
+    if (!fs.existsSync(dir)) return { count, totalHbd };
+    const insert = this.db.prepare('INSERT INTO challenges (at, cid, passed, amount_hbd) VALUES (?, ?, 1, ?)');
+    for (const name of fs.readdirSync(dir).filter((n) => /^\d{4}-\d{2}\.jsonl$/.test(n)).sort()) {
+      for (const line of fs.readFileSync(path.join(dir, name), 'utf-8').split('\n')) {
+        if (!line.trim()) continue;
+        try {
+          const record = JSON.parse(line);
+          const at = Date.parse(record.timestamp);
+          if (!Number.isFinite(at) || typeof record.amountHbd !== 'number') continue;
+          insert.run(at, record.cid ?? null, record.amountHbd);
+          count++;
+          totalHbd += record.amountHbd;
+        } catch {
+          // torn write from a crash — skip
+        }
+      }
+    }
+    return { count, totalHbd };
+  }
+}
@@ -0,0 +1,101 @@
+/**
+ * earnings-milestones.ts — Earnings milestone and goal events
+ *
+ * Each time total earnings cross a milestone threshold (or the operator's
+ * earningsGoalHbd), an event is appended to ~/.spk-ipfs/earnings-events.jsonl
+ * and the listener fires (desktop notification) alongside an earnings_milestone
+ * bus event (webhooks). The highest milestone and the
+ * goal already reached are persisted in config, so nothing is logged twice
+ * across restarts.
+ */
+
+import * as fs from 'fs';
+import { agentEvents } from './agent-events';
+
+export const EARNINGS_MILESTONES_HBD = [1, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+export interface EarningsEvent {
+  type: 'milestone' | 'goal';
+  thresholdHbd: number;
+  totalHbd: number;
+  reachedAt: string;
+}
+
+/** Persisted progress, kept in the config store. */
+export interface MilestoneState {
+  highestMilestoneHbd: number | null; // null = never checked (fresh install or upgrade)
+  goalReachedHbd: number | null;      // goal value last logged as reached
+}
+
+/** Milestones in (fromHbd, toHbd], ascending. */
+export function milestonesCrossed(fromHbd: number, toHbd: number): number[] {
+  return EARNINGS_MILESTONES_HBD.filter((m) => m > fromHbd && m <= toHbd);
+}
+
+export class EarningsMilestoneTracker {
+  private filePath: string;
+  private readState: () => MilestoneState;
+  private writeState: (state: MilestoneState) => void;
+  private listener: ((event: EarningsEvent) => void) | null = null;
+
+  constructor(filePath: string, readState: () => MilestoneState, writeState: (state: MilestoneState) => void) {
+    this.filePath = filePath;
+    this.readState = readState;
+    this.writeState = writeState;
+  }
+
+  setListener(listener: (event: EarningsEvent) => void): void {
+    this.listener = listener;
+  }
+
+  list(): EarningsEvent[] {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        return fs.readFileSync(this.filePath, 'utf-8')
+          .split('\n')
+          .filter((line) => line.trim())
+          .map((line) => JSON.parse(line));
+      }
+    } catch (error) {
+      console.error('[Earnings] Failed to read milestone events:', error);
+    }
+    return [];
+  }
+
+  getState(): MilestoneState {
+    return this.readState();
+  }
+
+  /**
+   * Log any milestone or goal crossed by moving from `previousHbd` to `totalHbd`.
+   * On first use the baseline is taken from `previousHbd`, so upgrading with
+   * existing earnings doesn't replay every past milestone.
+   */
+  check(previousHbd: number, totalHbd: number, goalHbd: number | null): EarningsEvent[] {
+    const state = this.readState();
+    const baseline = state.highestMilestoneHbd ?? Math.max(0, ...milestonesCrossed(0, previousHbd));
+    const now = new Date().toISOString();
+    const events: EarningsEvent[] = milestonesCrossed(baseline, totalHbd)
+      .map((thresholdHbd) => ({ type: 'milestone' as const, thresholdHbd, totalHbd, reachedAt: now }));
+
+    if (goalHbd && goalHbd > 0 && totalHbd >= goalHbd && state.goalReachedHbd !== goalHbd) {
+      events.push({ type: 'goal', thresholdHbd: goalHbd, totalHbd, reachedAt: now });
+    }
+
+    const next: MilestoneState = {
+      highestMilestoneHbd: Math.max(baseline, ...events.filter((e) => e.type === 'milestone').map((e) => e.thresholdHbd)),
+      goalReachedHbd: events.some((e) => e.type === 'goal') ? goalHbd : state.goalReachedHbd,
+    };
+    if (next.highestMilestoneHbd !== state.highestMilestoneHbd || next.goalReachedHbd !== state.goalReachedHbd) {
+      this.writeState(next);
+    }
+
+    for (const event of events) {
+      fs.appendFileSync(this.filePath, JSON.stringify(event) + '\n');
+      console.log(`[Earnings] ${event.type === 'goal' ? 'Goal' : 'Milestone'} reached: ${event.thresholdHbd} HBD`);
+      this.listener?.(event);
+      agentEvents.publish('earnings_milestone', { ...event });
+    }
+    return events;
+  }
+}
@@ -0,0 +1,60 @@
+/**
+ * earnings-quarantine.ts — Earnings held back for manual review
+ *
+ * With offlineEarningsPolicy = 'quarantine', a passed challenge that is
+ * credited while the IPFS daemon is not running goes here instead of into
+ * earnings.json. An operator can then release (credit) or discard each entry
+ * via /api/earnings/quarantine.
+ */
+
+import * as crypto from 'crypto';
+import * as fs from 'fs';
+
+export interface QuarantinedEarning {
+  id: string;
+  hbdEarned: number;
+  receivedAt: string;
+  reason: string;
+}
+
+export class EarningsQuarantine {
+  private filePath: string;
+
+  constructor(filePath: string) {
+    this.filePath = filePath;
+  }
+
+  list(): QuarantinedEarning[] {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        return JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+      }
+    } catch (error) {
+      console.error('[Earnings] Failed to read quarantine:', error);
+    }
+    return [];
+  }
+
+  add(hbdEarned: number, reason: string): QuarantinedEarning {
+    const entry: QuarantinedEarning = {
+      id: crypto.randomBytes(8).toString('hex'),
+      hbdEarned,
+      receivedAt: new Date().toISOString(),
+      reason,
+    };
+    const entries = this.list();
+    entries.push(entry);
+    fs.writeFileSync(this.filePath, JSON.stringify(entries, null, 2));
+    return entry;
+  }
+
+  /** Remove entries by id (all entries if `ids` is omitted) and return them. */
+  take(ids?: string[]): QuarantinedEarning[] {
+    const entries = this.list();
+    const wanted = ids ? new Set(ids) : null;
+    const taken = entries.filter((e) => !wanted || wanted.has(e.id));
+    const kept = entries.filter((e) => wanted && !wanted.has(e.id));
+    fs.writeFileSync(this.filePath, JSON.stringify(kept, null, 2));
+    return taken;
+  }
+}
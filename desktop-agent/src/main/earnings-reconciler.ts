@@ -0,0 +1,261 @@
+/**
+ * earnings-reconciler.ts — Check the earnings ledger against HBD actually received
+ *
+ * The ledger credits whatever validators report; this is the ground truth
+ * check. Every RECONCILE_INTERVAL_MS (with payoutReconcileEnabled and a
+ * hiveUsername) it reads the account's history for the last
+ * payoutReconcileDays and matches it against ledger credits:
+ *
+ *   - Incoming HBD transfers whose memo looks like a PoA reward
+ *     ("SPK PoA 2.0 batch reward: 12 proofs verified") are payouts. Each one
+ *     consumes its proof count of the oldest unpaid credits before it, and
+ *     the HBD must equal what those credits add up to.
+ *   - Credits still unpaid UNPAID_GRACE_MS after they were earned are flagged
+ *     (coordinators pay in batches, so younger ones are just pending).
+ *   - HBD from claim_reward_balance is summed for the report, not matched —
+ *     those are author/curation rewards, not PoA.
+ *
+ * A payout early in the window may pay for proofs credited before it, which
+ * then shows up as PAYOUT_WITHOUT_LEDGER. The latest reports are kept for
+ * GET /api/earnings/reconciliation.
+ */
+
+import type { ConfigStore } from './config';
+import type { EarningsRecord } from './earnings-ledger';
+import { AgentHiveClient } from './hive';
+import { agentEvents } from './agent-events';
+
+export type DiscrepancyType = 'AMOUNT_MISMATCH' | 'PAYOUT_WITHOUT_LEDGER' | 'UNATTRIBUTED_PAYOUT' | 'UNPAID';
+
+export interface PayoutDiscrepancy {
+  type: DiscrepancyType;
+  at: string;                  // transfer time, or the oldest unpaid credit
+  txId: string | null;
+  from: string | null;
+  paidHbd: number;             // 0 for UNPAID
+  expectedHbd: number;         // what the matched ledger credits add up to
+  proofs: number | null;       // proof count from the memo
+  matchedEntries: number;
+}
+
+export interface OnChainPayout {
+  at: string;
+  txId: string;
+  from: string;
+  amountHbd: number;
+  proofs: number | null;
+  memo: string;
+}
+
+export interface ReconciliationReport {
+  startedAt: string;
+  finishedAt: string;
+  account: string;
+  windowStart: string;
+  ledgerEntries: number;
+  ledgerHbd: number;
+  payouts: number;
+  paidHbd: number;
+  claimedHbd: number;
+  matchedEntries: number;
+  unpaidEntries: number;
+  unpaidHbd: number;
+  pendingHbd: number;          // credits still inside the grace period
+  discrepancies: PayoutDiscrepancy[];
+  error: string | null;
+}
+
+const RECONCILE_INTERVAL_MS = 6 * 3600000;
+const UNPAID_GRACE_MS = 48 * 3600000;
+const HISTORY_PAGE = 1000;
+const MAX_HISTORY_PAGES = 50;
+const MAX_REPORTS = 10;
+const HBD_TOLERANCE = 0.0015;  // transfers carry 3 decimals
+const REWARD_MEMO = /poa.*reward/i;
+// get_account_history operation filter: transfer (2) and claim_reward_balance (39)
+const HISTORY_FILTER_LOW = 2 ** 2 + 2 ** 39;
+
+function hbdAmount(amount: string): number | null {
+  const [value, symbol] = String(amount).split(' ');
+  return symbol === 'HBD' ? parseFloat(value) : null;
+}
+
+function round3(n: number): number {
+  return Math.round(n * 1000) / 1000;
+}
+
+export class EarningsReconciler {
+  private config: ConfigStore;
+  private timer: NodeJS.Timeout | null = null;
+  private firstPass: NodeJS.Timeout | null = null;
+  private running = false;
+  private reports: ReconciliationReport[] = [];
+
+  constructor(config: ConfigStore) {
+    this.config = config;
+  }
+
+  start(): void {
+    const cfg = this.config.getConfig();
+    if (!cfg.payoutReconcileEnabled || !cfg.hiveUsername || this.timer) return;
+    this.timer = setInterval(() => this.reconcile().catch(() => {}), RECONCILE_INTERVAL_MS);
+    this.firstPass = setTimeout(() => this.reconcile().catch(() => {}), 120000);
+  }
+
+  stop(): void {
+    if (this.firstPass) {
+      clearTimeout(this.firstPass);
+      this.firstPass = null;
+    }
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  getReports(): ReconciliationReport[] {
+    return [...this.reports];
+  }
+
+  /** Run one pass now. Concurrent calls return null. */
+  async reconcile(): Promise<ReconciliationReport | null> {
+    if (this.running) return null;
+    this.running = true;
+
+    const { hiveUsername, hiveApiNodes, payoutReconcileDays } = this.config.getConfig();
+    const now = Date.now();
+    const windowStart = now - payoutReconcileDays * 86400000;
+    const report: ReconciliationReport = {
+      startedAt: new Date(now).toISOString(),
+      finishedAt: '',
+      account: hiveUsername || '',
+      windowStart: new Date(windowStart).toISOString(),
+      ledgerEntries: 0,
+      ledgerHbd: 0,
+      payouts: 0,
+      paidHbd: 0,
+      claimedHbd: 0,
+      matchedEntries: 0,
+      unpaidEntries: 0,
+      unpaidHbd: 0,
+      pendingHbd: 0,
+      discrepancies: [],
+      error: null,
+    };
+
+    try {
+      if (!hiveUsername) throw new Error('hiveUsername is not configured');
+      const hive = new AgentHiveClient({ username: hiveUsername, nodes: hiveApiNodes });
+      const { payouts, claimedHbd } = await this.fetchPayouts(hive, hiveUsername, windowStart);
+      const ledger = this.config.getEarningsHistory(windowStart, now).records;
+      report.ledgerEntries = ledger.length;
+      report.ledgerHbd = round3(ledger.reduce((sum, r) => sum + r.amountHbd, 0));
+      report.payouts = payouts.length;
+      report.paidHbd = round3(payouts.reduce((sum, p) => sum + p.amountHbd, 0));
+      report.claimedHbd = round3(claimedHbd);
+      this.match(payouts, ledger, now, report);
+
+      if (report.discrepancies.length > 0) {
+        agentEvents.publish('payout_discrepancy', {
+          discrepancies: report.discrepancies.length,
+          unpaidHbd: report.unpaidHbd,
+          ledgerHbd: report.ledgerHbd,
+          paidHbd: report.paidHbd,
+        });
+      }
+      console.log(`[Reconcile] ${report.payouts} payouts (${report.paidHbd} HBD) vs ${report.ledgerEntries} credits ` +
+        `(${report.ledgerHbd} HBD): ${report.discrepancies.length} discrepancies`);
+    } catch (err: any) {
+      report.error = err.message;
+      console.warn(`[Reconcile] Failed: ${err.message}`);
+    } finally {
+      report.finishedAt = new Date().toISOString();
+      this.reports.push(report);
+      if (this.reports.length > MAX_REPORTS) this.reports.shift();
+      this.running = false;
+    }
+    return report;
+  }
+
+  /** Oldest-first FIFO: each payout pays for the `proofs` oldest unpaid credits earned before it. */
+  private match(payouts: OnChainPayout[], ledger: EarningsRecord[], now: number, report: ReconciliationReport): void {
+    let next = 0;
+    for (const payout of payouts) {
+      const paidAt = Date.parse(payout.at);
+      if (payout.proofs === null) {
+        report.discrepancies.push({
+          type: 'UNATTRIBUTED_PAYOUT', at: payout.at, txId: payout.txId, from: payout.from,
+          paidHbd: payout.amountHbd, expectedHbd: 0, proofs: null, matchedEntries: 0,
+        });
+        continue;
+      }
+      let matched = 0;
+      let expected = 0;
+      while (matched < payout.proofs && next < ledger.length && Date.parse(ledger[next].timestamp) <= paidAt) {
+        expected += ledger[next].amountHbd;
+        matched++;
+        next++;
+      }
+      report.matchedEntries += matched;
+      const type: DiscrepancyType | null = matched < payout.proofs
+        ? 'PAYOUT_WITHOUT_LEDGER'
+        : Math.abs(expected - payout.amountHbd) > HBD_TOLERANCE ? 'AMOUNT_MISMATCH' : null;
+      if (type) {
+        report.discrepancies.push({
+          type, at: payout.at, txId: payout.txId, from: payout.from,
+          paidHbd: payout.amountHbd, expectedHbd: round3(expected), proofs: payout.proofs, matchedEntries: matched,
+        });
+      }
+    }
+
+    const unpaid = ledger.slice(next).filter((r) => now - Date.parse(r.timestamp) > UNPAID_GRACE_MS);
+    const pending = ledger.slice(next + unpaid.length);
+    report.unpaidEntries = unpaid.length;
+    report.unpaidHbd = round3(unpaid.reduce((sum, r) => sum + r.amountHbd, 0));
+    report.pendingHbd = round3(pending.reduce((sum, r) => sum + r.amountHbd, 0));
+    if (unpaid.length > 0) {
+      report.discrepancies.push({
+        type: 'UNPAID', at: unpaid[0].timestamp, txId: null, from: null,
+        paidHbd: 0, expectedHbd: report.unpaidHbd, proofs: null, matchedEntries: unpaid.length,
+      });
+    }
+  }
+
+  /** Reward transfers to `account` and HBD claimed since `sinceMs`, oldest first. */
+  private async fetchPayouts(hive: AgentHiveClient, account: string, sinceMs: number): Promise<{ payouts: OnChainPayout[]; claimedHbd: number }> {
+    const payouts: OnChainPayout[] = [];
+    let claimedHbd = 0;
+    let start = -1;
+    for (let page = 0; page < MAX_HISTORY_PAGES; page++) {
+      // Hive rejects a limit larger than start + 1
+      const limit = start < 0 ? HISTORY_PAGE : Math.min(HISTORY_PAGE, start + 1);
+      const entries = await hive.getAccountHistory(account, start, limit, HISTORY_FILTER_LOW);
+      if (entries.length === 0) break;
+      let reachedStart = false;
+      for (const [, entry] of entries) {
+        const at = Date.parse(`${entry.timestamp}Z`); // chain times are UTC without a zone
+        if (at < sinceMs) {
+          reachedStart = true;
+          continue;
+        }
+        const [name, op] = entry.op;
+        if (name === 'transfer' && op.to === account && REWARD_MEMO.test(op.memo || '')) {
+          const amountHbd = hbdAmount(op.amount);
+          if (amountHbd === null) continue;
+          const proofs = /(\d+)\s+proofs?/i.exec(op.memo);
+          payouts.push({
+            at: new Date(at).toISOString(), txId: entry.trx_id, from: op.from, amountHbd,
+            proofs: proofs ? Number(proofs[1]) : null, memo: op.memo,
+          });
+        } else if (name === 'claim_reward_balance') {
+          claimedHbd += hbdAmount(op.reward_hbd) || 0;
+        }
+      }
+      const oldestIndex: number = entries[0][0];
+      if (reachedStart || oldestIndex <= 0) break;
+      start = oldestIndex - 1;
+    }
+    payouts.sort((a, b) => Date.parse(a.at) - Date.parse(b.at));
+    return { payouts, claimedHbd };
+  }
+}
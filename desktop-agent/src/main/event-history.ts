@@ -0,0 +1,95 @@
+/**
+ * event-history.ts — Rolling on-disk log of agent events behind GET /api/events
+ *
+ * The event bus (agent-events.ts) only reaches clients that are connected at
+ * the time. This store appends each event to ~/.spk-ipfs/events.jsonl and keeps
+ * the latest MAX_EVENTS in memory, so the dashboard can render an activity
+ * timeline and an operator can see what happened overnight (pins, challenges,
+ * daemon restarts, config changes, errors) after the fact.
+ *
+ * challenge_received and proof_submitted are not kept — challenge_completed
+ * already records each challenge's outcome, latency and proof. The file is
+ * compacted back to MAX_EVENTS lines once it grows to twice that.
+ */
+
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { agentEvents, AgentEvent, AgentEventType } from './agent-events';
+
+export interface EventQuery {
+  since?: number;           // ms epoch, exclusive
+  types?: AgentEventType[];
+  limit?: number;
+}
+
+const MAX_EVENTS = 5000;
+const SKIPPED_TYPES: AgentEventType[] = ['challenge_received', 'proof_submitted'];
+
+export class EventHistory {
+  private filePath: string;
+  private events: AgentEvent[] = [];
+  private lines = 0;
+  private writes: Promise<void> = Promise.resolve();
+  private unsubscribe: (() => void) | null = null;
+
+  constructor(filePath: string = path.join(os.homedir(), '.spk-ipfs', 'events.jsonl')) {
+    this.filePath = filePath;
+    try {
+      for (const line of fs.readFileSync(filePath, 'utf-8').split('\n')) {
+        if (!line) continue;
+        try { this.events.push(JSON.parse(line)); } catch { continue; }
+        this.lines++;
+      }
+    } catch {}
+    if (this.events.length > MAX_EVENTS) this.events = this.events.slice(-MAX_EVENTS);
+    // Keep ids increasing across restarts so `id` stays a usable cursor
+    const lastId = this.events.length > 0 ? this.events[this.events.length - 1].id : 0;
+    agentEvents.resumeIdsAfter(lastId);
+  }
+
+  start(): void {
+    if (this.unsubscribe) return;
+    this.unsubscribe = agentEvents.subscribe((event) => this.record(event));
+  }
+
+  stop(): void {
+    this.unsubscribe?.();
+    this.unsubscribe = null;
+  }
+
+  /** Matching events, oldest first — the latest `limit` of them. */
+  query({ since, types, limit = 200 }: EventQuery = {}): AgentEvent[] {
+    const matches = this.events.filter((event) =>
+      (since === undefined || Date.parse(event.at) > since) && (!types || types.includes(event.type)));
+    return matches.slice(-limit);
+  }
+
+  private record(event: AgentEvent): void {
+    if (SKIPPED_TYPES.includes(event.type)) return;
+    this.events.push(event);
+    if (this.events.length > MAX_EVENTS) this.events.shift();
+
+    const line = JSON.stringify(event) + '\n';
+    this.writes = this.writes.then(async () => {
+      try {
+        await fs.promises.mkdir(path.dirname(this.filePath), { recursive: true });
+        if (++this.lines >= MAX_EVENTS * 2) {
+          await this.compact();
+        } else {
+          await fs.promises.appendFile(this.filePath, line);
+        }
+      } catch (err: any) {
+        console.warn(`[Events] Failed to write ${this.filePath}: ${err.message}`);
+      }
+    });
+  }
+
+  /** Rewrite the file with just the in-memory window. */
+  private async compact(): Promise<void> {
+    const tmpPath = `${this.filePath}.tmp`;
+    await fs.promises.writeFile(tmpPath, this.events.map((e) => JSON.stringify(e) + '\n').join(''));
+    await fs.promises.rename(tmpPath, this.filePath);
+    this.lines = this.events.length;
+  }
+}
@@ -0,0 +1,136 @@
+/**
+ * gc-scheduler.ts — Garbage-collect the repo when it's quiet or getting full
+ *
+ * Kubo's own --enable-gc only runs once the datastore passes its watermark,
+ * mid-day or not. When gcScheduleEnabled is set, every GC_CHECK_INTERVAL_MS
+ * this checks two triggers:
+ *   - quiet hours: the local hour is inside gcQuietStartHour..gcQuietEndHour
+ *     and there has been no GC in the last QUIET_RUN_SPACING_MS
+ *   - watermark: repo usage is at or above gcWatermarkPercent of storageMaxGB
+ *     (0 disables), at most once per WATERMARK_RUN_SPACING_MS so a repo full
+ *     of pinned data doesn't GC in a loop
+ *
+ * POST /api/gc runs one immediately. Every run publishes 'gc_completed' with
+ * the blocks removed and bytes reclaimed.
+ */
+
+import type { KuboManager } from './kubo';
+import type { ConfigStore } from './config';
+import { agentEvents } from './agent-events';
+import { bootCooldown } from './boot-cooldown';
+
+export type GcReason = 'manual' | 'quiet-hours' | 'watermark';
+
+export interface GcRun {
+  reason: GcReason;
+  startedAt: string;
+  durationMs: number;
+  removedBlocks: number;
+  reclaimedBytes: number;
+  errors: number;
+  error: string | null;
+}
+
+const GC_CHECK_INTERVAL_MS = 10 * 60000;
+const QUIET_RUN_SPACING_MS = 20 * 3600000;
+const WATERMARK_RUN_SPACING_MS = 3600000;
+const MAX_RUNS = 50;
+
+/** True if `hour` falls in the quiet window; windows may wrap midnight (22 → 4). */
+export function inQuietHours(hour: number, startHour: number, endHour: number): boolean {
+  if (startHour === endHour) return false;
+  return startHour < endHour ? hour >= startHour && hour < endHour : hour >= startHour || hour < endHour;
+}
+
+export class GcScheduler {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private timer: NodeJS.Timeout | null = null;
+  private running = false;
+  private runs: GcRun[] = [];
+
+  constructor(kubo: KuboManager, config: ConfigStore) {
+    this.kubo = kubo;
+    this.config = config;
+  }
+
+  start(): void {
+    const cfg = this.config.getConfig();
+    if (!cfg.gcScheduleEnabled || this.timer) return;
+    this.timer = setInterval(() => {
+      if (!bootCooldown.isActive()) this.check().catch(() => {});
+    }, GC_CHECK_INTERVAL_MS);
+    console.log(`[GC] Scheduled (quiet hours ${cfg.gcQuietStartHour}:00-${cfg.gcQuietEndHour}:00, watermark ${cfg.gcWatermarkPercent}%)`);
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  getStatus(): Record<string, any> {
+    const cfg = this.config.getConfig();
+    return {
+      enabled: cfg.gcScheduleEnabled,
+      quietStartHour: cfg.gcQuietStartHour,
+      quietEndHour: cfg.gcQuietEndHour,
+      watermarkPercent: cfg.gcWatermarkPercent,
+      running: this.running,
+      runs: [...this.runs].reverse(),
+    };
+  }
+
+  /** Run GC now. Returns null if a run is already in progress or IPFS is down. */
+  async run(reason: GcReason): Promise<GcRun | null> {
+    if (this.running || !this.kubo.isRunning()) return null;
+    this.running = true;
+    const start = Date.now();
+    const run: GcRun = {
+      reason,
+      startedAt: new Date(start).toISOString(),
+      durationMs: 0,
+      removedBlocks: 0,
+      reclaimedBytes: 0,
+      errors: 0,
+      error: null,
+    };
+    try {
+      Object.assign(run, await this.kubo.runGc());
+      console.log(`[GC] ${reason}: removed ${run.removedBlocks} blocks, reclaimed ${run.reclaimedBytes} bytes`);
+    } catch (err: any) {
+      run.error = err.response?.data?.Message || err.message;
+      console.warn(`[GC] ${reason} run failed: ${run.error}`);
+    } finally {
+      run.durationMs = Date.now() - start;
+      this.running = false;
+    }
+    this.runs.push(run);
+    if (this.runs.length > MAX_RUNS) this.runs.shift();
+    agentEvents.publish('gc_completed', { ...run });
+    return run;
+  }
+
+  private async check(): Promise<void> {
+    if (this.running || !this.kubo.isRunning()) return;
+    const cfg = this.config.getConfig();
+    const sinceLast = this.lastRunAt() === null ? Infinity : Date.now() - this.lastRunAt()!;
+
+    if (cfg.gcWatermarkPercent > 0 && sinceLast >= WATERMARK_RUN_SPACING_MS) {
+      const storage = await this.kubo.getStorageInfo();
+      if (storage.percentage >= cfg.gcWatermarkPercent) {
+        await this.run('watermark');
+        return;
+      }
+    }
+    if (inQuietHours(new Date().getHours(), cfg.gcQuietStartHour, cfg.gcQuietEndHour) && sinceLast >= QUIET_RUN_SPACING_MS) {
+      await this.run('quiet-hours');
+    }
+  }
+
+  private lastRunAt(): number | null {
+    const last = this.runs[this.runs.length - 1];
+    return last ? Date.parse(last.startedAt) : null;
+  }
+}
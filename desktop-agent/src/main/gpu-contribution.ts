@@ -47,6 +47,8 @@ export interface GpuContributionConfig {
   scheduleEnd: string;            // "08:00"
   hivePoaUrl: string;             // HivePoA server URL
   hiveUsername: string | null;
+  nodeId: string | null;          // Coordinator-facing node ID (see resolveNodeId)
+  heartbeatBackoffMaxMs: number;  // cap on heartbeat retry backoff while the coordinator is down
   lendTargetIp: string | null;    // IP of the computer to lend GPU to (lend mode only)
 }
 
@@ -75,6 +77,8 @@ const DEFAULT_GPU_CONFIG: GpuContributionConfig = {
   scheduleEnd: '08:00',
   hivePoaUrl: 'http://localhost:5000',
   hiveUsername: null,
+  nodeId: null,
+  heartbeatBackoffMaxMs: 900000,
   lendTargetIp: null,
 };
 
@@ -95,7 +99,7 @@ export class GpuContributionManager extends EventEmitter {
   // Timers
   private gamingCheckTimer: ReturnType<typeof setInterval> | null = null;
   private metricsTimer: ReturnType<typeof setInterval> | null = null;
-  private heartbeatTimer: ReturnType<typeof setInterval> | null = null;
+  private heartbeatTimer: ReturnType<typeof setTimeout> | null = null;
   private scheduleTimer: ReturnType<typeof setInterval> | null = null;
 
   // Stats
@@ -106,6 +110,13 @@ export class GpuContributionManager extends EventEmitter {
   private gamingIdleChecks: number = 0;
   private lastError: string | null = null;
 
+  // Coordinator heartbeat backoff
+  private heartbeatFailures: number = 0;
+  private heartbeatBackoffMs: number = 0;
+  private nextHeartbeatAt: number = 0;
+  private lastHeartbeatSuccess: number = 0;
+  private coordinatorReachable: boolean = true;
+
   constructor(config: Partial<GpuContributionConfig> = {}) {
     super();
     this.config = { ...DEFAULT_GPU_CONFIG, ...config };
@@ -350,6 +361,7 @@ export class GpuContributionManager extends EventEmitter {
         headers: { 'Content-Type': 'application/json' },
         body: JSON.stringify({
           hiveUsername: this.config.hiveUsername,
+          nodeId: this.config.nodeId,
           gpuModel: gpuInfo.name,
           gpuVramGb: gpuInfo.vramTotalGb,
           deviceUuid: gpuInfo.uuid,
@@ -373,10 +385,8 @@ export class GpuContributionManager extends EventEmitter {
       if (metrics) this.emit('metrics', metrics);
     }, METRICS_POLL_INTERVAL_MS);
 
-    // Heartbeat to HivePoA
-    this.heartbeatTimer = setInterval(async () => {
-      await this.sendHeartbeat();
-    }, HEARTBEAT_INTERVAL_MS);
+    // Heartbeat to HivePoA (self-rescheduling so failures can back off)
+    this.scheduleHeartbeat(HEARTBEAT_INTERVAL_MS);
 
     // Gaming mode detection
     if (this.config.autoGamingMode) {
@@ -402,7 +412,7 @@ export class GpuContributionManager extends EventEmitter {
 
   private stopMonitoring(): void {
     if (this.metricsTimer) { clearInterval(this.metricsTimer); this.metricsTimer = null; }
-    if (this.heartbeatTimer) { clearInterval(this.heartbeatTimer); this.heartbeatTimer = null; }
+    if (this.heartbeatTimer) { clearTimeout(this.heartbeatTimer); this.heartbeatTimer = null; }
     if (this.gamingCheckTimer) { clearInterval(this.gamingCheckTimer); this.gamingCheckTimer = null; }
     this.docker.stopHealthCheck();
   }
@@ -465,22 +475,82 @@ export class GpuContributionManager extends EventEmitter {
     }
   }
 
-  private async sendHeartbeat(): Promise<void> {
-    if (!this.config.hiveUsername) return;
+  private scheduleHeartbeat(delayMs: number): void {
+    this.nextHeartbeatAt = Date.now() + delayMs;
+    this.heartbeatTimer = setTimeout(async () => {
+      const ok = await this.sendHeartbeat();
+      if (this.heartbeatTimer === null) return; // monitoring stopped mid-request
+      this.scheduleHeartbeat(this.recordHeartbeatResult(ok));
+    }, delayMs);
+  }
+
+  /**
+   * Update backoff state and return the delay until the next heartbeat:
+   * the normal interval on success, doubling per consecutive failure up to
+   * heartbeatBackoffMaxMs. Reachability transitions are logged once each.
+   */
+  private recordHeartbeatResult(ok: boolean): number {
+    if (ok) {
+      if (!this.coordinatorReachable) {
+        console.log(`[GPU] Coordinator reachable again after ${this.heartbeatFailures} failed heartbeat(s)`);
+      }
+      this.coordinatorReachable = true;
+      this.heartbeatFailures = 0;
+      this.heartbeatBackoffMs = 0;
+      this.lastHeartbeatSuccess = Date.now();
+      return HEARTBEAT_INTERVAL_MS;
+    }
+
+    this.heartbeatFailures++;
+    this.heartbeatBackoffMs = Math.min(
+      HEARTBEAT_INTERVAL_MS * Math.pow(2, this.heartbeatFailures),
+      Math.max(HEARTBEAT_INTERVAL_MS, this.config.heartbeatBackoffMaxMs),
+    );
+    if (this.coordinatorReachable) {
+      this.coordinatorReachable = false;
+      console.warn(`[GPU] Coordinator ${this.config.hivePoaUrl} unreachable — backing off heartbeats (challenges are still served locally)`);
+    }
+    return this.heartbeatBackoffMs;
+  }
+
+  /** Heartbeat backoff snapshot for /api/status. */
+  getHeartbeatState(): {
+    coordinatorReachable: boolean;
+    consecutiveFailures: number;
+    backoffMs: number;
+    nextHeartbeatAt: string | null;
+    lastSuccessAt: string | null;
+  } {
+    return {
+      coordinatorReachable: this.coordinatorReachable,
+      consecutiveFailures: this.heartbeatFailures,
+      backoffMs: this.heartbeatBackoffMs,
+      nextHeartbeatAt: this.heartbeatTimer ? new Date(this.nextHeartbeatAt).toISOString() : null,
+      lastSuccessAt: this.lastHeartbeatSuccess ? new Date(this.lastHeartbeatSuccess).toISOString() : null,
+    };
+  }
+
+  /** Returns true if the coordinator accepted the heartbeat (or there is nothing to send). */
+  private async sendHeartbeat(): Promise<boolean> {
+    if (!this.config.hiveUsername) return true;
 
     try {
-      await fetch(`${this.config.hivePoaUrl}/api/compute/nodes/heartbeat`, {
+      const response = await fetch(`${this.config.hivePoaUrl}/api/compute/nodes/heartbeat`, {
         method: 'POST',
         headers: { 'Content-Type': 'application/json' },
         body: JSON.stringify({
           hiveUsername: this.config.hiveUsername,
+          nodeId: this.config.nodeId,
           state: this.state,
           uptimeMs: this.startTime > 0 ? Date.now() - this.startTime : 0,
         }),
         signal: AbortSignal.timeout(10000),
       });
+      // A 4xx means the coordinator is up but rejected us — backing off won't help
+      return response.status < 500;
     } catch {
-      // Non-fatal
+      // Non-fatal — network error or timeout, caller backs off
+      return false;
     }
   }
 
@@ -0,0 +1,53 @@
+/**
+ * headless.ts — Running without a display (--headless)
+ *
+ * The Electron app skips its window and tray; both entry points tee console
+ * output to a log file (~/.spk-ipfs/logs/agent.log, or --log-file=<path>)
+ * and write what would have been desktop notifications to that log instead.
+ * Remote alerting goes through `webhooks` (see webhooks.ts).
+ */
+
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { format } from 'util';
+
+const MAX_LOG_BYTES = 10 * 1024 * 1024;
+
+export function isHeadless(argv: string[]): boolean {
+  return argv.includes('--headless');
+}
+
+/** Log file from --log-file=<path>, else ~/.spk-ipfs/logs/agent.log. */
+export function resolveLogFile(argv: string[]): string {
+  const flag = argv.find((arg) => arg.startsWith('--log-file='));
+  const value = flag?.slice('--log-file='.length);
+  return value ? path.resolve(value) : path.join(os.homedir(), '.spk-ipfs', 'logs', 'agent.log');
+}
+
+/**
+ * Append every console.log/warn/error line to `filePath`, timestamped. A log
+ * already past MAX_LOG_BYTES is rotated to `<file>.1` first (one generation).
+ */
+export function startFileLogging(filePath: string): void {
+  fs.mkdirSync(path.dirname(filePath), { recursive: true });
+  try {
+    if (fs.statSync(filePath).size > MAX_LOG_BYTES) fs.renameSync(filePath, `${filePath}.1`);
+  } catch {}
+  const stream = fs.createWriteStream(filePath, { flags: 'a' });
+  stream.on('error', () => {}); // a full disk must not take the agent down
+
+  for (const level of ['log', 'warn', 'error'] as const) {
+    const original = console[level].bind(console);
+    console[level] = (...args: any[]) => {
+      original(...args);
+      stream.write(`${new Date().toISOString()} ${level.toUpperCase().padEnd(5)} ${format(...args)}\n`);
+    };
+  }
+  console.log(`[Headless] Logging to ${filePath}`);
+}
+
+/** Stand-in for a desktop notification. */
+export function logNotification(title: string, body: string): void {
+  console.warn(`[Notify] ${title}: ${body}`);
+}
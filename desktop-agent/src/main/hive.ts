@@ -11,6 +11,16 @@ const DEFAULT_HIVE_NODES = [
   'https://rpc.mahdiyari.info',
 ];
 
+/** Hive account name rules: 3-16 chars, dot-separated segments of 3+ that start with a letter. */
+export function isValidHiveUsername(name: string): boolean {
+  return name.length >= 3 && name.length <= 16 &&
+    name.split('.').every((segment) => /^[a-z][a-z0-9-]{1,}[a-z0-9]$/.test(segment) && !segment.includes('--'));
+}
+
+export type HiveAccountCheck =
+  | { ok: true }
+  | { ok: false; error: 'INVALID_HIVE_USERNAME' | 'HIVE_ACCOUNT_NOT_FOUND' | 'HIVE_KEY_MISMATCH' | 'HIVE_UNREACHABLE'; message: string };
+
 export interface AgentHiveConfig {
   nodes?: string[];
   username: string;
@@ -49,7 +59,7 @@ export class AgentHiveClient {
 
   constructor(config: AgentHiveConfig) {
     this.config = config;
-    this.client = new Client(config.nodes || DEFAULT_HIVE_NODES, {
+    this.client = new Client(config.nodes?.length ? config.nodes : DEFAULT_HIVE_NODES, {
       timeout: 8000,         // 8s per-request timeout
       failoverThreshold: 2,  // switch node after 2 failures
       rebrandedApi: true,
@@ -148,6 +158,40 @@ export class AgentHiveClient {
     return props.head_block_number;
   }
 
+  /** Witness median price feed: 1 HIVE in HBD. */
+  async getMedianHivePrice(): Promise<number> {
+    await this.throttle();
+    try {
+      const price = await this.client.database.call('get_current_median_history_price', []);
+      this.onSuccess();
+      const hbd = parseFloat(String(price.base));   // "0.231 HBD"
+      const hive = parseFloat(String(price.quote)); // "1.000 HIVE"
+      if (!(hbd > 0) || !(hive > 0)) throw new Error('Empty median price feed');
+      return hbd / hive;
+    } catch (err) {
+      this.onFailure();
+      throw err;
+    }
+  }
+
+  /**
+   * One page of account history, newest last: `[index, { block, timestamp, trx_id, op: [name, payload] }]`.
+   * `start` -1 means the latest entry; `filterLow`/`filterHigh` are operation-id bitmasks (0 = all).
+   */
+  async getAccountHistory(account: string, start: number, limit: number, filterLow = 0, filterHigh = 0): Promise<any[]> {
+    await this.throttle();
+    try {
+      const params: any[] = [account, start, Math.min(limit, 1000)];
+      if (filterLow || filterHigh) params.push(filterLow, filterHigh);
+      const history = await this.client.database.call('get_account_history', params);
+      this.onSuccess();
+      return history || [];
+    } catch (err) {
+      this.onFailure();
+      throw err;
+    }
+  }
+
   /**
    * Fetch multiple blocks in a single batched call via block_api.get_block_range.
    * Falls back to sequential fetch if the batch API isn't available.
@@ -232,6 +276,36 @@ export class AgentHiveClient {
     }
   }
 
+  /**
+   * Confirm `username` is an existing account and, if `publicKey` is given, that
+   * the key is one of its owner/active/posting/memo keys. HIVE_UNREACHABLE when
+   * no API node answered — nothing was learned about the account.
+   */
+  async checkAccount(username: string, publicKey?: string): Promise<HiveAccountCheck> {
+    if (!isValidHiveUsername(username)) {
+      return { ok: false, error: 'INVALID_HIVE_USERNAME', message: `"${username}" is not a valid Hive account name` };
+    }
+    let account: any;
+    try {
+      account = await this.getAccount(username);
+    } catch (err: any) {
+      return { ok: false, error: 'HIVE_UNREACHABLE', message: `Could not reach a Hive API node to verify @${username}: ${err.message}` };
+    }
+    if (!account) {
+      return { ok: false, error: 'HIVE_ACCOUNT_NOT_FOUND', message: `Hive account @${username} does not exist` };
+    }
+    if (publicKey !== undefined) {
+      const keys = new Set<string>([account.memo_key]);
+      for (const role of ['owner', 'active', 'posting']) {
+        for (const [key] of account[role]?.key_auths || []) keys.add(String(key));
+      }
+      if (!keys.has(publicKey)) {
+        return { ok: false, error: 'HIVE_KEY_MISMATCH', message: `${publicKey} is not a key of @${username}` };
+      }
+    }
+    return { ok: true };
+  }
+
   /** Get Hive account reputation score (0-100 scale). */
   async getReputationScore(username: string): Promise<number> {
     const account = await this.getAccount(username);
@@ -346,6 +420,22 @@ export class AgentHiveClient {
     });
   }
 
+  /** Broadcast a proof this node answered (storage side of a PoA challenge). */
+  async broadcastProof(
+    cid: string,
+    salt: string,
+    proof: string,
+    latencyMs: number | null
+  ): Promise<string | null> {
+    return this.broadcastCustomJson('spk_poa_proof', {
+      cid,
+      salt,
+      proof,
+      latencyMs,
+      timestamp: new Date().toISOString(),
+    });
+  }
+
   /** Fetch top witnesses by vote from the Hive blockchain. */
   async getTopWitnesses(limit: number = 150): Promise<string[]> {
     await this.throttle();
@@ -373,6 +463,12 @@ export class AgentHiveClient {
     return idx >= 0 ? idx + 1 : null;
   }
 
+  /** The account's posting public keys, empty if it doesn't exist. */
+  async getPostingPublicKeys(username: string): Promise<string[]> {
+    const account = await this.getAccount(username);
+    return account ? account.posting.key_auths.map(([key]: [string, number]) => String(key)) : [];
+  }
+
   /** Verify a Hive Keychain signature against the account's posting key authorities. */
   async verifySignature(username: string, message: string, signature: string): Promise<boolean> {
     try {
@@ -0,0 +1,824 @@
+/**
+ * http-challenge.ts — PoA challenges received over the HTTP API
+ *
+ * Backs POST /api/challenge and POST /api/challenge/batch (legacy HTTP mode,
+ * as opposed to the PubSub flow in challenge-handler.ts).
+ *
+ * Proof = SHA256(salt || block[i0] || block[i1] ...) in request order.
+ *
+ * Strict offline mode (`offline: true`) is a PoST-style timing proof: every
+ * block is read with Kubo's --offline flag against a tight deadline. Blocks
+ * that are not local are still fetched so the proof completes, but they are
+ * reported in `networkIndices` and the response is marked `servedOffline:
+ * false` — letting the coordinator tell storers from just-in-time fetchers.
+ *
+ * Private-storage pools set `hmacKeyId`: the response then also carries an
+ * HMAC of the same input keyed by the coordinator-shared secret configured
+ * under that id (challengeHmacKeys), so a proof can't be precomputed from the
+ * public CID alone. Encrypted content needs nothing special — proofs are always
+ * over the stored bytes.
+ *
+ * Blocks are read concurrently (at most READ_CONCURRENCY at a time) in both
+ * modes and hashed in request order; `blockTimingsMs` reports how long each
+ * read took, in request order, to show which block ate the latency budget.
+ *
+ * `deadlineMs` is the validator's latency budget, counted from receipt. Online,
+ * when it runs out the reads still in flight are cancelled and the agent answers
+ * with a partial proof — `partial: true`, the proof over just the blocks read
+ * (`completedIndices`, hashed in request order), `pendingIndices` and the
+ * elapsed time — so the validator can credit what was served in time. In
+ * strict offline mode the same field is the hard deadline for the local reads
+ * (default STRICT_OFFLINE_DEADLINE_MS); missing it fails the challenge.
+ *
+ * Freshness: every successful response also carries
+ * freshProof = SHA256(salt || timestamp || serverNonce || blocks...), where
+ * timestamp is the challenge's own (ms epoch; receipt time if omitted) and
+ * serverNonce is random per response, so it can't come from a stored proof.
+ * A (cid, salt) pair already answered within CHALLENGE_REPLAY_WINDOW_MS is
+ * rejected with 409, as is a timestamp older than that window; a challenge
+ * that ends without a proof gives its salt back. With requireBlockSalts set,
+ * the request must also carry `blockHash` and `saltNonce` proving the salt was
+ * derived from a recent Hive block (see challenge-freshness.ts). These checks
+ * run before the challenge_type dispatch, so retrieval challenges get them too.
+ *
+ * `proofType: 'merkle'` adds merkleProof — each leaf's hash and its path to the
+ * CID root (see proof.ts) — so a validator can check the leaves against the
+ * root without holding the content.
+ *
+ * With signChallengeProofs set, successful responses also carry `signature`
+ * and `account`: the posting key's signature over sha256(cid|salt|proof), so
+ * a validator can attribute the proof to a Hive account on-chain (check it
+ * with AgentHiveClient.verifySignature and the message `${cid}|${salt}|${proof}`).
+ *
+ * Retrieval challenges (`challenge_type: 'retrieval'`) test that the node can
+ * actually serve the content, not just hash it: the agent reads `length` bytes
+ * from `offset` out of its local blockstore and returns them base64-encoded in
+ * `data`, with proof = SHA256(salt || range) and the measured read throughput.
+ * A read that misses `deadlineMs` fails the challenge. The validator checks the
+ * bytes against its own copy and times the whole round trip on its side.
+ */
+
+import * as crypto from 'crypto';
+import type { KuboManager } from './kubo';
+import type { ConfigStore } from './config';
+import type { AgentMetrics } from './metrics';
+import { bootCooldown } from './boot-cooldown';
+import { isValidCid, retryTransient, httpProofFromBlocks, httpProofsFromBlocks, httpProofHmac, PROOF_HASH_ALGOS, isProofHashAlgo } from './poa-crypto';
+import { buildMerkleProof, MerkleProof, ProofType, PROOF_TYPES } from './proof';
+import { BlockSaltVerifier, SaltReplayGuard } from './challenge-freshness';
+
+export interface HttpChallengeRequest {
+  cid: string;
+  blockIndex?: number;
+  blockIndices?: number[];
+  salt: string;
+  validatorId?: string;
+  offline?: boolean;     // strict offline timing mode
+  deadlineMs?: number;   // latency budget from receipt: partial proof online, hard deadline offline
+  hmacKeyId?: string;    // id of a configured challengeHmacKeys secret
+  hashAlgos?: string[];  // extra proofs over the same reads, e.g. ['sha256', 'sha3-256']
+  timestamp?: number;    // challenge issue time (ms epoch), mixed into freshProof
+  proofType?: ProofType; // 'hash' (default) or 'merkle' (adds merkleProof)
+  blockHash?: string;    // Hive block id the salt was derived from (requireBlockSalts)
+  saltNonce?: string;    // salt = sha256(`${blockHash}:${saltNonce}`)
+  challenge_type?: ChallengeType; // 'proof' (default) or 'retrieval'
+  offset?: number;       // retrieval: first byte of the range
+  length?: number;       // retrieval: bytes to serve, at most RETRIEVAL_MAX_BYTES
+}
+
+export type ChallengeType = 'proof' | 'retrieval';
+export const CHALLENGE_TYPES: ChallengeType[] = ['proof', 'retrieval'];
+
+/** Signs a sha256 digest (hex) with the posting key; null when no key is unlocked. */
+export type ProofSigner = (digestHex: string) => string | null;
+
+/** HTTP status + JSON body, so single and batch endpoints can share one code path. */
+export interface HttpChallengeResult {
+  status: number;
+  body: Record<string, any>;
+}
+
+const BATCH_CONCURRENCY = 4;
+const STRICT_OFFLINE_DEADLINE_MS = 2000;
+const MAX_OFFLINE_DEADLINE_MS = 30000;
+const MAX_HASH_ALGOS = 4;
+const CHALLENGE_REPLAY_WINDOW_MS = 10 * 60000;
+const READ_CONCURRENCY = 8;
+// Recently read blocks (block CID → bytes). Blocks are content-addressed so a hit
+// is always correct; locality is still checked on every challenge before reading.
+const BLOCK_CACHE_MAX_BYTES = 32 * 1024 * 1024;
+const BLOCK_CACHE_TTL_MS = 5 * 60000;
+const MAX_SEEN_SALTS = 10000;
+const MAX_CLOCK_SKEW_MS = 60000;
+const RETRIEVAL_MAX_BYTES = 4 * 1024 * 1024;
+const RETRIEVAL_DEADLINE_MS = 10000;
+const MAX_CHALLENGE_BUDGET_MS = 120000;
+
+interface KeyedProof {
+  hmacKeyId: string;
+  secret: string;
+}
+
+/** Extra response fields for a keyed proof (none when no hmacKeyId was requested). */
+function hmacFields(keyed: KeyedProof | null, salt: string, blocks: Buffer[]): { hmac?: string; hmacKeyId?: string } {
+  return keyed ? { hmac: httpProofHmac(keyed.secret, salt, blocks), hmacKeyId: keyed.hmacKeyId } : {};
+}
+
+/** Proof of a fresh read: binds the blocks to the challenge time and a per-response nonce. */
+function freshnessFields(salt: string, blocks: Buffer[], timestamp: number): {
+  freshProof: string; serverNonce: string; challengeTimestamp: number; respondedAt: string;
+} {
+  const serverNonce = crypto.randomBytes(16).toString('hex');
+  return {
+    freshProof: httpProofFromBlocks(`${salt}${timestamp}${serverNonce}`, blocks),
+    serverNonce,
+    challengeTimestamp: timestamp,
+    respondedAt: new Date().toISOString(),
+  };
+}
+
+/**
+ * `proof` (always sha256, for existing coordinators) plus a `proofs` map when
+ * hashAlgos was requested. All digests come from a single pass over the blocks.
+ */
+function proofFields(salt: string, blocks: Buffer[], hashAlgos: string[] | null): { proof: string; proofs?: Record<string, string> } {
+  if (!hashAlgos) {
+    return { proof: httpProofFromBlocks(salt, blocks) };
+  }
+  const algos = hashAlgos.includes('sha256') ? hashAlgos : ['sha256', ...hashAlgos];
+  const all = httpProofsFromBlocks(salt, blocks, algos);
+  const proofs: Record<string, string> = {};
+  for (const algo of hashAlgos) proofs[algo] = all[algo];
+  return { proof: all.sha256, proofs };
+}
+
+/**
+ * Reject indices past the end of the DAG before any fetch, so "no such block in
+ * this DAG" (coordinator bug) is never confused with "I lost a block" (data loss).
+ * Not recorded as a failed challenge.
+ */
+export function indexRangeError(indices: number[], blockCount: number): HttpChallengeResult | null {
+  const outOfRangeIndices = indices.filter((i) => i >= blockCount);
+  if (outOfRangeIndices.length === 0) return null;
+  return {
+    status: 400,
+    body: {
+      success: false,
+      error: 'INDEX_OUT_OF_RANGE',
+      message: blockCount > 0
+        ? `CID has ${blockCount} blocks; valid indices are 0-${blockCount - 1}`
+        : 'CID has no child blocks; it cannot be challenged by block index',
+      outOfRangeIndices,
+      blockCount,
+      validRange: blockCount > 0 ? [0, blockCount - 1] : null,
+    },
+  };
+}
+
+export class HttpChallengeService {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private metrics: AgentMetrics;
+  private proofSigner: ProofSigner | null = null;
+  private saltVerifier: BlockSaltVerifier | null = null;
+  private replayGuard = new SaltReplayGuard(CHALLENGE_REPLAY_WINDOW_MS, MAX_SEEN_SALTS);
+  private blockCache: Map<string, { data: Buffer; at: number }> = new Map();
+  private blockCacheBytes = 0;
+
+  constructor(kubo: KuboManager, config: ConfigStore, metrics: AgentMetrics) {
+    this.kubo = kubo;
+    this.config = config;
+    this.metrics = metrics;
+  }
+
+  setProofSigner(signer: ProofSigner): void {
+    this.proofSigner = signer;
+  }
+
+  setSaltVerifier(verifier: BlockSaltVerifier): void {
+    this.saltVerifier = verifier;
+  }
+
+  /** Validate, read the requested blocks, and compute the proof. */
+  async run(request: HttpChallengeRequest, signal?: AbortSignal): Promise<HttpChallengeResult> {
+    const { cid, salt, timestamp, blockHash, saltNonce, challenge_type } = request || ({} as HttpChallengeRequest);
+    if (challenge_type !== undefined && !CHALLENGE_TYPES.includes(challenge_type)) {
+      return { status: 400, body: { error: `challenge_type must be one of: ${CHALLENGE_TYPES.join(', ')}` } };
+    }
+    if (this.config.getConfig().challengesPaused) {
+      return { status: 503, body: { success: false, error: 'CHALLENGES_PAUSED', message: 'Challenges are paused on this node' } };
+    }
+    if (!cid || !salt) {
+      return { status: 400, body: { error: 'Missing required fields: cid, salt' } };
+    }
+    if (!isValidCid(cid)) {
+      return { status: 400, body: { error: 'Valid CID required' } };
+    }
+
+    // Freshness applies to every challenge type, so it runs before the dispatch
+    const receivedAt = Date.now();
+    const stale = await this.checkFreshness(cid, salt, timestamp, blockHash, saltNonce, receivedAt);
+    if (stale) return stale;
+
+    // Only an answered challenge uses up its salt — a malformed request or a
+    // failed read can be retried with the same one
+    let result: HttpChallengeResult;
+    try {
+      result = challenge_type === 'retrieval'
+        ? await this.runRetrieval(request, signal)
+        : await this.runBlocks(request, receivedAt, signal);
+    } catch (error) {
+      this.replayGuard.release(cid, salt);
+      throw error;
+    }
+    if (!result.body.proof) this.replayGuard.release(cid, salt);
+    return result;
+  }
+
+  /** Block challenge — cid and salt are validated and claimed by run(). */
+  private async runBlocks(request: HttpChallengeRequest, receivedAt: number, signal?: AbortSignal): Promise<HttpChallengeResult> {
+    const { cid, blockIndex, blockIndices, salt, validatorId, offline, deadlineMs, hmacKeyId, hashAlgos, timestamp, proofType } = request;
+    const challengeTimestamp = timestamp ?? receivedAt;
+    const indices: number[] = Array.isArray(blockIndices)
+      ? blockIndices
+      : blockIndex !== undefined ? [blockIndex] : [];
+
+    if (indices.length === 0) {
+      return { status: 400, body: { error: 'Missing required field: blockIndex (or blockIndices)' } };
+    }
+    // Bound the cost of a single challenge before touching any block
+    const { maxBlocksPerChallenge } = this.config.getConfig();
+    if (indices.length > maxBlocksPerChallenge) {
+      return {
+        status: 400,
+        body: {
+          success: false,
+          error: 'TOO_MANY_BLOCKS',
+          message: `Challenge requested ${indices.length} blocks; this node allows at most ${maxBlocksPerChallenge}`,
+          requested: indices.length,
+          maxBlocksPerChallenge,
+        },
+      };
+    }
+    if (!indices.every((i) => Number.isInteger(i) && i >= 0)) {
+      return { status: 400, body: { error: 'Block indices must be non-negative integers' } };
+    }
+    const maxDeadlineMs = offline ? MAX_OFFLINE_DEADLINE_MS : MAX_CHALLENGE_BUDGET_MS;
+    if (deadlineMs !== undefined && (!Number.isInteger(deadlineMs) || deadlineMs <= 0 || deadlineMs > maxDeadlineMs)) {
+      return { status: 400, body: { error: `deadlineMs must be 1-${maxDeadlineMs}${offline ? ' for offline challenges' : ''}` } };
+    }
+    let hmacSecret: string | null = null;
+    if (hmacKeyId !== undefined) {
+      // Own keys only, so ids like "__proto__" or "constructor" can't resolve to inherited values
+      const keys = this.config.getConfig().challengeHmacKeys;
+      hmacSecret = typeof hmacKeyId === 'string' && Object.hasOwn(keys, hmacKeyId) ? keys[hmacKeyId] || null : null;
+      if (!hmacSecret) {
+        return { status: 400, body: { error: `Unknown hmacKeyId: ${hmacKeyId}` } };
+      }
+    }
+    const keyed = hmacSecret ? { hmacKeyId: hmacKeyId!, secret: hmacSecret } : null;
+
+    if (hashAlgos !== undefined) {
+      if (!Array.isArray(hashAlgos) || hashAlgos.length === 0 || hashAlgos.length > MAX_HASH_ALGOS ||
+          new Set(hashAlgos).size !== hashAlgos.length) {
+        return { status: 400, body: { error: `hashAlgos must list 1-${MAX_HASH_ALGOS} distinct algorithms` } };
+      }
+      const unsupported = hashAlgos.filter((algo) => !isProofHashAlgo(algo));
+      if (unsupported.length > 0) {
+        return {
+          status: 400,
+          body: {
+            success: false,
+            error: 'UNSUPPORTED_HASH_ALGO',
+            unsupported,
+            supported: Object.keys(PROOF_HASH_ALGOS),
+          },
+        };
+      }
+    }
+    const algos = hashAlgos ?? null;
+    if (proofType !== undefined && !PROOF_TYPES.includes(proofType)) {
+      return { status: 400, body: { error: `proofType must be one of: ${PROOF_TYPES.join(', ')}` } };
+    }
+    const merkle = proofType === 'merkle';
+
+    // Attribution for the earnings ledger only — never trusted for anything else
+    const validator = typeof validatorId === 'string' && validatorId ? validatorId.slice(0, 64) : undefined;
+
+    if (offline) {
+      return this.runStrictOffline(cid, indices, salt, deadlineMs ?? STRICT_OFFLINE_DEADLINE_MS, keyed, algos, challengeTimestamp, merkle, validator, signal);
+    }
+
+    const { challengeReadahead, challengeRetries } = this.config.getConfig();
+    const readahead = bootCooldown.isActive() ? 0 : challengeReadahead; // no prefetch right after boot
+    const startTime = Date.now();
+    let retriesUsed = 0;
+
+    try {
+      const blocks = await this.challengeBlocks(cid);
+
+      const outOfRange = indexRangeError(indices, blocks.length);
+      if (outOfRange) return outOfRange;
+
+      // A pin that is still fetching can be challenged over its local blocks —
+      // only fail if a requested block is missing, and never wait on the network for it.
+      const localMask = await this.kubo.getLocalBlockMask(indices.map((i) => blocks[i]));
+      const missingIndices = indices.filter((_, n) => !localMask[n]);
+      if (missingIndices.length > 0) {
+        this.config.recordChallenge(false, 0, cid, {
+          validator, latencyMs: Date.now() - startTime, blocks: indices.length, error: 'BLOCKS_NOT_LOCAL',
+        });
+        this.metrics.recordChallenge(cid, false);
+        return {
+          status: 409,
+          body: {
+            success: false,
+            error: 'BLOCKS_NOT_LOCAL',
+            missingIndices,
+            responseTime: Date.now() - startTime,
+          },
+        };
+      }
+
+      // Fan reads out, but hash in request order so the proof stays deterministic.
+      // The first failure cancels the reads still in flight.
+      const data: Buffer[] = new Array(indices.length);
+      const blockTimingsMs: number[] = new Array(indices.length);
+      const warmed = new Set<number>();
+      const readAbort = new AbortController();
+      const onAbort = () => readAbort.abort();
+      signal?.addEventListener('abort', onAbort);
+      let failed: { index: number; error: any } | null = null;
+      let next = 0;
+      // deadlineMs: when the budget runs out, cancel the reads and answer with what was read
+      let budgetExpired = false;
+      const budgetTimer = deadlineMs !== undefined
+        ? setTimeout(() => {
+            budgetExpired = true;
+            readAbort.abort();
+          }, Math.max(0, deadlineMs - (Date.now() - receivedAt)))
+        : null;
+      const worker = async () => {
+        while (next < indices.length && !failed && !budgetExpired) {
+          const n = next++;
+          const readStart = Date.now();
+          try {
+            data[n] = await retryTransient(
+              () => this.readBlockCached(blocks, indices[n], readahead, warmed, readAbort.signal),
+              challengeRetries,
+              readAbort.signal,
+              () => retriesUsed++
+            );
+            blockTimingsMs[n] = Date.now() - readStart;
+          } catch (error) {
+            if (budgetExpired) return;
+            if (!failed) failed = { index: indices[n], error };
+            readAbort.abort();
+          }
+        }
+      };
+      try {
+        await Promise.all(Array.from({ length: Math.min(READ_CONCURRENCY, indices.length) }, worker));
+      } finally {
+        signal?.removeEventListener('abort', onAbort);
+        if (budgetTimer) clearTimeout(budgetTimer);
+      }
+      if (budgetExpired && !failed && !signal?.aborted) {
+        return this.partialResult(cid, salt, indices, blocks, data, blockTimingsMs, {
+          keyed, algos, challengeTimestamp, validator, deadlineMs: deadlineMs!, receivedAt, startTime, retriesUsed,
+        });
+      }
+      if (failed) {
+        const { index, error } = failed as { index: number; error: any };
+        if (signal?.aborted) throw error;
+        this.config.recordChallenge(false, 0, cid, {
+          validator, latencyMs: Date.now() - startTime, blocks: indices.length, error: error.message,
+        });
+        this.metrics.recordChallenge(cid, false);
+        return {
+          status: 500,
+          body: {
+            success: false,
+            error: error.message,
+            failedIndex: index,
+            retriesUsed,
+            responseTime: Date.now() - startTime,
+          },
+        };
+      }
+      const proofs = proofFields(salt, data, algos);
+      this.metrics.recordBlocksHashed(data.length);
+      const merkleProof = merkle ? await this.merkleProof(cid, blocks, indices, data, signal) : null;
+
+      const responseTime = Date.now() - startTime;
+      const hbdEarned = 0.001;
+      this.config.recordChallenge(true, hbdEarned, cid, { validator, latencyMs: responseTime, blocks: data.length });
+      this.metrics.recordChallenge(cid, true, responseTime);
+
+      return {
+        status: 200,
+        body: {
+          success: true,
+          ...proofs,
+          blockCid: blocks[indices[0]],
+          blockCids: indices.map((i) => blocks[i]),
+          ...hmacFields(keyed, salt, data),
+          ...freshnessFields(salt, data, challengeTimestamp),
+          ...(merkleProof && { merkleProof }),
+          ...this.signatureFields(cid, salt, proofs.proof),
+          retriesUsed,
+          blockTimingsMs,
+          responseTime,
+        },
+      };
+    } catch (error: any) {
+      // Cancelled by the caller (batch client disconnected) — not the node's fault
+      if (signal?.aborted) {
+        return { status: 499, body: { success: false, error: 'CANCELLED', responseTime: Date.now() - startTime } };
+      }
+      this.config.recordChallenge(false, 0, cid, {
+        validator, latencyMs: Date.now() - startTime, blocks: indices.length, error: error.message,
+      });
+      this.metrics.recordChallenge(cid, false);
+      return {
+        status: 500,
+        body: {
+          success: false,
+          error: error.message,
+          retriesUsed,
+          responseTime: Date.now() - startTime,
+        },
+      };
+    }
+  }
+
+  /**
+   * deadlineMs ran out mid-read: prove the blocks that were read in time. Not a
+   * pass — the ledger records DEADLINE_EXCEEDED — but the validator may credit
+   * `blocksCompleted` of `blocksRequested`.
+   */
+  private partialResult(
+    cid: string,
+    salt: string,
+    indices: number[],
+    blocks: string[],
+    data: Buffer[],
+    blockTimingsMs: number[],
+    ctx: {
+      keyed: KeyedProof | null; algos: string[] | null; challengeTimestamp: number; validator: string | undefined;
+      deadlineMs: number; receivedAt: number; startTime: number; retriesUsed: number;
+    },
+  ): HttpChallengeResult {
+    const completed = indices.map((_, n) => n).filter((n) => data[n] !== undefined);
+    const completedData = completed.map((n) => data[n]);
+    const completedIndices = completed.map((n) => indices[n]);
+    const pendingIndices = indices.filter((_, n) => data[n] === undefined);
+    const proofs = proofFields(salt, completedData, ctx.algos);
+    this.metrics.recordBlocksHashed(completedData.length);
+
+    const responseTime = Date.now() - ctx.startTime;
+    this.config.recordChallenge(false, 0, cid, {
+      validator: ctx.validator, latencyMs: responseTime, blocks: indices.length, error: 'DEADLINE_EXCEEDED',
+    });
+    this.metrics.recordChallenge(cid, false);
+    console.warn(`[HttpChallenge] deadlineMs ${ctx.deadlineMs} exceeded for ${cid} — partial proof over ${completed.length}/${indices.length} blocks`);
+
+    return {
+      status: 200,
+      body: {
+        success: false,
+        partial: true,
+        error: 'DEADLINE_EXCEEDED',
+        ...proofs,
+        completedIndices,
+        pendingIndices,
+        blocksCompleted: completed.length,
+        blocksRequested: indices.length,
+        blockCids: completedIndices.map((i) => blocks[i]),
+        ...hmacFields(ctx.keyed, salt, completedData),
+        ...freshnessFields(salt, completedData, ctx.challengeTimestamp),
+        ...this.signatureFields(cid, salt, proofs.proof),
+        blockTimingsMs: completed.map((n) => blockTimingsMs[n]),
+        deadlineMs: ctx.deadlineMs,
+        elapsedMs: Date.now() - ctx.receivedAt,
+        retriesUsed: ctx.retriesUsed,
+        responseTime,
+      },
+    };
+  }
+
+  /**
+   * Strict offline variant of run(). Only a challenge answered entirely from
+   * the local blockstore within the deadline counts as passed.
+   */
+  private async runStrictOffline(
+    cid: string,
+    indices: number[],
+    salt: string,
+    deadlineMs: number,
+    keyed: KeyedProof | null,
+    hashAlgos: string[] | null,
+    challengeTimestamp: number,
+    merkle: boolean,
+    validator: string | undefined,
+    signal?: AbortSignal
+  ): Promise<HttpChallengeResult> {
+    const startTime = Date.now();
+
+    try {
+      const blocks = await this.challengeBlocks(cid);
+      const outOfRange = indexRangeError(indices, blocks.length);
+      if (outOfRange) return outOfRange;
+
+      // Timing starts after the DAG walk (cached manifest), so only block reads are measured
+      const offlineStart = Date.now();
+      const blockData: Buffer[] = new Array(indices.length);
+      const blockTimingsMs: number[] = new Array(indices.length);
+      const network = new Set<number>();
+      let next = 0;
+      const worker = async () => {
+        while (next < indices.length) {
+          const n = next++;
+          const index = indices[n];
+          const readStart = Date.now();
+          const remaining = deadlineMs - (readStart - offlineStart);
+          let data: Buffer | null = null;
+          if (remaining > 0) {
+            try {
+              data = await this.kubo.getBlock(blocks[index], remaining, signal, true);
+            } catch (err) {
+              if (signal?.aborted) throw err;
+            }
+          }
+          if (!data) {
+            // Not local (or deadline spent) — fetch anyway so the proof completes, but flag it
+            network.add(n);
+            data = await this.kubo.getBlock(blocks[index], 2000, signal);
+          }
+          blockData[n] = data;
+          blockTimingsMs[n] = Date.now() - readStart;
+        }
+      };
+      await Promise.all(Array.from({ length: Math.min(READ_CONCURRENCY, indices.length) }, worker));
+      const networkIndices = indices.filter((_, n) => network.has(n));
+
+      const offlineLatencyMs = Date.now() - offlineStart;
+      const servedOffline = networkIndices.length === 0 && offlineLatencyMs <= deadlineMs;
+      const proofs = proofFields(salt, blockData, hashAlgos);
+      this.metrics.recordBlocksHashed(blockData.length);
+      const merkleProof = merkle ? await this.merkleProof(cid, blocks, indices, blockData, signal) : null;
+
+      const latencyMs = Date.now() - startTime;
+      this.config.recordChallenge(servedOffline, servedOffline ? 0.001 : 0, cid, {
+        validator, latencyMs, blocks: blockData.length, ...(!servedOffline && { error: 'NOT_SERVED_OFFLINE' }),
+      });
+      this.metrics.recordChallenge(cid, servedOffline, latencyMs);
+
+      return {
+        status: 200,
+        body: {
+          success: true,
+          ...proofs,
+          blockCid: blocks[indices[0]],
+          blockCids: indices.map((i) => blocks[i]),
+          ...hmacFields(keyed, salt, blockData),
+          ...freshnessFields(salt, blockData, challengeTimestamp),
+          ...(merkleProof && { merkleProof }),
+          ...this.signatureFields(cid, salt, proofs.proof),
+          servedOffline,
+          offlineLatencyMs,
+          deadlineMs,
+          networkIndices,
+          blockTimingsMs,
+          responseTime: Date.now() - startTime,
+        },
+      };
+    } catch (error: any) {
+      if (signal?.aborted) {
+        return { status: 499, body: { success: false, error: 'CANCELLED', responseTime: Date.now() - startTime } };
+      }
+      this.config.recordChallenge(false, 0, cid, {
+        validator, latencyMs: Date.now() - startTime, blocks: indices.length, error: error.message,
+      });
+      this.metrics.recordChallenge(cid, false);
+      return {
+        status: 500,
+        body: {
+          success: false,
+          servedOffline: false,
+          error: error.message,
+          responseTime: Date.now() - startTime,
+        },
+      };
+    }
+  }
+
+  /**
+   * Timestamp window, requireBlockSalts and the (cid, salt) replay claim —
+   * shared by every challenge type. Null when the challenge is fresh.
+   */
+  private async checkFreshness(cid: string, salt: string, timestamp: number | undefined, blockHash: unknown,
+    saltNonce: unknown, receivedAt: number): Promise<HttpChallengeResult | null> {
+    if (timestamp !== undefined) {
+      if (!Number.isInteger(timestamp) || timestamp <= 0) {
+        return { status: 400, body: { error: 'timestamp must be a positive integer (ms since epoch)' } };
+      }
+      if (receivedAt - timestamp > CHALLENGE_REPLAY_WINDOW_MS || timestamp - receivedAt > MAX_CLOCK_SKEW_MS) {
+        return {
+          status: 400,
+          body: { success: false, error: 'CHALLENGE_EXPIRED', message: 'Challenge timestamp is outside the accepted window' },
+        };
+      }
+    }
+    if (this.config.getConfig().requireBlockSalts) {
+      const check = this.saltVerifier
+        ? await this.saltVerifier.verify(salt, blockHash, saltNonce)
+        : { ok: false as const, reason: 'No Hive client to verify block salts', unverifiable: true };
+      if (!check.ok) {
+        return {
+          status: check.unverifiable ? 503 : 400,
+          body: { success: false, error: check.unverifiable ? 'SALT_UNVERIFIABLE' : 'STALE_SALT', message: check.reason },
+        };
+      }
+    }
+    if (!this.replayGuard.claim(cid, salt, receivedAt)) {
+      return {
+        status: 409,
+        body: {
+          success: false,
+          error: 'SALT_REPLAYED',
+          message: `Salt already used for this CID within the last ${CHALLENGE_REPLAY_WINDOW_MS / 60000} min`,
+        },
+      };
+    }
+    return null;
+  }
+
+  /**
+   * Retrieval challenge: serve a byte range of `cid` from local blocks within the
+   * deadline and report throughput. cid and salt are validated and claimed by run().
+   */
+  private async runRetrieval(request: HttpChallengeRequest, signal?: AbortSignal): Promise<HttpChallengeResult> {
+    const { cid, salt, offset = 0, length, deadlineMs = RETRIEVAL_DEADLINE_MS, validatorId } = request;
+    if (length === undefined) {
+      return { status: 400, body: { error: 'Missing required field: length' } };
+    }
+    if (!Number.isInteger(offset) || offset < 0) {
+      return { status: 400, body: { error: 'offset must be a non-negative integer' } };
+    }
+    if (!Number.isInteger(length) || length <= 0 || length > RETRIEVAL_MAX_BYTES) {
+      return { status: 400, body: { error: `length must be 1-${RETRIEVAL_MAX_BYTES}` } };
+    }
+    if (!Number.isInteger(deadlineMs) || deadlineMs <= 0 || deadlineMs > MAX_OFFLINE_DEADLINE_MS) {
+      return { status: 400, body: { error: `deadlineMs must be 1-${MAX_OFFLINE_DEADLINE_MS}` } };
+    }
+    const validator = typeof validatorId === 'string' && validatorId ? validatorId.slice(0, 64) : undefined;
+    const startTime = Date.now();
+
+    try {
+      const data = await this.kubo.catRange(cid, offset, length, deadlineMs, signal);
+      const elapsedMs = Date.now() - startTime;
+      if (data.length === 0) {
+        // Past end of file — a bad request, not a failed retrieval
+        return { status: 416, body: { success: false, error: 'RANGE_NOT_SATISFIABLE', offset, length } };
+      }
+      const proof = httpProofFromBlocks(salt, [data]);
+      const throughputBps = Math.round(data.length / (Math.max(elapsedMs, 1) / 1000));
+      this.config.recordChallenge(true, 0.001, cid, { validator, latencyMs: elapsedMs, blocks: 0 });
+      this.metrics.recordChallenge(cid, true, elapsedMs);
+
+      return {
+        status: 200,
+        body: {
+          success: true,
+          challengeType: 'retrieval',
+          proof,
+          data: data.toString('base64'),
+          offset,
+          bytesServed: data.length,
+          truncated: data.length < length,
+          elapsedMs,
+          throughputBps,
+          deadlineMs,
+          ...this.signatureFields(cid, salt, proof),
+          responseTime: Date.now() - startTime,
+        },
+      };
+    } catch (error: any) {
+      if (signal?.aborted) {
+        return { status: 499, body: { success: false, error: 'CANCELLED', responseTime: Date.now() - startTime } };
+      }
+      const elapsedMs = Date.now() - startTime;
+      const message = elapsedMs >= deadlineMs ? 'DEADLINE_EXCEEDED' : (error.response?.data?.Message || error.message);
+      this.config.recordChallenge(false, 0, cid, { validator, latencyMs: elapsedMs, blocks: 0, error: message });
+      this.metrics.recordChallenge(cid, false);
+      return {
+        status: 500,
+        body: { success: false, challengeType: 'retrieval', error: message, deadlineMs, responseTime: elapsedMs },
+      };
+    }
+  }
+
+  /** signature + account when signChallengeProofs is on and the posting key is unlocked. */
+  private signatureFields(cid: string, salt: string, proof: string): { signature?: string; account?: string } {
+    const { signChallengeProofs, hiveUsername } = this.config.getConfig();
+    if (!signChallengeProofs || !this.proofSigner || !hiveUsername) return {};
+    const digest = crypto.createHash('sha256').update(`${cid}|${salt}|${proof}`).digest('hex');
+    const signature = this.proofSigner(digest);
+    return signature ? { signature, account: hiveUsername } : {};
+  }
+
+  /** Merkle path for the challenged leaves; the root node is always local for a pinned CID. */
+  /**
+   * Challengeable blocks of `cid`: the root's links, or the root itself for a
+   * single-block file — the same count getPinManifest reports.
+   */
+  private async challengeBlocks(cid: string): Promise<string[]> {
+    const refs = await this.kubo.getBlockRefs(cid);
+    return refs.length > 0 ? refs : [cid];
+  }
+
+  private async merkleProof(cid: string, blocks: string[], indices: number[], leaves: Buffer[], signal?: AbortSignal): Promise<MerkleProof> {
+    const rootNode = await this.kubo.getBlock(cid, 2000, signal, true);
+    return buildMerkleProof(cid, rootNode, blocks, indices, leaves);
+  }
+
+  /**
+   * Run many challenges with bounded concurrency, reporting each result as it
+   * completes. Once `signal` aborts, no new challenges start and in-flight
+   * block reads are cancelled.
+   */
+  async runBatch(
+    requests: HttpChallengeRequest[],
+    signal: AbortSignal,
+    onResult: (index: number, result: HttpChallengeResult) => void
+  ): Promise<number> {
+    let next = 0;
+    let completed = 0;
+
+    const worker = async () => {
+      while (next < requests.length && !signal.aborted) {
+        const index = next++;
+        const result = await this.run(requests[index], signal);
+        if (signal.aborted) return;
+        completed++;
+        onResult(index, result);
+      }
+    };
+
+    await Promise.all(Array.from({ length: Math.min(BATCH_CONCURRENCY, requests.length) }, worker));
+    return completed;
+  }
+
+  /** readBlock() through the recent-block cache. */
+  private async readBlockCached(
+    blocks: string[],
+    index: number,
+    readahead: number,
+    warmed: Set<number>,
+    signal?: AbortSignal
+  ): Promise<Buffer> {
+    const blockCid = blocks[index];
+    const cached = this.blockCache.get(blockCid);
+    if (cached && Date.now() - cached.at < BLOCK_CACHE_TTL_MS) return cached.data;
+
+    const data = await this.readBlock(blocks, index, readahead, warmed, signal);
+    if (cached) this.blockCacheBytes -= cached.data.length;
+    this.blockCache.delete(blockCid);
+    if (data.length <= BLOCK_CACHE_MAX_BYTES) {
+      this.blockCache.set(blockCid, { data, at: Date.now() });
+      this.blockCacheBytes += data.length;
+      // Map order is insertion order — evict oldest first
+      for (const [key, entry] of this.blockCache) {
+        if (this.blockCacheBytes <= BLOCK_CACHE_MAX_BYTES) break;
+        this.blockCache.delete(key);
+        this.blockCacheBytes -= entry.data.length;
+      }
+    }
+    return data;
+  }
+
+  /**
+   * Read block `index` of a challenged CID, first kicking off readahead of the
+   * next `readahead` blocks so sequential challenges hit a warm cache.
+   */
+  async readBlock(
+    blocks: string[],
+    index: number,
+    readahead: number,
+    warmed: Set<number>,
+    signal?: AbortSignal
+  ): Promise<Buffer> {
+    if (readahead > 0) {
+      const ahead: string[] = [];
+      for (let j = index + 1; j <= index + readahead && j < blocks.length; j++) {
+        if (!warmed.has(j)) {
+          warmed.add(j);
+          ahead.push(blocks[j]);
+        }
+      }
+      this.kubo.warmBlocks(ahead);
+    }
+    return this.kubo.getBlock(blocks[index], 2000, signal);
+  }
+}
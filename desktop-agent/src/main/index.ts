@@ -2,7 +2,7 @@ import { app, BrowserWindow, Tray, Menu, nativeImage, dialog, ipcMain, shell, No
 import * as path from 'path';
 import { KuboManager } from './kubo';
 import { ApiServer } from './api';
-import { ConfigStore } from './config';
+import { ConfigStore, resolveNodeId } from './config';
 import { AutoUpdater } from './updater';
 import { AgentWSClient } from './agent-ws';
 import { AgentHiveClient } from './hive';
@@ -11,15 +11,52 @@ import { PubSubBridge } from './pubsub';
 import { ChallengeHandler, ChallengeMessage, ChallengeResponse, CommitmentRequest, CommitmentResponse } from './challenge-handler';
 import { LocalValidator } from './validator';
 import { AutoPinner } from './auto-pinner';
+import { ProofBroadcaster } from './proof-broadcast';
 import { TreasurySigner } from './treasury-signer';
 import { WalletManager } from './wallet-manager';
+import { hashString } from './poa-crypto';
+import type { PinDamageEvent } from './integrity-scrubber';
+import type { SelfChallengeResult } from './self-challenge';
+import { formatDailySummary, DailySummaryReport } from './daily-summary';
+import type { DiskStatus } from './storage-monitor';
+import type { PairingRequest } from './pairing';
+import { bootCooldown, wasLaunchedAtLogin } from './boot-cooldown';
+import { isHeadless, resolveLogFile, startFileLogging, logNotification } from './headless';
 import { initializeFullServer, shutdownFullServer } from './server-init';
 import { GpuContributionManager } from './gpu-contribution';
 import { createGpuRoutes } from './gpu-api';
+import { DEEP_LINK_PROTOCOL, findDeepLinkArg, parseDeepLink } from './deep-link';
+import { InstanceLock } from './instance-lock';
+
+// --headless: no window or tray, log to a file, notifications go to the log
+const HEADLESS = isHeadless(process.argv);
+if (HEADLESS) startFileLogging(resolveLogFile(process.argv));
+
+// ─── Single instance + spk:// deep links ────────────────────────────────────
+// One agent per user — desktop, headless or CLI — owns Kubo and the API port. A
+// second launch (also how Windows and Linux deliver a clicked link) forwards its
+// argv to the running instance and exits (instance-lock.ts).
+let pendingDeepLink: string | null = findDeepLinkArg(process.argv);
+let instanceLock: InstanceLock | null = null;
+const instanceLockReady = InstanceLock.acquire(process.argv, onSecondInstance);
+if (!HEADLESS) {
+  // macOS delivers links as an event, including the one that launched the app
+  app.on('open-url', (event, url) => {
+    event.preventDefault();
+    handleDeepLink(url);
+  });
+  if (process.defaultApp && process.argv.length >= 2) {
+    // Unpackaged (electron .): register with the script path so the link relaunches it
+    app.setAsDefaultProtocolClient(DEEP_LINK_PROTOCOL, process.execPath, [path.resolve(process.argv[1])]);
+  } else {
+    app.setAsDefaultProtocolClient(DEEP_LINK_PROTOCOL);
+  }
+}
 
 // ─── Global error handlers — prevent silent crashes ─────────────────────────
 process.on('uncaughtException', (error) => {
   console.error('[SPK] Uncaught exception:', error);
+  if (HEADLESS) return;
   try {
     dialog.showErrorBox('SPK Desktop Agent', `Unexpected error: ${error.message}\n\nThe app will try to continue.`);
   } catch {}
@@ -31,6 +68,9 @@ process.on('unhandledRejection', (reason) => {
 
 let mainWindow: BrowserWindow | null = null;
 let tray: Tray | null = null;
+let trayStatus = 'Starting...';
+let trayStats: Awaited<ReturnType<ApiServer['getTrayStats']>> | null = null;
+let trayStatsTimer: NodeJS.Timeout | null = null;
 let kuboManager: KuboManager;
 let apiServer: ApiServer;
 let configStore: ConfigStore;
@@ -48,8 +88,19 @@ let pubsub: PubSubBridge | null = null;
 let challengeHandler: ChallengeHandler | null = null;
 let validator: LocalValidator | null = null;
 let autoPinner: AutoPinner | null = null;
+let proofBroadcaster: ProofBroadcaster | null = null;
 
 const CHALLENGE_TOPIC = 'hivepoa-challenges';
+const TRAY_STATS_INTERVAL_MS = 30000;
+
+/** Desktop notification, or a log line when headless or unsupported. */
+function notify(title: string, body: string): void {
+  if (HEADLESS || !Notification.isSupported()) {
+    logNotification(title, body);
+    return;
+  }
+  new Notification({ title, body, icon: path.join(__dirname, '../../assets/icon.png') }).show();
+}
 
 function createWindow(): void {
   mainWindow = new BrowserWindow({
@@ -82,6 +133,47 @@ function createWindow(): void {
   });
 }
 
+function onSecondInstance(argv: string[]): void {
+  console.log('[SPK] Another launch was handed to this instance');
+  const link = findDeepLinkArg(argv);
+  if (link && HEADLESS) {
+    console.warn(`[SPK] Ignoring ${link} — links need confirmation, which headless mode cannot show`);
+  } else if (link) {
+    handleDeepLink(link);
+  }
+  mainWindow?.show();
+  mainWindow?.focus();
+}
+
+/** Confirm a spk:// link with the user, then route it into the pin queue. */
+function handleDeepLink(url: string): void {
+  if (!apiServer?.getHttpServer()?.listening) {
+    pendingDeepLink = url; // handled once the API server is up
+    return;
+  }
+  const link = parseDeepLink(url);
+  if ('error' in link) {
+    notify('SPK Desktop Agent — Link not handled', `${link.error}: ${url}`);
+    return;
+  }
+  const choice = dialog.showMessageBoxSync({
+    type: 'question',
+    buttons: ['Pin', 'Cancel'],
+    defaultId: 0,
+    cancelId: 1,
+    title: 'Pin content',
+    message: `Pin ${link.name ? `"${link.name}"` : 'this content'} on this node?`,
+    detail: `CID: ${link.cid}\n\nA web page asked to pin this content. It will use your disk space and bandwidth.`,
+  });
+  if (choice !== 0) return;
+  apiServer.queuePin({ cid: link.cid, name: link.name ?? undefined })
+    .then(({ status, body }) => {
+      if (status === 202) notify('SPK Desktop Agent — Pinning', `${link.name || link.cid} was queued for pinning.`);
+      else notify('SPK Desktop Agent — Pin refused', body.message || body.error || `HTTP ${status}`);
+    })
+    .catch((err) => notify('SPK Desktop Agent — Pin failed', err.message));
+}
+
 function createTray(): void {
   const iconPath = path.join(__dirname, '../../assets/icon.png');
   const icon = nativeImage.createFromPath(iconPath).resize({ width: 16, height: 16 });
@@ -94,9 +186,27 @@ function createTray(): void {
     mainWindow?.show();
     mainWindow?.focus();
   });
+  trayStatsTimer = setInterval(refreshTrayStats, TRAY_STATS_INTERVAL_MS);
+}
+
+/** Re-read pinned count, repo size and today's earnings, then redraw the menu. */
+function refreshTrayStats(): void {
+  apiServer?.getTrayStats()
+    .then((stats) => {
+      trayStats = stats;
+      updateTrayMenu(trayStatus);
+    })
+    .catch(() => {});
+}
+
+function formatTrayBytes(bytes: number): string {
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(0)} KB`;
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  return `${(bytes / (1024 * 1024 * 1024)).toFixed(2)} GB`;
 }
 
 function updateTrayMenu(status: string): void {
+  trayStatus = status;
   if (!tray) return;
 
   const gpuState = gpuManager ? (gpuManager as any).state as string : 'stopped';
@@ -117,9 +227,18 @@ function updateTrayMenu(status: string): void {
 
   const template: Electron.MenuItemConstructorOptions[] = [
     { label: `Status: ${status}`, enabled: false },
+  ];
+  if (trayStats) {
+    template.push(
+      { label: `IPFS: ${trayStats.daemonRunning ? 'Running' : 'Stopped'}`, enabled: false },
+      { label: `Pinned: ${trayStats.pinnedFiles} (${formatTrayBytes(trayStats.repoSizeBytes)})`, enabled: false },
+      { label: `Today: ${trayStats.todayHbd.toFixed(3)} HBD`, enabled: false },
+    );
+  }
+  template.push(
     { label: gpuLabels[gpuState] || 'GPU: Unknown', enabled: false },
     { type: 'separator' },
-  ];
+  );
 
   // GPU control actions
   if (gpuStopped) {
@@ -147,6 +266,34 @@ function updateTrayMenu(status: string): void {
     });
   }
 
+  // Storage actions — the same services /api/config and /api/gc drive
+  template.push(
+    { type: 'separator' },
+    {
+      label: 'Pause Challenges',
+      type: 'checkbox',
+      checked: configStore?.getConfig().challengesPaused ?? false,
+      click: (item) => {
+        apiServer?.setChallengesPaused(item.checked);
+        refreshTrayStats();
+      },
+    },
+    {
+      label: 'Run GC Now',
+      enabled: !!trayStats?.daemonRunning,
+      click: () => {
+        apiServer?.runGc()
+          .then((run) => {
+            if (!run) notify('SPK Desktop Agent — GC', 'GC is already running or IPFS is stopped.');
+            else if (run.error) notify('SPK Desktop Agent — GC failed', run.error);
+            else notify('SPK Desktop Agent — GC complete', `Reclaimed ${formatTrayBytes(run.reclaimedBytes)} (${run.removedBlocks} blocks).`);
+            refreshTrayStats();
+          })
+          .catch(() => {});
+      },
+    },
+  );
+
   template.push(
     { type: 'separator' },
     { label: 'Show Dashboard', click: () => { mainWindow?.show(); mainWindow?.focus(); } },
@@ -179,10 +326,15 @@ async function initializeP2P(): Promise<void> {
 
   // Initialize Hive client with on-demand key callback
   hiveClient = new AgentHiveClient({
+    nodes: cfg.hiveApiNodes,
     username: cfg.hiveUsername,
     getPostingKey: () => walletManager.getPostingKey(),
   });
 
+  // Passed proofs → spk_poa_proof custom_json (no-op unless broadcastProofs is on)
+  proofBroadcaster = new ProofBroadcaster(hiveClient, configStore);
+  proofBroadcaster.start();
+
   // Get peer ID from IPFS
   const peerId = await kuboManager.getPeerId();
   if (!peerId) {
@@ -231,7 +383,8 @@ async function initializeP2P(): Promise<void> {
       cfg.hiveUsername,
       cfg.challengeIntervalMs,
       hasKey, // Only broadcast if posting key is available
-      cfg.requireSignedMessages
+      cfg.requireSignedMessages,
+      cfg.serverUrl
     );
     await validator.start();
   }
@@ -287,6 +440,17 @@ async function initialize(): Promise<void> {
   console.log('[SPK] Initializing desktop agent...');
 
   configStore = new ConfigStore();
+  if (wasLaunchedAtLogin(process.argv, app.getLoginItemSettings().wasOpenedAtLogin)) {
+    bootCooldown.begin(configStore.getConfig().bootCooldownMin * 60000);
+  }
+  configStore.setEarningsEventListener((event) => {
+    notify(
+      event.type === 'goal' ? 'SPK Desktop Agent — Goal reached!' : 'SPK Desktop Agent — Milestone',
+      event.type === 'goal'
+        ? `You reached your earnings goal of ${event.thresholdHbd} HBD.`
+        : `You've earned ${event.thresholdHbd} HBD storing content for the network.`,
+    );
+  });
 
   // Initialize encrypted wallet for key management
   walletManager = new WalletManager();
@@ -305,38 +469,69 @@ async function initialize(): Promise<void> {
 
   kuboManager = new KuboManager(configStore);
   apiServer = new ApiServer(kuboManager, configStore, walletManager);
-  autoUpdater = new AutoUpdater();
+  apiServer.getScrubber().on('pin-damage', (event: PinDamageEvent) => {
+    notify(
+      'SPK Desktop Agent — Pin damaged',
+      `Block ${event.blockIndex} of ${event.cid} is ${event.reason}. Challenges on this pin will fail until it is repaired.`,
+    );
+    mainWindow?.webContents.send('pin-damage', event);
+  });
+  apiServer.getPairing().on('pairing-code', (request: PairingRequest) => {
+    notify('SPK Desktop Agent — Pairing code', `${request.origin} wants ${request.scopes.join(', ')} access. Code: ${request.code}`);
+    mainWindow?.webContents.send('pairing-code', request);
+  });
+  apiServer.getSelfChallenger().on('self-challenge-failed', (result: SelfChallengeResult) => {
+    const outcome = result.repaired === null ? '' : result.repaired ? ' Bad blocks were re-fetched.' : ' Repair failed — re-pin it.';
+    notify('SPK Desktop Agent — Self-challenge failed', `${result.cid}: ${result.error}.${outcome}`);
+    mainWindow?.webContents.send('self-challenge-failed', result);
+  });
+  apiServer.getDailySummary().on('daily-summary', (report: DailySummaryReport) => {
+    notify('SPK Desktop Agent — Daily summary', formatDailySummary(report));
+  });
+  apiServer.getStorageMonitor().on('disk-space', (status: DiskStatus & { message: string }) => {
+    notify(status.level === 'floor' ? 'SPK Desktop Agent — Disk full' : 'SPK Desktop Agent — Disk space low', status.message);
+  });
+  autoUpdater = new AutoUpdater(configStore.getConfig().updateMode, HEADLESS);
   autoUpdater.setMainWindow(mainWindow);
+  autoUpdater.setRestartGuard(() => !agentWS?.hasActiveChallenges() && (challengeHandler?.hasCapacity() ?? true));
+  apiServer.setUpdater(autoUpdater);
 
   try {
     await kuboManager.start();
     updateTrayMenu('Running');
     console.log('[SPK] IPFS daemon started successfully');
 
-    // Auto-restart Kubo if it crashes unexpectedly
+    // KuboManager's watchdog restarts a crashed daemon with backoff; mirror it in the tray
     kuboManager.onExit(() => {
-      console.log('[SPK] Kubo exited unexpectedly, attempting restart in 5s...');
+      console.log('[SPK] Kubo exited unexpectedly, watchdog restarting...');
       updateTrayMenu('IPFS Restarting...');
-      setTimeout(async () => {
-        try {
-          await kuboManager.start();
-          updateTrayMenu('Running');
-          console.log('[SPK] Kubo restarted successfully');
-        } catch (err) {
-          console.error('[SPK] Kubo restart failed:', err);
-          updateTrayMenu('IPFS Error');
-        }
-      }, 5000);
+    });
+    kuboManager.onRestart(() => {
+      updateTrayMenu('Running');
+      console.log('[SPK] Kubo restarted successfully');
+    });
+    kuboManager.onRestartFailing((attempts, error) => {
+      updateTrayMenu('IPFS Error');
+      notify(
+        'SPK Desktop Agent — IPFS keeps failing',
+        `The IPFS daemon failed to restart ${attempts} times (${error}). Still retrying; challenges will fail until it is back.`,
+      );
     });
   } catch (error) {
     console.error('[SPK] Failed to start IPFS:', error);
     updateTrayMenu('Error');
-    dialog.showErrorBox('SPK Desktop Agent', `Failed to start IPFS: ${error}`);
+    if (!HEADLESS) dialog.showErrorBox('SPK Desktop Agent', `Failed to start IPFS: ${error}`);
   }
 
   try {
     await apiServer.start();
-    console.log('[SPK] API server started on port 5111');
+    console.log(`[SPK] API server started on port ${apiServer.getPort()}`);
+    refreshTrayStats();
+    if (pendingDeepLink && !HEADLESS) {
+      const link = pendingDeepLink;
+      pendingDeepLink = null;
+      handleDeepLink(link);
+    }
 
     // Initialize the full server backend (SQLite + 154 endpoints)
     // Agent-specific routes are already mounted and take priority
@@ -368,9 +563,13 @@ async function initialize(): Promise<void> {
     scheduleEnd: gpuCfg.gpuScheduleEnd,
     hivePoaUrl: gpuCfg.serverUrl,
     hiveUsername: gpuCfg.hiveUsername,
+    nodeId: resolveNodeId(gpuCfg, await kuboManager.getPeerId()),
+    heartbeatBackoffMaxMs: gpuCfg.heartbeatBackoffMaxSec * 1000,
     lendTargetIp: gpuCfg.gpuLendTargetIp,
   });
 
+  apiServer.setGpuManager(gpuManager);
+
   // Mount GPU API routes
   const expressApp = apiServer.getExpressApp();
   if (expressApp) {
@@ -382,13 +581,8 @@ async function initialize(): Promise<void> {
   gpuManager.on('notification', (data: { type: string; message: string }) => {
     console.log(`[GPU] ${data.type}: ${data.message}`);
 
-    if (Notification.isSupported()) {
-      const notif = new Notification({
-        title: 'Spirit Bomb',
-        body: data.message,
-        icon: path.join(__dirname, '../../assets/icon.png'),
-      });
-      notif.show();
+    if (!HEADLESS && Notification.isSupported()) {
+      new Notification({ title: 'Spirit Bomb', body: data.message, icon: path.join(__dirname, '../../assets/icon.png') }).show();
     }
 
     // Forward to renderer
@@ -404,26 +598,14 @@ async function initialize(): Promise<void> {
 
     // Milestone notifications
     if (to === 'running' && from === 'starting') {
-      if (Notification.isSupported()) {
-        new Notification({
-          title: 'Spirit Bomb',
-          body: 'GPU is live and earning! Your GPU is now serving the community.',
-          icon: path.join(__dirname, '../../assets/icon.png'),
-        }).show();
-      }
+      notify('Spirit Bomb', 'GPU is live and earning! Your GPU is now serving the community.');
     }
   });
 
   gpuManager.on('metrics', (metrics: any) => {
     // Temperature warning at 85°C
     if (metrics.temperatureC >= 85) {
-      if (Notification.isSupported()) {
-        new Notification({
-          title: 'Spirit Bomb — Temperature Warning',
-          body: `GPU temperature is ${metrics.temperatureC}°C. Consider pausing contribution.`,
-          icon: path.join(__dirname, '../../assets/icon.png'),
-        }).show();
-      }
+      notify('Spirit Bomb — Temperature Warning', `GPU temperature is ${metrics.temperatureC}°C. Consider pausing contribution.`);
     }
   });
 
@@ -455,21 +637,41 @@ async function initialize(): Promise<void> {
     setTimeout(() => {
       // Re-check in case the web app synced the username while we waited
       if (!configStore.getConfig().hiveUsername) {
-        console.log('[SPK] No Hive username configured — opening Keychain auth in browser');
         const port = configStore.getConfig().apiPort || 5111;
+        if (HEADLESS) {
+          console.warn(`[SPK] No Hive username configured — set hiveUsername via POST /api/config or open http://127.0.0.1:${port}/auth/keychain`);
+          return;
+        }
+        console.log('[SPK] No Hive username configured — opening Keychain auth in browser');
         shell.openExternal(`http://127.0.0.1:${port}/auth/keychain`);
       }
     }, 3000);
     mainWindow?.show();
   }
 
-  // Check for updates after startup
-  setTimeout(() => {
-    autoUpdater.checkForUpdates();
-  }, 5000);
+  // Check for updates after startup and every few hours. Headless installs only
+  // self-update in 'auto' mode; otherwise they update through their package manager.
+  if (!HEADLESS || configStore.getConfig().updateMode === 'auto') {
+    setTimeout(() => {
+      autoUpdater.startPeriodicChecks();
+    }, 5000);
+  }
 }
 
 app.whenReady().then(async () => {
+  try {
+    instanceLock = await instanceLockReady;
+  } catch (err: any) {
+    console.error(`[SPK] ${err.message}`);
+    app.exit(1);
+    return;
+  }
+  if (!instanceLock) {
+    console.log('[SPK] The agent is already running — handed this launch over to it');
+    app.exit(0);
+    return;
+  }
+
   // IPC handlers for secure renderer ↔ main communication
   ipcMain.handle('get-status', async () => {
     const peerId = await kuboManager?.getPeerId();
@@ -483,16 +685,32 @@ app.whenReady().then(async () => {
   });
   ipcMain.handle('get-earnings', () => configStore?.getEarnings());
 
+  // The HTTP API's core operations, called in-process so the bundled UI needs
+  // neither the local HTTP server nor CORS. Each returns { status, body }
+  // exactly as the matching endpoint would.
+  const notReady = { status: 503, body: { error: 'Agent is still starting' } };
+  ipcMain.handle('api-status', async () => (apiServer ? { status: 200, body: await apiServer.buildStatus() } : notReady));
+  ipcMain.handle('api-list-pins', (_event, query: Record<string, any>) => apiServer?.listPins(query || {}) ?? notReady);
+  ipcMain.handle('api-pin', (_event, cid: string, name?: string) => apiServer?.queuePin({ cid, name }) ?? notReady);
+  ipcMain.handle('api-unpin', (_event, cid: string) => apiServer?.unpin({ cid }) ?? notReady);
+  ipcMain.handle('api-apply-config', (_event, updates: Record<string, any>) => apiServer?.applyConfig(updates || {}) ?? notReady);
+  ipcMain.handle('api-earnings-history', (_event, fromMs: number, toMs: number) =>
+    configStore ? { status: 200, body: configStore.getEarningsHistory(fromMs || 0, toMs || Date.now()) } : notReady);
+
   // New P2P-related IPC handlers
   ipcMain.handle('get-peers', () => peerDiscovery?.getAllPeers() || []);
   ipcMain.handle('get-validation-stats', () => validator?.getStats() || { issued: 0, passed: 0, failed: 0, timeouts: 0 });
   ipcMain.handle('has-posting-key', () => configStore?.hasPostingKey() || false);
   ipcMain.handle('set-posting-key', (_event, key: string) => {
-    configStore?.setPostingKey(key);
+    // Key goes into the encrypted wallet only — config keeps public key + hash
+    if (!configStore || !walletManager?.isInitialized()) return false;
+    configStore.setPostingPublicKey(walletManager.importPostingKey(key.trim()));
+    configStore.setPostingKeyHash(hashString(key.trim()));
     return true;
   });
   ipcMain.handle('clear-posting-key', () => {
-    configStore?.clearPostingKey();
+    if (walletManager?.isInitialized()) walletManager.removePostingKey();
+    configStore?.clearPostingPublicKey();
     return true;
   });
   ipcMain.handle('open-keychain-auth', async () => {
@@ -534,15 +752,20 @@ app.whenReady().then(async () => {
   });
 
   try {
-    createTray();
-    createWindow();
+    if (HEADLESS) {
+      app.dock?.hide();
+      console.log('[SPK] Headless mode — no window or tray');
+    } else {
+      createTray();
+      createWindow();
+    }
     await initialize();
   } catch (err: any) {
     console.error('[SPK] Init error:', err);
   }
 
   app.on('activate', () => {
-    if (BrowserWindow.getAllWindows().length === 0) {
+    if (!HEADLESS && BrowserWindow.getAllWindows().length === 0) {
       createWindow();
     }
   });
@@ -554,9 +777,12 @@ app.on('window-all-closed', () => {
 
 app.on('before-quit', async () => {
   console.log('[SPK] Shutting down...');
+  if (trayStatsTimer) clearInterval(trayStatsTimer);
+  autoUpdater?.stopPeriodicChecks();
 
   // P2P cleanup
   autoPinner?.stop();
+  proofBroadcaster?.stop();
   validator?.stop();
   challengeHandler?.stop();
   peerDiscovery?.stop();
@@ -573,5 +799,7 @@ app.on('before-quit', async () => {
 
   await kuboManager?.stop();
   await apiServer?.stop();
+  configStore?.flush();
+  instanceLock?.release();
   app.exit(0);
 });
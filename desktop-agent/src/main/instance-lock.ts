@@ -0,0 +1,117 @@
+/**
+ * instance-lock.ts — One agent per user
+ *
+ * A second agent (desktop or CLI) would start a second Kubo on the same repo
+ * and fight over the repo lock and port 5111. The first instance listens on a
+ * local socket — ~/.spk-ipfs/agent.sock, or a per-user named pipe on Windows;
+ * a later launch connects, hands over its argv (e.g. a spk:// link) and exits.
+ *
+ * The socket doubles as the lock: a socket file left by a crash refuses
+ * connections and is replaced.
+ */
+
+import * as crypto from 'crypto';
+import * as fs from 'fs';
+import * as net from 'net';
+import * as os from 'os';
+import * as path from 'path';
+
+const HANDOFF_TIMEOUT_MS = 5000;
+const MAX_HANDOFF_BYTES = 64 * 1024;
+
+export function instanceSocketPath(): string {
+  if (process.platform === 'win32') {
+    const user = crypto.createHash('sha256').update(os.homedir()).digest('hex').slice(0, 16);
+    return `\\\\.\\pipe\\hivepoa-agent-${user}`;
+  }
+  return path.join(os.homedir(), '.spk-ipfs', 'agent.sock');
+}
+
+export class InstanceLock {
+  private constructor(private server: net.Server, private socketPath: string) {}
+
+  /**
+   * Become the running instance, or forward `argv` to the one that is.
+   * Returns the lock if this process is now the instance, null if it handed
+   * off and should exit. `onSecondInstance` receives later launches' argv.
+   */
+  static async acquire(argv: string[], onSecondInstance: (argv: string[]) => void): Promise<InstanceLock | null> {
+    const socketPath = instanceSocketPath();
+    if (process.platform !== 'win32') fs.mkdirSync(path.dirname(socketPath), { recursive: true });
+
+    for (let attempt = 0; attempt < 2; attempt++) {
+      try {
+        const server = await InstanceLock.listen(socketPath, onSecondInstance);
+        if (process.platform !== 'win32') fs.chmodSync(socketPath, 0o600);
+        return new InstanceLock(server, socketPath);
+      } catch (err: any) {
+        if (err.code !== 'EADDRINUSE') throw err;
+      }
+      const handoff = await InstanceLock.handOff(socketPath, argv);
+      if (handoff === 'forwarded') return null;
+      if (handoff === 'unresponsive') break;
+      // Connection refused: a stale socket from a crashed instance
+      if (process.platform !== 'win32') {
+        try { fs.unlinkSync(socketPath); } catch {}
+      }
+    }
+    throw new Error(`Another agent holds ${socketPath} but does not answer — is it hung?`);
+  }
+
+  release(): void {
+    this.server.close();
+    if (process.platform !== 'win32') {
+      try { fs.unlinkSync(this.socketPath); } catch {}
+    }
+  }
+
+  private static listen(socketPath: string, onSecondInstance: (argv: string[]) => void): Promise<net.Server> {
+    const server = net.createServer((socket) => {
+      let data = '';
+      socket.setEncoding('utf-8');
+      socket.setTimeout(HANDOFF_TIMEOUT_MS, () => socket.destroy());
+      socket.on('data', (chunk: string) => {
+        data += chunk;
+        if (data.length > MAX_HANDOFF_BYTES) {
+          socket.destroy();
+          return;
+        }
+        if (!data.includes('\n')) return;
+        socket.end('ok\n');
+        try {
+          const { argv } = JSON.parse(data.slice(0, data.indexOf('\n')));
+          if (Array.isArray(argv)) onSecondInstance(argv.filter((arg: unknown): arg is string => typeof arg === 'string'));
+        } catch {
+          console.warn('[Instance] Ignoring malformed handoff from a second launch');
+        }
+      });
+      socket.on('error', () => {});
+    });
+    return new Promise((resolve, reject) => {
+      server.once('error', reject);
+      server.listen(socketPath, () => {
+        server.off('error', reject);
+        resolve(server);
+      });
+    });
+  }
+
+  /** Send argv to the running instance. 'stale' if nothing is listening on the socket. */
+  private static handOff(socketPath: string, argv: string[]): Promise<'forwarded' | 'stale' | 'unresponsive'> {
+    return new Promise((resolve) => {
+      const socket = net.connect(socketPath);
+      socket.setTimeout(HANDOFF_TIMEOUT_MS, () => {
+        socket.destroy();
+        resolve('unresponsive');
+      });
+      socket.once('connect', () => socket.write(JSON.stringify({ argv }) + '\n'));
+      socket.once('data', () => {
+        socket.end();
+        resolve('forwarded');
+      });
+      socket.once('error', (err: NodeJS.ErrnoException) => {
+        resolve(err.code === 'ECONNREFUSED' || err.code === 'ENOENT' ? 'stale' : 'unresponsive');
+      });
+    });
+  }
+}
@@ -0,0 +1,258 @@
+/**
+ * integrity-scrubber.ts — Background re-verification of pinned blocks
+ *
+ * Challenges only touch the blocks a validator happens to ask for; silent disk
+ * corruption anywhere else goes unnoticed until a challenge fails. When
+ * scrubEnabled is set, every scrubIntervalMin minutes the scrubber:
+ *   1. Picks the pins scrubbed longest ago
+ *   2. Re-reads up to scrubBlocksPerPass blocks offline, resuming where the
+ *      previous pass stopped on each pin
+ *   3. Checks each block's bytes against the sha2-256 digest in its CID
+ *
+ * Reads are throttled to scrubMaxBytesPerSec so challenges keep priority.
+ * Missing or corrupt blocks emit 'pin-damage'; with scrubAutoRepair they are
+ * queued for repair (see block-repair.ts: a pin with corrupt blocks is unpinned,
+ * the bad copies deleted and re-fetched, and the pin re-added).
+ * Per-pin progress is kept in ~/.spk-ipfs/scrub-state.json.
+ */
+
+import { EventEmitter } from 'events';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import type { KuboManager } from './kubo';
+import type { ConfigStore } from './config';
+import { blockMatchesCid } from './poa-crypto';
+import { DamagedBlock, repairBlocks } from './block-repair';
+import { bootCooldown } from './boot-cooldown';
+
+export interface PinScrubState {
+  blockCount: number;
+  cursor: number;                 // next block index to verify
+  lastScrubAt: string | null;     // last pass that touched this pin
+  lastCompletedAt: string | null; // last time the cursor wrapped (whole DAG verified)
+  damaged: string[];              // block CIDs found missing/corrupt and not yet repaired
+}
+
+export interface PinDamageEvent {
+  cid: string;
+  blockCid: string;
+  blockIndex: number;
+  reason: 'missing' | 'corrupt';
+  detectedAt: string;
+}
+
+export interface ScrubStatus {
+  enabled: boolean;
+  running: boolean;
+  passes: number;
+  lastPassAt: string | null;
+  lastPassBlocks: number;
+  lastPassBytes: number;
+  repairQueue: number;
+  repaired: number;
+  pins: Record<string, PinScrubState>;
+}
+
+const FIRST_PASS_DELAY_MS = 120000; // let IPFS and pending challenges settle first
+const REPAIR_TIMEOUT_MS = 60000;
+
+export class IntegrityScrubber extends EventEmitter {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private statePath: string;
+  private timer: NodeJS.Timeout | null = null;
+  private firstPass: NodeJS.Timeout | null = null;
+  private running = false;
+  private passes = 0;
+  private lastPassAt: string | null = null;
+  private lastPassBlocks = 0;
+  private lastPassBytes = 0;
+  private repairQueue: Array<{ cid: string } & DamagedBlock> = [];
+  private repaired = 0;
+
+  constructor(kubo: KuboManager, config: ConfigStore) {
+    super();
+    this.kubo = kubo;
+    this.config = config;
+    this.statePath = path.join(os.homedir(), '.spk-ipfs', 'scrub-state.json');
+  }
+
+  start(): void {
+    const cfg = this.config.getConfig();
+    if (!cfg.scrubEnabled || this.timer) return;
+    const intervalMs = Math.max(1, cfg.scrubIntervalMin) * 60000;
+    const scheduled = () => {
+      if (!bootCooldown.isActive()) this.scrub().catch(() => {});
+    };
+    this.timer = setInterval(scheduled, intervalMs);
+    this.firstPass = setTimeout(scheduled, FIRST_PASS_DELAY_MS);
+    console.log(`[Scrubber] Started (every ${cfg.scrubIntervalMin} min, ${cfg.scrubBlocksPerPass} blocks/pass, ${cfg.scrubMaxBytesPerSec} B/s)`);
+  }
+
+  stop(): void {
+    if (this.firstPass) {
+      clearTimeout(this.firstPass);
+      this.firstPass = null;
+    }
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  getStatus(): ScrubStatus {
+    return {
+      enabled: this.config.getConfig().scrubEnabled,
+      running: this.running,
+      passes: this.passes,
+      lastPassAt: this.lastPassAt,
+      lastPassBlocks: this.lastPassBlocks,
+      lastPassBytes: this.lastPassBytes,
+      repairQueue: this.repairQueue.length,
+      repaired: this.repaired,
+      pins: this.loadState(),
+    };
+  }
+
+  /** Run one scrub pass now. Returns false if a pass is already running or IPFS is down. */
+  async scrub(): Promise<boolean> {
+    if (this.running || !this.kubo.isRunning()) return false;
+    this.running = true;
+
+    const cfg = this.config.getConfig();
+    let budget = cfg.scrubBlocksPerPass;
+    let blocksRead = 0;
+    let bytesRead = 0;
+    const passStart = Date.now();
+
+    try {
+      const state = await this.syncPins(this.loadState());
+      const now = new Date().toISOString();
+
+      // Least recently scrubbed first; never-scrubbed pins sort to the front
+      const order = Object.keys(state).sort((a, b) =>
+        (state[a].lastScrubAt || '').localeCompare(state[b].lastScrubAt || ''));
+
+      for (const cid of order) {
+        if (budget <= 0 || !this.kubo.isRunning()) break;
+        const pin = state[cid];
+        let blocks: string[];
+        try {
+          blocks = [cid, ...await this.kubo.getBlockRefs(cid, 30000)];
+        } catch (err: any) {
+          console.warn(`[Scrubber] Could not list blocks of ${cid}: ${err.message}`);
+          continue;
+        }
+        pin.blockCount = blocks.length;
+        if (pin.cursor >= blocks.length) pin.cursor = 0;
+
+        while (budget > 0 && pin.cursor < blocks.length) {
+          const blockCid = blocks[pin.cursor];
+          const result = await this.verifyBlock(blockCid);
+          bytesRead += result.bytes;
+          blocksRead++;
+          budget--;
+
+          if (result.reason && !pin.damaged.includes(blockCid)) {
+            pin.damaged.push(blockCid);
+            this.reportDamage({ cid, blockCid, blockIndex: pin.cursor, reason: result.reason, detectedAt: now });
+          }
+          pin.cursor++;
+
+          // Throttle: hold the average read rate at or below scrubMaxBytesPerSec
+          if (cfg.scrubMaxBytesPerSec > 0) {
+            const aheadMs = (bytesRead / cfg.scrubMaxBytesPerSec) * 1000 - (Date.now() - passStart);
+            if (aheadMs > 0) await new Promise((r) => setTimeout(r, aheadMs));
+          }
+        }
+
+        if (pin.cursor >= blocks.length) {
+          pin.cursor = 0;
+          pin.lastCompletedAt = now;
+        }
+        pin.lastScrubAt = now;
+      }
+
+      this.saveState(state);
+      if (cfg.scrubAutoRepair) await this.runRepairs(state);
+    } catch (err: any) {
+      console.warn(`[Scrubber] Pass failed: ${err.message}`);
+    } finally {
+      this.passes++;
+      this.lastPassAt = new Date().toISOString();
+      this.lastPassBlocks = blocksRead;
+      this.lastPassBytes = bytesRead;
+      this.running = false;
+    }
+    console.log(`[Scrubber] Pass ${this.passes}: ${blocksRead} blocks, ${bytesRead} bytes verified`);
+    return true;
+  }
+
+  /** Read a block from the local datastore only and check it against its CID. */
+  private async verifyBlock(blockCid: string): Promise<{ bytes: number; reason: 'missing' | 'corrupt' | null }> {
+    let data: Buffer;
+    try {
+      data = await this.kubo.getBlock(blockCid, 10000, undefined, true);
+    } catch {
+      return { bytes: 0, reason: 'missing' };
+    }
+    // null = not a sha2-256 CID; a successful read is all we can check
+    return { bytes: data.length, reason: blockMatchesCid(blockCid, data) === false ? 'corrupt' : null };
+  }
+
+  private reportDamage(event: PinDamageEvent): void {
+    console.error(`[Scrubber] Pin damage: ${event.cid} block ${event.blockIndex} (${event.blockCid}) is ${event.reason}`);
+    this.emit('pin-damage', event);
+    if (this.config.getConfig().scrubAutoRepair &&
+        !this.repairQueue.some((r) => r.blockCid === event.blockCid)) {
+      this.repairQueue.push({ cid: event.cid, blockCid: event.blockCid, reason: event.reason });
+    }
+  }
+
+  /** Repair the queued blocks one pin at a time, so each pin is unpinned at most once. */
+  private async runRepairs(state: Record<string, PinScrubState>): Promise<void> {
+    while (this.repairQueue.length > 0 && this.kubo.isRunning()) {
+      const cid = this.repairQueue[0].cid;
+      const blocks = this.repairQueue.filter((r) => r.cid === cid);
+      this.repairQueue = this.repairQueue.filter((r) => r.cid !== cid);
+      const { repaired, failed } = await repairBlocks(this.kubo, cid, blocks, REPAIR_TIMEOUT_MS);
+      for (const blockCid of repaired) {
+        if (state[cid]) state[cid].damaged = state[cid].damaged.filter((b) => b !== blockCid);
+        this.repaired++;
+        console.log(`[Scrubber] Repaired ${blockCid} (pin ${cid})`);
+      }
+      for (const { blockCid, error } of failed) {
+        console.error(`[Scrubber] Repair of ${blockCid} (pin ${cid}) failed: ${error}`);
+      }
+    }
+    this.saveState(state);
+  }
+
+  /** Add new recursive pins to the state and drop ones no longer pinned. */
+  private async syncPins(state: Record<string, PinScrubState>): Promise<Record<string, PinScrubState>> {
+    const pinned = await this.kubo.listPins();
+    const synced: Record<string, PinScrubState> = {};
+    for (const cid of pinned) {
+      synced[cid] = state[cid] || { blockCount: 0, cursor: 0, lastScrubAt: null, lastCompletedAt: null, damaged: [] };
+    }
+    return synced;
+  }
+
+  private loadState(): Record<string, PinScrubState> {
+    try {
+      if (fs.existsSync(this.statePath)) {
+        return JSON.parse(fs.readFileSync(this.statePath, 'utf-8'));
+      }
+    } catch (error) {
+      console.error('[Scrubber] Failed to read scrub state:', error);
+    }
+    return {};
+  }
+
+  private saveState(state: Record<string, PinScrubState>): void {
+    const tmpPath = `${this.statePath}.tmp`;
+    fs.writeFileSync(tmpPath, JSON.stringify(state, null, 2));
+    fs.renameSync(tmpPath, this.statePath);
+  }
+}
@@ -0,0 +1,55 @@
+/**
+ * json-writer.ts — Debounced, atomic JSON file writes
+ *
+ * The config stores keep their state in memory and persist it through a
+ * JsonFileWriter: a burst of updates (a POST /api/config touching many
+ * fields, a run of challenges) becomes one write DEBOUNCE_MS after the last
+ * change. Each write goes to `<file>.tmp` and is renamed over the target, so
+ * a crash mid-write leaves the previous file intact rather than half a JSON
+ * document. Call flush() before exit to persist anything still pending.
+ */
+
+import * as fs from 'fs';
+import * as path from 'path';
+
+const DEBOUNCE_MS = 250;
+
+export class JsonFileWriter {
+  private filePath: string;
+  private snapshot: () => unknown;
+  private timer: NodeJS.Timeout | null = null;
+
+  /** `snapshot` is called at write time, so the latest in-memory state is what lands on disk. */
+  constructor(filePath: string, snapshot: () => unknown) {
+    this.filePath = filePath;
+    this.snapshot = snapshot;
+  }
+
+  schedule(): void {
+    if (this.timer) clearTimeout(this.timer);
+    this.timer = setTimeout(() => {
+      this.timer = null;
+      this.write();
+    }, DEBOUNCE_MS);
+    this.timer.unref();
+  }
+
+  /** Write now if a write is pending. */
+  flush(): void {
+    if (!this.timer) return;
+    clearTimeout(this.timer);
+    this.timer = null;
+    this.write();
+  }
+
+  private write(): void {
+    try {
+      fs.mkdirSync(path.dirname(this.filePath), { recursive: true });
+      const tmpPath = `${this.filePath}.tmp`;
+      fs.writeFileSync(tmpPath, JSON.stringify(this.snapshot(), null, 2));
+      fs.renameSync(tmpPath, this.filePath);
+    } catch (error) {
+      console.error(`[Config] Failed to write ${this.filePath}:`, error);
+    }
+  }
+}
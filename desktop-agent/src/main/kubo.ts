@@ -1,8 +1,12 @@
-import { spawn, ChildProcess, execSync } from 'child_process';
+import { spawn, ChildProcess, execSync, execFile } from 'child_process';
 import * as path from 'path';
 import * as fs from 'fs';
 import * as os from 'os';
+import * as http from 'http';
+import { Readable } from 'stream';
+import axios, { AxiosInstance } from 'axios';
 import type { ConfigStore } from './config';
+import { agentEvents } from './agent-events';
 
 // Electron is optional — CLI mode runs without it
 let electronApp: { isPackaged: boolean } | null = null;
@@ -12,19 +16,122 @@ try {
   electronApp = null;
 }
 
+/** A running `ipfs` subprocess, as reported by GET /api/kubo/processes. */
+export interface KuboProcessInfo {
+  pid: number;
+  kind: 'daemon' | 'command';
+  args: string[];
+  startedAt: string;
+  ageMs: number;
+}
+
+const REAP_INTERVAL_MS = 60000;
+// Crash watchdog: restart delay doubles per consecutive failure, 1s → 30s
+const RESTART_BACKOFF_MIN_MS = 1000;
+const RESTART_BACKOFF_MAX_MS = 30000;
+const RESTART_STABLE_MS = 60000; // a daemon up this long resets the backoff
+const RESTART_ALERT_AFTER = 3;    // consecutive failed restarts before alerting the user
+
+export interface BandwidthStats {
+  daemonUp: boolean;
+  totalIn: number;   // bytes
+  totalOut: number;  // bytes
+  rateIn: number;    // bytes/s
+  rateOut: number;   // bytes/s
+  numPeers: number;
+}
+
+const BANDWIDTH_CACHE_TTL_MS = 5000;
+
+export interface KuboWatchdogState {
+  restartCount: number;
+  lastRestartAt: string | null;
+  restarting: boolean;
+  nextRetryMs: number | null;
+  failedAttempts: number;         // consecutive failed restarts, 0 once one succeeds
+  lastError: string | null;
+}
+
+/** One entry of a UnixFS directory listing; `children` is set for directories expanded by listUnixfs. */
+export interface UnixfsEntry {
+  name: string;
+  cid: string;
+  size: number;
+  type: 'file' | 'directory' | 'symlink' | 'unknown';
+  children?: UnixfsEntry[];
+}
+
+// ls Link.Type → entry type (unixfs Data.Type; HAMT shards list like directories)
+const UNIXFS_LINK_TYPES: Record<number, UnixfsEntry['type']> = { 0: 'file', 1: 'directory', 2: 'file', 4: 'symlink', 5: 'directory' };
+
+/** Options for KuboManager.addStream, passed through to /api/v0/add. */
+export interface AddOptions {
+  chunker?: string;      // size-<bytes>, rabin[-min-avg-max] or buzhash
+  cidVersion?: 0 | 1;
+  rawLeaves?: boolean;
+  pin?: boolean;         // default true
+}
+
 export class KuboManager {
   private process: ChildProcess | null = null;
   private config: ConfigStore;
   private ipfsPath: string;
   private repoPath: string;
   private usingExternal = false;
+  // Configured external daemon RPC URL (systemd/Docker-managed Kubo) — never spawned or stopped by us
+  private externalApi: string | null;
   private exitCallback: (() => void) | null = null;
+  private restartCallback: (() => void) | null = null;
+  private restartFailingCallback: ((attempts: number, error: string) => void) | null = null;
+  private restartFailures = 0;
+  private lastRestartError: string | null = null;
   private intentionalStop = false;
+  private restartCount = 0;
+  private lastRestartAt: number | null = null;
+  private restartAttempt = 0;
+  private restartTimer: NodeJS.Timeout | null = null;
+  // Manifest cache: root CID → child block CIDs. A CID's links never change, so no TTL.
+  private manifestCache: Map<string, string[]> = new Map();
+  private static readonly MANIFEST_CACHE_MAX = 500;
+  // Pin size cache for /api/pins: CID → CumulativeSize. Dropped on pin/unpin or after the TTL.
+  private pinSizeCache: Map<string, { size: number; at: number }> = new Map();
+  private static readonly PIN_SIZE_TTL_MS = 5 * 60000;
+  private bandwidthCache: { stats: BandwidthStats; at: number } | null = null;
+  // Short-lived `ipfs` CLI subprocesses (pid → child), reaped if they hang
+  private commands: Map<number, { child: ChildProcess; args: string[]; startedAt: number }> = new Map();
+  private daemonStartedAt = 0;
+  private reapTimer: NodeJS.Timeout;
+  // One keep-alive pool for every RPC call — avoids a TCP handshake per request
+  private rpc: AxiosInstance = axios.create({
+    httpAgent: new http.Agent({ keepAlive: true, maxSockets: 64 }),
+  });
 
   constructor(config: ConfigStore) {
     this.config = config;
     this.repoPath = config.getConfig().ipfsRepoPath;
-    this.ipfsPath = this.findIpfsBinary();
+    this.externalApi = config.getConfig().externalDaemonApi?.replace(/\/+$/, '') || null;
+    // With an external daemon configured the binary is optional (CLI-only features degrade)
+    try {
+      this.ipfsPath = this.findIpfsBinary();
+    } catch (error) {
+      if (!this.externalApi) throw error;
+      console.warn('[Kubo] No IPFS binary found — running against external daemon only');
+      this.ipfsPath = '';
+    }
+    config.setDaemonProbe(() => this.isRunning());
+
+    // Best-effort stop if the agent exits without calling stop(). Only sync work
+    // is possible here, so the daemon gets SIGTERM (a graceful signal) and no wait.
+    process.once('exit', () => {
+      if (this.process && this.process.exitCode === null) this.process.kill('SIGTERM');
+    });
+
+    // Limit is re-read each tick so config changes apply without a restart
+    this.reapTimer = setInterval(() => {
+      const limitSec = this.config.getConfig().kuboProcessTimeoutSec;
+      if (limitSec > 0) this.killStuckProcesses(limitSec * 1000);
+    }, REAP_INTERVAL_MS);
+    this.reapTimer.unref();
   }
 
   private findIpfsBinary(): string {
@@ -54,6 +161,15 @@ export class KuboManager {
   }
 
   async start(): Promise<void> {
+    if (this.externalApi) {
+      if (!(await this.detectExternalDaemon())) {
+        throw new Error(`External IPFS daemon not reachable at ${this.externalApi}`);
+      }
+      console.log(`[Kubo] Using configured external daemon at ${this.externalApi}`);
+      this.usingExternal = true;
+      return;
+    }
+
     console.log(`[Kubo] Using binary: ${this.ipfsPath}`);
     console.log(`[Kubo] Repo path: ${this.repoPath}`);
 
@@ -80,8 +196,7 @@ export class KuboManager {
 
   private async detectExternalDaemon(): Promise<boolean> {
     try {
-      const axios = require('axios');
-      const response = await axios.post(`${this.getApiUrl()}/api/v0/id`, null, { timeout: 3000 });
+      const response = await this.rpc.post(`${this.getApiUrl()}/api/v0/id`, null, { timeout: 3000 });
       if (response.data?.ID) {
         console.log(`[Kubo] Found external daemon with peer ID: ${response.data.ID}`);
         return true;
@@ -116,71 +231,131 @@ export class KuboManager {
     });
   }
 
-  private configureForDesktop(): void {
+  /** Keys Kubo refuses to start without — a config missing any of these is never written. */
+  private static readonly REQUIRED_CONFIG_KEYS = ['Identity', 'Addresses', 'Datastore', 'Bootstrap'];
+
+  /**
+   * Transactionally edit the repo config: snapshot the original, mutate an
+   * in-memory copy, validate it, then write atomically (temp file + rename).
+   * On any failure the snapshot is restored so a botched edit can never leave
+   * the daemon unable to start. Returns the dotted paths of changed settings.
+   */
+  private updateRepoConfig(mutate: (config: any) => void): string[] {
     const configPath = path.join(this.repoPath, 'config');
-    if (!fs.existsSync(configPath)) return;
+    if (!fs.existsSync(configPath)) return [];
+
+    const snapshot = fs.readFileSync(configPath, 'utf-8');
+    const original = JSON.parse(snapshot);
+    const updated = JSON.parse(snapshot);
+    mutate(updated);
 
+    const changed = KuboManager.diffConfig(original, updated);
+    if (changed.length === 0) return [];
+
+    const serialized = JSON.stringify(updated, null, 2);
+    const reparsed = JSON.parse(serialized);
+    const missing = KuboManager.REQUIRED_CONFIG_KEYS.filter((key) => !(key in reparsed));
+    if (missing.length > 0) {
+      throw new Error(`Refusing to write IPFS config missing required keys: ${missing.join(', ')}`);
+    }
+
+    fs.writeFileSync(`${configPath}.bak`, snapshot);
+    const tmpPath = `${configPath}.tmp-${process.pid}`;
     try {
-      const config = JSON.parse(fs.readFileSync(configPath, 'utf-8'));
-      
-      // Use default ports but make API accessible
-      config.Addresses = {
-        ...config.Addresses,
-        API: '/ip4/127.0.0.1/tcp/5001',
-        Gateway: '/ip4/127.0.0.1/tcp/8080',
-      };
+      const fd = fs.openSync(tmpPath, 'w');
+      try {
+        fs.writeSync(fd, serialized);
+        fs.fsyncSync(fd);
+      } finally {
+        fs.closeSync(fd);
+      }
+      fs.renameSync(tmpPath, configPath);
+      JSON.parse(fs.readFileSync(configPath, 'utf-8'));
+    } catch (error) {
+      try { fs.unlinkSync(tmpPath); } catch {}
+      fs.writeFileSync(configPath, snapshot);
+      console.error('[Kubo] Config write failed — original restored');
+      throw error;
+    }
 
-      // Enable pubsub for real-time features
-      config.Pubsub = { Enabled: true };
-
-      // Lower resource usage for desktop — fewer connections = less CPU/bandwidth
-      config.Swarm = {
-        ...config.Swarm,
-        ConnMgr: {
-          LowWater: 20,
-          HighWater: 50,
-          GracePeriod: '30s',
-        },
-      };
+    return changed;
+  }
 
-      // DHT client mode — participate in DHT without serving queries to others
-      // Dramatically reduces CPU and network usage vs full DHT server
-      config.Routing = { ...config.Routing, Type: 'dhtclient' };
+  /** Dotted paths whose values differ between two config objects (arrays compared whole). */
+  private static diffConfig(before: any, after: any, prefix: string = ''): string[] {
+    const isObject = (v: any) => v !== null && typeof v === 'object' && !Array.isArray(v);
+    if (!isObject(before) || !isObject(after)) {
+      return JSON.stringify(before) === JSON.stringify(after) ? [] : [prefix];
+    }
+    const keys = new Set([...Object.keys(before), ...Object.keys(after)]);
+    const changed: string[] = [];
+    for (const key of keys) {
+      changed.push(...KuboManager.diffConfig(before[key], after[key], prefix ? `${prefix}.${key}` : key));
+    }
+    return changed;
+  }
+
+  private configureForDesktop(): string[] {
+    try {
+      const changed = this.updateRepoConfig((config) => {
+        // Use default ports but make API accessible
+        config.Addresses = {
+          ...config.Addresses,
+          API: '/ip4/127.0.0.1/tcp/5001',
+          Gateway: '/ip4/127.0.0.1/tcp/8080',
+        };
 
-      // Set default storage quota
-      if (!config.Datastore) config.Datastore = {};
-      config.Datastore.StorageMax = '50GB';
+        // Enable pubsub for real-time features
+        config.Pubsub = { Enabled: true };
 
-      fs.writeFileSync(configPath, JSON.stringify(config, null, 2));
-      console.log('[Kubo] Desktop configuration applied');
+        // Lower resource usage for desktop — fewer connections = less CPU/bandwidth
+        config.Swarm = {
+          ...config.Swarm,
+          ConnMgr: {
+            LowWater: 20,
+            HighWater: 50,
+            GracePeriod: '30s',
+          },
+        };
+
+        // DHT client mode — participate in DHT without serving queries to others
+        // Dramatically reduces CPU and network usage vs full DHT server
+        config.Routing = { ...config.Routing, Type: 'dhtclient' };
+
+        // Storage quota from agent config
+        if (!config.Datastore) config.Datastore = {};
+        config.Datastore.StorageMax = KuboManager.storageMaxFor(this.config.getConfig().storageMaxGB);
+      });
+      console.log('[Kubo] Desktop configuration applied:', changed.join(', ') || 'no changes');
+      return changed;
     } catch (error) {
       console.error('[Kubo] Failed to configure:', error);
+      return [];
     }
   }
 
   /** Apply optimized settings to existing IPFS repos (after agent upgrade). */
   private ensureOptimizedConfig(): void {
-    const configPath = path.join(this.repoPath, 'config');
     try {
-      const config = JSON.parse(fs.readFileSync(configPath, 'utf-8'));
-      let changed = false;
+      const changed = this.updateRepoConfig((config) => {
+        // Downgrade from full DHT server to client mode
+        if (!config.Routing || config.Routing.Type !== 'dhtclient') {
+          config.Routing = { ...config.Routing, Type: 'dhtclient' };
+        }
 
-      // Downgrade from full DHT server to client mode
-      if (!config.Routing || config.Routing.Type !== 'dhtclient') {
-        config.Routing = { ...config.Routing, Type: 'dhtclient' };
-        changed = true;
-      }
+        // Lower connection limits if still at old defaults (50/200)
+        const cm = config.Swarm?.ConnMgr;
+        if (cm && (cm.HighWater >= 200 || cm.LowWater >= 50)) {
+          config.Swarm.ConnMgr = { LowWater: 20, HighWater: 50, GracePeriod: '30s' };
+        }
 
-      // Lower connection limits if still at old defaults (50/200)
-      const cm = config.Swarm?.ConnMgr;
-      if (cm && (cm.HighWater >= 200 || cm.LowWater >= 50)) {
-        config.Swarm.ConnMgr = { LowWater: 20, HighWater: 50, GracePeriod: '30s' };
-        changed = true;
-      }
+        // Keep StorageMax in sync with storageMaxGB (older builds always wrote 50GB)
+        if (!config.Datastore) config.Datastore = {};
+        config.Datastore.StorageMax = KuboManager.storageMaxFor(this.config.getConfig().storageMaxGB);
+      });
 
-      if (changed) {
-        fs.writeFileSync(configPath, JSON.stringify(config, null, 2));
-        console.log('[Kubo] Upgraded existing IPFS config (dhtclient + lower connections)');
+      if (changed.length > 0) {
+        console.log('[Kubo] Upgraded existing IPFS config:', changed.join(', '));
       }
     } catch {
       // Non-critical — will use existing config
@@ -190,69 +365,73 @@ export class KuboManager {
   /**
    * Apply bandwidth limits by adjusting Swarm.ConnMgr connection counts.
    * ~5 KB/s per connection as rough estimate.
-   * Returns true if IPFS config changed (restart needed).
+   * Returns the changed IPFS settings (non-empty = restart needed).
    */
-  applyBandwidthConfig(bandwidthLimitUp: number, bandwidthLimitDown: number): boolean {
-    const configPath = path.join(this.repoPath, 'config');
-    if (!fs.existsSync(configPath)) return false;
-
+  applyBandwidthConfig(bandwidthLimitUp: number, bandwidthLimitDown: number): string[] {
+    if (this.externalApi) {
+      console.log('[Kubo] External daemon — bandwidth limits must be set on that daemon');
+      return [];
+    }
     try {
-      const config = JSON.parse(fs.readFileSync(configPath, 'utf-8'));
-      const oldConnMgr = JSON.stringify(config.Swarm?.ConnMgr || {});
+      const changed = this.updateRepoConfig((config) => {
+        if (bandwidthLimitUp === 0 && bandwidthLimitDown === 0) {
+          config.Swarm = { ...config.Swarm, ConnMgr: { LowWater: 20, HighWater: 50, GracePeriod: '30s' } };
+        } else {
+          const effectiveLimit = Math.min(
+            bandwidthLimitUp || Infinity,
+            bandwidthLimitDown || Infinity
+          );
+          const maxConns = Math.max(10, Math.floor(effectiveLimit / 5));
+          config.Swarm = {
+            ...config.Swarm,
+            ConnMgr: {
+              LowWater: Math.max(5, Math.floor(maxConns * 0.25)),
+              HighWater: maxConns,
+              GracePeriod: '20s',
+            },
+          };
+        }
+      });
 
-      if (bandwidthLimitUp === 0 && bandwidthLimitDown === 0) {
-        config.Swarm = { ...config.Swarm, ConnMgr: { LowWater: 20, HighWater: 50, GracePeriod: '30s' } };
-      } else {
-        const effectiveLimit = Math.min(
-          bandwidthLimitUp || Infinity,
-          bandwidthLimitDown || Infinity
-        );
-        const maxConns = Math.max(10, Math.floor(effectiveLimit / 5));
-        config.Swarm = {
-          ...config.Swarm,
-          ConnMgr: {
-            LowWater: Math.max(5, Math.floor(maxConns * 0.25)),
-            HighWater: maxConns,
-            GracePeriod: '20s',
-          },
-        };
+      if (changed.length > 0) {
+        console.log('[Kubo] Bandwidth config applied:', changed.join(', '));
       }
-
-      const newConnMgr = JSON.stringify(config.Swarm.ConnMgr);
-      if (oldConnMgr === newConnMgr) return false;
-
-      fs.writeFileSync(configPath, JSON.stringify(config, null, 2));
-      console.log('[Kubo] Bandwidth config applied:', config.Swarm.ConnMgr);
-      return true;
+      return changed;
     } catch (error) {
       console.error('[Kubo] Failed to apply bandwidth config:', error);
-      return false;
+      return [];
     }
   }
 
+  /** Datastore.StorageMax value for a storageMaxGB setting (0 = 100GB). */
+  private static storageMaxFor(storageMaxGB: number): string {
+    return storageMaxGB === 0 ? '100GB' : `${storageMaxGB}GB`;
+  }
+
   /**
    * Apply storage quota by setting Datastore.StorageMax in IPFS config.
-   * Returns true if config changed (restart needed).
+   * Returns the changed IPFS settings (non-empty = restart needed).
    */
-  applyStorageQuota(storageMaxGB: number): boolean {
-    const configPath = path.join(this.repoPath, 'config');
-    if (!fs.existsSync(configPath)) return false;
+  applyStorageQuota(storageMaxGB: number): string[] {
+    const newMax = KuboManager.storageMaxFor(storageMaxGB);
+    if (this.externalApi) {
+      console.log('[Kubo] External daemon — storage quota must be set on that daemon');
+      return [];
+    }
 
     try {
-      const config = JSON.parse(fs.readFileSync(configPath, 'utf-8'));
-      const newMax = storageMaxGB === 0 ? '100GB' : `${storageMaxGB}GB`;
-
-      if (!config.Datastore) config.Datastore = {};
-      const oldMax = config.Datastore.StorageMax;
-      if (oldMax === newMax) return false;
+      const changed = this.updateRepoConfig((config) => {
+        if (!config.Datastore) config.Datastore = {};
+        config.Datastore.StorageMax = newMax;
+      });
 
-      config.Datastore.StorageMax = newMax;
-      fs.writeFileSync(configPath, JSON.stringify(config, null, 2));
-      console.log(`[Kubo] Storage quota set to ${newMax}`);
-      return true;
+      if (changed.length > 0) {
+        console.log(`[Kubo] Storage quota set to ${newMax}`);
+      }
+      return changed;
     } catch (error) {
       console.error('[Kubo] Failed to apply storage quota:', error);
-      return false;
+      return [];
     }
   }
 
@@ -273,7 +452,15 @@ export class KuboManager {
   /**
    * Get storage usage info: current usage vs configured limit.
    */
-  async getStorageInfo(): Promise<{ usedBytes: number; maxBytes: number; usedFormatted: string; maxFormatted: string; percentage: number }> {
+  async getStorageInfo(): Promise<{
+    usedBytes: number;
+    maxBytes: number;
+    headroomBytes: number;
+    usedFormatted: string;
+    maxFormatted: string;
+    headroomFormatted: string;
+    percentage: number;
+  }> {
     const stats = await this.getStats();
     const usedBytes = stats?.repoSize || 0;
 
@@ -291,15 +478,117 @@ export class KuboManager {
       }
     } catch {}
 
+    const headroomBytes = Math.max(0, maxBytes - usedBytes);
     return {
       usedBytes,
       maxBytes,
+      headroomBytes,
       usedFormatted: this.formatBytes(usedBytes),
       maxFormatted: this.formatBytes(maxBytes),
+      headroomFormatted: this.formatBytes(headroomBytes),
       percentage: maxBytes > 0 ? Math.round((usedBytes / maxBytes) * 100) : 0,
     };
   }
 
+  /** Cumulative size of a DAG in bytes, or null if it can't be resolved in time. */
+  async getObjectSize(cid: string, timeoutMs: number = 10000, offline: boolean = false): Promise<number | null> {
+    try {
+      const response = await this.rpc.post(
+        `${this.getApiUrl()}/api/v0/object/stat?arg=${cid}${offline ? '&offline=true' : ''}`,
+        null,
+        { timeout: timeoutMs }
+      );
+      const size = response.data?.CumulativeSize;
+      return typeof size === 'number' ? size : null;
+    } catch {
+      return null;
+    }
+  }
+
+  /**
+   * UnixFS listing of `cid`, expanding sub-directories down to `depth` levels
+   * and stopping after `maxEntries` entries in total (`truncated` is then set).
+   * Offline: only local blocks are read, so browsing never fetches from peers.
+   * A file root lists as no entries.
+   */
+  async listUnixfs(cid: string, depth: number = 1, maxEntries: number = 1000, timeoutMs: number = 10000): Promise<{
+    type: UnixfsEntry['type']; size: number; entries: UnixfsEntry[]; truncated: boolean;
+  }> {
+    const stat = await this.rpc.post(
+      `${this.getApiUrl()}/api/v0/files/stat?arg=/ipfs/${cid}&offline=true`,
+      null,
+      { timeout: timeoutMs }
+    );
+    const type: UnixfsEntry['type'] = stat.data?.Type === 'directory' ? 'directory' : stat.data?.Type === 'file' ? 'file' : 'unknown';
+    const size = Number(stat.data?.CumulativeSize) || 0;
+    if (type !== 'directory') return { type, size, entries: [], truncated: false };
+
+    let budget = maxEntries;
+    let truncated = false;
+    const walk = async (dirCid: string, level: number): Promise<UnixfsEntry[]> => {
+      const response = await this.rpc.post(
+        `${this.getApiUrl()}/api/v0/ls?arg=${dirCid}&resolve-type=true&size=true&offline=true`,
+        null,
+        { timeout: timeoutMs }
+      );
+      const entries: UnixfsEntry[] = [];
+      for (const link of response.data?.Objects?.[0]?.Links || []) {
+        if (budget <= 0) {
+          truncated = true;
+          break;
+        }
+        budget--;
+        entries.push({ name: link.Name, cid: link.Hash, size: Number(link.Size) || 0, type: UNIXFS_LINK_TYPES[link.Type] ?? 'unknown' });
+      }
+      if (level < depth) {
+        for (const entry of entries) {
+          if (entry.type !== 'directory') continue;
+          if (budget <= 0) {
+            truncated = true;
+            break;
+          }
+          entry.children = await walk(entry.cid, level + 1);
+        }
+      }
+      return entries;
+    };
+    return { type, size, entries: await walk(cid, 1), truncated };
+  }
+
+  /**
+   * Sizes of many pins, stat'ed from the local datastore with bounded concurrency.
+   * A pin that can't be stat'ed reports 0 (and isn't cached) instead of failing the batch.
+   */
+  async getPinSizes(cids: string[], concurrency: number = 8): Promise<Record<string, number>> {
+    const sizes: Record<string, number> = {};
+    const now = Date.now();
+    const pending = cids.filter((cid) => {
+      const cached = this.pinSizeCache.get(cid);
+      if (cached && now - cached.at < KuboManager.PIN_SIZE_TTL_MS) {
+        sizes[cid] = cached.size;
+        return false;
+      }
+      return true;
+    });
+
+    let next = 0;
+    const worker = async () => {
+      while (next < pending.length) {
+        const cid = pending[next++];
+        const size = await this.getObjectSize(cid, 5000, true);
+        sizes[cid] = size ?? 0;
+        if (size !== null) this.pinSizeCache.set(cid, { size, at: Date.now() });
+      }
+    };
+    await Promise.all(Array.from({ length: Math.min(concurrency, pending.length) }, worker));
+    return sizes;
+  }
+
+  /** Forget a cached pin size (after pin/unpin). */
+  invalidatePinSize(cid: string): void {
+    this.pinSizeCache.delete(cid);
+  }
+
   private formatBytes(bytes: number): string {
     if (bytes === 0) return '0 B';
     const k = 1024;
@@ -315,6 +604,7 @@ export class KuboManager {
         env: { ...process.env, IPFS_PATH: this.repoPath },
         stdio: ['ignore', 'pipe', 'pipe'],
       });
+      this.daemonStartedAt = Date.now();
 
       let started = false;
 
@@ -324,6 +614,7 @@ export class KuboManager {
         
         if (output.includes('Daemon is ready') && !started) {
           started = true;
+          agentEvents.publish('daemon_ready', { restartCount: this.restartCount });
           resolve();
         }
       });
@@ -340,9 +631,16 @@ export class KuboManager {
       this.process.on('exit', (code) => {
         console.log(`[Kubo] Process exited with code ${code}`);
         this.process = null;
-        // Notify crash recovery if this wasn't an intentional stop
-        if (!this.intentionalStop && started && this.exitCallback) {
-          this.exitCallback();
+        agentEvents.publish('daemon_stopped', { code, intentional: this.intentionalStop });
+        if (!started) {
+          reject(new Error(`IPFS daemon exited during startup (code ${code})`));
+          return;
+        }
+        // Crash (not via stop()) — notify and let the watchdog bring it back
+        if (!this.intentionalStop) {
+          if (Date.now() - this.daemonStartedAt >= RESTART_STABLE_MS) this.restartAttempt = 0;
+          this.exitCallback?.();
+          this.scheduleRestart();
         }
       });
 
@@ -360,28 +658,105 @@ export class KuboManager {
     this.exitCallback = callback;
   }
 
+  /** Register a callback fired when the watchdog has brought a crashed daemon back up. */
+  onRestart(callback: () => void): void {
+    this.restartCallback = callback;
+  }
+
+  /** Register a callback fired once after RESTART_ALERT_AFTER consecutive failed restarts. */
+  onRestartFailing(callback: (attempts: number, error: string) => void): void {
+    this.restartFailingCallback = callback;
+  }
+
+  getWatchdogState(): KuboWatchdogState {
+    return {
+      restartCount: this.restartCount,
+      lastRestartAt: this.lastRestartAt ? new Date(this.lastRestartAt).toISOString() : null,
+      restarting: this.restartTimer !== null,
+      nextRetryMs: this.restartTimer ? this.restartDelayMs() : null,
+      failedAttempts: this.restartFailures,
+      lastError: this.lastRestartError,
+    };
+  }
+
+  private restartDelayMs(): number {
+    return Math.min(RESTART_BACKOFF_MAX_MS, RESTART_BACKOFF_MIN_MS * 2 ** this.restartAttempt);
+  }
+
+  /** Restart the crashed daemon after a backoff delay; failed attempts back off further. */
+  private scheduleRestart(): void {
+    if (this.restartTimer || this.intentionalStop) return;
+    const delayMs = this.restartDelayMs();
+    console.warn(`[Kubo] Daemon exited unexpectedly — restarting in ${delayMs / 1000}s (attempt ${this.restartAttempt + 1})`);
+    this.restartTimer = setTimeout(async () => {
+      this.restartTimer = null;
+      if (this.intentionalStop) return;
+      this.restartAttempt++;
+      try {
+        await this.startDaemon();
+        this.restartCount++;
+        this.lastRestartAt = Date.now();
+        this.restartFailures = 0;
+        console.log(`[Kubo] Daemon restarted by watchdog (${this.restartCount} restarts so far)`);
+        this.restartCallback?.();
+      } catch (error: any) {
+        console.error(`[Kubo] Watchdog restart failed: ${error.message}`);
+        this.process?.kill('SIGKILL');
+        this.process = null;
+        this.restartFailures++;
+        this.lastRestartError = error.message;
+        if (this.restartFailures === RESTART_ALERT_AFTER) {
+          agentEvents.publish('daemon_restart_failing', { attempts: this.restartFailures, error: error.message });
+          this.restartFailingCallback?.(this.restartFailures, error.message);
+        }
+        this.scheduleRestart();
+      }
+    }, delayMs);
+  }
+
   async stop(): Promise<void> {
+    // A clean stop must never wake the watchdog
+    this.intentionalStop = true;
+    if (this.restartTimer) {
+      clearTimeout(this.restartTimer);
+      this.restartTimer = null;
+    }
     if (this.usingExternal) {
       console.log('[Kubo] Using external daemon — not stopping');
       return;
     }
 
-    if (this.process) {
-      this.intentionalStop = true;
+    const child = this.process;
+    if (child) {
+      // Graceful first so Kubo can flush the datastore (incl. in-progress GC) and
+      // release repo.lock. `ipfs shutdown` over RPC works everywhere; on Windows
+      // a signal is always a hard kill, so SIGTERM is only the Unix fallback.
+      const graceMs = Math.max(1, this.config.getConfig().kuboShutdownGraceSec) * 1000;
+      const stopStart = Date.now();
       console.log('[Kubo] Stopping daemon...');
-      this.process.kill('SIGTERM');
+      const exited = new Promise<void>((resolve) => child.once('exit', () => resolve()));
 
-      await new Promise<void>((resolve) => {
-        const timeout = setTimeout(() => {
-          this.process?.kill('SIGKILL');
-          resolve();
-        }, 5000);
+      try {
+        await this.rpc.post(`${this.getApiUrl()}/api/v0/shutdown`, null, { timeout: 5000 });
+      } catch {
+        if (process.platform !== 'win32') child.kill('SIGTERM');
+      }
 
-        this.process?.on('exit', () => {
-          clearTimeout(timeout);
+      let forced = false;
+      await Promise.race([
+        exited,
+        new Promise<void>((resolve) => setTimeout(() => {
+          forced = true;
           resolve();
-        });
-      });
+        }, graceMs)),
+      ]);
+      if (forced) {
+        console.warn(`[Kubo] Daemon did not exit within ${graceMs / 1000}s — forcing shutdown (SIGKILL)`);
+        child.kill('SIGKILL');
+        await exited;
+      } else {
+        console.log(`[Kubo] Daemon shut down gracefully in ${Date.now() - stopStart}ms`);
+      }
 
       this.process = null;
     }
@@ -392,25 +767,163 @@ export class KuboManager {
   }
 
   getApiUrl(): string {
-    return 'http://127.0.0.1:5001';
+    return this.externalApi || 'http://127.0.0.1:5001';
+  }
+
+  /** How we reach Kubo: our own spawned daemon, or one we don't manage. */
+  getMode(): { mode: 'managed' | 'external'; apiUrl: string; binaryAvailable: boolean } {
+    return {
+      mode: this.usingExternal || this.externalApi ? 'external' : 'managed',
+      apiUrl: this.getApiUrl(),
+      binaryAvailable: this.ipfsPath !== '',
+    };
+  }
+
+  getBinaryPath(): string {
+    return this.ipfsPath;
+  }
+
+  getRepoPath(): string {
+    return this.repoPath;
+  }
+
+  /**
+   * Kubo version — from the running daemon over RPC, else `ipfs version --number`
+   * (preflight runs before the daemon starts). Null if neither works.
+   */
+  async getBinaryVersion(): Promise<string | null> {
+    if (this.isRunning()) {
+      try {
+        const response = await this.rpc.post(`${this.getApiUrl()}/api/v0/version`, null, { timeout: 5000 });
+        if (response.data?.Version) return response.data.Version;
+      } catch {}
+    }
+    if (!this.ipfsPath) return null;
+    try {
+      return (await this.runCommand(['version', '--number'], 5000)).trim() || null;
+    } catch {
+      return null;
+    }
+  }
+
+  /** Default timeout for one-shot CLI/RPC calls (kuboCommandTimeoutSec). */
+  private commandTimeoutMs(): number {
+    return Math.max(1, this.config.getConfig().kuboCommandTimeoutSec) * 1000;
+  }
+
+  /**
+   * Run a one-shot `ipfs` CLI command against our repo. The child is tracked
+   * until it exits so a hung command shows up in listProcesses() and can be reaped.
+   * Past `timeoutMs` (default kuboCommandTimeoutSec) the child is SIGKILLed and
+   * the promise rejects with "ipfs command timed out".
+   */
+  runCommand(args: string[], timeoutMs: number = this.commandTimeoutMs()): Promise<string> {
+    if (!this.ipfsPath) {
+      return Promise.reject(new Error('No IPFS binary available (external daemon mode)'));
+    }
+    return new Promise((resolve, reject) => {
+      const child = execFile(this.ipfsPath, args, {
+        timeout: timeoutMs,
+        killSignal: 'SIGKILL',
+        env: { ...process.env, IPFS_PATH: this.repoPath },
+        maxBuffer: 64 * 1024 * 1024,
+      }, (error, stdout) => {
+        if (child.pid !== undefined) this.commands.delete(child.pid);
+        if (error && (error as any).killed) {
+          console.warn(`[Kubo] ipfs ${args.join(' ')} timed out after ${timeoutMs}ms — killed`);
+          reject(new Error('ipfs command timed out'));
+        } else if (error) reject(error);
+        else resolve(stdout);
+      });
+      if (child.pid !== undefined) {
+        this.commands.set(child.pid, { child, args, startedAt: Date.now() });
+      }
+    });
+  }
+
+  /** The daemon (if we spawned it) plus every in-flight CLI command. */
+  listProcesses(): KuboProcessInfo[] {
+    const now = Date.now();
+    const list: KuboProcessInfo[] = [];
+    if (this.process?.pid !== undefined) {
+      list.push({
+        pid: this.process.pid,
+        kind: 'daemon',
+        args: ['daemon', '--enable-gc'],
+        startedAt: new Date(this.daemonStartedAt).toISOString(),
+        ageMs: now - this.daemonStartedAt,
+      });
+    }
+    for (const [pid, entry] of this.commands) {
+      list.push({
+        pid,
+        kind: 'command',
+        args: entry.args,
+        startedAt: new Date(entry.startedAt).toISOString(),
+        ageMs: now - entry.startedAt,
+      });
+    }
+    return list;
+  }
+
+  /** SIGKILL CLI commands running longer than `olderThanMs`. Never touches the daemon. */
+  killStuckProcesses(olderThanMs: number): KuboProcessInfo[] {
+    const killed = this.listProcesses().filter((p) => p.kind === 'command' && p.ageMs > olderThanMs);
+    for (const info of killed) {
+      console.warn(`[Kubo] Killing stuck subprocess ${info.pid} (ipfs ${info.args.join(' ')}) after ${Math.round(info.ageMs / 1000)}s`);
+      this.commands.get(info.pid)?.child.kill('SIGKILL');
+      this.commands.delete(info.pid);
+    }
+    return killed;
   }
 
   async getPeerId(): Promise<string | null> {
     try {
-      const axios = require('axios');
-      const response = await axios.post(`${this.getApiUrl()}/api/v0/id`);
+      const response = await this.rpc.post(`${this.getApiUrl()}/api/v0/id`, null, { timeout: this.commandTimeoutMs() });
       return response.data.ID;
     } catch {
       return null;
     }
   }
 
+  /** Multiaddrs other peers can dial us on (with the /p2p/<id> suffix), loopback excluded. */
+  async getListenAddresses(): Promise<string[]> {
+    try {
+      const response = await this.rpc.post(`${this.getApiUrl()}/api/v0/id`, null, { timeout: this.commandTimeoutMs() });
+      const addresses: string[] = response.data.Addresses || [];
+      return addresses.filter((addr) => !addr.startsWith('/ip4/127.') && !addr.startsWith('/ip6/::1/'));
+    } catch {
+      return [];
+    }
+  }
+
+  /** Best-effort dial of a peer multiaddr (e.g. a pin request's origins). */
+  async swarmConnect(multiaddr: string, timeoutMs: number = 10000): Promise<boolean> {
+    try {
+      await this.rpc.post(`${this.getApiUrl()}/api/v0/swarm/connect?arg=${encodeURIComponent(multiaddr)}`, null, { timeout: timeoutMs });
+      return true;
+    } catch {
+      return false;
+    }
+  }
+
+  /** Remove a recursive pin. */
+  async unpin(cid: string): Promise<void> {
+    await this.rpc.post(`${this.getApiUrl()}/api/v0/pin/rm?arg=${cid}`, null, { timeout: this.commandTimeoutMs() });
+    this.invalidatePinSize(cid);
+  }
+
+  /**
+   * Repo size and bandwidth totals. Bounded by timeouts (repo stat gets a third of
+   * kuboCommandTimeoutSec) so a wedged daemon can't stall /api/status callers.
+   */
   async getStats(): Promise<any> {
     try {
-      const axios = require('axios');
+      const timeoutMs = this.commandTimeoutMs();
       const [repoStats, bwStats] = await Promise.all([
-        axios.post(`${this.getApiUrl()}/api/v0/repo/stat`).catch(() => ({ data: {} })),
-        axios.post(`${this.getApiUrl()}/api/v0/stats/bw`).catch(() => ({ data: {} })),
+        this.rpc.post(`${this.getApiUrl()}/api/v0/repo/stat`, null, { timeout: Math.max(1000, Math.floor(timeoutMs / 3)) })
+          .catch(() => ({ data: {} })),
+        this.rpc.post(`${this.getApiUrl()}/api/v0/stats/bw`, null, { timeout: timeoutMs }).catch(() => ({ data: {} })),
       ]);
 
       return {
@@ -423,4 +936,431 @@ export class KuboManager {
       return null;
     }
   }
+
+  /**
+   * Bandwidth totals/rates (stats bw) and connected peer count (swarm peers),
+   * cached for a few seconds so dashboards can poll freely. Returns zeros with
+   * daemonUp: false when the daemon isn't running.
+   */
+  async getBandwidth(): Promise<BandwidthStats> {
+    if (!this.isRunning()) {
+      return { daemonUp: false, totalIn: 0, totalOut: 0, rateIn: 0, rateOut: 0, numPeers: 0 };
+    }
+    if (this.bandwidthCache && Date.now() - this.bandwidthCache.at < BANDWIDTH_CACHE_TTL_MS) {
+      return this.bandwidthCache.stats;
+    }
+
+    const timeout = this.commandTimeoutMs();
+    const [bw, peers] = await Promise.all([
+      this.rpc.post(`${this.getApiUrl()}/api/v0/stats/bw`, null, { timeout }).catch(() => ({ data: {} })),
+      this.rpc.post(`${this.getApiUrl()}/api/v0/swarm/peers`, null, { timeout }).catch(() => ({ data: {} })),
+    ]);
+    // Field casing and number encoding have varied across Kubo versions
+    const num = (data: any, key: string): number => {
+      const value = data?.[key] ?? data?.[key.toLowerCase()];
+      const parsed = typeof value === 'string' ? parseFloat(value) : value;
+      return typeof parsed === 'number' && Number.isFinite(parsed) ? parsed : 0;
+    };
+    const peerList = peers.data?.Peers ?? peers.data?.peers;
+
+    const stats: BandwidthStats = {
+      daemonUp: true,
+      totalIn: num(bw.data, 'TotalIn'),
+      totalOut: num(bw.data, 'TotalOut'),
+      rateIn: num(bw.data, 'RateIn'),
+      rateOut: num(bw.data, 'RateOut'),
+      numPeers: Array.isArray(peerList) ? peerList.length : 0,
+    };
+    this.bandwidthCache = { stats, at: Date.now() };
+    return stats;
+  }
+
+  /**
+   * List the block CIDs under a root CID (via refs).
+   * Unlike poa-crypto getBlockCids(), this throws on failure so callers can tell
+   * an unreachable daemon apart from a single-block file.
+   */
+  async getBlockRefs(cid: string, timeoutMs: number = 2000): Promise<string[]> {
+    const cached = this.manifestCache.get(cid);
+    if (cached) return cached;
+
+    const response = await this.rpc.post(
+      `${this.getApiUrl()}/api/v0/refs?arg=${cid}`,
+      null,
+      { timeout: timeoutMs, responseType: 'text' }
+    );
+
+    const refs: string[] = String(response.data).split('\n')
+      .filter((line: string) => line.trim())
+      .map((line: string) => {
+        try { return JSON.parse(line).Ref; } catch { return null; }
+      })
+      .filter(Boolean);
+
+    this.manifestCache.set(cid, refs);
+    if (this.manifestCache.size > KuboManager.MANIFEST_CACHE_MAX) {
+      const oldest = this.manifestCache.keys().next().value;
+      if (oldest) this.manifestCache.delete(oldest);
+    }
+    return refs;
+  }
+
+  /** Recursive pins, over RPC (default timeout kuboCommandTimeoutSec). */
+  async listPins(timeoutMs: number = this.commandTimeoutMs()): Promise<string[]> {
+    const response = await this.rpc.post(`${this.getApiUrl()}/api/v0/pin/ls?type=recursive`, null, { timeout: timeoutMs });
+    return Object.keys(response.data?.Keys || {});
+  }
+
+  /** Whether `cid` is pinned recursively. pin/ls errors for an unpinned CID, which counts as false. */
+  async isPinned(cid: string): Promise<boolean> {
+    try {
+      const response = await this.rpc.post(
+        `${this.getApiUrl()}/api/v0/pin/ls?arg=${cid}&type=recursive`,
+        null,
+        { timeout: this.commandTimeoutMs() }
+      );
+      return !!response.data?.Keys?.[cid];
+    } catch {
+      return false;
+    }
+  }
+
+  /** Whether a block is in the local datastore. Never fetches from the network. */
+  async hasBlockLocally(blockCid: string): Promise<boolean> {
+    try {
+      await this.rpc.post(
+        `${this.getApiUrl()}/api/v0/block/stat?arg=${blockCid}&offline=true`,
+        null,
+        { timeout: 2000 }
+      );
+      return true;
+    } catch {
+      return false;
+    }
+  }
+
+  /** Local-presence flag for each block, checked with bounded concurrency. */
+  async getLocalBlockMask(blockCids: string[], concurrency: number = 16): Promise<boolean[]> {
+    const mask: boolean[] = new Array(blockCids.length).fill(false);
+    let next = 0;
+    const worker = async () => {
+      while (next < blockCids.length) {
+        const i = next++;
+        mask[i] = await this.hasBlockLocally(blockCids[i]);
+      }
+    };
+    await Promise.all(Array.from({ length: Math.min(concurrency, blockCids.length) }, worker));
+    return mask;
+  }
+
+  /**
+   * Pin manifest: how much of a CID's DAG is local. Works for pins that are
+   * still fetching, so a coordinator can challenge only the local block range.
+   */
+  async getPinManifest(cid: string): Promise<{
+    cid: string;
+    blockCount: number;
+    localBlocks: number;
+    localFraction: number;
+    localRanges: Array<[number, number]>;
+  }> {
+    const blocks = await this.getBlockRefs(cid, 10000);
+
+    if (blocks.length === 0) {
+      // Single-block file — the root is the only block
+      const local = await this.hasBlockLocally(cid);
+      return { cid, blockCount: 1, localBlocks: local ? 1 : 0, localFraction: local ? 1 : 0, localRanges: local ? [[0, 0]] : [] };
+    }
+
+    const mask = await this.getLocalBlockMask(blocks);
+    const localRanges: Array<[number, number]> = [];
+    let localBlocks = 0;
+    for (let i = 0; i < mask.length; i++) {
+      if (!mask[i]) continue;
+      localBlocks++;
+      const last = localRanges[localRanges.length - 1];
+      if (last && last[1] === i - 1) last[1] = i;
+      else localRanges.push([i, i]);
+    }
+
+    return {
+      cid,
+      blockCount: blocks.length,
+      localBlocks,
+      localFraction: Math.round((localBlocks / blocks.length) * 10000) / 10000,
+      localRanges,
+    };
+  }
+
+  /**
+   * Recursively pin a CID, streaming `pin/add?progress=true`. Kubo reports
+   * progress as a count of DAG nodes fetched, passed to onProgress as it
+   * arrives. There is no overall deadline — a 10GB pin takes as long as it
+   * takes — but a pin that reports nothing for stallTimeoutMs is aborted.
+   */
+  async pinWithProgress(cid: string, onProgress: (blocksFetched: number) => void, stallTimeoutMs: number = 300000): Promise<void> {
+    const controller = new AbortController();
+    let stalled = false;
+    let stallTimer: NodeJS.Timeout | undefined;
+    const armStallTimer = () => {
+      clearTimeout(stallTimer);
+      stallTimer = setTimeout(() => {
+        stalled = true;
+        controller.abort();
+      }, stallTimeoutMs);
+    };
+
+    armStallTimer();
+    try {
+      const response = await this.rpc.post(
+        `${this.getApiUrl()}/api/v0/pin/add?arg=${cid}&progress=true`,
+        null,
+        { responseType: 'stream', signal: controller.signal }
+      );
+      let buffered = '';
+      let streamError: string | null = null;
+      for await (const chunk of response.data) {
+        armStallTimer();
+        buffered += chunk.toString();
+        const lines = buffered.split('\n');
+        buffered = lines.pop() || '';
+        for (const line of lines) {
+          if (!line.trim()) continue;
+          let message: any;
+          try { message = JSON.parse(line); } catch { continue; }
+          if (typeof message.Progress === 'number') onProgress(message.Progress);
+          if (message.Type === 'error') streamError = message.Message || 'pin failed';
+        }
+      }
+      // Errors after the 200 header arrive as an error line or the X-Stream-Error trailer
+      const trailerError = response.data.trailers?.['x-stream-error'];
+      if (streamError || trailerError) throw new Error(streamError || trailerError);
+    } catch (error: any) {
+      if (stalled) throw new Error(`pin stalled: no progress for ${Math.round(stallTimeoutMs / 1000)}s`);
+      throw error;
+    } finally {
+      clearTimeout(stallTimer);
+    }
+  }
+
+  /**
+   * Run `repo gc`, removing every block no pin references. Returns how many
+   * blocks went and how much repo usage dropped; per-block errors are counted,
+   * not thrown, so one bad block doesn't hide the rest of the pass.
+   */
+  async runGc(timeoutMs: number = 30 * 60000): Promise<{ removedBlocks: number; reclaimedBytes: number; errors: number }> {
+    const before = (await this.getStorageInfo()).usedBytes;
+    const response = await this.rpc.post(
+      `${this.getApiUrl()}/api/v0/repo/gc?stream-errors=true`,
+      null,
+      { timeout: timeoutMs, responseType: 'text' }
+    );
+    // One JSON object per line: { Key: { "/": cid } } or { Error }
+    let removedBlocks = 0;
+    let errors = 0;
+    for (const line of String(response.data).split('\n')) {
+      if (!line.trim()) continue;
+      let message: any;
+      try { message = JSON.parse(line); } catch { continue; }
+      if (message.Error) errors++;
+      else if (message.Key) removedBlocks++;
+    }
+    const after = (await this.getStorageInfo()).usedBytes;
+    return { removedBlocks, reclaimedBytes: Math.max(0, before - after), errors };
+  }
+
+  /**
+   * Add content by streaming a multipart `body` straight to /api/v0/add — a
+   * client's own multipart upload can be passed through untouched, so nothing
+   * is held in memory. Returns every entry Kubo added; the root is last.
+   */
+  async addStream(body: Readable, contentType: string, options: AddOptions = {}): Promise<Array<{ name: string; cid: string; size: number }>> {
+    const params = new URLSearchParams({ progress: 'false', pin: String(options.pin ?? true) });
+    if (options.chunker) params.set('chunker', options.chunker);
+    if (options.cidVersion !== undefined) params.set('cid-version', String(options.cidVersion));
+    if (options.rawLeaves !== undefined) params.set('raw-leaves', String(options.rawLeaves));
+    const response = await this.rpc.post(
+      `${this.getApiUrl()}/api/v0/add?${params}`,
+      body,
+      {
+        headers: { 'Content-Type': contentType },
+        maxBodyLength: Infinity,
+        maxContentLength: Infinity,
+        responseType: 'text',
+      }
+    );
+    // One JSON object per line: { Name, Hash, Size }
+    const entries: Array<{ name: string; cid: string; size: number }> = [];
+    for (const line of String(response.data).split('\n')) {
+      if (!line.trim()) continue;
+      let message: any;
+      try { message = JSON.parse(line); } catch { continue; }
+      if (message.Hash) entries.push({ name: message.Name || '', cid: message.Hash, size: Number(message.Size) || 0 });
+    }
+    if (options.pin ?? true) entries.forEach(e => this.invalidatePinSize(e.cid));
+    return entries;
+  }
+
+  /**
+   * Write the full DAG under `cid` to a CAR file at `filePath`. Offline: every
+   * block must already be local, so a partial pin fails instead of silently
+   * fetching from the network. Written to `<file>.tmp` and renamed on success.
+   */
+  async exportCar(cid: string, filePath: string): Promise<{ bytes: number }> {
+    fs.mkdirSync(path.dirname(filePath), { recursive: true });
+    const tmpPath = `${filePath}.tmp`;
+    try {
+      const response = await this.rpc.post(
+        `${this.getApiUrl()}/api/v0/dag/export?arg=${cid}&offline=true`,
+        null,
+        { responseType: 'stream' }
+      );
+      let bytes = 0;
+      const out = fs.createWriteStream(tmpPath);
+      await new Promise<void>((resolve, reject) => {
+        response.data.on('data', (chunk: Buffer) => { bytes += chunk.length; });
+        response.data.on('error', reject);
+        out.on('error', reject);
+        out.on('finish', () => resolve());
+        response.data.pipe(out);
+      });
+      // A block missing mid-export truncates the stream and reports via the trailer
+      const trailerError = response.data.trailers?.['x-stream-error'];
+      if (trailerError) throw new Error(trailerError);
+      fs.renameSync(tmpPath, filePath);
+      return { bytes };
+    } catch (error) {
+      try { fs.unlinkSync(tmpPath); } catch {}
+      throw error;
+    }
+  }
+
+  /**
+   * Import a CAR file, streamed from disk (never buffered whole), and pin its
+   * roots. Returns each root with the pin error Kubo reported, if any.
+   */
+  async importCar(filePath: string): Promise<Array<{ cid: string; error: string | null }>> {
+    const boundary = '----IPFSCar' + Date.now();
+    const fileName = path.basename(filePath).replace(/"/g, '');
+    async function* body() {
+      yield Buffer.from(
+        `--${boundary}\r\n` +
+        `Content-Disposition: form-data; name="file"; filename="${fileName}"\r\n` +
+        `Content-Type: application/vnd.ipld.car\r\n\r\n`
+      );
+      yield* fs.createReadStream(filePath);
+      yield Buffer.from(`\r\n--${boundary}--\r\n`);
+    }
+    const response = await this.rpc.post(
+      `${this.getApiUrl()}/api/v0/dag/import?pin-roots=true`,
+      Readable.from(body()),
+      {
+        headers: { 'Content-Type': `multipart/form-data; boundary=${boundary}` },
+        maxBodyLength: Infinity,
+        maxContentLength: Infinity,
+        responseType: 'text',
+      }
+    );
+    // One JSON object per line: { Root: { Cid: { "/": cid }, PinErrorMsg } }
+    const roots: Array<{ cid: string; error: string | null }> = [];
+    for (const line of String(response.data).split('\n')) {
+      if (!line.trim()) continue;
+      let message: any;
+      try { message = JSON.parse(line); } catch { continue; }
+      if (message.Root?.Cid?.['/']) {
+        roots.push({ cid: message.Root.Cid['/'], error: message.Root.PinErrorMsg || null });
+      }
+    }
+    return roots;
+  }
+
+  /** Read a single raw block. `offline` fails fast instead of fetching a missing block from peers. */
+  async getBlock(blockCid: string, timeoutMs: number = 2000, signal?: AbortSignal, offline: boolean = false): Promise<Buffer> {
+    const response = await this.rpc.post(
+      `${this.getApiUrl()}/api/v0/block/get?arg=${blockCid}${offline ? '&offline=true' : ''}`,
+      null,
+      { timeout: timeoutMs, responseType: 'arraybuffer', signal }
+    );
+    return Buffer.from(response.data);
+  }
+
+  /**
+   * Delete a block from the local blockstore. Kubo answers 200 with one
+   * { Hash, Error } line per block, so a refusal (the block is pinned, or not
+   * there) only shows up in the body — it is thrown here.
+   */
+  async removeBlock(blockCid: string): Promise<void> {
+    const response = await this.rpc.post(
+      `${this.getApiUrl()}/api/v0/block/rm?arg=${blockCid}`,
+      null,
+      { timeout: this.commandTimeoutMs(), responseType: 'text' }
+    );
+    for (const line of String(response.data).split('\n')) {
+      if (!line.trim()) continue;
+      let message: any;
+      try { message = JSON.parse(line); } catch { continue; }
+      if (message.Error) throw new Error(`block/rm ${blockCid}: ${message.Error}`);
+    }
+  }
+
+  /**
+   * Read `length` bytes of a file starting at `offset`, from local blocks only.
+   * Shorter than `length` at end of file; empty past it.
+   */
+  async catRange(cid: string, offset: number, length: number, timeoutMs: number, signal?: AbortSignal): Promise<Buffer> {
+    const response = await this.rpc.post(
+      `${this.getApiUrl()}/api/v0/cat?arg=${cid}&offset=${offset}&length=${length}&offline=true`,
+      null,
+      { timeout: timeoutMs, responseType: 'arraybuffer', signal }
+    );
+    return Buffer.from(response.data);
+  }
+
+  /** Store a raw block (CIDv1, raw codec, sha2-256) and return the CID Kubo assigned. */
+  async putBlock(data: Buffer, timeoutMs: number = 10000): Promise<string> {
+    const boundary = '----IPFSBlock' + Date.now();
+    const body = Buffer.concat([
+      Buffer.from(
+        `--${boundary}\r\n` +
+        `Content-Disposition: form-data; name="file"; filename="block"\r\n` +
+        `Content-Type: application/octet-stream\r\n\r\n`
+      ),
+      data,
+      Buffer.from(`\r\n--${boundary}--\r\n`),
+    ]);
+    const response = await this.rpc.post(
+      `${this.getApiUrl()}/api/v0/block/put?cid-codec=raw&mhtype=sha2-256`,
+      body,
+      { headers: { 'Content-Type': `multipart/form-data; boundary=${boundary}` }, timeout: timeoutMs }
+    );
+    return response.data.Key;
+  }
+
+  /**
+   * Fire-and-forget reads of blocks we expect to need shortly, so they are in
+   * the datastore/OS page cache by the time the challenge loop reaches them.
+   * Offline-only: warming must never trigger a network fetch.
+   */
+  warmBlocks(blockCids: string[]): void {
+    for (const blockCid of blockCids) {
+      this.rpc.post(
+        `${this.getApiUrl()}/api/v0/block/get?arg=${blockCid}&offline=true`,
+        null,
+        { timeout: 5000, responseType: 'arraybuffer' }
+      ).catch(() => {});
+    }
+  }
+
+  /** Datastore backing the blockstore (flatfs, badger, pebble, leveldb), read from the repo config. */
+  getDatastoreType(): string {
+    try {
+      const config = JSON.parse(fs.readFileSync(path.join(this.repoPath, 'config'), 'utf-8'));
+      const spec = JSON.stringify(config.Datastore?.Spec || {});
+      if (spec.includes('"flatfs"')) return 'flatfs';
+      if (spec.includes('"badgerds"')) return 'badger';
+      if (spec.includes('"pebbleds"')) return 'pebble';
+      if (spec.includes('"levelds"')) return 'leveldb';
+    } catch {}
+    return 'unknown';
+  }
 }
@@ -0,0 +1,202 @@
+/**
+ * metrics.ts — Prometheus text-format metrics for headless nodes
+ *
+ * Counters live in memory and are updated by the code paths that own them
+ * (HTTP challenges, PubSub challenges, legacy WebSocket challenges), so a
+ * scrape never touches disk or the Kubo API.
+ *
+ * Per-CID series are capped to the busiest N CIDs — a node with thousands
+ * of pins must not turn into thousands of Prometheus series.
+ *
+ * Node-level gauges (repo size, pin count, earnings, daemon up/restarts) are passed in
+ * by the caller from cached values; see ApiServer's /metrics route.
+ */
+
+export interface CidChallengeStats {
+  challenges: number;
+  passed: number;
+  lastChallengedAt: number; // epoch ms
+  lastPassed: boolean;
+}
+
+/** Node-wide values rendered alongside the counters. */
+export interface NodeGauges {
+  repoSizeBytes: number;
+  pinnedFiles: number;
+  totalEarnedHbd: number;
+  daemonUp: boolean;
+  daemonRestarts: number;
+  bandwidthInBytes: number;
+  bandwidthOutBytes: number;
+  swarmPeers: number;
+}
+
+/** Hard cap on CIDs tracked in memory (least recently challenged are evicted). */
+const MAX_TRACKED_CIDS = 10000;
+
+/** Challenge latency histogram buckets, in seconds. */
+const LATENCY_BUCKETS_SEC = [0.05, 0.1, 0.25, 0.5, 1, 2.5, 5, 10, 30];
+
+function escapeLabel(value: string): string {
+  return value.replace(/\\/g, '\\\\').replace(/"/g, '\\"').replace(/\n/g, '\\n');
+}
+
+export class AgentMetrics {
+  private cidStats: Map<string, CidChallengeStats> = new Map();
+  private topCids: number;
+  private challengesAbandoned = 0;
+  private challengesTotal = 0;
+  private challengesPassed = 0;
+  private blocksHashed = 0;
+  private latencyBuckets: number[] = LATENCY_BUCKETS_SEC.map(() => 0);
+  private latencyCount = 0;
+  private latencySumSec = 0;
+  private startedAt = Date.now();
+
+  constructor(topCids: number = 50) {
+    this.topCids = topCids;
+  }
+
+  setTopCids(topCids: number): void {
+    this.topCids = topCids;
+  }
+
+  /** Record a challenge outcome against its CID, with its latency when known. */
+  recordChallenge(cid: string, passed: boolean, latencyMs?: number): void {
+    this.challengesTotal++;
+    if (passed) this.challengesPassed++;
+    if (latencyMs !== undefined) {
+      const sec = latencyMs / 1000;
+      this.latencyCount++;
+      this.latencySumSec += sec;
+      LATENCY_BUCKETS_SEC.forEach((bound, i) => {
+        if (sec <= bound) this.latencyBuckets[i]++;
+      });
+    }
+
+    const stats = this.cidStats.get(cid) || { challenges: 0, passed: 0, lastChallengedAt: 0, lastPassed: false };
+    stats.challenges++;
+    if (passed) stats.passed++;
+    stats.lastChallengedAt = Date.now();
+    stats.lastPassed = passed;
+
+    // Re-insert so Map order tracks recency for eviction
+    this.cidStats.delete(cid);
+    this.cidStats.set(cid, stats);
+    if (this.cidStats.size > MAX_TRACKED_CIDS) {
+      const oldest = this.cidStats.keys().next().value;
+      if (oldest) this.cidStats.delete(oldest);
+    }
+  }
+
+  /** Blocks read and hashed into a challenge proof. */
+  recordBlocksHashed(count: number): void {
+    this.blocksHashed += count;
+  }
+
+  /** A hung in-flight challenge was force-failed by the TTL reaper. */
+  recordAbandoned(): void {
+    this.challengesAbandoned++;
+  }
+
+  getCidStats(cid: string): CidChallengeStats | null {
+    return this.cidStats.get(cid) || null;
+  }
+
+  /** The `limit` most recently challenged CIDs, newest first. */
+  getRecentChallenges(limit: number): Array<{ cid: string } & CidChallengeStats> {
+    return Array.from(this.cidStats.entries())
+      .slice(-limit)
+      .reverse()
+      .map(([cid, stats]) => ({ cid, ...stats }));
+  }
+
+  /** Render all metrics in Prometheus text exposition format. */
+  render(node?: NodeGauges): string {
+    const lines: string[] = [];
+    const now = Date.now();
+
+    if (node) {
+      lines.push('# HELP hivepoa_repo_size_bytes IPFS repo size (cached repo stat).');
+      lines.push('# TYPE hivepoa_repo_size_bytes gauge');
+      lines.push(`hivepoa_repo_size_bytes ${node.repoSizeBytes}`);
+      lines.push('# HELP hivepoa_pinned_files Recursive pins in the IPFS repo (cached).');
+      lines.push('# TYPE hivepoa_pinned_files gauge');
+      lines.push(`hivepoa_pinned_files ${node.pinnedFiles}`);
+      lines.push('# HELP hivepoa_total_earned_hbd All-time HBD earned from challenges.');
+      lines.push('# TYPE hivepoa_total_earned_hbd gauge');
+      lines.push(`hivepoa_total_earned_hbd ${node.totalEarnedHbd}`);
+      lines.push('# HELP hivepoa_daemon_up Whether the IPFS daemon is running (1) or not (0).');
+      lines.push('# TYPE hivepoa_daemon_up gauge');
+      lines.push(`hivepoa_daemon_up ${node.daemonUp ? 1 : 0}`);
+      lines.push('# HELP hivepoa_daemon_restarts_total IPFS daemon restarts by the crash watchdog.');
+      lines.push('# TYPE hivepoa_daemon_restarts_total counter');
+      lines.push(`hivepoa_daemon_restarts_total ${node.daemonRestarts}`);
+      lines.push('# HELP hivepoa_bandwidth_in_bytes_total Bytes received by the IPFS daemon (stats bw).');
+      lines.push('# TYPE hivepoa_bandwidth_in_bytes_total counter');
+      lines.push(`hivepoa_bandwidth_in_bytes_total ${node.bandwidthInBytes}`);
+      lines.push('# HELP hivepoa_bandwidth_out_bytes_total Bytes served by the IPFS daemon (stats bw).');
+      lines.push('# TYPE hivepoa_bandwidth_out_bytes_total counter');
+      lines.push(`hivepoa_bandwidth_out_bytes_total ${node.bandwidthOutBytes}`);
+      lines.push('# HELP hivepoa_swarm_peers Connected IPFS swarm peers.');
+      lines.push('# TYPE hivepoa_swarm_peers gauge');
+      lines.push(`hivepoa_swarm_peers ${node.swarmPeers}`);
+    }
+
+    lines.push('# HELP hivepoa_uptime_seconds Seconds since the agent started.');
+    lines.push('# TYPE hivepoa_uptime_seconds gauge');
+    lines.push(`hivepoa_uptime_seconds ${((now - this.startedAt) / 1000).toFixed(0)}`);
+
+    lines.push('# HELP hivepoa_challenges_total Challenges answered since the agent started.');
+    lines.push('# TYPE hivepoa_challenges_total counter');
+    lines.push(`hivepoa_challenges_total ${this.challengesTotal}`);
+    lines.push('# HELP hivepoa_challenges_passed_total Challenges passed since the agent started.');
+    lines.push('# TYPE hivepoa_challenges_passed_total counter');
+    lines.push(`hivepoa_challenges_passed_total ${this.challengesPassed}`);
+    lines.push('# HELP hivepoa_blocks_hashed_total Blocks read and hashed into challenge proofs.');
+    lines.push('# TYPE hivepoa_blocks_hashed_total counter');
+    lines.push(`hivepoa_blocks_hashed_total ${this.blocksHashed}`);
+
+    lines.push('# HELP hivepoa_challenge_latency_seconds Time to answer a challenge.');
+    lines.push('# TYPE hivepoa_challenge_latency_seconds histogram');
+    LATENCY_BUCKETS_SEC.forEach((bound, i) => {
+      lines.push(`hivepoa_challenge_latency_seconds_bucket{le="${bound}"} ${this.latencyBuckets[i]}`);
+    });
+    lines.push(`hivepoa_challenge_latency_seconds_bucket{le="+Inf"} ${this.latencyCount}`);
+    lines.push(`hivepoa_challenge_latency_seconds_sum ${this.latencySumSec.toFixed(3)}`);
+    lines.push(`hivepoa_challenge_latency_seconds_count ${this.latencyCount}`);
+
+    const busiest = Array.from(this.cidStats.entries())
+      .sort((a, b) => b[1].challenges - a[1].challenges)
+      .slice(0, this.topCids);
+
+    lines.push('# HELP hivepoa_pin_challenges_total Challenges received per CID (top CIDs by volume).');
+    lines.push('# TYPE hivepoa_pin_challenges_total counter');
+    for (const [cid, stats] of busiest) {
+      lines.push(`hivepoa_pin_challenges_total{cid="${escapeLabel(cid)}"} ${stats.challenges}`);
+    }
+
+    lines.push('# HELP hivepoa_pin_challenges_passed_total Challenges passed per CID (top CIDs by volume).');
+    lines.push('# TYPE hivepoa_pin_challenges_passed_total counter');
+    for (const [cid, stats] of busiest) {
+      lines.push(`hivepoa_pin_challenges_passed_total{cid="${escapeLabel(cid)}"} ${stats.passed}`);
+    }
+
+    lines.push('# HELP hivepoa_pin_last_challenged_seconds Seconds since the CID was last challenged.');
+    lines.push('# TYPE hivepoa_pin_last_challenged_seconds gauge');
+    for (const [cid, stats] of busiest) {
+      const age = Math.max(0, (now - stats.lastChallengedAt) / 1000);
+      lines.push(`hivepoa_pin_last_challenged_seconds{cid="${escapeLabel(cid)}"} ${age.toFixed(3)}`);
+    }
+
+    lines.push('# HELP hivepoa_challenges_abandoned_total In-flight challenges force-failed after exceeding the TTL.');
+    lines.push('# TYPE hivepoa_challenges_abandoned_total counter');
+    lines.push(`hivepoa_challenges_abandoned_total ${this.challengesAbandoned}`);
+
+    lines.push('# HELP hivepoa_tracked_cids CIDs with challenge history (before the top-N export cap).');
+    lines.push('# TYPE hivepoa_tracked_cids gauge');
+    lines.push(`hivepoa_tracked_cids ${this.cidStats.size}`);
+
+    return lines.join('\n') + '\n';
+  }
+}
@@ -0,0 +1,73 @@
+/**
+ * pairing.ts — Pair a web app with the agent through a one-time code
+ *
+ * Rather than handing the web app the full-access token, the app asks to pair
+ * (POST /api/pair/request, from its own origin, with the scopes it wants).
+ * The agent shows a 6-digit code with that origin and those scopes — desktop
+ * notification, or the log in CLI mode — and the user types it into the app,
+ * which submits it to POST /api/pair and gets back a scoped session token
+ * bound to its origin (see api-tokens.ts).
+ *
+ * One code is pending at a time. It expires after CODE_TTL_MS, is single-use,
+ * and is discarded after MAX_ATTEMPTS wrong guesses.
+ */
+
+import * as crypto from 'crypto';
+import { EventEmitter } from 'events';
+import type { ApiScope } from './api-tokens';
+
+export interface PairingRequest {
+  code: string;
+  origin: string;
+  scopes: ApiScope[];
+  name: string;
+  expiresAt: string;
+}
+
+export type PairingClaim =
+  | { ok: true; request: PairingRequest }
+  | { ok: false; error: 'NO_PENDING_PAIRING' | 'PAIRING_EXPIRED' | 'ORIGIN_MISMATCH' | 'INVALID_CODE' };
+
+const CODE_TTL_MS = 5 * 60000;
+const MAX_ATTEMPTS = 5;
+const MIN_REQUEST_INTERVAL_MS = 10000;
+
+export class PairingManager extends EventEmitter {
+  private pending: (PairingRequest & { attempts: number }) | null = null;
+  private lastRequestAt = 0;
+
+  /**
+   * Start pairing for `origin`, replacing any pending code. Returns null if
+   * asked again within MIN_REQUEST_INTERVAL_MS. Emits 'pairing-code'.
+   */
+  request(origin: string, scopes: ApiScope[], name: string): { expiresAt: string } | null {
+    const now = Date.now();
+    if (now - this.lastRequestAt < MIN_REQUEST_INTERVAL_MS) return null;
+    this.lastRequestAt = now;
+    const code = crypto.randomInt(0, 1000000).toString().padStart(6, '0');
+    this.pending = { code, origin, scopes, name, expiresAt: new Date(now + CODE_TTL_MS).toISOString(), attempts: 0 };
+    const { attempts: _attempts, ...request } = this.pending;
+    this.emit('pairing-code', request);
+    return { expiresAt: request.expiresAt };
+  }
+
+  /** Redeem the pending code. Any outcome but a wrong guess below MAX_ATTEMPTS ends the pairing. */
+  claim(code: string, origin: string): PairingClaim {
+    const pending = this.pending;
+    if (!pending) return { ok: false, error: 'NO_PENDING_PAIRING' };
+    if (Date.now() > Date.parse(pending.expiresAt)) {
+      this.pending = null;
+      return { ok: false, error: 'PAIRING_EXPIRED' };
+    }
+    if (origin !== pending.origin) return { ok: false, error: 'ORIGIN_MISMATCH' };
+    const a = Buffer.from(code.padEnd(6));
+    const b = Buffer.from(pending.code);
+    if (a.length !== b.length || !crypto.timingSafeEqual(a, b)) {
+      if (++pending.attempts >= MAX_ATTEMPTS) this.pending = null;
+      return { ok: false, error: 'INVALID_CODE' };
+    }
+    this.pending = null;
+    const { attempts: _attempts, ...request } = pending;
+    return { ok: true, request };
+  }
+}
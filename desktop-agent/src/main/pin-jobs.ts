@@ -0,0 +1,170 @@
+/**
+ * pin-jobs.ts — Background queue behind POST /api/pin
+ *
+ * A recursive pin of a large CID can take far longer than any HTTP client will
+ * wait, so /api/pin only enqueues a job and returns its id; callers poll
+ * GET /api/jobs/:id. Jobs move queued → fetching → pinned | failed.
+ *
+ * Progress comes from Kubo's `pin/add?progress=true` stream, which counts DAG
+ * nodes fetched rather than bytes; totalBytes (the DAG's cumulative size, when
+ * it could be resolved up front) gives the scale. Jobs live in memory only —
+ * finished ones are kept for a while so late pollers still see the outcome.
+ */
+
+import * as crypto from 'crypto';
+import type { KuboManager } from './kubo';
+import type { PinMetadataStore, PinSource } from './pin-metadata';
+import { agentEvents } from './agent-events';
+
+export type PinJobState = 'queued' | 'fetching' | 'pinned' | 'failed';
+
+export interface PinJob {
+  id: string;
+  cid: string;
+  name: string | null;
+  source: PinSource;
+  state: PinJobState;
+  blocksFetched: number;
+  totalBytes: number | null;
+  createdAt: string;
+  startedAt: string | null;
+  finishedAt: string | null;
+  error: string | null;
+}
+
+const PIN_CONCURRENCY = 2;
+const MAX_FINISHED_JOBS = 200;
+
+export class PinJobQueue {
+  private kubo: KuboManager;
+  private pinMetadata: PinMetadataStore;
+  private jobs: Map<string, PinJob> = new Map();
+  private queue: PinJob[] = [];
+  private active = 0;
+  private cancelled: Set<string> = new Set();
+  private waiters: Map<string, Array<(job: PinJob) => void>> = new Map();
+
+  constructor(kubo: KuboManager, pinMetadata: PinMetadataStore) {
+    this.kubo = kubo;
+    this.pinMetadata = pinMetadata;
+  }
+
+  /** Queue a pin. A CID that already has an unfinished job returns that job. */
+  enqueue(cid: string, name: string | null, totalBytes: number | null, source: PinSource = 'manual'): PinJob {
+    const existing = Array.from(this.jobs.values())
+      .find((j) => j.cid === cid && (j.state === 'queued' || j.state === 'fetching'));
+    if (existing) return existing;
+
+    const job: PinJob = {
+      id: crypto.randomBytes(8).toString('hex'),
+      cid,
+      name,
+      source,
+      state: 'queued',
+      blocksFetched: 0,
+      totalBytes,
+      createdAt: new Date().toISOString(),
+      startedAt: null,
+      finishedAt: null,
+      error: null,
+    };
+    this.jobs.set(job.id, job);
+    this.queue.push(job);
+    this.pruneFinished();
+    this.drain();
+    return job;
+  }
+
+  getJob(id: string): PinJob | null {
+    return this.jobs.get(id) || null;
+  }
+
+  /** Resolves with the job once it is pinned or failed. */
+  whenFinished(job: PinJob): Promise<PinJob> {
+    if (job.finishedAt) return Promise.resolve(job);
+    return new Promise((resolve) => {
+      const waiting = this.waiters.get(job.id) || [];
+      waiting.push(resolve);
+      this.waiters.set(job.id, waiting);
+    });
+  }
+
+  /**
+   * Cancel an unfinished job. A queued job never starts; a fetching one can't be
+   * interrupted, so it is unpinned as soon as its pin completes. Both end failed.
+   */
+  cancel(id: string): boolean {
+    const job = this.jobs.get(id);
+    if (!job || (job.state !== 'queued' && job.state !== 'fetching')) return false;
+    const queued = this.queue.indexOf(job);
+    if (queued !== -1) {
+      this.queue.splice(queued, 1);
+      job.state = 'failed';
+      job.error = 'cancelled';
+      job.finishedAt = new Date().toISOString();
+    } else {
+      this.cancelled.add(id);
+    }
+    return true;
+  }
+
+  listJobs(): PinJob[] {
+    return Array.from(this.jobs.values());
+  }
+
+  /** Bytes still to land from queued and fetching jobs (unknown sizes count as 0). */
+  pendingBytes(): number {
+    return Array.from(this.jobs.values())
+      .filter((j) => j.state === 'queued' || j.state === 'fetching')
+      .reduce((sum, j) => sum + (j.totalBytes || 0), 0);
+  }
+
+  private drain(): void {
+    while (this.active < PIN_CONCURRENCY && this.queue.length > 0) {
+      const job = this.queue.shift()!;
+      this.active++;
+      this.run(job).finally(() => {
+        this.active--;
+        this.drain();
+      });
+    }
+  }
+
+  private async run(job: PinJob): Promise<void> {
+    job.state = 'fetching';
+    job.startedAt = new Date().toISOString();
+    try {
+      await this.kubo.pinWithProgress(job.cid, (blocks) => {
+        job.blocksFetched = blocks;
+      });
+      if (this.cancelled.delete(job.id)) {
+        await this.kubo.unpin(job.cid);
+        throw new Error('cancelled');
+      }
+      job.state = 'pinned';
+      this.kubo.invalidatePinSize(job.cid);
+      // Optional label, shown as the pin's name in GET /api/pins
+      if (job.name) this.pinMetadata.updateMany({ [job.cid]: { name: job.name } });
+      agentEvents.publish('pin_added', { cid: job.cid, name: job.name || '', jobId: job.id, source: job.source });
+      console.log(`[PinJobs] Pinned ${job.cid} (${job.blocksFetched} blocks)`);
+    } catch (err: any) {
+      this.cancelled.delete(job.id);
+      job.state = 'failed';
+      job.error = err.message;
+      console.error(`[PinJobs] Pin of ${job.cid} failed: ${err.message}`);
+      agentEvents.publish('error', { source: 'pin-jobs', cid: job.cid, jobId: job.id, message: err.message });
+    } finally {
+      job.finishedAt = new Date().toISOString();
+      for (const resolve of this.waiters.get(job.id) || []) resolve(job);
+      this.waiters.delete(job.id);
+    }
+  }
+
+  /** Drop the oldest finished jobs beyond MAX_FINISHED_JOBS. */
+  private pruneFinished(): void {
+    const finished = Array.from(this.jobs.values()).filter((j) => j.finishedAt);
+    for (const job of finished.slice(0, Math.max(0, finished.length - MAX_FINISHED_JOBS))) {
+      this.jobs.delete(job.id);
+    }
+  }
+}
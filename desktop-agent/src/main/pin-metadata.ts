@@ -0,0 +1,168 @@
+/**
+ * pin-metadata.ts — Operator metadata for pins (name, tags, priority, provenance)
+ *
+ * Kubo only knows CIDs; names and organization live in a JSON sidecar next to
+ * the agent config (~/.spk-ipfs/pin-metadata.json). Every write replaces the
+ * whole file via temp-file + rename, so a bulk update lands all-or-nothing.
+ * A sidecar that no longer parses is moved aside to pin-metadata.json.bak
+ * rather than silently overwritten by the next write.
+ *
+ * Provenance is filled in from the event bus once started: pin_added records
+ * where a pin came from (its `source`, default manual) and when, pin_removed
+ * clears that, and challenge_completed stamps lastChallengedAt. Challenges are
+ * frequent, so event-driven fields are batched into one write every few seconds.
+ */
+
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { agentEvents, AgentEvent } from './agent-events';
+
+export type PinSource = 'manual' | 'upload' | 'import' | 'contract' | 'auto-pin' | 'pinning-service' | 'reconciler';
+
+export const PIN_SOURCES: PinSource[] = ['manual', 'upload', 'import', 'contract', 'auto-pin', 'pinning-service', 'reconciler'];
+
+export interface PinMetadata {
+  name?: string;
+  tags?: string[];
+  priority?: number;
+  source?: PinSource;
+  pinnedAt?: string;
+  lastChallengedAt?: string;
+  updatedAt: string;
+}
+
+export type PinMetadataUpdate = Partial<Pick<PinMetadata, 'name' | 'tags' | 'priority'>>;
+
+// Event-driven fields awaiting the next write; null deletes the field
+type TrackedUpdate = { source?: PinSource | null; pinnedAt?: string | null; lastChallengedAt?: string };
+
+const TRACK_FLUSH_MS = 5000;
+
+export class PinMetadataStore {
+  private filePath: string;
+  private tracked: Map<string, TrackedUpdate> = new Map();
+  private flushTimer: NodeJS.Timeout | null = null;
+  private unsubscribe: (() => void) | null = null;
+
+  constructor(filePath: string = path.join(os.homedir(), '.spk-ipfs', 'pin-metadata.json')) {
+    this.filePath = filePath;
+  }
+
+  /** Track pin provenance and challenges from the event bus. */
+  start(): void {
+    if (this.unsubscribe) return;
+    this.unsubscribe = agentEvents.subscribe((event) => this.onEvent(event));
+  }
+
+  stop(): void {
+    this.unsubscribe?.();
+    this.unsubscribe = null;
+    this.flush();
+  }
+
+  getAll(): Record<string, PinMetadata> {
+    let all: Record<string, PinMetadata> = {};
+    let raw: string | null = null;
+    try {
+      if (fs.existsSync(this.filePath)) raw = fs.readFileSync(this.filePath, 'utf-8');
+    } catch (error) {
+      console.error('[PinMetadata] Failed to read sidecar:', error);
+    }
+    if (raw !== null) {
+      try {
+        all = JSON.parse(raw);
+      } catch (error: any) {
+        this.moveAside(error.message);
+      }
+    }
+    this.applyTracked(all);
+    return all;
+  }
+
+  get(cid: string): PinMetadata | null {
+    return this.getAll()[cid] || null;
+  }
+
+  /** Merge updates for many CIDs in a single atomic write. Returns the CIDs written. */
+  updateMany(updates: Record<string, PinMetadataUpdate>): string[] {
+    const all = this.getAll();
+    const now = new Date().toISOString();
+    const written: string[] = [];
+
+    for (const [cid, update] of Object.entries(updates)) {
+      all[cid] = { ...all[cid], ...update, updatedAt: now };
+      written.push(cid);
+    }
+
+    if (written.length > 0) this.write(all);
+    return written;
+  }
+
+  /** Write pending provenance/challenge fields now (also called on stop). */
+  flush(): void {
+    if (this.flushTimer) {
+      clearTimeout(this.flushTimer);
+      this.flushTimer = null;
+    }
+    if (this.tracked.size === 0) return;
+    try {
+      this.write(this.getAll()); // getAll() overlays the tracked fields
+    } catch (error) {
+      console.error('[PinMetadata] Failed to write sidecar:', error);
+    }
+  }
+
+  private onEvent(event: AgentEvent): void {
+    const cid = event.data?.cid;
+    if (typeof cid !== 'string' || !cid) return;
+    const entry = this.tracked.get(cid) || {};
+    if (event.type === 'pin_added') {
+      entry.source = PIN_SOURCES.includes(event.data.source) ? event.data.source : 'manual';
+      entry.pinnedAt = event.at;
+    } else if (event.type === 'pin_removed') {
+      entry.source = null;
+      entry.pinnedAt = null;
+    } else if (event.type === 'challenge_completed') {
+      entry.lastChallengedAt = event.at;
+    } else {
+      return;
+    }
+    this.tracked.set(cid, entry);
+    if (!this.flushTimer) {
+      this.flushTimer = setTimeout(() => this.flush(), TRACK_FLUSH_MS);
+      this.flushTimer.unref?.();
+    }
+  }
+
+  private applyTracked(all: Record<string, PinMetadata>): void {
+    this.tracked.forEach((update, cid) => {
+      if (!all[cid] && update.lastChallengedAt === undefined && !update.pinnedAt) return;
+      const entry: PinMetadata = { ...all[cid], updatedAt: all[cid]?.updatedAt || new Date().toISOString() };
+      for (const key of ['source', 'pinnedAt', 'lastChallengedAt'] as const) {
+        if (update[key] === null) delete entry[key];
+        else if (update[key] !== undefined) (entry as any)[key] = update[key];
+      }
+      all[cid] = entry;
+    });
+  }
+
+  /** Keep a corrupt sidecar as .bak so the next write doesn't destroy it. */
+  private moveAside(reason: string): void {
+    const backupPath = `${this.filePath}.bak`;
+    try {
+      fs.renameSync(this.filePath, backupPath);
+      console.error(`[PinMetadata] Sidecar is corrupt (${reason}) — moved to ${backupPath}, starting empty`);
+    } catch (error: any) {
+      console.error(`[PinMetadata] Sidecar is corrupt (${reason}) and could not be moved aside: ${error.message}`);
+    }
+  }
+
+  private write(all: Record<string, PinMetadata>): void {
+    fs.mkdirSync(path.dirname(this.filePath), { recursive: true });
+    const tmpPath = `${this.filePath}.tmp`;
+    fs.writeFileSync(tmpPath, JSON.stringify(all, null, 2));
+    fs.renameSync(tmpPath, this.filePath);
+    this.tracked.clear();
+  }
+}
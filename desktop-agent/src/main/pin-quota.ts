@@ -0,0 +1,70 @@
+/**
+ * pin-quota.ts — Per-caller quota on pin operations
+ *
+ * A safety valve against a buggy or hostile coordinator filling the disk with
+ * pin requests. Each caller (the API token it authenticated with) may make
+ * pinQuotaPerWindow pin operations per pinQuotaWindowMin-minute sliding window.
+ * This protects storage; challenge throughput is limited separately.
+ */
+
+export interface PinQuotaUsage {
+  identity: string;
+  used: number;
+  limit: number;
+  resetsAt: string | null; // when the oldest counted operation leaves the window
+}
+
+export class PinQuota {
+  private ops: Map<string, number[]> = new Map(); // identity → op timestamps (ms), oldest first
+
+  /**
+   * Count one operation for `identity`. Returns null if allowed, or the usage
+   * that made it exceed the quota. `limit` <= 0 disables the quota.
+   */
+  consume(identity: string, limit: number, windowMs: number): PinQuotaUsage | null {
+    if (limit <= 0) return null;
+    const now = Date.now();
+    const recent = this.prune(identity, now, windowMs);
+    if (recent.length >= limit) {
+      return this.usageOf(identity, recent, limit, windowMs);
+    }
+    recent.push(now);
+    this.ops.set(identity, recent);
+    return null;
+  }
+
+  /** Undo the latest consume() for `identity` — the operation was rejected. */
+  refund(identity: string): void {
+    const recent = this.ops.get(identity);
+    if (!recent) return;
+    recent.pop();
+    if (recent.length === 0) this.ops.delete(identity);
+  }
+
+  /** Current usage for every identity with operations inside the window. */
+  getUsage(limit: number, windowMs: number): PinQuotaUsage[] {
+    const now = Date.now();
+    const usage: PinQuotaUsage[] = [];
+    for (const identity of Array.from(this.ops.keys())) {
+      const recent = this.prune(identity, now, windowMs);
+      if (recent.length > 0) usage.push(this.usageOf(identity, recent, limit, windowMs));
+    }
+    return usage;
+  }
+
+  private prune(identity: string, now: number, windowMs: number): number[] {
+    const recent = (this.ops.get(identity) || []).filter((t) => now - t < windowMs);
+    if (recent.length === 0) this.ops.delete(identity);
+    else this.ops.set(identity, recent);
+    return recent;
+  }
+
+  private usageOf(identity: string, recent: number[], limit: number, windowMs: number): PinQuotaUsage {
+    return {
+      identity,
+      used: recent.length,
+      limit,
+      resetsAt: recent.length > 0 ? new Date(recent[0] + windowMs).toISOString() : null,
+    };
+  }
+}
@@ -0,0 +1,216 @@
+/**
+ * pin-reconciler.ts — Converge local pins to a coordinator's desired pinset
+ *
+ * Opt-in (pinReconcileEnabled). Every pinReconcileIntervalMin minutes:
+ *   1. GET the desired pin list from the coordinator
+ *   2. Diff against local recursive pins
+ *   3. Queue what's missing on the PinJobQueue (source 'reconciler'), stopping
+ *      once a pin's size plus the bytes still queued would pass storageMaxGB or
+ *      leave the repo volume with less than diskMinFreeGB free
+ *   4. If pinReconcileUnpinExtras is set, unpin extras — but only pins this
+ *      reconciler added itself, never pins the operator made by hand
+ *
+ * pinReconcileAllowlist / pinReconcileDenylist limit which CIDs the reconciler
+ * touches at all: a CID outside the allowlist (when it is non-empty) or on the
+ * denylist is neither pinned nor unpinned, and is counted in skippedByFilter.
+ *
+ * Each pass is kept as a report for GET /api/pins/reconcile.
+ */
+
+import axios from 'axios';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import type { KuboManager } from './kubo';
+import type { ConfigStore } from './config';
+import type { PinJobQueue } from './pin-jobs';
+import type { StorageMonitor } from './storage-monitor';
+import { resolveNodeId } from './config';
+import { isValidCid } from './poa-crypto';
+import { bootCooldown } from './boot-cooldown';
+import { agentEvents } from './agent-events';
+
+export interface ReconcileReport {
+  startedAt: string;
+  finishedAt: string;
+  desired: number;
+  local: number;
+  queued: string[];
+  removed: string[];
+  failed: { cid: string; error: string }[];
+  skippedForQuota: number;
+  skippedByFilter: number;
+  error: string | null;
+}
+
+const MAX_PINS_PER_PASS = 50;   // spread large backlogs over several passes
+const MAX_REPORTS = 20;
+
+export class PinReconciler {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private pinJobs: PinJobQueue;
+  private storageMonitor: StorageMonitor;
+  private timer: NodeJS.Timeout | null = null;
+  private firstPass: NodeJS.Timeout | null = null;
+  private running = false;
+  private reports: ReconcileReport[] = [];
+  private ownedPath: string;
+
+  constructor(kubo: KuboManager, config: ConfigStore, pinJobs: PinJobQueue, storageMonitor: StorageMonitor) {
+    this.kubo = kubo;
+    this.config = config;
+    this.pinJobs = pinJobs;
+    this.storageMonitor = storageMonitor;
+    this.ownedPath = path.join(os.homedir(), '.spk-ipfs', 'reconciled-pins.json');
+  }
+
+  start(): void {
+    const cfg = this.config.getConfig();
+    if (!cfg.pinReconcileEnabled || this.timer) return;
+    const intervalMs = Math.max(1, cfg.pinReconcileIntervalMin) * 60000;
+    const scheduled = () => {
+      if (!bootCooldown.isActive()) this.reconcile().catch(() => {});
+    };
+    this.timer = setInterval(scheduled, intervalMs);
+    // First pass after 60 seconds (let IPFS stabilize)
+    this.firstPass = setTimeout(scheduled, 60000);
+    console.log(`[Reconciler] Started (every ${cfg.pinReconcileIntervalMin} min, unpinExtras=${cfg.pinReconcileUnpinExtras})`);
+  }
+
+  stop(): void {
+    if (this.firstPass) {
+      clearTimeout(this.firstPass);
+      this.firstPass = null;
+    }
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  getReports(): ReconcileReport[] {
+    return [...this.reports];
+  }
+
+  /** Run one reconciliation pass now. Concurrent calls return null. */
+  async reconcile(): Promise<ReconcileReport | null> {
+    if (this.running || !this.kubo.isRunning()) return null;
+    this.running = true;
+
+    const cfg = this.config.getConfig();
+    const report: ReconcileReport = {
+      startedAt: new Date().toISOString(),
+      finishedAt: '',
+      desired: 0,
+      local: 0,
+      queued: [],
+      removed: [],
+      failed: [],
+      skippedForQuota: 0,
+      skippedByFilter: 0,
+      error: null,
+    };
+
+    try {
+      const desired = await this.fetchDesired();
+      const local = new Set(await this.kubo.listPins());
+      const owned = this.loadOwned();
+      report.desired = desired.size;
+      report.local = local.size;
+
+      const allow = new Set(cfg.pinReconcileAllowlist);
+      const deny = new Set(cfg.pinReconcileDenylist);
+      const permitted = (cid: string) => !deny.has(cid) && (allow.size === 0 || allow.has(cid));
+
+      const missing = Array.from(desired).filter((cid) => {
+        if (local.has(cid)) return false;
+        if (permitted(cid)) return true;
+        report.skippedByFilter++;
+        return false;
+      });
+      // enqueue() dedupes pins still fetching from an earlier pass
+      for (const cid of missing.slice(0, MAX_PINS_PER_PASS)) {
+        const storage = await this.kubo.getStorageInfo();
+        const sizeBytes = await this.kubo.getObjectSize(cid);
+        const projectedBytes = storage.usedBytes + this.pinJobs.pendingBytes() + (sizeBytes || 0);
+        const diskError = this.storageMonitor.checkPin(sizeBytes || 0, this.pinJobs.pendingBytes());
+        if ((storage.maxBytes > 0 && projectedBytes > storage.maxBytes) || diskError) {
+          report.skippedForQuota = missing.length - report.queued.length;
+          console.warn(`[Reconciler] ${diskError || 'Storage limit reached'} — ${report.skippedForQuota} desired pins deferred`);
+          break;
+        }
+        this.pinJobs.enqueue(cid, null, sizeBytes, 'reconciler');
+        owned.add(cid);
+        report.queued.push(cid);
+      }
+
+      if (cfg.pinReconcileUnpinExtras) {
+        for (const cid of Array.from(owned)) {
+          if (desired.has(cid) || !local.has(cid)) continue;
+          if (!permitted(cid)) {
+            report.skippedByFilter++;
+            continue;
+          }
+          try {
+            await this.kubo.unpin(cid);
+            owned.delete(cid);
+            report.removed.push(cid);
+            agentEvents.publish('pin_removed', { cid });
+          } catch (err: any) {
+            report.failed.push({ cid, error: err.message });
+          }
+        }
+      }
+
+      this.saveOwned(owned);
+      console.log(`[Reconciler] Pass complete: +${report.queued.length} -${report.removed.length} (${report.failed.length} failed, desired=${report.desired})`);
+    } catch (err: any) {
+      report.error = err.message;
+      console.warn(`[Reconciler] Pass failed: ${err.message}`);
+    } finally {
+      report.finishedAt = new Date().toISOString();
+      this.reports.push(report);
+      if (this.reports.length > MAX_REPORTS) this.reports.shift();
+      this.running = false;
+    }
+    return report;
+  }
+
+  /**
+   * Desired set from pinReconcileUrl (default: <serverUrl>/api/pool/desired-pins).
+   * Accepts `{ pins: [...] }` or a bare array, entries either CID strings or `{ cid }`.
+   */
+  private async fetchDesired(): Promise<Set<string>> {
+    const cfg = this.config.getConfig();
+    const url = cfg.pinReconcileUrl || `${cfg.serverUrl}/api/pool/desired-pins`;
+    const response = await axios.get(url, {
+      timeout: 30000,
+      params: {
+        nodeId: resolveNodeId(cfg, await this.kubo.getPeerId()) || undefined,
+        hiveUsername: cfg.hiveUsername || undefined,
+      },
+    });
+    const list: any[] = Array.isArray(response.data) ? response.data : response.data?.pins || [];
+    const desired = new Set<string>();
+    for (const entry of list) {
+      const cid = typeof entry === 'string' ? entry : entry?.cid;
+      if (typeof cid === 'string' && isValidCid(cid)) desired.add(cid);
+    }
+    return desired;
+  }
+
+  /** CIDs this reconciler pinned — the only ones it may ever unpin. */
+  private loadOwned(): Set<string> {
+    try {
+      if (fs.existsSync(this.ownedPath)) {
+        return new Set(JSON.parse(fs.readFileSync(this.ownedPath, 'utf-8')));
+      }
+    } catch {}
+    return new Set();
+  }
+
+  private saveOwned(owned: Set<string>): void {
+    fs.writeFileSync(this.ownedPath, JSON.stringify(Array.from(owned), null, 2));
+  }
+}
@@ -0,0 +1,337 @@
+/**
+ * pinning-service.ts — IPFS Pinning Service API (https://ipfs.github.io/pinning-services-api-spec/)
+ *
+ * Mounted at /pins on the agent API so standard tooling can use the agent as
+ * a remote pinning service:
+ *
+ *   ipfs pin remote service add hivepoa http://127.0.0.1:5111 $(cat ~/.spk-ipfs/api-token)
+ *   ipfs pin remote add --service=hivepoa --name=dataset <cid>
+ *
+ * Every request needs `Authorization: Bearer <api token>`. A pin request is
+ * backed by a PinJobQueue job; its status maps queued → queued,
+ * fetching → pinning, pinned → pinned, failed → failed. Requests persist in
+ * ~/.spk-ipfs/pin-requests.json, and unfinished ones are re-queued on start.
+ *
+ * Deleting a request unpins its CID unless another request still holds it or
+ * the CID was already pinned (by /api/pin, contracts, …) when it was requested.
+ */
+
+import { Router, Request, Response } from 'express';
+import * as crypto from 'crypto';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import type { KuboManager } from './kubo';
+import type { PinJobQueue, PinJobState } from './pin-jobs';
+import { isValidCid } from './poa-crypto';
+import { JsonFileWriter } from './json-writer';
+import { agentEvents } from './agent-events';
+
+export type PinningStatus = 'queued' | 'pinning' | 'pinned' | 'failed';
+
+const PINNING_STATUSES: PinningStatus[] = ['queued', 'pinning', 'pinned', 'failed'];
+const JOB_STATUS: Record<PinJobState, PinningStatus> = {
+  queued: 'queued',
+  fetching: 'pinning',
+  pinned: 'pinned',
+  failed: 'failed',
+};
+const MAX_ORIGINS = 20;
+const MAX_META_KEYS = 32;
+const DEFAULT_LIST_LIMIT = 10;
+const MAX_LIST_LIMIT = 1000;
+const DELEGATES_TTL_MS = 60000;
+
+interface PinRequest {
+  requestid: string;
+  cid: string;
+  name: string | null;
+  origins: string[];
+  meta: Record<string, string>;
+  created: string;
+  jobId: string | null;
+  /** Last status seen — what a request reports once its job is gone (pruned, or an agent restart). */
+  status: PinningStatus;
+  error: string | null;
+  /** The CID was already pinned locally when requested — deleting the request leaves it pinned. */
+  preexisting: boolean;
+}
+
+/** The spec's PinStatus object. */
+interface PinStatus {
+  requestid: string;
+  status: PinningStatus;
+  created: string;
+  pin: { cid: string; name?: string; origins: string[]; meta: Record<string, string> };
+  delegates: string[];
+  info: Record<string, string>;
+}
+
+export class PinRequestStore {
+  private kubo: KuboManager;
+  private pinJobs: PinJobQueue;
+  private requests: Map<string, PinRequest> = new Map();
+  private writer: JsonFileWriter;
+  private delegates: { at: number; addresses: string[] } | null = null;
+
+  constructor(kubo: KuboManager, pinJobs: PinJobQueue, filePath: string = path.join(os.homedir(), '.spk-ipfs', 'pin-requests.json')) {
+    this.kubo = kubo;
+    this.pinJobs = pinJobs;
+    this.writer = new JsonFileWriter(filePath, () => Array.from(this.requests.values()));
+    try {
+      if (fs.existsSync(filePath)) {
+        for (const request of JSON.parse(fs.readFileSync(filePath, 'utf-8')) as PinRequest[]) {
+          this.requests.set(request.requestid, request);
+        }
+      }
+    } catch (error) {
+      console.error('[Pinning] Failed to read pin requests:', error);
+    }
+  }
+
+  /** Re-queue requests that were still queued or pinning when the agent last stopped. */
+  resume(): void {
+    let resumed = 0;
+    for (const request of this.requests.values()) {
+      if (request.status !== 'queued' && request.status !== 'pinning') continue;
+      if (request.jobId && this.pinJobs.getJob(request.jobId)) continue;
+      request.jobId = this.pinJobs.enqueue(request.cid, request.name, null, 'pinning-service').id;
+      resumed++;
+    }
+    if (resumed > 0) {
+      console.log(`[Pinning] Re-queued ${resumed} unfinished pin requests`);
+      this.writer.schedule();
+    }
+  }
+
+  async add(cid: string, name: string | null, origins: string[], meta: Record<string, string>): Promise<PinStatus> {
+    // Pinned outside the service (not by one of our own requests) — never ours to unpin
+    const heldByRequest = Array.from(this.requests.values()).some((r) => r.cid === cid);
+    const preexisting = !heldByRequest && (await this.kubo.listPins().catch(() => [] as string[])).includes(cid);
+    // Dial origins first so the fetch can start from a provider we know has the data
+    for (const origin of origins) void this.kubo.swarmConnect(origin);
+    const request: PinRequest = {
+      requestid: crypto.randomBytes(16).toString('hex'),
+      cid,
+      name,
+      origins,
+      meta,
+      created: new Date().toISOString(),
+      jobId: null,
+      status: 'queued',
+      error: null,
+      preexisting,
+    };
+    if (preexisting) request.status = 'pinned';
+    else request.jobId = this.pinJobs.enqueue(cid, name, null, 'pinning-service').id;
+    this.requests.set(request.requestid, request);
+    this.writer.schedule();
+    return this.toPinStatus(request);
+  }
+
+  async get(requestid: string): Promise<PinStatus | null> {
+    const request = this.requests.get(requestid);
+    return request ? this.toPinStatus(request) : null;
+  }
+
+  /** Remove a request, unpinning its CID when nothing else holds it. False if unknown. */
+  async remove(requestid: string): Promise<boolean> {
+    const request = this.requests.get(requestid);
+    if (!request) return false;
+    this.requests.delete(requestid);
+    this.writer.schedule();
+
+    const stillWanted = Array.from(this.requests.values()).some((r) => r.cid === request.cid);
+    if (stillWanted || request.preexisting) return true;
+    if (request.jobId && this.pinJobs.cancel(request.jobId)) return true;
+    try {
+      await this.kubo.unpin(request.cid);
+      agentEvents.publish('pin_removed', { cid: request.cid });
+    } catch (err: any) {
+      console.warn(`[Pinning] Unpin of ${request.cid} failed: ${err.message}`);
+    }
+    return true;
+  }
+
+  /** GET /pins — filters per the spec; newest first. */
+  async list(filter: {
+    cids: string[] | null;
+    name: string | null;
+    match: 'exact' | 'iexact' | 'partial' | 'ipartial';
+    statuses: PinningStatus[];
+    before: number | null;
+    after: number | null;
+    meta: Record<string, string> | null;
+    limit: number;
+  }): Promise<{ count: number; results: PinStatus[] }> {
+    const matchName = (name: string | null): boolean => {
+      if (filter.name === null) return true;
+      if (name === null) return false;
+      switch (filter.match) {
+        case 'exact': return name === filter.name;
+        case 'iexact': return name.toLowerCase() === filter.name.toLowerCase();
+        case 'partial': return name.includes(filter.name);
+        case 'ipartial': return name.toLowerCase().includes(filter.name.toLowerCase());
+      }
+    };
+    const matching = Array.from(this.requests.values())
+      .map((request) => ({ request, status: this.refresh(request) }))
+      .filter(({ request, status }) => {
+        const created = Date.parse(request.created);
+        return (filter.cids === null || filter.cids.includes(request.cid))
+          && matchName(request.name)
+          && filter.statuses.includes(status)
+          && (filter.before === null || created < filter.before)
+          && (filter.after === null || created > filter.after)
+          && (filter.meta === null || Object.entries(filter.meta).every(([k, v]) => request.meta[k] === v));
+      })
+      .sort((a, b) => b.request.created.localeCompare(a.request.created));
+
+    const results: PinStatus[] = [];
+    for (const { request } of matching.slice(0, filter.limit)) results.push(await this.toPinStatus(request));
+    return { count: matching.length, results };
+  }
+
+  /** Current status from the backing job (if still tracked), recorded on the request. */
+  private refresh(request: PinRequest): PinningStatus {
+    const job = request.jobId ? this.pinJobs.getJob(request.jobId) : null;
+    if (job) {
+      const status = JOB_STATUS[job.state];
+      if (status !== request.status || job.error !== request.error) {
+        request.status = status;
+        request.error = job.error;
+        this.writer.schedule();
+      }
+    }
+    return request.status;
+  }
+
+  private async toPinStatus(request: PinRequest): Promise<PinStatus> {
+    const status = this.refresh(request);
+    const info: Record<string, string> = {};
+    if (request.error) info.status_details = request.error;
+    return {
+      requestid: request.requestid,
+      status,
+      created: request.created,
+      pin: { cid: request.cid, ...(request.name ? { name: request.name } : {}), origins: request.origins, meta: request.meta },
+      delegates: await this.getDelegates(),
+      info,
+    };
+  }
+
+  private async getDelegates(): Promise<string[]> {
+    if (!this.delegates || Date.now() - this.delegates.at > DELEGATES_TTL_MS) {
+      this.delegates = { at: Date.now(), addresses: (await this.kubo.getListenAddresses()).slice(0, 20) };
+    }
+    return this.delegates.addresses;
+  }
+
+  flush(): void {
+    this.writer.flush();
+  }
+}
+
+/** Spec error body: { error: { reason, details } }. */
+function fail(res: Response, status: number, reason: string, details: string): void {
+  res.status(status).json({ error: { reason, details } });
+}
+
+/** Validate a spec Pin object ({ cid, name?, origins?, meta? }). Returns an error string or the parsed pin. */
+function parsePin(body: any): string | { cid: string; name: string | null; origins: string[]; meta: Record<string, string> } {
+  const { cid, name, origins, meta } = body || {};
+  if (typeof cid !== 'string' || !isValidCid(cid)) return 'cid must be a valid CID';
+  if (name !== undefined && (typeof name !== 'string' || name.length > 255)) return 'name must be a string of at most 255 characters';
+  if (origins !== undefined && (!Array.isArray(origins) || origins.length > MAX_ORIGINS
+    || !origins.every((o) => typeof o === 'string' && o.startsWith('/')))) {
+    return `origins must be an array of at most ${MAX_ORIGINS} multiaddrs`;
+  }
+  if (meta !== undefined && (typeof meta !== 'object' || meta === null || Array.isArray(meta)
+    || Object.keys(meta).length > MAX_META_KEYS || !Object.values(meta).every((v) => typeof v === 'string'))) {
+    return `meta must be an object of at most ${MAX_META_KEYS} string values`;
+  }
+  return { cid, name: name ?? null, origins: origins ?? [], meta: meta ?? {} };
+}
+
+/**
+ * Spec routes. `checkCapacity` refuses a pin that would exceed the storage
+ * limit (same rule as POST /api/pin); it returns an error message or null.
+ */
+export function createPinningServiceRoutes(store: PinRequestStore, checkCapacity: (cid: string) => Promise<string | null>): Router {
+  const router = Router();
+
+  router.get('/', async (req: Request, res: Response) => {
+    const q = req.query;
+    const cids = typeof q.cid === 'string' ? q.cid.split(',').filter(Boolean) : null;
+    if (cids && (cids.length > 10 || !cids.every(isValidCid))) return fail(res, 400, 'BAD_REQUEST', 'cid must be up to 10 comma-separated CIDs');
+    const match = String(q.match || 'exact');
+    if (!['exact', 'iexact', 'partial', 'ipartial'].includes(match)) return fail(res, 400, 'BAD_REQUEST', 'match must be exact, iexact, partial or ipartial');
+    const statuses = String(q.status || 'pinned').split(',') as PinningStatus[];
+    if (!statuses.every((s) => PINNING_STATUSES.includes(s))) return fail(res, 400, 'BAD_REQUEST', `status must be one of ${PINNING_STATUSES.join(', ')}`);
+    const before = q.before === undefined ? null : Date.parse(String(q.before));
+    const after = q.after === undefined ? null : Date.parse(String(q.after));
+    if (Number.isNaN(before) || Number.isNaN(after)) return fail(res, 400, 'BAD_REQUEST', 'before and after must be ISO 8601 timestamps');
+    const limit = q.limit === undefined ? DEFAULT_LIST_LIMIT : Number(q.limit);
+    if (!Number.isInteger(limit) || limit < 1 || limit > MAX_LIST_LIMIT) return fail(res, 400, 'BAD_REQUEST', `limit must be an integer from 1 to ${MAX_LIST_LIMIT}`);
+    let meta: Record<string, string> | null = null;
+    if (typeof q.meta === 'string') {
+      try { meta = JSON.parse(q.meta); } catch { return fail(res, 400, 'BAD_REQUEST', 'meta must be a JSON object'); }
+    }
+
+    res.json(await store.list({
+      cids,
+      name: typeof q.name === 'string' ? q.name : null,
+      match: match as 'exact' | 'iexact' | 'partial' | 'ipartial',
+      statuses,
+      before,
+      after,
+      meta,
+      limit,
+    }));
+  });
+
+  router.post('/', async (req: Request, res: Response) => {
+    const pin = parsePin(req.body);
+    if (typeof pin === 'string') return fail(res, 400, 'BAD_REQUEST', pin);
+    try {
+      const capacityError = await checkCapacity(pin.cid);
+      if (capacityError) return fail(res, 507, 'INSUFFICIENT_STORAGE', capacityError);
+      res.status(202).json(await store.add(pin.cid, pin.name, pin.origins, pin.meta));
+    } catch (error: any) {
+      fail(res, 500, 'INTERNAL_SERVER_ERROR', error.message);
+    }
+  });
+
+  router.get('/:requestid', async (req: Request, res: Response) => {
+    const status = await store.get(req.params.requestid);
+    if (!status) return fail(res, 404, 'NOT_FOUND', 'The specified resource was not found');
+    res.json(status);
+  });
+
+  // Replace: the new pin gets a new requestid; the old request is removed
+  router.post('/:requestid', async (req: Request, res: Response) => {
+    const pin = parsePin(req.body);
+    if (typeof pin === 'string') return fail(res, 400, 'BAD_REQUEST', pin);
+    if (!(await store.get(req.params.requestid))) return fail(res, 404, 'NOT_FOUND', 'The specified resource was not found');
+    try {
+      const capacityError = await checkCapacity(pin.cid);
+      if (capacityError) return fail(res, 507, 'INSUFFICIENT_STORAGE', capacityError);
+      const status = await store.add(pin.cid, pin.name, pin.origins, pin.meta);
+      await store.remove(req.params.requestid);
+      res.status(202).json(status);
+    } catch (error: any) {
+      fail(res, 500, 'INTERNAL_SERVER_ERROR', error.message);
+    }
+  });
+
+  router.delete('/:requestid', async (req: Request, res: Response) => {
+    try {
+      if (!(await store.remove(req.params.requestid))) return fail(res, 404, 'NOT_FOUND', 'The specified resource was not found');
+      res.status(202).end();
+    } catch (error: any) {
+      fail(res, 500, 'INTERNAL_SERVER_ERROR', error.message);
+    }
+  });
+
+  return router;
+}
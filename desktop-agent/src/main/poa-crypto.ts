@@ -1,5 +1,7 @@
 import * as crypto from 'crypto';
+import { blake3 } from '@noble/hashes/blake3';
 import axios from 'axios';
+import type { Readable } from 'stream';
 
 /**
  * Shared PoA proof computation module.
@@ -34,6 +36,15 @@ export function createSaltWithEntropy(hiveBlockHash: string): string {
   return crypto.createHash('sha256').update(combined).digest('hex');
 }
 
+/**
+ * Verifiable salt: sha256(`${hiveBlockId}:${nonce}`). Unlike
+ * createSaltWithEntropy(), a challenged node can recompute it and check that
+ * the block is recent, so a relay can't feed it a salt minted in advance.
+ */
+export function deriveBlockSalt(hiveBlockId: string, nonce: string): string {
+  return hashString(`${hiveBlockId}:${nonce}`);
+}
+
 /**
  * Validate CID format (CIDv0: Qm... or CIDv1: baf...).
  * Prevents injection attacks when CIDs are passed to IPFS API URLs.
@@ -44,6 +55,82 @@ export function isValidCid(cid: string): boolean {
   return typeof cid === 'string' && CID_REGEX.test(cid);
 }
 
+const BASE58_ALPHABET = '123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz';
+const BASE32_ALPHABET = 'abcdefghijklmnopqrstuvwxyz234567';
+
+function decodeBase58(str: string): Buffer {
+  let value = BigInt(0);
+  for (const ch of str) {
+    const digit = BASE58_ALPHABET.indexOf(ch);
+    if (digit < 0) throw new Error(`Invalid base58 character: ${ch}`);
+    value = value * BigInt(58) + BigInt(digit);
+  }
+  const hex = value.toString(16);
+  const bytes = Buffer.from(hex.length % 2 ? '0' + hex : hex, 'hex');
+  const leadingZeros = str.length - str.replace(/^1+/, '').length;
+  return Buffer.concat([Buffer.alloc(leadingZeros), bytes]);
+}
+
+function decodeBase32(str: string): Buffer {
+  const out: number[] = [];
+  let bits = 0;
+  let value = 0;
+  for (const ch of str) {
+    const digit = BASE32_ALPHABET.indexOf(ch);
+    if (digit < 0) throw new Error(`Invalid base32 character: ${ch}`);
+    value = (value << 5) | digit;
+    bits += 5;
+    if (bits >= 8) {
+      out.push((value >>> (bits - 8)) & 0xff);
+      bits -= 8;
+    }
+  }
+  return Buffer.from(out);
+}
+
+/** Unsigned LEB128 varint at `offset` → [value, offset after it]. */
+export function readVarint(buf: Buffer, offset: number): [number, number] {
+  let result = 0;
+  let shift = 0;
+  let pos = offset;
+  while (pos < buf.length) {
+    const byte = buf[pos++];
+    result += (byte & 0x7f) * 2 ** shift;
+    if ((byte & 0x80) === 0) return [result, pos];
+    shift += 7;
+  }
+  throw new Error('Truncated varint');
+}
+
+/**
+ * The sha2-256 digest a CID commits to, or null if the CID uses another hash
+ * function. Lets a block's bytes be checked against its CID without trusting
+ * the datastore (Kubo does not re-hash on read by default).
+ */
+export function cidSha256Digest(cid: string): Buffer | null {
+  if (!isValidCid(cid)) return null;
+  let multihash: Buffer;
+  if (cid.startsWith('Qm')) {
+    multihash = decodeBase58(cid);           // CIDv0 is a bare base58btc multihash
+  } else {
+    const bytes = decodeBase32(cid.slice(1)); // CIDv1, multibase 'b' (base32 lower)
+    const [, afterVersion] = readVarint(bytes, 0);
+    const [, afterCodec] = readVarint(bytes, afterVersion);
+    multihash = bytes.subarray(afterCodec);
+  }
+  const [hashCode, afterCode] = readVarint(multihash, 0);
+  const [length, afterLength] = readVarint(multihash, afterCode);
+  if (hashCode !== 0x12 || length !== 32) return null;
+  return multihash.subarray(afterLength, afterLength + 32);
+}
+
+/** Whether `data` hashes to the digest in `cid`. Null when the CID isn't sha2-256 (can't check). */
+export function blockMatchesCid(cid: string, data: Buffer): boolean | null {
+  const digest = cidSha256Digest(cid);
+  if (!digest) return null;
+  return crypto.createHash('sha256').update(data).digest().equals(digest);
+}
+
 /**
  * FNV-1a hash — must match getIntFromHash() in server/services/poa-crypto.ts exactly.
  */
@@ -59,6 +146,54 @@ export function getIntFromHash(hash: string, length: number): number {
   return h % length;
 }
 
+const RETRY_DELAY_MS = 250; // grows linearly per attempt
+
+/**
+ * Transient = worth retrying: timeouts, dropped connections to the daemon, or
+ * datastore lock contention. A block that is genuinely missing is permanent —
+ * retrying would only hide data loss.
+ */
+export function isTransientBlockError(err: any): boolean {
+  const code = err?.code;
+  if (code === 'ECONNABORTED' || code === 'ETIMEDOUT' || code === 'ECONNRESET' || code === 'ECONNREFUSED') {
+    return true;
+  }
+  const message = String(err?.response?.data?.Message || err?.message || '').toLowerCase();
+  if (message.includes('not found') || message.includes('could not find')) return false;
+  return message.includes('timeout') || message.includes('deadline exceeded') || message.includes('lock');
+}
+
+function sleepUnlessAborted(ms: number, signal?: AbortSignal): Promise<void> {
+  return new Promise((resolve, reject) => {
+    const timer = setTimeout(resolve, ms);
+    signal?.addEventListener('abort', () => {
+      clearTimeout(timer);
+      reject(new Error('CANCELLED'));
+    }, { once: true });
+  });
+}
+
+/**
+ * Run `fn`, retrying transient errors up to `retries` times with a short,
+ * linearly growing delay. Permanent errors and aborts are rethrown immediately.
+ */
+export async function retryTransient<T>(
+  fn: () => Promise<T>,
+  retries: number,
+  signal?: AbortSignal,
+  onRetry?: () => void
+): Promise<T> {
+  for (let attempt = 0; ; attempt++) {
+    try {
+      return await fn();
+    } catch (err) {
+      if (attempt >= retries || signal?.aborted || !isTransientBlockError(err)) throw err;
+      onRetry?.();
+      await sleepUnlessAborted(RETRY_DELAY_MS * (attempt + 1), signal);
+    }
+  }
+}
+
 /**
  * Fetch block CIDs for a file using IPFS refs command.
  * Returns empty array if the file has no sub-blocks (small file).
@@ -95,9 +230,13 @@ export async function computeProofHash(
   kuboApiUrl: string,
   salt: string,
   cid: string,
-  blockCids: string[]
+  blockCids: string[],
+  memoryCeilingBytes: number = 0
 ): Promise<string> {
   const length = blockCids.length;
+  if (memoryCeilingBytes > 0) {
+    return computeProofHashStreaming(kuboApiUrl, salt, cid, blockCids, memoryCeilingBytes);
+  }
 
   if (length === 0) {
     // Small file: SHA256(fileData + salt)
@@ -106,24 +245,11 @@ export async function computeProofHash(
       null,
       { timeout: 15000, responseType: 'arraybuffer' }
     );
-    const fileBuffer = Buffer.from(fileResponse.data);
-    const combined = Buffer.concat([fileBuffer, Buffer.from(salt)]);
-    return hashFile(combined);
+    return proofHashFromFile(salt, Buffer.from(fileResponse.data));
   }
 
   // Multi-block file: deterministic block selection + parallel fetch
-  const blocksToFetch: number[] = [];
-  let seed = getIntFromHash(salt, length);
-  let tempProofHash = '';
-
-  const maxBlocks = Math.min(5, length);
-  for (let i = 0; i < maxBlocks && seed < length; i++) {
-    blocksToFetch.push(seed);
-    const simulatedHash = hashString(`block_${seed}_${salt}`);
-    tempProofHash += simulatedHash;
-    const increment = getIntFromHash(salt + tempProofHash, length);
-    seed = seed + (increment === 0 ? 1 : increment); // Prevent infinite loop when hash returns 0
-  }
+  const blocksToFetch = selectProofBlockIndices(salt, length);
 
   // Parallel block fetching
   const blockPromises = blocksToFetch.map(async (blockIndex) => {
@@ -139,18 +265,210 @@ export async function computeProofHash(
 
   // Sort by index for deterministic order
   fetchedBlocks.sort((a, b) => a.index - b.index);
+  return proofHashFromBlocks(salt, fetchedBlocks.map((b) => b.buffer));
+}
 
-  // Compute per-block hashes
-  const proofHashes: string[] = [];
-  for (const block of fetchedBlocks) {
-    const combined = Buffer.concat([block.buffer, Buffer.from(salt)]);
-    proofHashes.push(hashFile(combined));
+const STREAM_CHUNK_BYTES = 256 * 1024;
+
+/** SHA256(stream bytes + suffix), fed chunk by chunk so the data is never held whole. */
+export async function hashStream(stream: Readable, suffix: string): Promise<string> {
+  const hash = crypto.createHash('sha256');
+  for await (const chunk of stream) hash.update(chunk);
+  hash.update(suffix);
+  return hash.digest('hex');
+}
+
+/**
+ * computeProofHash() for low-memory nodes: each read is streamed into its
+ * hasher instead of buffered, and at most memoryCeilingBytes / STREAM_CHUNK_BYTES
+ * reads are in flight, so a challenge over multi-megabyte blocks stays within
+ * roughly the ceiling. Produces the same proof.
+ */
+async function computeProofHashStreaming(
+  kuboApiUrl: string,
+  salt: string,
+  cid: string,
+  blockCids: string[],
+  memoryCeilingBytes: number
+): Promise<string> {
+  const read = async (endpoint: string, arg: string, timeout: number): Promise<string> => {
+    const response = await axios.post(`${kuboApiUrl}/api/v0/${endpoint}?arg=${arg}`, null, { timeout, responseType: 'stream' });
+    return hashStream(response.data, salt);
+  };
+  if (blockCids.length === 0) {
+    return read('cat', cid, 15000);
   }
 
-  // Final hash = SHA256(allBlockHashesConcatenated)
+  const indices = selectProofBlockIndices(salt, blockCids.length);
+  const hashes: string[] = new Array(indices.length);
+  const concurrency = Math.max(1, Math.floor(memoryCeilingBytes / STREAM_CHUNK_BYTES));
+  let next = 0;
+  const worker = async () => {
+    while (next < indices.length) {
+      const n = next++;
+      hashes[n] = await read('block/get', blockCids[indices[n]], 10000);
+    }
+  };
+  await Promise.all(Array.from({ length: Math.min(concurrency, indices.length) }, worker));
+  return hashString(hashes.join(''));
+}
+
+/**
+ * Deterministic block selection for a multi-block proof: up to 5 indices
+ * derived from the salt. Pure — shared by computeProofHash and receipt verification.
+ */
+export function selectProofBlockIndices(salt: string, length: number): number[] {
+  const indices: number[] = [];
+  let seed = getIntFromHash(salt, length);
+  let tempProofHash = '';
+
+  const maxBlocks = Math.min(5, length);
+  for (let i = 0; i < maxBlocks && seed < length; i++) {
+    indices.push(seed);
+    const simulatedHash = hashString(`block_${seed}_${salt}`);
+    tempProofHash += simulatedHash;
+    const increment = getIntFromHash(salt + tempProofHash, length);
+    seed = seed + (increment === 0 ? 1 : increment); // Prevent infinite loop when hash returns 0
+  }
+  return indices;
+}
+
+/** Blocks computeProofHash hashes for a CID with `length` child blocks (a small file counts as one). */
+export function proofBlockCount(salt: string, length: number): number {
+  return length === 0 ? 1 : selectProofBlockIndices(salt, length).length;
+}
+
+/** HTTP challenge proof: SHA256(salt || block[i0] || block[i1] ...) in request order. */
+export function httpProofFromBlocks(salt: string, blocks: Buffer[]): string {
+  const hash = crypto.createHash('sha256');
+  hash.update(salt);
+  for (const block of blocks) hash.update(block);
+  return hash.digest('hex');
+}
+
+/** Incremental digest as both Node's crypto and @noble/hashes expose it. */
+interface ProofHasher {
+  update(data: Uint8Array): unknown;
+  digest(): Uint8Array;
+}
+
+/** Proof algorithms a challenge may request via hashAlgos. blake3 comes from @noble/hashes. */
+export const PROOF_HASH_ALGOS: Record<string, () => ProofHasher> = {
+  'sha256': () => crypto.createHash('sha256'),
+  'sha3-256': () => crypto.createHash('sha3-256'),
+  'blake2b-512': () => crypto.createHash('blake2b512'),
+  'blake2s-256': () => crypto.createHash('blake2s256'),
+  'blake3': () => blake3.create({}),
+};
+
+/** True for a name in PROOF_HASH_ALGOS — own keys only, so 'constructor' or '__proto__' don't pass. */
+export function isProofHashAlgo(algo: unknown): algo is string {
+  return typeof algo === 'string' && Object.hasOwn(PROOF_HASH_ALGOS, algo);
+}
+
+/**
+ * HTTP proofs for several algorithms in one pass over the blocks — each block
+ * is fed to every hasher as it is visited. The 'sha256' entry equals
+ * httpProofFromBlocks().
+ */
+export function httpProofsFromBlocks(salt: string, blocks: Buffer[], algos: string[]): Record<string, string> {
+  const hashers = algos.map((algo) => {
+    if (!isProofHashAlgo(algo)) throw new Error(`Unsupported proof hash algorithm: ${algo}`);
+    const hash = PROOF_HASH_ALGOS[algo]();
+    hash.update(Buffer.from(salt));
+    return hash;
+  });
+  for (const block of blocks) {
+    for (const hash of hashers) hash.update(block);
+  }
+  const proofs: Record<string, string> = {};
+  algos.forEach((algo, i) => { proofs[algo] = Buffer.from(hashers[i].digest()).toString('hex'); });
+  return proofs;
+}
+
+/**
+ * Keyed variant of the HTTP proof for private-storage pools:
+ * HMAC-SHA256(secret, salt || block[i0] || block[i1] ...). Still computed over
+ * the stored (possibly encrypted) bytes, but can't be precomputed by someone
+ * who only knows the public CID.
+ */
+export function httpProofHmac(secret: string, salt: string, blocks: Buffer[]): string {
+  const hmac = crypto.createHmac('sha256', secret);
+  hmac.update(salt);
+  for (const block of blocks) hmac.update(block);
+  return hmac.digest('hex');
+}
+
+/** Small-file proof: SHA256(fileData + salt). */
+export function proofHashFromFile(salt: string, fileData: Buffer): string {
+  return hashFile(Buffer.concat([fileData, Buffer.from(salt)]));
+}
+
+/**
+ * Multi-block proof from the selected blocks in ascending index order:
+ * SHA256(concat(SHA256(block + salt) for each block)).
+ */
+export function proofHashFromBlocks(salt: string, blocks: Buffer[]): string {
+  const proofHashes = blocks.map((block) => hashFile(Buffer.concat([block, Buffer.from(salt)])));
   return hashString(proofHashes.join(''));
 }
 
+/**
+ * Proof-of-replication sealing. Matches server/services/poa-crypto.ts.
+ *
+ * A sealed block is the block XOR an scrypt keystream keyed by the node's
+ * replica key and the block CID. The replica key is an HMAC of the Hive
+ * account under a random per-node secret (seal-store.ts), so nobody else can
+ * produce this node's replica: ten accounts behind one disk need ten
+ * differently sealed copies, and re-sealing on demand costs one scrypt run
+ * (~2^SEAL_COST_LOG2 × 1KiB of memory-hard work) per challenged block — far
+ * slower than reading a stored replica. The key is only handed to the
+ * coordinator at registration; what is published is its commitment.
+ */
+export const SEAL_COST_LOG2 = 17;
+const SEAL_SCRYPT_R = 8;
+
+export function sealReplicaKey(secret: Buffer, account: string): Buffer {
+  return crypto.createHmac('sha256', secret).update(`hivepoa-seal-v1:${account}`).digest();
+}
+
+/** Public commitment to a replica key — the coordinator pins it at first registration. */
+export function sealKeyCommitment(replicaKey: Buffer): string {
+  return hashString(`hivepoa-seal-commit-v1:${replicaKey.toString('hex')}`);
+}
+
+/** Async so the scrypt run (around a second per block) happens on the libuv pool, not the event loop. */
+export async function sealBlock(replicaKey: Buffer, blockCid: string, data: Buffer): Promise<Buffer> {
+  const N = 2 ** SEAL_COST_LOG2;
+  const keystream = await new Promise<Buffer>((resolve, reject) => {
+    crypto.scrypt(replicaKey, `seal:${blockCid}`, Math.max(1, data.length), {
+      N, r: SEAL_SCRYPT_R, p: 1, maxmem: 256 * N * SEAL_SCRYPT_R,
+    }, (err, key) => (err ? reject(err) : resolve(key)));
+  });
+  const sealed = Buffer.allocUnsafe(data.length);
+  for (let i = 0; i < data.length; i++) sealed[i] = data[i] ^ keystream[i];
+  return sealed;
+}
+
+/**
+ * computeProofHash() over the sealed representation: the same block selection,
+ * but every selected block (or the whole file, for a small one) is read sealed
+ * via `readSealed`, keyed by block CID (the file CID for a small file).
+ */
+export async function computeSealedProofHash(
+  salt: string,
+  cid: string,
+  blockCids: string[],
+  readSealed: (blockCid: string) => Promise<Buffer>
+): Promise<string> {
+  if (blockCids.length === 0) {
+    return proofHashFromFile(salt, await readSealed(cid));
+  }
+  const indices = selectProofBlockIndices(salt, blockCids.length);
+  const blocks = await Promise.all(indices.map((i) => readSealed(blockCids[i])));
+  return proofHashFromBlocks(salt, blocks);
+}
+
 /**
  * Compute a commitment hash for the two-phase PoA protocol.
  * Phase 1 (commitment): node proves it has the block list locally by returning
@@ -164,13 +482,13 @@ export async function computeBlockListHash(
   cid: string
 ): Promise<{ blockCount: number; blockListHash: string; blockCids: string[] }> {
   const blockCids = await getBlockCids(kuboApiUrl, cid);
-  const blockCount = blockCids.length;
+  return { blockCount: blockCids.length, blockListHash: blockListHashFromCids(cid, blockCids), blockCids };
+}
 
-  // Hash the sorted block CID list — deterministic regardless of refs order
+/** Hash the sorted block CID list — deterministic regardless of refs order. */
+export function blockListHashFromCids(cid: string, blockCids: string[]): string {
   const sorted = [...blockCids].sort();
-  const blockListHash = hashString(sorted.join(':') + ':' + cid);
-
-  return { blockCount, blockListHash, blockCids };
+  return hashString(sorted.join(':') + ':' + cid);
 }
 
 /**
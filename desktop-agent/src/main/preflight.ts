@@ -0,0 +1,192 @@
+/**
+ * preflight.ts — Read-only machine suitability checks
+ *
+ * Answers "can this machine run a node?" before anything is created:
+ *   - IPFS binary present and recent enough
+ *   - Data directories writable
+ *   - Required ports free (or held by this agent's own daemon)
+ *   - Enough disk for the configured storage quota
+ *   - Outbound connectivity to Hive
+ *
+ * Nothing here initializes a repo, writes config, or touches autostart.
+ */
+
+import * as fs from 'fs';
+import * as net from 'net';
+import * as os from 'os';
+import * as path from 'path';
+import axios from 'axios';
+import type { KuboManager } from './kubo';
+import type { AgentConfig } from './config';
+
+export interface PreflightCheck {
+  name: string;
+  ok: boolean;
+  detail: string;
+  remediation?: string;
+}
+
+export interface PreflightResult {
+  ok: boolean;
+  checks: PreflightCheck[];
+}
+
+const MIN_KUBO_VERSION = '0.18.0'; // first release with the pubsub/RPC surface we rely on
+const KUBO_PORTS = [4001, 5001, 8080]; // swarm, RPC API, gateway
+
+function compareVersions(a: string, b: string): number {
+  const pa = a.split('.').map((n) => parseInt(n, 10) || 0);
+  const pb = b.split('.').map((n) => parseInt(n, 10) || 0);
+  for (let i = 0; i < Math.max(pa.length, pb.length); i++) {
+    const diff = (pa[i] || 0) - (pb[i] || 0);
+    if (diff !== 0) return diff;
+  }
+  return 0;
+}
+
+/** Nearest existing ancestor of a path — we check it without creating anything. */
+function existingAncestor(p: string): string {
+  let current = path.resolve(p);
+  while (!fs.existsSync(current)) {
+    const parent = path.dirname(current);
+    if (parent === current) break;
+    current = parent;
+  }
+  return current;
+}
+
+function isPortFree(port: number): Promise<boolean> {
+  return new Promise((resolve) => {
+    const server = net.createServer();
+    server.once('error', () => resolve(false));
+    server.once('listening', () => server.close(() => resolve(true)));
+    server.listen(port, '127.0.0.1');
+  });
+}
+
+async function checkBinary(kubo: KuboManager): Promise<PreflightCheck> {
+  const { mode, apiUrl, binaryAvailable } = kubo.getMode();
+  if (mode === 'external' && !binaryAvailable) {
+    const peerId = await kubo.getPeerId();
+    return peerId
+      ? { name: 'ipfs-binary', ok: true, detail: `No local binary; using external daemon at ${apiUrl} (${peerId})` }
+      : {
+          name: 'ipfs-binary',
+          ok: false,
+          detail: `No local binary and external daemon at ${apiUrl} is not reachable`,
+          remediation: 'Start the external Kubo daemon or fix externalDaemonApi',
+        };
+  }
+
+  const version = await kubo.getBinaryVersion();
+  if (!version) {
+    return {
+      name: 'ipfs-binary',
+      ok: false,
+      detail: `IPFS binary at ${kubo.getBinaryPath()} did not report a version`,
+      remediation: 'Reinstall the agent or run: npm run download-kubo',
+    };
+  }
+  if (compareVersions(version, MIN_KUBO_VERSION) < 0) {
+    return {
+      name: 'ipfs-binary',
+      ok: false,
+      detail: `Kubo ${version} is older than the required ${MIN_KUBO_VERSION}`,
+      remediation: 'Update Kubo (npm run download-kubo) or upgrade your external daemon',
+    };
+  }
+  return { name: 'ipfs-binary', ok: true, detail: `Kubo ${version} at ${kubo.getBinaryPath()}` };
+}
+
+function checkWritable(name: string, dir: string): PreflightCheck {
+  const target = existingAncestor(dir);
+  try {
+    fs.accessSync(target, fs.constants.W_OK);
+    return { name, ok: true, detail: `${dir} is writable (checked ${target})` };
+  } catch {
+    return {
+      name,
+      ok: false,
+      detail: `${target} is not writable`,
+      remediation: `Fix permissions on ${target} or choose a different location`,
+    };
+  }
+}
+
+async function checkPorts(kubo: KuboManager, apiPort: number): Promise<PreflightCheck> {
+  const busy: number[] = [];
+  for (const port of KUBO_PORTS) {
+    if (!(await isPortFree(port))) busy.push(port);
+  }
+
+  // Our own daemon holding its ports is expected, not a conflict
+  if (busy.length > 0 && kubo.isRunning()) {
+    return { name: 'ports', ok: true, detail: `Ports ${busy.join(', ')} held by the running IPFS daemon; API on ${apiPort}` };
+  }
+  if (busy.length > 0) {
+    return {
+      name: 'ports',
+      ok: false,
+      detail: `Ports in use by another process: ${busy.join(', ')}`,
+      remediation: 'Stop the conflicting service, or point the agent at it as an external daemon',
+    };
+  }
+  return { name: 'ports', ok: true, detail: `Ports ${KUBO_PORTS.join(', ')} are free; API on ${apiPort}` };
+}
+
+async function checkDisk(kubo: KuboManager, storageMaxGB: number): Promise<PreflightCheck> {
+  const quotaGB = storageMaxGB === 0 ? 100 : storageMaxGB;
+  const quotaBytes = quotaGB * 1024 * 1024 * 1024;
+  const target = existingAncestor(kubo.getRepoPath());
+
+  try {
+    const stat = fs.statfsSync(target);
+    const freeBytes = stat.bavail * stat.bsize;
+    // Space the repo already occupies counts toward the quota
+    const usedBytes = kubo.isRunning() ? (await kubo.getStats())?.repoSize || 0 : 0;
+    const neededBytes = Math.max(0, quotaBytes - usedBytes);
+    const freeGB = (freeBytes / (1024 * 1024 * 1024)).toFixed(1);
+
+    if (freeBytes < neededBytes) {
+      return {
+        name: 'disk-space',
+        ok: false,
+        detail: `${freeGB} GB free on ${target}, quota needs ${quotaGB} GB`,
+        remediation: 'Lower storageMaxGB or move the IPFS repo to a larger volume',
+      };
+    }
+    return { name: 'disk-space', ok: true, detail: `${freeGB} GB free on ${target} for a ${quotaGB} GB quota` };
+  } catch (err: any) {
+    return { name: 'disk-space', ok: false, detail: `Could not read free space: ${err.message}` };
+  }
+}
+
+async function checkConnectivity(): Promise<PreflightCheck> {
+  try {
+    await axios.post('https://api.hive.blog', {
+      jsonrpc: '2.0', method: 'condenser_api.get_dynamic_global_properties', params: [], id: 1,
+    }, { timeout: 5000 });
+    return { name: 'connectivity', ok: true, detail: 'Reached api.hive.blog' };
+  } catch (err: any) {
+    return {
+      name: 'connectivity',
+      ok: false,
+      detail: `Could not reach api.hive.blog: ${err.message}`,
+      remediation: 'Check your internet connection, proxy, or firewall rules for outbound HTTPS',
+    };
+  }
+}
+
+/** Run every check. Never mutates state. */
+export async function runPreflight(kubo: KuboManager, config: AgentConfig): Promise<PreflightResult> {
+  const checks = await Promise.all([
+    checkBinary(kubo),
+    Promise.resolve(checkWritable('data-dir', path.join(os.homedir(), '.spk-ipfs'))),
+    Promise.resolve(checkWritable('repo-dir', kubo.getRepoPath())),
+    checkPorts(kubo, config.apiPort),
+    checkDisk(kubo, config.storageMaxGB),
+    checkConnectivity(),
+  ]);
+
+  return { ok: checks.every((c) => c.ok), checks };
+}
@@ -5,6 +5,13 @@ contextBridge.exposeInMainWorld('spkAgent', {
   getConfig: () => ipcRenderer.invoke('get-config'),
   setConfig: (cfg: Record<string, unknown>) => ipcRenderer.invoke('set-config', cfg),
   getEarnings: () => ipcRenderer.invoke('get-earnings'),
+  // HTTP API operations over IPC — each resolves to { status, body }
+  apiStatus: () => ipcRenderer.invoke('api-status'),
+  apiListPins: (query?: Record<string, unknown>) => ipcRenderer.invoke('api-list-pins', query),
+  apiPin: (cid: string, name?: string) => ipcRenderer.invoke('api-pin', cid, name),
+  apiUnpin: (cid: string) => ipcRenderer.invoke('api-unpin', cid),
+  apiApplyConfig: (updates: Record<string, unknown>) => ipcRenderer.invoke('api-apply-config', updates),
+  apiEarningsHistory: (fromMs?: number, toMs?: number) => ipcRenderer.invoke('api-earnings-history', fromMs, toMs),
   // P2P
   getPeers: () => ipcRenderer.invoke('get-peers'),
   getValidationStats: () => ipcRenderer.invoke('get-validation-stats'),
@@ -0,0 +1,134 @@
+/**
+ * price-feed.ts — HBD/HIVE exchange rates for fiat earnings display
+ *
+ * With priceFeedEnabled set, polls CoinGecko every PRICE_POLL_INTERVAL_MS for
+ * HBD and HIVE in USD and in displayCurrency. If CoinGecko is unreachable it
+ * falls back to the chain itself: the witness median price feed gives HIVE in
+ * HBD, and HBD is taken at its $1 peg — USD only, so a non-USD display
+ * currency then has no fiat value until CoinGecko answers again.
+ *
+ * The last good rate is cached and served with its age; GET /api/earnings and
+ * /api/earnings/history use it to add fiat values (at the current rate, not
+ * the rate on the day a challenge was credited).
+ */
+
+import axios from 'axios';
+import type { ConfigStore } from './config';
+import { AgentHiveClient } from './hive';
+
+export interface PriceRates {
+  currency: string;            // displayCurrency, lowercase ISO code
+  hbd: number | null;          // 1 HBD in `currency`
+  hive: number | null;         // 1 HIVE in `currency`
+  hbdUsd: number;
+  hiveUsd: number;
+  source: 'coingecko' | 'hive-feed';
+  updatedAt: string;
+}
+
+const COINGECKO_URL = 'https://api.coingecko.com/api/v3/simple/price';
+const PRICE_POLL_INTERVAL_MS = 10 * 60000;
+const PRICE_REQUEST_TIMEOUT_MS = 10000;
+// Older than this and the rate is reported as stale
+const PRICE_STALE_MS = 3600000;
+
+/** `amount` converted at `rate`, to 6 decimals (a single challenge is ~0.001 HBD). */
+export function toFiat(amount: number, rate: number): number {
+  return Math.round(amount * rate * 1e6) / 1e6;
+}
+
+export class PriceFeed {
+  private config: ConfigStore;
+  private rates: PriceRates | null = null;
+  private timer: NodeJS.Timeout | null = null;
+  private lastError: string | null = null;
+
+  constructor(config: ConfigStore) {
+    this.config = config;
+  }
+
+  start(): void {
+    if (!this.config.getConfig().priceFeedEnabled || this.timer) return;
+    this.refresh().catch(() => {});
+    this.timer = setInterval(() => this.refresh().catch(() => {}), PRICE_POLL_INTERVAL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  /** Latest rates, or null before the first successful poll (or when disabled). */
+  getRates(): (PriceRates & { stale: boolean }) | null {
+    if (!this.rates || !this.config.getConfig().priceFeedEnabled) return null;
+    return { ...this.rates, stale: Date.now() - Date.parse(this.rates.updatedAt) > PRICE_STALE_MS };
+  }
+
+  getStatus(): Record<string, any> {
+    return {
+      enabled: this.config.getConfig().priceFeedEnabled,
+      rates: this.getRates(),
+      lastError: this.lastError,
+    };
+  }
+
+  /** Poll now. Keeps the previous rates if every source fails. */
+  async refresh(): Promise<PriceRates | null> {
+    const { displayCurrency, hiveApiNodes, hiveUsername } = this.config.getConfig();
+    const currency = displayCurrency.toLowerCase();
+    try {
+      this.rates = await this.fromCoinGecko(currency);
+    } catch (coingeckoError: any) {
+      try {
+        this.rates = await this.fromHiveFeed(currency, hiveApiNodes, hiveUsername || '');
+        console.warn(`[Price] CoinGecko unavailable (${coingeckoError.message}) — using the Hive price feed`);
+      } catch (feedError: any) {
+        this.lastError = `CoinGecko: ${coingeckoError.message}; Hive feed: ${feedError.message}`;
+        console.warn(`[Price] No price source answered — ${this.lastError}`);
+        return null;
+      }
+    }
+    this.lastError = null;
+    return this.rates;
+  }
+
+  private async fromCoinGecko(currency: string): Promise<PriceRates> {
+    const vs = Array.from(new Set(['usd', currency])).join(',');
+    const { data } = await axios.get(COINGECKO_URL, {
+      params: { ids: 'hive_dollar,hive', vs_currencies: vs },
+      timeout: PRICE_REQUEST_TIMEOUT_MS,
+    });
+    const hbdUsd = Number(data?.hive_dollar?.usd);
+    const hiveUsd = Number(data?.hive?.usd);
+    if (!(hbdUsd > 0) || !(hiveUsd > 0)) throw new Error('Unexpected response');
+    const hbd = Number(data.hive_dollar[currency]);
+    const hive = Number(data.hive[currency]);
+    return {
+      currency,
+      hbd: hbd > 0 ? hbd : null,
+      hive: hive > 0 ? hive : null,
+      hbdUsd,
+      hiveUsd,
+      source: 'coingecko',
+      updatedAt: new Date().toISOString(),
+    };
+  }
+
+  private async fromHiveFeed(currency: string, nodes: string[], username: string): Promise<PriceRates> {
+    const hiveInHbd = await new AgentHiveClient({ username, nodes }).getMedianHivePrice();
+    const hbdUsd = 1; // pegged
+    const hiveUsd = hiveInHbd * hbdUsd;
+    const usd = currency === 'usd';
+    return {
+      currency,
+      hbd: usd ? hbdUsd : null,
+      hive: usd ? hiveUsd : null,
+      hbdUsd,
+      hiveUsd,
+      source: 'hive-feed',
+      updatedAt: new Date().toISOString(),
+    };
+  }
+}
@@ -0,0 +1,138 @@
+/**
+ * proof-broadcast.ts — Publish passed proofs on Hive (opt-in: broadcastProofs)
+ *
+ * Every proof_submitted event (HTTP, PubSub or validator WebSocket) is queued
+ * and broadcast as a `spk_poa_proof` custom_json signed with the posting key,
+ * giving a public, timestamped record of the node's storage work.
+ *
+ * Broadcasts cost Resource Credits, so they are paced: at most one every
+ * proofBroadcastIntervalSec, paused while RC is low, and retried with backoff
+ * when a node rejects them. The queue is bounded — under sustained load the
+ * oldest proofs are dropped rather than spending RC the account doesn't have.
+ */
+
+import type { AgentHiveClient } from './hive';
+import type { ConfigStore } from './config';
+import { agentEvents, AgentEvent } from './agent-events';
+
+interface PendingProof {
+  cid: string;
+  salt: string;
+  proof: string;
+  latencyMs: number | null;
+  attempts: number;
+}
+
+export interface ProofBroadcastStats {
+  queued: number;
+  broadcast: number;
+  dropped: number;
+  lastTxId: string | null;
+  lastError: string | null;
+}
+
+const MAX_QUEUE = 100;
+const MAX_ATTEMPTS = 3;
+const RETRY_BASE_MS = 30000;
+const LOW_RC_PAUSE_MS = 10 * 60000;
+
+export class ProofBroadcaster {
+  private hive: AgentHiveClient;
+  private config: ConfigStore;
+  private queue: PendingProof[] = [];
+  private unsubscribe: (() => void) | null = null;
+  private timer: NodeJS.Timeout | null = null;
+  private busy = false;
+  private nextAllowedAt = 0;
+  private broadcast = 0;
+  private dropped = 0;
+  private lastTxId: string | null = null;
+  private lastError: string | null = null;
+
+  constructor(hive: AgentHiveClient, config: ConfigStore) {
+    this.hive = hive;
+    this.config = config;
+  }
+
+  start(): void {
+    if (this.unsubscribe) return;
+    this.unsubscribe = agentEvents.subscribe((event) => this.onEvent(event));
+  }
+
+  stop(): void {
+    this.unsubscribe?.();
+    this.unsubscribe = null;
+    if (this.timer) {
+      clearTimeout(this.timer);
+      this.timer = null;
+    }
+  }
+
+  getStats(): ProofBroadcastStats {
+    return {
+      queued: this.queue.length,
+      broadcast: this.broadcast,
+      dropped: this.dropped,
+      lastTxId: this.lastTxId,
+      lastError: this.lastError,
+    };
+  }
+
+  private onEvent(event: AgentEvent): void {
+    if (event.type !== 'proof_submitted' || !this.config.getConfig().broadcastProofs) return;
+    const { cid, salt, proof, latencyMs } = event.data;
+    if (!cid || !salt || !proof || !this.hive.hasPostingKey()) return;
+
+    this.queue.push({ cid, salt, proof, latencyMs: latencyMs ?? null, attempts: 0 });
+    if (this.queue.length > MAX_QUEUE) {
+      this.queue.shift();
+      this.dropped++;
+    }
+    this.schedule(0);
+  }
+
+  private schedule(delayMs: number): void {
+    if (this.timer || !this.unsubscribe) return;
+    const wait = Math.max(delayMs, this.nextAllowedAt - Date.now(), 0);
+    this.timer = setTimeout(() => {
+      this.timer = null;
+      this.flush().catch(() => {});
+    }, wait);
+  }
+
+  /** Broadcast the oldest queued proof, then reschedule for the rest. */
+  private async flush(): Promise<void> {
+    if (this.busy || this.queue.length === 0) return;
+    this.busy = true;
+    let retryDelay = 0;
+    try {
+      if (!(await this.hive.hasEnoughRC())) {
+        this.lastError = 'Insufficient Resource Credits';
+        retryDelay = LOW_RC_PAUSE_MS;
+        return;
+      }
+
+      const item = this.queue[0];
+      item.attempts++;
+      const txId = await this.hive.broadcastProof(item.cid, item.salt, item.proof, item.latencyMs);
+      this.nextAllowedAt = Date.now() + Math.max(1, this.config.getConfig().proofBroadcastIntervalSec) * 1000;
+      if (txId) {
+        this.queue.shift();
+        this.broadcast++;
+        this.lastTxId = txId;
+        this.lastError = null;
+      } else if (item.attempts >= MAX_ATTEMPTS) {
+        this.queue.shift();
+        this.dropped++;
+        this.lastError = `Gave up on proof for ${item.cid} after ${MAX_ATTEMPTS} attempts`;
+        console.warn(`[ProofBroadcast] ${this.lastError}`);
+      } else {
+        this.lastError = `Broadcast failed (attempt ${item.attempts}/${MAX_ATTEMPTS})`;
+        retryDelay = RETRY_BASE_MS * 2 ** (item.attempts - 1);
+      }
+    } finally {
+      this.busy = false;
+      if (this.queue.length > 0) this.schedule(retryDelay);
+    }
+  }
+}
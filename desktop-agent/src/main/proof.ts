@@ -0,0 +1,162 @@
+/**
+ * proof.ts — Merkle-path challenge proofs (proofType: 'merkle')
+ *
+ * A hash proof, SHA256(salt || blocks...), can only be checked by a validator
+ * holding the same blocks. A Merkle proof ties each challenged leaf to the CID
+ * root instead: the response carries the leaf's CID and SHA-256, plus the raw
+ * dag-pb nodes on the path from the root down to the leaf's parent. The
+ * validator re-hashes every path node against its CID and follows the links,
+ * so it needs only the root CID — not the content — to check that the leaves
+ * really are blocks [i0, i1, ...] of that DAG.
+ *
+ * Challenged indices are the root's direct links (see KuboManager.getBlockRefs),
+ * so today every path is just [root]; verification accepts deeper paths too.
+ * A single-block file is its own only leaf: index 0 with an empty path.
+ */
+
+import * as crypto from 'crypto';
+import { blockMatchesCid, cidSha256Digest, readVarint } from './poa-crypto';
+
+export type ProofType = 'hash' | 'merkle';
+export const PROOF_TYPES: ProofType[] = ['hash', 'merkle'];
+
+export interface MerkleLeafProof {
+  index: number;
+  blockCid: string;
+  leafHash: string;  // sha256 of the leaf bytes, hex
+  path: string[];    // node CIDs from the root to the leaf's parent
+}
+
+export interface MerkleProof {
+  root: string;
+  nodes: Record<string, string>; // path node CID → raw dag-pb bytes, base64
+  leaves: MerkleLeafProof[];
+}
+
+const BASE58_ALPHABET = '123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz';
+const BASE32_ALPHABET = 'abcdefghijklmnopqrstuvwxyz234567';
+
+function encodeBase58(bytes: Buffer): string {
+  let value = BigInt('0x' + (bytes.toString('hex') || '0'));
+  let out = '';
+  while (value > BigInt(0)) {
+    out = BASE58_ALPHABET[Number(value % BigInt(58))] + out;
+    value /= BigInt(58);
+  }
+  for (const byte of bytes) {
+    if (byte !== 0) break;
+    out = '1' + out;
+  }
+  return out;
+}
+
+function encodeBase32(bytes: Buffer): string {
+  let out = '';
+  let bits = 0;
+  let value = 0;
+  for (const byte of bytes) {
+    value = ((value << 8) | byte) & 0xffff; // only the unconsumed low bits matter
+    bits += 8;
+    while (bits >= 5) {
+      out += BASE32_ALPHABET[(value >>> (bits - 5)) & 31];
+      bits -= 5;
+    }
+  }
+  if (bits > 0) out += BASE32_ALPHABET[(value << (5 - bits)) & 31];
+  return out;
+}
+
+/** Binary CID as stored in a dag-pb link → string form (base58btc v0, base32 v1). */
+function cidToString(bytes: Buffer): string {
+  if (bytes[0] === 0x12 && bytes[1] === 0x20) return encodeBase58(bytes);
+  return 'b' + encodeBase32(bytes);
+}
+
+/** Skip one protobuf field of the given wire type, returning the next offset. */
+function skipField(buf: Buffer, pos: number, wireType: number): number {
+  if (wireType === 0) return readVarint(buf, pos)[1];
+  if (wireType === 1) return pos + 8;
+  if (wireType === 2) {
+    const [length, start] = readVarint(buf, pos);
+    return start + length;
+  }
+  if (wireType === 5) return pos + 4;
+  throw new Error(`Unsupported protobuf wire type ${wireType}`);
+}
+
+/** Link CIDs of a dag-pb node (PBNode.Links[].Hash), in link order. */
+export function dagPbLinks(node: Buffer): string[] {
+  const links: string[] = [];
+  let pos = 0;
+  while (pos < node.length) {
+    const [key, afterKey] = readVarint(node, pos);
+    const field = key >> 3;
+    const wireType = key & 7;
+    if (field !== 2 || wireType !== 2) {
+      pos = skipField(node, afterKey, wireType);
+      continue;
+    }
+    const [length, start] = readVarint(node, afterKey);
+    const link = node.subarray(start, start + length);
+    pos = start + length;
+
+    let linkPos = 0;
+    while (linkPos < link.length) {
+      const [linkKey, afterLinkKey] = readVarint(link, linkPos);
+      if (linkKey >> 3 === 1 && (linkKey & 7) === 2) {
+        const [hashLength, hashStart] = readVarint(link, afterLinkKey);
+        links.push(cidToString(link.subarray(hashStart, hashStart + hashLength)));
+        break;
+      }
+      linkPos = skipField(link, afterLinkKey, linkKey & 7);
+    }
+  }
+  return links;
+}
+
+/** Build the proof for leaves read from the root's direct links. */
+export function buildMerkleProof(root: string, rootNode: Buffer, blockCids: string[], indices: number[], leaves: Buffer[]): MerkleProof {
+  return {
+    root,
+    nodes: { [root]: rootNode.toString('base64') },
+    leaves: indices.map((index, n) => ({
+      index,
+      blockCid: blockCids[index],
+      leafHash: crypto.createHash('sha256').update(leaves[n]).digest('hex'),
+      path: blockCids[index] === root ? [] : [root],
+    })),
+  };
+}
+
+/**
+ * Validator side: true when every path node hashes to its CID and links to
+ * the next, each leaf hangs off its last path node (at link `index` for a
+ * one-node path), and each leafHash matches the digest in its CID.
+ */
+export function verifyMerkleProof(proof: MerkleProof): boolean {
+  try {
+    for (const leaf of proof.leaves) {
+      if (leaf.path.length === 0) {
+        // Single-block file: the root is leaf 0 and the digest check below covers it
+        if (leaf.index !== 0 || leaf.blockCid !== proof.root) return false;
+      } else if (leaf.path[0] !== proof.root) {
+        return false;
+      }
+      for (let i = 0; i < leaf.path.length; i++) {
+        const encoded = proof.nodes[leaf.path[i]];
+        if (!encoded) return false;
+        const node = Buffer.from(encoded, 'base64');
+        if (blockMatchesCid(leaf.path[i], node) !== true) return false;
+        const links = dagPbLinks(node);
+        const isParent = i === leaf.path.length - 1;
+        const child = isParent ? leaf.blockCid : leaf.path[i + 1];
+        if (isParent && leaf.path.length === 1 ? links[leaf.index] !== child : !links.includes(child)) return false;
+      }
+      const digest = cidSha256Digest(leaf.blockCid);
+      if (!digest || digest.toString('hex') !== leaf.leafHash) return false;
+    }
+    return proof.leaves.length > 0;
+  } catch {
+    return false;
+  }
+}
@@ -1,5 +1,6 @@
 import axios from 'axios';
 import { IncomingMessage } from 'http';
+import { signReceipt } from './receipt';
 
 export interface PubSubMessage {
   from: string;      // Sender's peer ID
@@ -176,21 +177,10 @@ export class PubSubBridge {
     signer?: (payload: string) => string | null
   ): Promise<boolean> {
     try {
-      const payload = JSON.stringify(data);
-      let publishData: string;
-
-      if (signer) {
-        // Signed envelope format
-        const signature = signer(payload);
-        const envelope: any = { payload, timestamp: Date.now() };
-        if (signature) {
-          envelope.signature = signature;
-          envelope.signerUsername = (data as any).validatorPeer || (data as any).targetPeer || '';
-        }
-        publishData = JSON.stringify(envelope);
-      } else {
-        publishData = payload;
-      }
+      // Signed envelope format — canonical serialization shared with verifyReceipt()
+      const publishData = signer
+        ? JSON.stringify(signReceipt(data, signer))
+        : JSON.stringify(data);
 
       // Kubo expects the data as a URL-encoded form parameter
       await axios.post(
@@ -231,6 +221,11 @@ export class PubSubBridge {
     }
   }
 
+  /** IPFS peer ID this bridge publishes as. */
+  getPeerId(): string {
+    return this.myPeerId;
+  }
+
   /** Check if subscribed to a topic. */
   isSubscribed(topic: string): boolean {
     return this.subscriptions.has(topic);
@@ -0,0 +1,159 @@
+/**
+ * receipt.ts — Canonical serialization and verification of signed PoA receipts
+ *
+ * A receipt is any message this agent signs before publishing (challenge
+ * responses, commitment responses). Nodes sign and coordinators verify using
+ * the same functions in this module, so the two can never drift.
+ *
+ * Canonical bytes (version 1):
+ *   - The message object, minus transport fields whose names start with "__"
+ *     and minus undefined values
+ *   - Serialized as JSON with object keys sorted lexicographically at every
+ *     depth and no whitespace, encoded as UTF-8
+ *   - Signature = Hive posting-key signature over SHA256(canonical bytes)
+ *
+ * The canonical string is also what goes on the wire as the envelope's
+ * `payload`, so a verifier can work from either the raw payload or the
+ * parsed object.
+ */
+
+import { Signature, PublicKey, cryptoUtils } from '@hiveio/dhive';
+import { proofHashFromBlocks, proofHashFromFile } from './poa-crypto';
+
+export const RECEIPT_CANONICAL_VERSION = 1;
+
+/** Wire envelope for a signed message (see PubSubBridge.publish). */
+export interface SignedReceipt {
+  payload: string;
+  signature?: string;
+  signerUsername?: string;
+  timestamp: number;
+}
+
+/**
+ * Data the verifier expects the proof to cover: the salt-selected blocks in
+ * ascending index order (see selectProofBlockIndices), or the whole file for
+ * single-block content.
+ */
+export interface ExpectedProofData {
+  blocks: Buffer[];
+  wholeFile?: boolean;
+}
+
+export interface VerifyResult {
+  valid: boolean;
+  signatureValid: boolean;
+  proofValid: boolean | null; // null = no expected data supplied, proof not checked
+  expectedProofHash: string | null;
+  errors: string[];
+}
+
+function canonicalize(value: any): any {
+  if (Array.isArray(value)) return value.map(canonicalize);
+  if (value && typeof value === 'object' && !Buffer.isBuffer(value)) {
+    const out: Record<string, any> = {};
+    for (const key of Object.keys(value).sort()) {
+      if (key.startsWith('__') || value[key] === undefined) continue;
+      out[key] = canonicalize(value[key]);
+    }
+    return out;
+  }
+  return value;
+}
+
+/** Canonical serialization of a receipt — the exact string that gets signed. */
+export function canonicalReceiptPayload(receipt: object): string {
+  return JSON.stringify(canonicalize(receipt));
+}
+
+/** Build a signed envelope. `signer` returns null when no key is configured (unsigned envelope). */
+export function signReceipt(
+  receipt: object,
+  signer?: (payload: string) => string | null
+): SignedReceipt {
+  const payload = canonicalReceiptPayload(receipt);
+  const envelope: SignedReceipt = { payload, timestamp: Date.now() };
+  const signature = signer ? signer(payload) : null;
+  if (signature) {
+    envelope.signature = signature;
+    // Responses are signed by the challenged node, requests by the validator
+    const { type, validatorPeer, targetPeer } = receipt as any;
+    envelope.signerUsername = (type === 'response' || type === 'commitment-response' ? targetPeer : validatorPeer) || '';
+  }
+  return envelope;
+}
+
+/** True if `signature` over `payload` recovers to one of `publicKeys`. */
+export function verifyReceiptSignature(payload: string, signature: string, publicKeys: string[]): boolean {
+  try {
+    const recovered = Signature.fromString(signature).recover(cryptoUtils.sha256(payload)).toString();
+    return publicKeys.some((key) => {
+      try {
+        return PublicKey.fromString(key).toString() === recovered;
+      } catch {
+        return false;
+      }
+    });
+  } catch {
+    return false;
+  }
+}
+
+/**
+ * Verify a signed receipt: the signature must recover to one of the signer's
+ * posting `publicKeys`, and, if `expected` is given, the receipt's proofHash
+ * must match the proof recomputed from the expected block data.
+ *
+ * Accepts the wire envelope or a parsed receipt carrying `__signature`.
+ * Pure — no network access, so callers fetch keys and blocks themselves.
+ */
+export function verifyReceipt(
+  receipt: SignedReceipt | (Record<string, any> & { __signature?: string }),
+  expected: ExpectedProofData | null,
+  publicKeys: string[]
+): VerifyResult {
+  const errors: string[] = [];
+  let body: Record<string, any>;
+  let signature: string | undefined;
+
+  if (typeof (receipt as SignedReceipt).payload === 'string') {
+    try {
+      body = JSON.parse((receipt as SignedReceipt).payload);
+    } catch {
+      return { valid: false, signatureValid: false, proofValid: null, expectedProofHash: null, errors: ['Payload is not valid JSON'] };
+    }
+    signature = (receipt as SignedReceipt).signature;
+  } else {
+    body = receipt as Record<string, any>;
+    signature = body.__signature;
+  }
+
+  // Always re-canonicalize — never trust the sender's key order
+  const payload = canonicalReceiptPayload(body);
+  const signatureValid = !!signature && verifyReceiptSignature(payload, signature, publicKeys);
+  if (!signature) errors.push('Receipt is unsigned');
+  else if (!signatureValid) errors.push('Signature does not match any provided public key');
+
+  let proofValid: boolean | null = null;
+  let expectedProofHash: string | null = null;
+  if (expected) {
+    if (typeof body.salt !== 'string' || typeof body.proofHash !== 'string') {
+      errors.push('Receipt has no salt/proofHash to check');
+      proofValid = false;
+    } else {
+      expectedProofHash = expected.wholeFile
+        ? proofHashFromFile(body.salt, expected.blocks[0] || Buffer.alloc(0))
+        : proofHashFromBlocks(body.salt, expected.blocks);
+      proofValid = expectedProofHash === body.proofHash;
+      if (!proofValid) errors.push('proofHash does not match expected blocks');
+    }
+  }
+
+  return {
+    valid: signatureValid && proofValid !== false,
+    signatureValid,
+    proofValid,
+    expectedProofHash,
+    errors,
+  };
+}
@@ -0,0 +1,181 @@
+/**
+ * remote-pin-import.ts — Bulk import from a remote pinning service
+ *
+ * Lists pins from any IPFS Pinning Service API endpoint (Pinata, web3.storage,
+ * an IPFS Cluster's pinning-service proxy, ...) and pins them on the local
+ * node through the PinJobQueue, so imports show up in /api/jobs with source
+ * 'import' and get the same progress and stall handling as manual pins. Runs as
+ * a background job so the caller can poll progress instead of holding a request
+ * open for hours.
+ *
+ * Spec: https://ipfs.github.io/pinning-services-api-spec/
+ */
+
+import axios from 'axios';
+import * as crypto from 'crypto';
+import type { KuboManager } from './kubo';
+import type { PinJobQueue } from './pin-jobs';
+import { isValidCid } from './poa-crypto';
+
+export interface RemotePinImportJob {
+  id: string;
+  endpoint: string;
+  status: 'listing' | 'pinning' | 'done' | 'failed' | 'cancelled';
+  listed: number;        // remote pins seen so far
+  alreadyLocal: number;  // skipped — already pinned here
+  queued: number;        // waiting to be pinned locally
+  imported: number;
+  failed: number;
+  errors: { cid: string; error: string }[]; // first MAX_RECORDED_ERRORS only
+  startedAt: string;
+  finishedAt: string | null;
+  error: string | null;  // fatal error (listing failed)
+}
+
+const PAGE_LIMIT = 1000;          // spec maximum
+const MAX_PAGES = 1000;           // safety stop against a service that never stops paginating
+const PIN_CONCURRENCY = 2;       // imports in the pin queue at once, so manual pins aren't starved
+const MAX_RECORDED_ERRORS = 50;
+
+export class RemotePinImporter {
+  private kubo: KuboManager;
+  private pinJobs: PinJobQueue;
+  private jobs: Map<string, RemotePinImportJob> = new Map();
+  private cancelled: Set<string> = new Set();
+
+  constructor(kubo: KuboManager, pinJobs: PinJobQueue) {
+    this.kubo = kubo;
+    this.pinJobs = pinJobs;
+  }
+
+  /** Start an import in the background. Only one import runs at a time. */
+  start(endpoint: string, token: string): RemotePinImportJob {
+    const active = Array.from(this.jobs.values()).find((j) => j.status === 'listing' || j.status === 'pinning');
+    if (active) {
+      throw new Error(`Import ${active.id} is already running`);
+    }
+
+    const job: RemotePinImportJob = {
+      id: crypto.randomBytes(8).toString('hex'),
+      endpoint,
+      status: 'listing',
+      listed: 0,
+      alreadyLocal: 0,
+      queued: 0,
+      imported: 0,
+      failed: 0,
+      errors: [],
+      startedAt: new Date().toISOString(),
+      finishedAt: null,
+      error: null,
+    };
+    this.jobs.set(job.id, job);
+
+    this.run(job, endpoint.replace(/\/+$/, ''), token).catch((err: any) => {
+      job.status = 'failed';
+      job.error = err.message;
+      job.finishedAt = new Date().toISOString();
+      console.error(`[PinImport] Import ${job.id} failed: ${err.message}`);
+    });
+    return job;
+  }
+
+  getJob(id: string): RemotePinImportJob | null {
+    return this.jobs.get(id) || null;
+  }
+
+  listJobs(): RemotePinImportJob[] {
+    return Array.from(this.jobs.values());
+  }
+
+  /** Stop queueing new pins; in-flight pins finish. */
+  cancel(id: string): boolean {
+    const job = this.jobs.get(id);
+    if (!job || (job.status !== 'listing' && job.status !== 'pinning')) return false;
+    this.cancelled.add(id);
+    return true;
+  }
+
+  private async run(job: RemotePinImportJob, endpoint: string, token: string): Promise<void> {
+    const local = new Set(await this.kubo.listPins());
+    const seen = new Set<string>();
+    const queue: string[] = [];
+
+    console.log(`[PinImport] Listing remote pins from ${endpoint}`);
+    for await (const cid of this.listRemotePins(endpoint, token)) {
+      if (this.cancelled.has(job.id)) break;
+      job.listed++;
+      if (seen.has(cid)) continue; // the same CID can be pinned under several names
+      seen.add(cid);
+      if (local.has(cid)) {
+        job.alreadyLocal++;
+        continue;
+      }
+      queue.push(cid);
+      job.queued++;
+    }
+
+    job.status = 'pinning';
+    console.log(`[PinImport] ${job.listed} remote pins, ${job.alreadyLocal} already local, ${job.queued} to import`);
+
+    const worker = async () => {
+      while (queue.length > 0 && !this.cancelled.has(job.id)) {
+        const cid = queue.shift()!;
+        const pin = await this.pinJobs.whenFinished(this.pinJobs.enqueue(cid, null, null, 'import'));
+        if (pin.state === 'pinned') {
+          job.imported++;
+        } else {
+          job.failed++;
+          if (job.errors.length < MAX_RECORDED_ERRORS) job.errors.push({ cid, error: pin.error || 'pin failed' });
+        }
+        job.queued--;
+      }
+    };
+    await Promise.all(Array.from({ length: PIN_CONCURRENCY }, worker));
+
+    job.status = this.cancelled.has(job.id) ? 'cancelled' : 'done';
+    job.finishedAt = new Date().toISOString();
+    this.cancelled.delete(job.id);
+    console.log(`[PinImport] Import ${job.id} ${job.status}: ${job.imported} imported, ${job.failed} failed`);
+  }
+
+  /**
+   * Page through GET /pins?status=pinned newest-first. The spec paginates with
+   * `before` = the `created` timestamp of the oldest result on the previous page,
+   * but `before` is exclusive, so pins sharing that timestamp would fall between
+   * pages. The bound is pushed 1 ms later to make it inclusive and the overlap is
+   * dropped by requestid; a page with nothing new ends the listing.
+   */
+  private async *listRemotePins(endpoint: string, token: string): AsyncGenerator<string> {
+    let before: string | null = null;
+    const seen = new Set<string>();
+
+    for (let page = 0; page < MAX_PAGES; page++) {
+      const params: Record<string, string | number> = { status: 'pinned', limit: PAGE_LIMIT };
+      if (before) params.before = before;
+
+      const response = await axios.get(`${endpoint}/pins`, {
+        params,
+        headers: { Authorization: `Bearer ${token}` },
+        timeout: 30000,
+      });
+      const results: any[] = response.data?.results || [];
+
+      let fresh = 0;
+      for (const result of results) {
+        const requestId = result?.requestid;
+        if (typeof requestId === 'string') {
+          if (seen.has(requestId)) continue;
+          seen.add(requestId);
+        }
+        fresh++;
+        const cid = result?.pin?.cid;
+        if (typeof cid === 'string' && isValidCid(cid)) yield cid;
+      }
+
+      const oldest = Date.parse(results[results.length - 1]?.created);
+      if (results.length < PAGE_LIMIT || fresh === 0 || Number.isNaN(oldest)) return;
+      before = new Date(oldest + 1).toISOString();
+    }
+  }
+}
@@ -0,0 +1,229 @@
+/**
+ * seal-store.ts — Sealed replicas of pinned data for proof-of-replication
+ *
+ * Plain block hashing lets several "nodes" behind one disk claim several
+ * copies. With sealingEnabled set, every pin is also kept in sealed form
+ * (poa-crypto.ts sealBlock: block XOR a memory-hard keystream keyed by this
+ * node's replica key), and a validator challenge carrying `Sealed: true` is
+ * answered from these bytes instead of the Kubo blockstore.
+ *
+ * Layout under ~/.spk-ipfs/sealed:
+ *   secret         — random per-node secret (owner-only); the replica key is
+ *                    HMAC(secret, account) and is only ever sent to the
+ *                    coordinator, which checks it against the commitment
+ *                    pinned at first registration
+ *   replica.json   — account and key commitment the replica was sealed
+ *                    under, and each sealed pin's block list (to drop its
+ *                    blocks on unpin)
+ *   blocks/<cid>   — one sealed block (a small file's whole content is
+ *                    stored under the file CID, matching computeProofHash)
+ *
+ * Sealing runs in the background, one pin at a time: all existing pins on
+ * start, then each new pin as it is added. Changing hiveUsername (or losing
+ * the secret) changes the replica key, so the old replica is discarded and
+ * everything is resealed.
+ */
+
+import axios from 'axios';
+import * as crypto from 'crypto';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import type { KuboManager } from './kubo';
+import type { ConfigStore } from './config';
+import { agentEvents, AgentEvent } from './agent-events';
+import { JsonFileWriter } from './json-writer';
+import { getBlockCids, sealBlock, sealReplicaKey, sealKeyCommitment } from './poa-crypto';
+
+interface ReplicaIndex {
+  account: string | null;
+  commitment: string | null;
+  pins: Record<string, string[]>; // pin CID → sealed block CIDs ([] = small file sealed under its own CID)
+}
+
+const BLOCK_READ_TIMEOUT_MS = 30000;
+
+export class SealStore {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private dir: string;
+  private index: ReplicaIndex = { account: null, commitment: null, pins: {} };
+  private replicaKey: { account: string; key: Buffer } | null = null;
+  private writer: JsonFileWriter;
+  private queue: string[] = [];
+  private sealing: string | null = null;
+  private unsubscribe: (() => void) | null = null;
+  private lastError: { cid: string; error: string; at: string } | null = null;
+
+  constructor(kubo: KuboManager, config: ConfigStore, dir: string = path.join(os.homedir(), '.spk-ipfs', 'sealed')) {
+    this.kubo = kubo;
+    this.config = config;
+    this.dir = dir;
+    this.writer = new JsonFileWriter(path.join(dir, 'replica.json'), () => this.index);
+    try {
+      this.index = JSON.parse(fs.readFileSync(path.join(dir, 'replica.json'), 'utf-8'));
+    } catch {}
+  }
+
+  start(): void {
+    const cfg = this.config.getConfig();
+    if (!cfg.sealingEnabled || this.unsubscribe) return;
+    if (!cfg.hiveUsername) {
+      console.warn('[Seal] sealingEnabled is set but no hiveUsername is configured — not sealing');
+      return;
+    }
+    const commitment = sealKeyCommitment(this.getReplicaKey()!);
+    if (this.index.account !== cfg.hiveUsername || this.index.commitment !== commitment) {
+      this.reset(cfg.hiveUsername, commitment);
+    }
+
+    this.unsubscribe = agentEvents.subscribe((event) => this.onEvent(event));
+    this.kubo.listPins()
+      .then((pins) => pins.filter((cid) => !this.index.pins[cid]).forEach((cid) => this.enqueue(cid)))
+      .catch((err) => console.warn(`[Seal] Could not list pins: ${err.message}`));
+    console.log(`[Seal] Started for ${cfg.hiveUsername} (${Object.keys(this.index.pins).length} pins sealed)`);
+  }
+
+  stop(): void {
+    this.unsubscribe?.();
+    this.unsubscribe = null;
+    this.queue = [];
+    this.writer.flush();
+  }
+
+  /** True once every block a proof of `cid` could select is sealed. */
+  isSealed(cid: string): boolean {
+    return this.index.account === this.config.getConfig().hiveUsername && this.index.pins[cid] !== undefined;
+  }
+
+  /** Replica key for the coordinator's registration — null unless sealing is configured. */
+  getReplicaKey(): Buffer | null {
+    const { sealingEnabled, hiveUsername } = this.config.getConfig();
+    if (!sealingEnabled || !hiveUsername) return null;
+    if (this.replicaKey?.account !== hiveUsername) {
+      this.replicaKey = { account: hiveUsername, key: sealReplicaKey(this.loadOrCreateSecret(), hiveUsername) };
+    }
+    return this.replicaKey.key;
+  }
+
+  /** Public commitment to the replica key (safe to publish), or null. */
+  getCommitment(): string | null {
+    const key = this.getReplicaKey();
+    return key ? sealKeyCommitment(key) : null;
+  }
+
+  /** Sealed bytes of a block (or of a small file, by its CID). Throws if not sealed. */
+  async readSealed(blockCid: string): Promise<Buffer> {
+    try {
+      return await fs.promises.readFile(this.blockPath(blockCid));
+    } catch {
+      throw new Error(`NOT_SEALED: ${blockCid}`);
+    }
+  }
+
+  getStatus(): Record<string, any> {
+    const cfg = this.config.getConfig();
+    return {
+      enabled: cfg.sealingEnabled,
+      account: this.index.account,
+      commitment: this.getCommitment(),
+      sealedPins: Object.keys(this.index.pins).length,
+      sealing: this.sealing,
+      queued: this.queue.length,
+      lastError: this.lastError,
+    };
+  }
+
+  private onEvent(event: AgentEvent): void {
+    const cid = event.data?.cid;
+    if (typeof cid !== 'string') return;
+    if (event.type === 'pin_added') this.enqueue(cid);
+    else if (event.type === 'pin_removed') this.drop(cid);
+  }
+
+  private enqueue(cid: string): void {
+    if (this.index.pins[cid] || this.queue.includes(cid) || this.sealing === cid) return;
+    this.queue.push(cid);
+    if (!this.sealing) this.drain().catch(() => {});
+  }
+
+  private async drain(): Promise<void> {
+    while (this.queue.length > 0 && this.unsubscribe) {
+      const cid = this.queue.shift()!;
+      this.sealing = cid;
+      try {
+        await this.sealPin(cid);
+      } catch (err: any) {
+        this.lastError = { cid, error: err.message, at: new Date().toISOString() };
+        console.warn(`[Seal] Sealing ${cid} failed: ${err.message}`);
+        agentEvents.publish('error', { source: 'seal', cid, message: err.message });
+      } finally {
+        this.sealing = null;
+      }
+    }
+  }
+
+  /** Seal every block of a pin the same way computeProofHash reads it. */
+  private async sealPin(cid: string): Promise<void> {
+    const key = this.getReplicaKey();
+    if (!key) throw new Error('sealing is not configured');
+    const apiUrl = this.kubo.getApiUrl();
+    const blockCids = await getBlockCids(apiUrl, cid);
+    fs.mkdirSync(path.join(this.dir, 'blocks'), { recursive: true });
+
+    const targets = blockCids.length === 0 ? [cid] : blockCids;
+    const endpoint = blockCids.length === 0 ? 'cat' : 'block/get';
+    for (const blockCid of targets) {
+      if (!this.unsubscribe) throw new Error('sealing stopped');
+      if (fs.existsSync(this.blockPath(blockCid))) continue; // shared with another sealed pin
+      const response = await axios.post(
+        `${apiUrl}/api/v0/${endpoint}?arg=${blockCid}&offline=true`,
+        null,
+        { timeout: BLOCK_READ_TIMEOUT_MS, responseType: 'arraybuffer' }
+      );
+      const tmpPath = `${this.blockPath(blockCid)}.tmp`;
+      await fs.promises.writeFile(tmpPath, await sealBlock(key, blockCid, Buffer.from(response.data)));
+      await fs.promises.rename(tmpPath, this.blockPath(blockCid));
+    }
+    this.index.pins[cid] = blockCids;
+    this.writer.schedule();
+    console.log(`[Seal] Sealed ${cid} (${targets.length} blocks)`);
+  }
+
+  /** Forget a pin's replica, deleting blocks no other sealed pin references. */
+  private drop(cid: string): void {
+    const blocks = this.index.pins[cid];
+    this.queue = this.queue.filter((c) => c !== cid);
+    if (!blocks) return;
+    delete this.index.pins[cid];
+    const stillUsed = new Set(Object.entries(this.index.pins).flatMap(([pin, b]) => (b.length === 0 ? [pin] : b)));
+    for (const blockCid of blocks.length === 0 ? [cid] : blocks) {
+      if (!stillUsed.has(blockCid)) fs.promises.unlink(this.blockPath(blockCid)).catch(() => {});
+    }
+    this.writer.schedule();
+  }
+
+  /** New replica key: everything sealed under the old one is useless. */
+  private reset(account: string, commitment: string): void {
+    if (this.index.account) console.log(`[Seal] Replica key changed (${this.index.account} → ${account}) — resealing all pins`);
+    fs.rmSync(path.join(this.dir, 'blocks'), { recursive: true, force: true });
+    this.index = { account, commitment, pins: {} };
+    this.writer.schedule();
+  }
+
+  private loadOrCreateSecret(): Buffer {
+    const secretPath = path.join(this.dir, 'secret');
+    try {
+      const existing = fs.readFileSync(secretPath, 'utf-8').trim();
+      if (/^[0-9a-f]{64}$/.test(existing)) return Buffer.from(existing, 'hex');
+    } catch {}
+    const secret = crypto.randomBytes(32);
+    fs.mkdirSync(this.dir, { recursive: true });
+    fs.writeFileSync(secretPath, secret.toString('hex') + '\n', { mode: 0o600 });
+    return secret;
+  }
+
+  private blockPath(blockCid: string): string {
+    return path.join(this.dir, 'blocks', blockCid);
+  }
+}
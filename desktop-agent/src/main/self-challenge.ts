@@ -0,0 +1,180 @@
+/**
+ * self-challenge.ts — Challenge our own pins before a validator does
+ *
+ * The scrubber walks every block slowly; a self-challenge instead samples the
+ * way a validator does. When selfChallengeEnabled is set, every
+ * selfChallengeIntervalMin minutes it:
+ *   1. Picks PINS_PER_PASS random pins
+ *   2. Lists each pin's blocks and reads BLOCKS_PER_CHALLENGE random ones
+ *      offline, checking them against their CIDs
+ *   3. Hashes them with a fresh salt through the same proof routine as
+ *      POST /api/challenge
+ *
+ * A failure emits 'self-challenge-failed' and repairs the bad blocks the same
+ * way the integrity scrubber does (block-repair.ts). Results are
+ * not recorded as earnings — nobody asked for these proofs.
+ */
+
+import * as crypto from 'crypto';
+import { EventEmitter } from 'events';
+import type { KuboManager } from './kubo';
+import type { ConfigStore } from './config';
+import { blockMatchesCid, httpProofFromBlocks } from './poa-crypto';
+import { bootCooldown } from './boot-cooldown';
+import { repairBlocks } from './block-repair';
+import { agentEvents } from './agent-events';
+
+export interface SelfChallengeResult {
+  cid: string;
+  at: string;
+  passed: boolean;
+  blockIndices: number[];
+  latencyMs: number;
+  proof: string | null;     // proof over the sampled blocks, as a validator would get it
+  badBlocks: { index: number; blockCid: string; reason: 'missing' | 'corrupt' }[];
+  error: string | null;
+  repaired: boolean | null; // null when nothing needed repair
+}
+
+const PINS_PER_PASS = 3;
+const BLOCKS_PER_CHALLENGE = 4;
+const MAX_RESULTS = 100;
+const FIRST_PASS_DELAY_MS = 180000;
+const REPAIR_TIMEOUT_MS = 60000;
+
+export class SelfChallenger extends EventEmitter {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private timer: NodeJS.Timeout | null = null;
+  private firstPass: NodeJS.Timeout | null = null;
+  private running = false;
+  private results: SelfChallengeResult[] = [];
+
+  constructor(kubo: KuboManager, config: ConfigStore) {
+    super();
+    this.kubo = kubo;
+    this.config = config;
+  }
+
+  start(): void {
+    const cfg = this.config.getConfig();
+    if (!cfg.selfChallengeEnabled || this.timer) return;
+    const intervalMs = Math.max(1, cfg.selfChallengeIntervalMin) * 60000;
+    const scheduled = () => {
+      if (!bootCooldown.isActive()) this.runPass().catch(() => {});
+    };
+    this.timer = setInterval(scheduled, intervalMs);
+    this.firstPass = setTimeout(scheduled, FIRST_PASS_DELAY_MS);
+    console.log(`[SelfChallenge] Started (every ${cfg.selfChallengeIntervalMin} min)`);
+  }
+
+  stop(): void {
+    if (this.firstPass) {
+      clearTimeout(this.firstPass);
+      this.firstPass = null;
+    }
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  /** Most recent results, newest first. */
+  getResults(): SelfChallengeResult[] {
+    return [...this.results].reverse();
+  }
+
+  /** Run one pass now. Returns null if a pass is already running or IPFS is down. */
+  async runPass(): Promise<SelfChallengeResult[] | null> {
+    if (this.running || !this.kubo.isRunning()) return null;
+    this.running = true;
+    const passResults: SelfChallengeResult[] = [];
+    try {
+      const pins = await this.kubo.listPins();
+      for (const cid of pickRandom(pins, PINS_PER_PASS)) {
+        if (!this.kubo.isRunning()) break;
+        const result = await this.challenge(cid);
+        passResults.push(result);
+        this.results.push(result);
+        if (this.results.length > MAX_RESULTS) this.results.shift();
+      }
+      const failed = passResults.filter((r) => !r.passed).length;
+      console.log(`[SelfChallenge] Pass complete: ${passResults.length - failed}/${passResults.length} pins passed`);
+    } catch (err: any) {
+      console.warn(`[SelfChallenge] Pass failed: ${err.message}`);
+    } finally {
+      this.running = false;
+    }
+    return passResults;
+  }
+
+  private async challenge(cid: string): Promise<SelfChallengeResult> {
+    const start = Date.now();
+    const result: SelfChallengeResult = {
+      cid,
+      at: new Date().toISOString(),
+      passed: false,
+      blockIndices: [],
+      latencyMs: 0,
+      proof: null,
+      badBlocks: [],
+      error: null,
+      repaired: null,
+    };
+
+    try {
+      const blocks = await this.kubo.getBlockRefs(cid, 30000);
+      if (blocks.length === 0) throw new Error('DAG has no blocks to challenge');
+      result.blockIndices = pickRandom(blocks.map((_, i) => i), BLOCKS_PER_CHALLENGE).sort((a, b) => a - b);
+
+      const data: Buffer[] = [];
+      for (const index of result.blockIndices) {
+        try {
+          const block = await this.kubo.getBlock(blocks[index], 10000, undefined, true);
+          if (blockMatchesCid(blocks[index], block) === false) {
+            result.badBlocks.push({ index, blockCid: blocks[index], reason: 'corrupt' });
+          }
+          data.push(block);
+        } catch {
+          result.badBlocks.push({ index, blockCid: blocks[index], reason: 'missing' });
+        }
+      }
+      if (result.badBlocks.length === 0) {
+        result.proof = httpProofFromBlocks(crypto.randomBytes(32).toString('hex'), data);
+        result.passed = true;
+      } else {
+        result.error = `${result.badBlocks.length} of ${result.blockIndices.length} sampled blocks missing or corrupt`;
+      }
+    } catch (err: any) {
+      result.error = err.message;
+    }
+    result.latencyMs = Date.now() - start;
+
+    if (!result.passed) {
+      console.error(`[SelfChallenge] ${cid} failed: ${result.error}`);
+      if (result.badBlocks.length > 0) result.repaired = await this.repair(result);
+      this.emit('self-challenge-failed', result);
+      agentEvents.publish('error', { source: 'self-challenge', cid, message: result.error, repaired: result.repaired });
+    }
+    return result;
+  }
+
+  /** Replace every bad block; true only if all of them were repaired. */
+  private async repair(result: SelfChallengeResult): Promise<boolean> {
+    const { failed } = await repairBlocks(this.kubo, result.cid, result.badBlocks, REPAIR_TIMEOUT_MS);
+    for (const { blockCid, error } of failed) {
+      console.error(`[SelfChallenge] Repair of ${blockCid} (pin ${result.cid}) failed: ${error}`);
+    }
+    return failed.length === 0;
+  }
+}
+
+/** Up to `count` distinct random elements. */
+function pickRandom<T>(items: T[], count: number): T[] {
+  const pool = [...items];
+  const picked: T[] = [];
+  while (picked.length < count && pool.length > 0) {
+    picked.push(pool.splice(crypto.randomInt(pool.length), 1)[0]);
+  }
+  return picked;
+}
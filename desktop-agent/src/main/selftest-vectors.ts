@@ -0,0 +1,137 @@
+/**
+ * selftest-vectors.ts — Protocol conformance test vectors
+ *
+ * Backs POST /api/selftest/vectors. A coordinator onboarding a node calls it
+ * once to confirm the node computes every challenge mode exactly like the
+ * reference implementation, instead of finding out mid-production.
+ *
+ * The vectors are fixed: eight deterministic raw blocks, one salt, and the
+ * expected output of each mode. Proofs are computed with the same functions
+ * the live challenge paths use. The `ipfs-blocks` mode additionally round-trips
+ * the vector blocks through the local daemon (block/put + block/get).
+ *
+ * Changing any expected value here is a protocol change.
+ */
+
+import type { KuboManager } from './kubo';
+import {
+  httpProofFromBlocks,
+  selectProofBlockIndices,
+  proofHashFromBlocks,
+  proofHashFromFile,
+  blockListHashFromCids,
+  hashString,
+} from './poa-crypto';
+import { canonicalReceiptPayload } from './receipt';
+
+export interface VectorResult {
+  mode: string;
+  passed: boolean;
+  expected: string;
+  actual: string | null;
+  error?: string;
+}
+
+export interface VectorReport {
+  passed: boolean;
+  vectorVersion: number;
+  results: VectorResult[];
+}
+
+export const VECTOR_VERSION = 1;
+
+const VECTOR_SALT = 'hivepoa-selftest-salt-v1';
+
+function vectorBlock(i: number): Buffer {
+  return Buffer.from(`hivepoa-selftest-block-${i}:` + 'x'.repeat(64 + i));
+}
+
+const VECTOR_BLOCKS: Buffer[] = Array.from({ length: 8 }, (_, i) => vectorBlock(i));
+
+// CIDv1 / raw / sha2-256 of VECTOR_BLOCKS
+const VECTOR_CIDS = [
+  'bafkreidyopg65qx6ek6re3s24azwdv3ua2nglhcmlrbmtsog5mlttse65i',
+  'bafkreieezzejyccartlaxpskn4ozpzlo2rsulbipnsrgrn6uinyikytt6q',
+  'bafkreibc5qwpqdrmpl6ett2smbuuzsnxquiui4pyzr5yvb6ykon4skjlda',
+  'bafkreifbqws3y4nsse7en2a4uerjalkvd3qkl5cx5rqndsuyu37dtsufmi',
+  'bafkreibbexz5kzy2wumnr3kcvh2sd4t3gvssxarq25c47b6hx55q5irmoy',
+  'bafkreih3iu5pldnyej5vpftniciesfps4qgwz36vbxzlnu7nuta3opp37a',
+  'bafkreiaj3qfu5s2hwkuecxwqxu2m7odumhapo4wrlm3mi7e6gbwalfwgau',
+  'bafkreibqf2jietvtmxmrtr3osfyccil2yj2cqybnwtvja3mk7dn5p4d2le',
+];
+
+const HTTP_INDICES = [2, 0, 5];
+
+const EXPECTED = {
+  http: 'dabc21345246acae80d073a2eccd54559979c4dfce89638f995ddf24bc61ced1',
+  pubsubIndices: '1,6',
+  pubsub: 'ce855087486058caaf78a44077f3e4673608beaa73ed557db6e435634765414d',
+  smallFile: 'a188169fbf774552be7b1677b8f7b354c3ca823e6acb587ce3734dfd54be6a73',
+  commitment: 'f7e9737e96a685fa9d719b32112b1c3c8c4522411e419a9ebcf8c88ae555048b',
+  receipt: '2369af66124679e7dbdc127b5d3e1221ddfb7212341fcbc7862b4d76faeb8826',
+};
+
+function check(mode: string, expected: string, compute: () => string): VectorResult {
+  try {
+    const actual = compute();
+    return { mode, passed: actual === expected, expected, actual };
+  } catch (err: any) {
+    return { mode, passed: false, expected, actual: null, error: err.message };
+  }
+}
+
+/** Round-trip the vector blocks through the daemon: CIDs must match and bytes must read back intact. */
+async function checkIpfsBlocks(kubo: KuboManager): Promise<VectorResult> {
+  const expected = VECTOR_CIDS.join(',');
+  if (!kubo.isRunning()) {
+    return { mode: 'ipfs-blocks', passed: false, expected, actual: null, error: 'IPFS daemon is not running' };
+  }
+  try {
+    const cids: string[] = [];
+    for (let i = 0; i < VECTOR_BLOCKS.length; i++) {
+      const cid = await kubo.putBlock(VECTOR_BLOCKS[i]);
+      cids.push(cid);
+      const readBack = await kubo.getBlock(cid, 2000, undefined, true);
+      if (!readBack.equals(VECTOR_BLOCKS[i])) {
+        return { mode: 'ipfs-blocks', passed: false, expected, actual: cids.join(','), error: `Block ${i} read back different bytes` };
+      }
+    }
+    const actual = cids.join(',');
+    return { mode: 'ipfs-blocks', passed: actual === expected, expected, actual };
+  } catch (err: any) {
+    return { mode: 'ipfs-blocks', passed: false, expected, actual: null, error: err.message };
+  }
+}
+
+export async function runTestVectors(kubo: KuboManager): Promise<VectorReport> {
+  const pubsubIndices = () => selectProofBlockIndices(VECTOR_SALT, VECTOR_BLOCKS.length);
+
+  const results: VectorResult[] = [
+    await checkIpfsBlocks(kubo),
+    check('http-v1', EXPECTED.http, () =>
+      httpProofFromBlocks(VECTOR_SALT, HTTP_INDICES.map((i) => VECTOR_BLOCKS[i]))),
+    check('pubsub-v1-selection', EXPECTED.pubsubIndices, () => pubsubIndices().join(',')),
+    check('pubsub-v1', EXPECTED.pubsub, () =>
+      proofHashFromBlocks(VECTOR_SALT, [...pubsubIndices()].sort((a, b) => a - b).map((i) => VECTOR_BLOCKS[i]))),
+    check('small-file-v1', EXPECTED.smallFile, () => proofHashFromFile(VECTOR_SALT, VECTOR_BLOCKS[0])),
+    check('commitment-v2', EXPECTED.commitment, () => blockListHashFromCids(VECTOR_CIDS[0], VECTOR_CIDS)),
+    check('receipt-canonical-v1', EXPECTED.receipt, () => hashString(canonicalReceiptPayload({
+      type: 'response',
+      targetPeer: 'selftest',
+      validatorPeer: 'validator',
+      cid: VECTOR_CIDS[0],
+      salt: VECTOR_SALT,
+      proofHash: '00',
+      elapsed: 1,
+      status: 'success',
+      nonce: 'n1',
+      __signature: 'transport-only',
+    }))),
+  ];
+
+  const failed = results.filter((r) => !r.passed).map((r) => r.mode);
+  if (failed.length > 0) {
+    console.warn(`[SelfTest] Test vectors FAILED for: ${failed.join(', ')}`);
+  }
+  return { passed: failed.length === 0, vectorVersion: VECTOR_VERSION, results };
+}
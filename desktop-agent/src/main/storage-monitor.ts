@@ -0,0 +1,164 @@
+/**
+ * storage-monitor.ts — Free disk space on the repo volume
+ *
+ * storageMaxGB only caps the IPFS repo; it says nothing about the disk it sits
+ * on, which other data also fills. Every DISK_CHECK_INTERVAL_MS this reads the
+ * volume's free space (statfs) and the repo size, keeps 24 h of samples to
+ * work out how fast the repo grows, and sorts the volume into a level:
+ *
+ *   ok     free space above diskWarnFreePercent, and not on course to reach
+ *          the floor within DISK_FORECAST_HOURS at the current growth rate
+ *   low    below diskWarnFreePercent, or on course to reach the floor soon
+ *   floor  less than diskMinFreeGB free
+ *
+ * Each change to a worse level emits 'disk-space' (desktop notification) and a
+ * disk_space_warning agent event (webhook 'disk-low'). checkPin() is what the
+ * pin, upload, import, contract and reconciler paths ask before adding data:
+ * it refuses anything that would leave less than diskMinFreeGB free, whatever
+ * storageMaxGB says.
+ */
+
+import * as fs from 'fs';
+import * as path from 'path';
+import { EventEmitter } from 'events';
+import type { KuboManager } from './kubo';
+import type { ConfigStore } from './config';
+import { agentEvents } from './agent-events';
+
+export type DiskLevel = 'ok' | 'low' | 'floor';
+
+export interface DiskStatus {
+  path: string;
+  totalBytes: number;
+  freeBytes: number;
+  freePercent: number;
+  repoBytes: number | null;
+  growthBytesPerHour: number | null;  // null until samples span DISK_MIN_RATE_SPAN_MS
+  hoursToFloor: number | null;        // null when not growing
+  level: DiskLevel;
+  warnFreePercent: number;
+  minFreeBytes: number;
+  checkedAt: string;
+}
+
+const DISK_CHECK_INTERVAL_MS = 60000;
+const DISK_SAMPLE_WINDOW_MS = 24 * 3600000;
+const DISK_MIN_RATE_SPAN_MS = 3600000;
+const DISK_FORECAST_HOURS = 24;
+const GB = 1024 * 1024 * 1024;
+const LEVEL_ORDER: DiskLevel[] = ['ok', 'low', 'floor'];
+// A level is only left once free space clears its threshold by this much, so it doesn't flap
+const DISK_REARM_MARGIN = 0.02;
+
+/** Closest existing directory at or above `p` — statfs needs a real path. */
+function existingAncestor(p: string): string {
+  let current = path.resolve(p);
+  while (!fs.existsSync(current)) {
+    const parent = path.dirname(current);
+    if (parent === current) break;
+    current = parent;
+  }
+  return current;
+}
+
+function formatGB(bytes: number): string {
+  return `${(bytes / GB).toFixed(1)} GB`;
+}
+
+export class StorageMonitor extends EventEmitter {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private timer: NodeJS.Timeout | null = null;
+  private samples: { at: number; repoBytes: number }[] = [];
+  private status: DiskStatus | null = null;
+
+  constructor(kubo: KuboManager, config: ConfigStore) {
+    super();
+    this.kubo = kubo;
+    this.config = config;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.check().catch(() => {});
+    this.timer = setInterval(() => this.check().catch(() => {}), DISK_CHECK_INTERVAL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  getStatus(): DiskStatus | null {
+    return this.status;
+  }
+
+  /**
+   * Why adding `sizeBytes` (plus `pendingBytes` still being fetched) would break
+   * the free-space floor, or null if it fits. Reads free space fresh.
+   */
+  checkPin(sizeBytes: number, pendingBytes: number): string | null {
+    const minFreeBytes = this.config.getConfig().diskMinFreeGB * GB;
+    if (minFreeBytes <= 0) return null;
+    const target = existingAncestor(this.kubo.getRepoPath());
+    let freeBytes: number;
+    try {
+      const stat = fs.statfsSync(target);
+      freeBytes = stat.bavail * stat.bsize;
+    } catch {
+      return null; // can't tell — storageMaxGB still applies
+    }
+    if (freeBytes - pendingBytes - sizeBytes >= minFreeBytes) return null;
+    return `Only ${formatGB(freeBytes)} free on ${target}; keeping at least ${formatGB(minFreeBytes)} free (diskMinFreeGB)`;
+  }
+
+  /** Sample the volume and repo now, and publish a warning if the level got worse. */
+  async check(): Promise<DiskStatus> {
+    const { diskWarnFreePercent, diskMinFreeGB } = this.config.getConfig();
+    const target = existingAncestor(this.kubo.getRepoPath());
+    const stat = fs.statfsSync(target);
+    const totalBytes = stat.blocks * stat.bsize;
+    const freeBytes = stat.bavail * stat.bsize;
+    const minFreeBytes = diskMinFreeGB * GB;
+    const now = Date.now();
+
+    const repoBytes = this.kubo.isRunning() ? (await this.kubo.getStats())?.repoSize ?? null : null;
+    if (repoBytes !== null) {
+      this.samples.push({ at: now, repoBytes });
+      while (this.samples.length > 0 && now - this.samples[0].at > DISK_SAMPLE_WINDOW_MS) this.samples.shift();
+    }
+    const oldest = this.samples[0];
+    const growthBytesPerHour = oldest && now - oldest.at >= DISK_MIN_RATE_SPAN_MS
+      ? Math.round(((repoBytes ?? oldest.repoBytes) - oldest.repoBytes) / ((now - oldest.at) / 3600000))
+      : null;
+    const hoursToFloor = growthBytesPerHour !== null && growthBytesPerHour > 0
+      ? Math.max(0, Math.round(((freeBytes - minFreeBytes) / growthBytesPerHour) * 10) / 10)
+      : null;
+    const freePercent = totalBytes > 0 ? Math.round((freeBytes / totalBytes) * 1000) / 10 : 0;
+
+    const previous = this.status?.level ?? 'ok';
+    const floorBytes = previous === 'floor' ? minFreeBytes + totalBytes * DISK_REARM_MARGIN : minFreeBytes;
+    const warnPercent = previous === 'ok' ? diskWarnFreePercent : diskWarnFreePercent + DISK_REARM_MARGIN * 100;
+    const level: DiskLevel = freeBytes < floorBytes ? 'floor'
+      : freePercent < warnPercent || (hoursToFloor !== null && hoursToFloor < DISK_FORECAST_HOURS) ? 'low'
+      : 'ok';
+    this.status = {
+      path: target, totalBytes, freeBytes, freePercent, repoBytes, growthBytesPerHour, hoursToFloor, level,
+      warnFreePercent: diskWarnFreePercent, minFreeBytes, checkedAt: new Date(now).toISOString(),
+    };
+
+    if (LEVEL_ORDER.indexOf(level) > LEVEL_ORDER.indexOf(previous)) {
+      const message = level === 'floor'
+        ? `Only ${formatGB(freeBytes)} free on ${target} — new pins are refused until ${formatGB(minFreeBytes)} is free again`
+        : hoursToFloor !== null && hoursToFloor < DISK_FORECAST_HOURS
+          ? `${formatGB(freeBytes)} free on ${target}; at the current growth rate the ${formatGB(minFreeBytes)} floor is ${hoursToFloor} h away`
+          : `${formatGB(freeBytes)} free on ${target} (${freePercent}%, warning below ${diskWarnFreePercent}%)`;
+      console.warn(`[Disk] ${message}`);
+      agentEvents.publish('disk_space_warning', { ...this.status, message });
+      this.emit('disk-space', { ...this.status, message });
+    }
+    return this.status;
+  }
+}
@@ -1,14 +1,69 @@
-import { autoUpdater, UpdateInfo, ProgressInfo } from 'electron-updater';
+import { autoUpdater, UpdateInfo, ProgressInfo, UpdateDownloadedEvent } from 'electron-updater';
 import { app, dialog, BrowserWindow, Notification } from 'electron';
+import * as crypto from 'crypto';
+import * as fs from 'fs';
+
+/**
+ * updateMode:
+ *   prompt  download in the background, ask before restarting (default)
+ *   auto    download and restart into the new version once no challenge is
+ *           in flight — for unattended fleets
+ *   off     no background checks; /api/update/check and /apply still work
+ *
+ * electron-updater reads the feed (latest*.yml on GitHub Releases), checks
+ * each artifact's sha512 and, on Windows, the installer's code signature
+ * against the running app's publisher. That only proves the artifact matches
+ * the feed, so on top of it the release signs the artifact with the Ed25519
+ * key pinned below: the feed carries `ed25519Signature`, base64, over
+ * "hivepoa-update-v1\n<version>\n<sha512 of the file, base64>". Nothing is
+ * installed — not on restart, not on quit — until the downloaded file
+ * verifies. A build without a pinned key can't verify, so it refuses 'auto'
+ * and only installs when someone asks (dialog or /api/update/apply).
+ */
+export type UpdateMode = 'auto' | 'prompt' | 'off';
+
+export type UpdateState = 'idle' | 'checking' | 'not-available' | 'downloading' | 'downloaded' | 'error';
+
+export interface UpdateStatus {
+  currentVersion: string;
+  state: UpdateState;
+  availableVersion: string | null;
+  downloadProgress: number;
+  lastCheckedAt: string | null;
+  error: string | null;
+  mode: UpdateMode;
+}
+
+// PEM of the Ed25519 key release artifacts are signed with; empty in builds made without one
+const UPDATE_SIGNING_PUBLIC_KEY = '';
+
+const CHECK_INTERVAL_MS = 6 * 3600000;
+const RESTART_RETRY_MS = 60000;
 
 export class AutoUpdater {
   private mainWindow: BrowserWindow | null = null;
   private updateAvailable = false;
   private downloadProgress = 0;
+  private mode: UpdateMode;
+  private state: UpdateState = 'idle';
+  private availableVersion: string | null = null;
+  private lastCheckedAt: string | null = null;
+  private lastError: string | null = null;
+  private checkTimer: NodeJS.Timeout | null = null;
+  private restartTimer: NodeJS.Timeout | null = null;
+  // Whether the agent can restart without dropping work (no challenge in flight)
+  private canRestart: () => boolean = () => true;
+  private headless: boolean;
+  // The downloaded update passed the signature check (always false without a pinned key)
+  private verified = false;
+  // Settles once the latest 'update-downloaded' has been checked
+  private downloadChecked: Promise<void> = Promise.resolve();
 
-  constructor() {
-    autoUpdater.autoDownload = true;
-    autoUpdater.autoInstallOnAppQuit = true;
+  constructor(mode: UpdateMode = 'prompt', headless = false) {
+    this.mode = this.allowedMode(mode);
+    this.headless = headless;
+    autoUpdater.autoDownload = this.mode !== 'off';
+    autoUpdater.autoInstallOnAppQuit = false; // turned on once a download verifies
     autoUpdater.allowDowngrade = false;
     autoUpdater.allowPrerelease = false;
 
@@ -20,17 +75,70 @@ export class AutoUpdater {
     console.log('[SPK] Auto-updater initialized');
     console.log('[SPK] App version:', app.getVersion());
     console.log('[SPK] Is packaged:', app.isPackaged);
-    console.log('[SPK] Auto download:', autoUpdater.autoDownload);
-    console.log('[SPK] Auto install on quit:', autoUpdater.autoInstallOnAppQuit);
+    console.log('[SPK] Update mode:', this.mode);
+    console.log('[SPK] Update signing key pinned:', this.canVerify());
+  }
+
+  /** Whether this build has a key to verify updates with — 'auto' needs one. */
+  canVerify(): boolean {
+    return UPDATE_SIGNING_PUBLIC_KEY !== '';
+  }
+
+  private allowedMode(mode: UpdateMode): UpdateMode {
+    if (mode !== 'auto' || this.canVerify()) return mode;
+    console.warn("[SPK] updateMode 'auto' needs a pinned update signing key — using 'prompt'");
+    return 'prompt';
+  }
+
+  /** Check the downloaded file against the release signature in the feed. */
+  private async verifyDownload(event: UpdateDownloadedEvent): Promise<void> {
+    if (!this.canVerify()) throw new Error('no update signing key is pinned in this build');
+    const signature = (event as any).ed25519Signature;
+    if (typeof signature !== 'string' || !signature) throw new Error('the release feed has no ed25519Signature');
+    const digest = crypto.createHash('sha512');
+    for await (const chunk of fs.createReadStream(event.downloadedFile)) digest.update(chunk);
+    const message = `hivepoa-update-v1\n${event.version}\n${digest.digest('base64')}`;
+    const key = crypto.createPublicKey(UPDATE_SIGNING_PUBLIC_KEY);
+    if (!crypto.verify(null, Buffer.from(message), key, Buffer.from(signature, 'base64'))) {
+      throw new Error(`signature check failed for ${event.version}`);
+    }
   }
 
   setMainWindow(window: BrowserWindow | null): void {
     this.mainWindow = window;
   }
 
+  setRestartGuard(canRestart: () => boolean): void {
+    this.canRestart = canRestart;
+  }
+
+  setMode(mode: UpdateMode): void {
+    this.mode = this.allowedMode(mode);
+    autoUpdater.autoDownload = this.mode !== 'off';
+    if (this.mode === 'off') {
+      this.stopPeriodicChecks();
+    } else {
+      this.startPeriodicChecks();
+      if (this.mode === 'auto' && this.state === 'downloaded' && this.verified) this.restartWhenIdle();
+    }
+  }
+
+  /** Check now and then every CHECK_INTERVAL_MS (not in 'off' mode). */
+  startPeriodicChecks(): void {
+    if (this.mode === 'off' || this.checkTimer) return;
+    this.checkForUpdates();
+    this.checkTimer = setInterval(() => this.checkForUpdates(), CHECK_INTERVAL_MS);
+  }
+
+  stopPeriodicChecks(): void {
+    if (this.checkTimer) clearInterval(this.checkTimer);
+    this.checkTimer = null;
+  }
+
   private setupEventListeners(): void {
     autoUpdater.on('checking-for-update', () => {
       console.log('[SPK] Checking for updates...');
+      this.state = 'checking';
       this.sendStatusToWindow('Checking for updates...');
     });
 
@@ -38,10 +146,12 @@ export class AutoUpdater {
       console.log('[SPK] Update available:', info.version);
       console.log('[SPK] Release date:', info.releaseDate);
       this.updateAvailable = true;
-      
-      this.sendStatusToWindow(`Update ${info.version} available, downloading...`);
-      
-      if (Notification.isSupported()) {
+      this.availableVersion = info.version;
+      this.state = autoUpdater.autoDownload ? 'downloading' : 'idle';
+
+      this.sendStatusToWindow(`Update ${info.version} available${autoUpdater.autoDownload ? ', downloading...' : ''}`);
+
+      if (Notification.isSupported() && autoUpdater.autoDownload) {
         new Notification({
           title: 'SPK Desktop Agent Update',
           body: `Version ${info.version} is available and downloading...`,
@@ -52,45 +162,96 @@ export class AutoUpdater {
 
     autoUpdater.on('update-not-available', (info: UpdateInfo) => {
       console.log('[SPK] App is up to date:', info.version);
+      this.state = 'not-available';
       this.sendStatusToWindow('App is up to date');
     });
 
     autoUpdater.on('download-progress', (progress: ProgressInfo) => {
       this.downloadProgress = progress.percent;
+      this.state = 'downloading';
       const msg = `Download progress: ${Math.round(progress.percent)}% (${this.formatBytes(progress.transferred)}/${this.formatBytes(progress.total)})`;
       console.log(`[SPK] ${msg}`);
       this.sendStatusToWindow(msg);
     });
 
-    autoUpdater.on('update-downloaded', (info: UpdateInfo) => {
-      console.log('[SPK] Update downloaded:', info.version);
-      this.sendStatusToWindow(`Update ${info.version} ready to install`);
-      
-      dialog.showMessageBox({
-        type: 'info',
-        title: 'Update Ready',
-        message: `Version ${info.version} has been downloaded.`,
-        detail: 'The update will be installed when you restart the app. Would you like to restart now?',
-        buttons: ['Restart Now', 'Later'],
-        defaultId: 0,
-        cancelId: 1,
-      }).then((result) => {
-        if (result.response === 0) {
-          console.log('[SPK] User chose to restart and install update');
-          autoUpdater.quitAndInstall(false, true);
-        } else {
-          console.log('[SPK] User chose to install update later');
-        }
-      });
+    autoUpdater.on('update-downloaded', (info: UpdateDownloadedEvent) => {
+      this.downloadChecked = this.onDownloaded(info);
     });
 
     autoUpdater.on('error', (error: Error) => {
       console.error('[SPK] Auto-updater error:', error.message);
       console.error('[SPK] Error stack:', error.stack);
+      this.state = 'error';
+      this.lastError = error.message;
       this.sendStatusToWindow(`Update error: ${error.message}`);
     });
   }
 
+  private async onDownloaded(info: UpdateDownloadedEvent): Promise<void> {
+    console.log('[SPK] Update downloaded:', info.version);
+    this.downloadProgress = 100;
+    this.verified = false;
+    try {
+      await this.verifyDownload(info);
+      this.verified = true;
+      autoUpdater.autoInstallOnAppQuit = true;
+    } catch (err: any) {
+      if (this.canVerify()) {
+        // A pinned key and a bad signature: never install this one
+        console.error(`[SPK] Rejecting update ${info.version}: ${err.message}`);
+        this.state = 'error';
+        this.lastError = `Update rejected: ${err.message}`;
+        this.sendStatusToWindow(this.lastError);
+        return;
+      }
+      console.warn(`[SPK] Update ${info.version} is unverified (${err.message}) — installing only when asked`);
+    }
+    this.state = 'downloaded';
+    this.sendStatusToWindow(`Update ${info.version} ready to install`);
+
+    if (this.mode === 'auto' && this.verified) {
+      this.restartWhenIdle();
+      return;
+    }
+    // Nobody to ask in headless mode — a verified update installs on the next restart
+    if (this.headless) return;
+
+    dialog.showMessageBox({
+      type: 'info',
+      title: 'Update Ready',
+      message: `Version ${info.version} has been downloaded.`,
+      detail: this.verified
+        ? 'The update will be installed when you restart the app. Would you like to restart now?'
+        : 'This build cannot verify the update signature, so it is only installed if you restart now.',
+      buttons: ['Restart Now', 'Later'],
+      defaultId: 0,
+      cancelId: 1,
+    }).then((result) => {
+      if (result.response === 0) {
+        console.log('[SPK] User chose to restart and install update');
+        autoUpdater.quitAndInstall(false, true);
+      } else {
+        console.log('[SPK] User chose to install update later');
+      }
+    });
+  }
+
+  /** Install the downloaded update, waiting for in-flight challenges to finish. */
+  private restartWhenIdle(): void {
+    if (this.restartTimer) return;
+    const attempt = () => {
+      this.restartTimer = null;
+      if (!this.canRestart()) {
+        console.log('[SPK] Update ready — waiting for in-flight challenges before restarting');
+        this.restartTimer = setTimeout(attempt, RESTART_RETRY_MS);
+        return;
+      }
+      console.log(`[SPK] Restarting to install ${this.availableVersion}`);
+      autoUpdater.quitAndInstall(true, true);
+    };
+    attempt();
+  }
+
   private sendStatusToWindow(message: string): void {
     if (this.mainWindow && !this.mainWindow.isDestroyed()) {
       this.mainWindow.webContents.send('update-status', message);
@@ -105,14 +266,16 @@ export class AutoUpdater {
     return parseFloat((bytes / Math.pow(k, i)).toFixed(2)) + ' ' + sizes[i];
   }
 
-  async checkForUpdates(): Promise<void> {
+  async checkForUpdates(): Promise<UpdateStatus> {
     if (!app.isPackaged) {
       console.log('[SPK] Skipping update check in development mode');
-      return;
+      return this.getStatus();
     }
 
     try {
       console.log('[SPK] Initiating update check...');
+      this.lastCheckedAt = new Date().toISOString();
+      this.lastError = null;
       const result = await autoUpdater.checkForUpdates();
       if (result) {
         console.log('[SPK] Update check result:', {
@@ -123,7 +286,10 @@ export class AutoUpdater {
     } catch (error: unknown) {
       const errorMessage = error instanceof Error ? error.message : String(error);
       console.error('[SPK] Failed to check for updates:', errorMessage);
+      this.state = 'error';
+      this.lastError = errorMessage;
     }
+    return this.getStatus();
   }
 
   async checkForUpdatesAndNotify(): Promise<void> {
@@ -140,6 +306,35 @@ export class AutoUpdater {
     }
   }
 
+  /**
+   * Download the available update if needed, then restart into it. Resolves
+   * (before quitting) with the status, or throws if there is nothing to apply.
+   */
+  async applyUpdate(): Promise<UpdateStatus> {
+    if (!this.updateAvailable) throw new Error('No update available — run a check first');
+    if (this.state !== 'downloaded') {
+      this.state = 'downloading';
+      await autoUpdater.downloadUpdate();
+    }
+    await this.downloadChecked;
+    if (this.state !== 'downloaded') throw new Error(this.lastError || 'Update download did not complete');
+    // Let the caller's response go out before the app quits
+    setTimeout(() => this.restartWhenIdle(), 1000);
+    return this.getStatus();
+  }
+
+  getStatus(): UpdateStatus {
+    return {
+      currentVersion: app.getVersion(),
+      state: this.state,
+      availableVersion: this.availableVersion,
+      downloadProgress: this.downloadProgress,
+      lastCheckedAt: this.lastCheckedAt,
+      error: this.lastError,
+      mode: this.mode,
+    };
+  }
+
   isUpdateAvailable(): boolean {
     return this.updateAvailable;
   }
@@ -1,14 +1,17 @@
 import * as crypto from 'crypto';
 import axios from 'axios';
 import { AgentHiveClient } from './hive';
+import { verifyReceipt } from './receipt';
 import { PeerDiscovery, PeerInfo } from './peer-discovery';
 import { PubSubBridge } from './pubsub';
 import { ChallengeMessage, ChallengeResponse, CommitmentRequest, CommitmentResponse } from './challenge-handler';
-import { createSaltWithEntropy, verifyProof, computeBlockListHash } from './poa-crypto';
+import { createSaltWithEntropy, deriveBlockSalt, verifyProof, computeBlockListHash, isValidCid } from './poa-crypto';
 
 const CHALLENGE_TOPIC = 'hivepoa-challenges';
 const CHALLENGE_TIMEOUT_MS = 25000; // 25 second anti-cheat window
 const COMMITMENT_TIMEOUT_MS = 2000; // 2 second commitment window (proves local storage)
+const POSTING_KEY_CACHE_TTL_MS = 10 * 60000;
+const MAX_RECENT_RESULTS = 100;
 
 export interface ValidatorStats {
   issued: number;
@@ -17,6 +20,17 @@ export interface ValidatorStats {
   timeouts: number;
 }
 
+/** One issued challenge and its outcome, newest kept in memory for /api/validation/results. */
+export interface ValidationResult {
+  peer: string;
+  cid: string;
+  passed: boolean;
+  timedOut: boolean;
+  proofHash: string;
+  latencyMs: number;
+  at: string;
+}
+
 interface PendingChallenge {
   resolve: (response: ChallengeResponse) => void;
   timeout: NodeJS.Timeout;
@@ -33,6 +47,11 @@ interface PendingCommitment {
  * Local PoA validation engine.
  * Periodically challenges random peers and verifies their storage proofs.
  * Results are broadcast to Hive blockchain as custom_json.
+ *
+ * Challenged CIDs must be pinned here (proofs are verified against our own
+ * blockstore). When a coordinator URL is set, only CIDs that also back an
+ * active storage contract (GET /api/contracts/active) are picked; if that list
+ * can't be fetched, any local pin is used.
  */
 export class LocalValidator {
   private hive: AgentHiveClient;
@@ -43,6 +62,9 @@ export class LocalValidator {
   private challengeIntervalMs: number;
   private broadcastResults: boolean;
   private requireSignedMessages: boolean;
+  // Posting keys of challenged peers, for checking their signed responses
+  private postingKeyCache: Map<string, { keys: string[]; at: number }> = new Map();
+  private serverUrl: string | null;
 
   private challengeTimer: NodeJS.Timeout | null = null;
   private blockHashTimer: NodeJS.Timeout | null = null;
@@ -52,6 +74,7 @@ export class LocalValidator {
   private cachedPins: string[] = [];
   private pinsCacheTime: number = 0;
   private static readonly PIN_CACHE_TTL_MS = 300000; // 5 minutes
+  private recentResults: ValidationResult[] = [];
 
   private stats: ValidatorStats = {
     issued: 0,
@@ -68,7 +91,8 @@ export class LocalValidator {
     myUsername: string,
     challengeIntervalMs: number = 7200000, // 2 hours (was 5 min)
     broadcastResults: boolean = true,
-    requireSignedMessages: boolean = false
+    requireSignedMessages: boolean = false,
+    serverUrl: string | null = null
   ) {
     this.hive = hive;
     this.peerDiscovery = peerDiscovery;
@@ -78,6 +102,7 @@ export class LocalValidator {
     this.challengeIntervalMs = challengeIntervalMs;
     this.broadcastResults = broadcastResults;
     this.requireSignedMessages = requireSignedMessages;
+    this.serverUrl = serverUrl;
   }
 
   /** Start the validator engine. */
@@ -177,6 +202,8 @@ export class LocalValidator {
 
     this.stats.issued++;
     const signer = (payload: string) => this.hive.signMessage(payload);
+    // Have the peer's keys ready before its response races the commitment window
+    await this.postingKeys(peer.hiveUsername).catch(() => {});
 
     // ── Phase 1: Commitment ──────────────────────────────────────────────
     const commitNonce = crypto.randomBytes(16).toString('hex');
@@ -250,7 +277,11 @@ export class LocalValidator {
     }
 
     // ── Phase 2: Challenge (same as v1) ─────────────────────────────────
-    const salt = createSaltWithEntropy(this.currentBlockHash);
+    // Derive the salt from the block id when we have a real one, so peers with
+    // requireBlockSalts can check it; otherwise fall back to plain entropy
+    const saltNonce = crypto.randomBytes(16).toString('hex');
+    const blockId = /^[0-9a-f]{40}$/.test(this.currentBlockHash) ? this.currentBlockHash : null;
+    const salt = blockId ? deriveBlockSalt(blockId, saltNonce) : createSaltWithEntropy(this.currentBlockHash);
     const challengeNonce = crypto.randomBytes(16).toString('hex');
 
     const challenge: ChallengeMessage = {
@@ -262,6 +293,7 @@ export class LocalValidator {
       blockHash: this.currentBlockHash.slice(0, 16),
       timestamp: Date.now(),
       nonce: challengeNonce,
+      ...(blockId && { blockId, saltNonce }),
     };
 
     console.log(`[Validator] Phase 2: Challenge to ${peer.hiveUsername} for CID ${cid.slice(0, 12)}...`);
@@ -294,6 +326,7 @@ export class LocalValidator {
       this.stats.timeouts++;
       this.peerDiscovery.recordChallenge(peer.hiveUsername, false);
       console.log(`[Validator] Phase 2: Challenge to ${peer.hiveUsername} timed out`);
+      this.logResult(peer, cid, false, '', CHALLENGE_TIMEOUT_MS, true);
       return;
     }
 
@@ -335,52 +368,75 @@ export class LocalValidator {
     }
   }
 
+  /** A peer's posting keys, cached so a response isn't held up by a Hive lookup every time. */
+  private async postingKeys(username: string): Promise<string[]> {
+    const cached = this.postingKeyCache.get(username);
+    if (cached && Date.now() - cached.at < POSTING_KEY_CACHE_TTL_MS) return cached.keys;
+    const keys = await this.hive.getPostingPublicKeys(username);
+    this.postingKeyCache.set(username, { keys, at: Date.now() });
+    return keys;
+  }
+
+  /**
+   * Check a signed receipt against the sender's on-chain posting keys
+   * (verifyReceipt). Unsigned receipts pass only without requireSignedMessages.
+   */
+  private async checkReceiptSignature(
+    receipt: { targetPeer: string; __signature?: string; __signerUsername?: string },
+    kind: string
+  ): Promise<boolean> {
+    if (!receipt.__signature) {
+      if (this.requireSignedMessages) {
+        console.log(`[Validator] Rejected unsigned ${kind} from ${receipt.targetPeer} (enforcement enabled)`);
+        return false;
+      }
+      console.log(`[Validator] Unsigned ${kind} from ${receipt.targetPeer} (legacy)`);
+      return true;
+    }
+    if (receipt.__signerUsername !== receipt.targetPeer) {
+      console.log(`[Validator] ${kind} signature mismatch: signer=${receipt.__signerUsername} != peer=${receipt.targetPeer}`);
+      return false;
+    }
+    try {
+      const result = verifyReceipt(receipt, null, await this.postingKeys(receipt.targetPeer));
+      if (!result.valid) {
+        console.log(`[Validator] Rejected ${kind} from ${receipt.targetPeer}: ${result.errors.join('; ')}`);
+        return false;
+      }
+    } catch (err: any) {
+      console.log(`[Validator] Could not check ${kind} signature from ${receipt.targetPeer}: ${err.message}`);
+      return false;
+    }
+    return true;
+  }
+
   /** Handle an incoming commitment response (protocol v2 phase 1). */
-  handleCommitmentResponse(response: CommitmentResponse & { __signature?: string; __signerUsername?: string }): void {
+  async handleCommitmentResponse(response: CommitmentResponse & { __signature?: string; __signerUsername?: string }): Promise<void> {
     if (response.validatorPeer !== this.myUsername) return;
-
-    const pending = this.pendingCommitments.get(response.nonce);
-    if (!pending) return;
+    if (!this.pendingCommitments.has(response.nonce)) return;
 
     // SECURITY: Verify signature on commitment responses
-    if (response.__signature && response.__signerUsername) {
-      if (response.__signerUsername !== response.targetPeer) {
-        console.log(`[Validator] Commitment signature mismatch: signer=${response.__signerUsername} != peer=${response.targetPeer}`);
-        return; // Discard spoofed response
-      }
-      console.log(`[Validator] Signed commitment response from ${response.targetPeer}`);
-    } else if (this.requireSignedMessages) {
-      console.log(`[Validator] Rejected unsigned commitment from ${response.targetPeer} (enforcement enabled)`);
-      return;
-    }
+    if (!(await this.checkReceiptSignature(response, 'commitment response'))) return;
 
+    // Re-read: the commitment may have timed out while the keys were fetched
+    const pending = this.pendingCommitments.get(response.nonce);
+    if (!pending) return;
     clearTimeout(pending.timeout);
     this.pendingCommitments.delete(response.nonce);
     pending.resolve(response);
   }
 
   /** Handle an incoming challenge response from PubSub. */
-  handleChallengeResponse(response: ChallengeResponse & { __signature?: string; __signerUsername?: string }): void {
+  async handleChallengeResponse(response: ChallengeResponse & { __signature?: string; __signerUsername?: string }): Promise<void> {
     // Must be addressed to us
     if (response.validatorPeer !== this.myUsername) return;
-
-    const pending = this.pendingChallenges.get(response.nonce);
-    if (!pending) return; // No matching pending challenge (already timed out or not ours)
+    if (!this.pendingChallenges.has(response.nonce)) return; // already timed out or not ours
 
     // SECURITY: Verify signature on challenge responses
-    if (response.__signature && response.__signerUsername) {
-      if (response.__signerUsername !== response.targetPeer) {
-        console.log(`[Validator] Response signature mismatch: signer=${response.__signerUsername} != peer=${response.targetPeer}`);
-        return; // Discard spoofed response
-      }
-      console.log(`[Validator] Signed response from ${response.targetPeer}`);
-    } else if (this.requireSignedMessages) {
-      console.log(`[Validator] Rejected unsigned response from ${response.targetPeer} (enforcement enabled)`);
-      return;
-    } else {
-      console.log(`[Validator] Unsigned response from ${response.targetPeer} (legacy)`);
-    }
+    if (!(await this.checkReceiptSignature(response, 'challenge response'))) return;
 
+    const pending = this.pendingChallenges.get(response.nonce);
+    if (!pending) return;
     clearTimeout(pending.timeout);
     this.pendingChallenges.delete(response.nonce);
     pending.resolve(response);
@@ -396,7 +452,12 @@ export class LocalValidator {
           null,
           { timeout: 5000 }
         );
-        this.cachedPins = Object.keys(response.data.Keys || {});
+        const local = Object.keys(response.data.Keys || {});
+        const contracted = await this.fetchContractCids();
+        this.cachedPins = contracted ? local.filter((cid) => contracted.has(cid)) : local;
+        if (contracted && this.cachedPins.length === 0) {
+          console.log('[Validator] None of our pins back an active storage contract');
+        }
         this.pinsCacheTime = now;
       } catch {
         // Use stale cache if available
@@ -408,6 +469,35 @@ export class LocalValidator {
     return this.cachedPins[Math.floor(Math.random() * this.cachedPins.length)];
   }
 
+  /** CIDs of active storage contracts on the coordinator, or null if unavailable. */
+  private async fetchContractCids(): Promise<Set<string> | null> {
+    if (!this.serverUrl) return null;
+    try {
+      const response = await axios.get(`${this.serverUrl}/api/contracts/active`, { timeout: 10000 });
+      if (!Array.isArray(response.data)) return null;
+      return new Set(response.data
+        .map((contract: any) => contract?.fileCid)
+        .filter((cid: any) => typeof cid === 'string' && isValidCid(cid)));
+    } catch (err: any) {
+      console.warn(`[Validator] Could not fetch active contracts, using all local pins: ${err.message}`);
+      return null;
+    }
+  }
+
+  /** Keep a challenge outcome in the in-memory history. */
+  private logResult(peer: PeerInfo, cid: string, passed: boolean, proofHash: string, latencyMs: number, timedOut: boolean = false): void {
+    this.recentResults.push({
+      peer: peer.hiveUsername,
+      cid,
+      passed,
+      timedOut,
+      proofHash,
+      latencyMs,
+      at: new Date().toISOString(),
+    });
+    if (this.recentResults.length > MAX_RECENT_RESULTS) this.recentResults.shift();
+  }
+
   /** Record and optionally broadcast a challenge result to Hive. */
   private async recordResult(
     peer: PeerInfo,
@@ -416,6 +506,7 @@ export class LocalValidator {
     proofHash: string,
     latencyMs: number
   ): Promise<void> {
+    this.logResult(peer, cid, success, proofHash, latencyMs);
     if (this.broadcastResults && this.hive.hasPostingKey()) {
       await this.hive.broadcastPoAResult(
         peer.hiveUsername,
@@ -444,6 +535,11 @@ export class LocalValidator {
     return { ...this.stats };
   }
 
+  /** The `limit` most recent challenge outcomes, newest first. */
+  getRecentResults(limit: number = MAX_RECENT_RESULTS): ValidationResult[] {
+    return this.recentResults.slice(-limit).reverse();
+  }
+
   /** Update the challenge interval. */
   setChallengeInterval(ms: number): void {
     this.challengeIntervalMs = ms;
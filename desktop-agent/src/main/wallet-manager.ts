@@ -132,6 +132,16 @@ export class WalletManager {
     return key.sign(digestBuffer).toString();
   }
 
+  /**
+   * Sign a hex digest using the posting key (for attributing challenge proofs).
+   * Returns null if the posting key isn't unlocked.
+   */
+  signDigestWithPostingKey(digestHex: string): string | null {
+    if (!this.postingKeyCache) return null;
+    const key = PrivateKey.fromString(this.postingKeyCache);
+    return key.sign(Buffer.from(digestHex, "hex")).toString();
+  }
+
   hasActiveKey(): boolean {
     return !!this.walletData?.keys.active;
   }
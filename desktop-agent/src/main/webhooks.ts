@@ -0,0 +1,193 @@
+/**
+ * webhooks.ts — POST agent events to operator-configured URLs
+ *
+ * Each entry in `webhooks` names a URL and the events it wants:
+ *   challenge      — every challenge answered (HTTP, PubSub or validator WebSocket)
+ *   milestone      — an earnings milestone or the earnings goal was reached
+ *   daemon-down    — the IPFS daemon exited unexpectedly or keeps failing to restart
+ *   quota-warning  — repo usage crossed QUOTA_WARN_PERCENT of storageMaxGB
+ *   disk-low       — free space on the repo volume is low or at the diskMinFreeGB floor
+ *   daily-summary  — the notifyDailySummary digest of the last 24 hours
+ *
+ * The body is JSON: { event, at, agent, text, content, data }. `text` (Slack)
+ * and `content` (Discord) carry the same one-line summary, so incoming-webhook
+ * URLs from either work as-is. Failed deliveries (network error, 429 or 5xx)
+ * are retried with backoff; other 4xx responses are not.
+ */
+
+import axios from 'axios';
+import type { KuboManager } from './kubo';
+import type { ConfigStore } from './config';
+import { agentEvents, AgentEvent } from './agent-events';
+import { formatDailySummary, DailySummaryReport } from './daily-summary';
+
+export const WEBHOOK_EVENTS = ['challenge', 'milestone', 'daemon-down', 'quota-warning', 'disk-low', 'daily-summary'] as const;
+export type WebhookEvent = typeof WEBHOOK_EVENTS[number];
+
+export interface WebhookConfig {
+  url: string;
+  events: WebhookEvent[];
+}
+
+export interface WebhookDelivery {
+  url: string; // origin only — webhook paths usually embed a secret token
+  event: WebhookEvent;
+  at: string;
+  ok: boolean;
+  attempts: number;
+  status: number | null;
+  error: string | null;
+}
+
+const MAX_ATTEMPTS = 3;
+const RETRY_BASE_MS = 5000;
+const REQUEST_TIMEOUT_MS = 10000;
+const MAX_DELIVERIES = 50;
+const QUOTA_CHECK_INTERVAL_MS = 5 * 60000;
+const QUOTA_WARN_PERCENT = 90;
+const QUOTA_REARM_PERCENT = 85;
+
+/** Origin of a webhook URL, safe to log or return from the API. */
+export function redactWebhookUrl(url: string): string {
+  try {
+    return new URL(url).origin;
+  } catch {
+    return '(invalid url)';
+  }
+}
+
+export class WebhookNotifier {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private unsubscribe: (() => void) | null = null;
+  private quotaTimer: NodeJS.Timeout | null = null;
+  private quotaWarned = false;
+  private pending: Set<NodeJS.Timeout> = new Set();
+  private deliveries: WebhookDelivery[] = [];
+
+  constructor(kubo: KuboManager, config: ConfigStore) {
+    this.kubo = kubo;
+    this.config = config;
+  }
+
+  start(): void {
+    if (this.unsubscribe) return;
+    this.unsubscribe = agentEvents.subscribe((event) => this.onEvent(event));
+    this.quotaTimer = setInterval(() => this.checkQuota().catch(() => {}), QUOTA_CHECK_INTERVAL_MS);
+  }
+
+  stop(): void {
+    this.unsubscribe?.();
+    this.unsubscribe = null;
+    if (this.quotaTimer) {
+      clearInterval(this.quotaTimer);
+      this.quotaTimer = null;
+    }
+    for (const timer of this.pending) clearTimeout(timer);
+    this.pending.clear();
+  }
+
+  /** Most recent deliveries, newest first. */
+  getDeliveries(): WebhookDelivery[] {
+    return [...this.deliveries].reverse();
+  }
+
+  /** Publish storage_quota_warning once per crossing of QUOTA_WARN_PERCENT. */
+  private async checkQuota(): Promise<void> {
+    if (!this.kubo.isRunning()) return;
+    const storage = await this.kubo.getStorageInfo();
+    if (storage.percentage >= QUOTA_WARN_PERCENT && !this.quotaWarned) {
+      this.quotaWarned = true;
+      agentEvents.publish('storage_quota_warning', {
+        usedBytes: storage.usedBytes,
+        maxBytes: storage.maxBytes,
+        percentage: storage.percentage,
+        usedFormatted: storage.usedFormatted,
+        maxFormatted: storage.maxFormatted,
+      });
+    } else if (storage.percentage < QUOTA_REARM_PERCENT) {
+      this.quotaWarned = false;
+    }
+  }
+
+  private onEvent(event: AgentEvent): void {
+    const mapped = toWebhookEvent(event);
+    if (!mapped) return;
+    const cfg = this.config.getConfig();
+    const hooks = (cfg.webhooks || []).filter((h) => h.events.includes(mapped));
+    if (hooks.length === 0) return;
+
+    const text = summarize(mapped, event.data);
+    const body = { event: mapped, at: event.at, agent: cfg.hiveUsername, text, content: text, data: event.data };
+    for (const hook of hooks) this.deliver(hook.url, mapped, body, 1);
+  }
+
+  private deliver(url: string, event: WebhookEvent, body: Record<string, any>, attempt: number): void {
+    axios.post(url, body, { timeout: REQUEST_TIMEOUT_MS }).then(
+      (response) => this.record({ url, event, ok: true, attempts: attempt, status: response.status, error: null }),
+      (err: any) => {
+        const status: number | null = err.response?.status ?? null;
+        const retryable = status === null || status === 429 || status >= 500;
+        if (retryable && attempt < MAX_ATTEMPTS && this.unsubscribe) {
+          const timer = setTimeout(() => {
+            this.pending.delete(timer);
+            this.deliver(url, event, body, attempt + 1);
+          }, RETRY_BASE_MS * 2 ** (attempt - 1));
+          this.pending.add(timer);
+          return;
+        }
+        console.warn(`[Webhooks] ${event} to ${redactWebhookUrl(url)} failed after ${attempt} attempt(s): ${err.message}`);
+        this.record({ url, event, ok: false, attempts: attempt, status, error: err.message });
+      },
+    );
+  }
+
+  private record(delivery: Omit<WebhookDelivery, 'at'>): void {
+    this.deliveries.push({ ...delivery, url: redactWebhookUrl(delivery.url), at: new Date().toISOString() });
+    if (this.deliveries.length > MAX_DELIVERIES) this.deliveries.shift();
+  }
+}
+
+function toWebhookEvent(event: AgentEvent): WebhookEvent | null {
+  switch (event.type) {
+    case 'challenge_completed':
+      return 'challenge';
+    case 'earnings_milestone':
+      return 'milestone';
+    case 'daemon_stopped':
+      return event.data.intentional ? null : 'daemon-down';
+    case 'daemon_restart_failing':
+      return 'daemon-down';
+    case 'storage_quota_warning':
+      return 'quota-warning';
+    case 'disk_space_warning':
+      return 'disk-low';
+    case 'daily_summary':
+      return 'daily-summary';
+    default:
+      return null;
+  }
+}
+
+function summarize(event: WebhookEvent, data: Record<string, any>): string {
+  switch (event) {
+    case 'challenge':
+      return data.success
+        ? `Challenge passed for ${data.cid} in ${data.latencyMs ?? '?'}ms (${data.source})`
+        : `Challenge FAILED for ${data.cid} (${data.source}): ${data.error || 'unknown error'}`;
+    case 'milestone':
+      return data.type === 'goal'
+        ? `Earnings goal of ${data.thresholdHbd} HBD reached (total ${data.totalHbd} HBD)`
+        : `Earnings milestone: ${data.thresholdHbd} HBD (total ${data.totalHbd} HBD)`;
+    case 'daemon-down':
+      return data.attempts !== undefined
+        ? `IPFS daemon restart failing after ${data.attempts} attempts: ${data.error}`
+        : `IPFS daemon stopped unexpectedly (exit code ${data.code})`;
+    case 'quota-warning':
+      return `Storage at ${data.percentage}% (${data.usedFormatted} of ${data.maxFormatted})`;
+    case 'disk-low':
+      return data.message;
+    case 'daily-summary':
+      return formatDailySummary(data as DailySummaryReport);
+  }
+}
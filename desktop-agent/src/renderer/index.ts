@@ -67,10 +67,21 @@ interface StatusResponse {
   };
 }
 
+// Status, pins and config go through the preload IPC commands (same code as the
+// HTTP endpoints, run in the main process) — no localhost HTTP or CORS involved.
+interface AgentResult {
+  status: number;
+  body: any;
+}
+
+function agentApi(): any {
+  return (window as any).spkAgent;
+}
+
 async function fetchStatus(): Promise<StatusResponse | null> {
   try {
-    const response = await fetch(`${API_URL}/api/status`);
-    return await response.json();
+    const { status, body }: AgentResult = await agentApi().apiStatus();
+    return status === 200 ? body : null;
   } catch (error) {
     console.error('Failed to fetch status:', error);
     return null;
@@ -79,14 +90,19 @@ async function fetchStatus(): Promise<StatusResponse | null> {
 
 async function fetchPins(): Promise<string[]> {
   try {
-    const response = await fetch(`${API_URL}/api/pins`);
-    const data = await response.json();
-    return data.pins || [];
+    const { status, body }: AgentResult = await agentApi().apiListPins();
+    return status === 200 ? body.pins || [] : [];
   } catch {
     return [];
   }
 }
 
+/** Same validation and side effects as POST /api/config. */
+async function applyConfig(updates: Record<string, unknown>): Promise<{ ok: boolean; data: any }> {
+  const { status, body }: AgentResult = await agentApi().apiApplyConfig(updates);
+  return { ok: status < 400, data: body };
+}
+
 async function saveConfig(): Promise<void> {
   const usernameInput = document.getElementById('hiveUsername') as HTMLInputElement;
   const postingKeyInput = document.getElementById('postingKey') as HTMLInputElement;
@@ -94,12 +110,12 @@ async function saveConfig(): Promise<void> {
   const postingKey = postingKeyInput.value.trim();
 
   try {
-    // Save username
-    await fetch(`${API_URL}/api/config`, {
-      method: 'POST',
-      headers: mutationHeaders(),
-      body: JSON.stringify({ hiveUsername: username }),
-    });
+    // Save username — the agent checks the account exists on Hive first
+    const { ok, data } = await applyConfig({ hiveUsername: username });
+    if (!ok) {
+      alert('Failed: ' + (data.message || data.error || 'Unknown error'));
+      return;
+    }
 
     // Save posting key if provided
     if (postingKey) {
@@ -124,13 +140,8 @@ async function saveBandwidth(): Promise<void> {
   const bandwidthLimitDown = parseInt(downInput.value) || 0;
 
   try {
-    const response = await fetch(`${API_URL}/api/config`, {
-      method: 'POST',
-      headers: mutationHeaders(),
-      body: JSON.stringify({ bandwidthLimitUp, bandwidthLimitDown }),
-    });
-    const data = await response.json();
-    if (!response.ok) {
+    const { ok, data } = await applyConfig({ bandwidthLimitUp, bandwidthLimitDown });
+    if (!ok) {
       alert('Failed: ' + (data.error || 'Unknown error'));
     } else if (data.restartDeferred) {
       alert('Bandwidth limits saved! IPFS restart deferred (challenge in progress).');
@@ -147,13 +158,8 @@ async function saveStorage(): Promise<void> {
   const storageMaxGB = parseInt(input.value) || 50;
 
   try {
-    const response = await fetch(`${API_URL}/api/config`, {
-      method: 'POST',
-      headers: mutationHeaders(),
-      body: JSON.stringify({ storageMaxGB }),
-    });
-    const data = await response.json();
-    if (!response.ok) {
+    const { ok, data } = await applyConfig({ storageMaxGB });
+    if (!ok) {
       alert('Failed: ' + (data.error || 'Unknown error'));
     } else if (data.restartDeferred) {
       alert('Storage limit saved! IPFS restart deferred (challenge in progress).');
@@ -172,11 +178,7 @@ async function saveValidation(): Promise<void> {
   const challengeIntervalMs = (parseInt(intervalInput.value) || 5) * 60000;
 
   try {
-    await fetch(`${API_URL}/api/config`, {
-      method: 'POST',
-      headers: mutationHeaders(),
-      body: JSON.stringify({ validatorEnabled, challengeIntervalMs }),
-    });
+    await applyConfig({ validatorEnabled, challengeIntervalMs });
     alert('Validation settings applied!');
   } catch {
     alert('Failed to apply validation settings');
@@ -479,9 +481,21 @@ async function keychainLogin(): Promise<void> {
   }, 2000);
 }
 
+async function copyApiToken(): Promise<void> {
+  if (!localAuthToken) return;
+  try {
+    await navigator.clipboard.writeText(localAuthToken);
+    alert('API token copied to clipboard');
+  } catch {
+    alert('Failed to copy API token');
+  }
+}
+
 document.addEventListener('DOMContentLoaded', async () => {
   // Fetch auth token from main process before any mutation calls
   await fetchAuthToken();
+  const apiTokenEl = document.getElementById('apiToken');
+  if (apiTokenEl) apiTokenEl.textContent = localAuthToken || 'Unavailable';
 
   document.getElementById('saveConfig')?.addEventListener('click', saveConfig);
   document.getElementById('saveBandwidth')?.addEventListener('click', saveBandwidth);
@@ -491,6 +505,7 @@ document.addEventListener('DOMContentLoaded', async () => {
   document.getElementById('saveActiveKey')?.addEventListener('click', saveActiveKey);
   document.getElementById('clearActiveKey')?.addEventListener('click', clearActiveKey);
   document.getElementById('saveTreasury')?.addEventListener('click', saveTreasury);
+  document.getElementById('copyApiToken')?.addEventListener('click', copyApiToken);
 
   updateUI();
   setInterval(updateUI, 5000);
@@ -0,0 +1,12 @@
+import { defineConfig } from "vitest/config";
+
+export default defineConfig({
+  test: {
+    globals: true,
+    environment: "node",
+    include: ["src/**/__tests__/**/*.test.ts"],
+    testTimeout: 15000,
+    // Each file gets its own process so a test's HOME (~/.spk-ipfs) can't leak into another's
+    pool: "forks",
+  },
+});
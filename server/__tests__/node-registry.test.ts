@@ -0,0 +1,71 @@
+/**
+ * Node registry: heartbeat TTL liveness (POST /api/compute/nodes/heartbeat)
+ *
+ * A node whose last heartbeat is older than the TTL is stale and the pool
+ * router stops selecting it; the next heartbeat brings it back.
+ */
+import { describe, it, expect, beforeEach } from "vitest";
+import { NodeRegistry } from "../services/node-registry";
+import { PoolRouterService } from "../services/pool-router-service";
+
+const TTL_MS = 90_000;
+
+describe("NodeRegistry", () => {
+  let registry: NodeRegistry;
+
+  beforeEach(() => {
+    registry = new NodeRegistry(TTL_MS);
+  });
+
+  it("goes stale after the TTL and recovers on the next heartbeat", () => {
+    registry.recordHeartbeat("gpu-a", "node-1", { inFlight: 1 }, 0);
+    expect(registry.isStale("gpu-a", TTL_MS)).toBe(false);
+    expect(registry.isStale("gpu-a", TTL_MS + 1)).toBe(true);
+    expect(registry.sweep(TTL_MS + 1)).toEqual(["gpu-a"]);
+    expect(registry.sweep(TTL_MS + 2)).toEqual([]); // reported once
+
+    registry.recordHeartbeat("gpu-a", "node-1", { inFlight: 0 }, TTL_MS + 5);
+    expect(registry.isStale("gpu-a", TTL_MS + 10)).toBe(false);
+  });
+
+  it("keeps capabilities a heartbeat didn't resend", () => {
+    registry.recordHeartbeat("gpu-a", "node-1", { gpuModel: "RTX 4090", vramFreeMb: 8000, inFlight: 2 }, 0);
+    registry.recordHeartbeat("gpu-a", "node-1", { inFlight: 0, queueDepth: 3 }, 1000);
+    const [entry] = registry.list(1000);
+    expect(entry.capabilities).toMatchObject({ gpuModel: "RTX 4090", vramFreeMb: 8000, inFlight: 0, queueDepth: 3 });
+    expect(entry.heartbeats).toBe(2);
+  });
+
+  it("treats nodes with no heartbeat on record as not stale", () => {
+    expect(registry.isStale("never-seen")).toBe(false);
+    registry.seed("gpu-b", "node-2", null);
+    expect(registry.list()).toHaveLength(0);
+  });
+});
+
+describe("PoolRouterService with a stale node", () => {
+  it("excludes nodes whose heartbeat lapsed", async () => {
+    const registry = new NodeRegistry(TTL_MS);
+    const longAgo = new Date(Date.now() - 10 * 60_000);
+    const router = new PoolRouterService({
+      getPoolReadyNodes: async () => [
+        { id: "node-1", nodeInstanceId: "gpu-live", inferenceEndpoint: "http://127.0.0.1:9", emaScore: 0.5,
+          immunityExpiresAt: null, gpuModel: "RTX 4090", gpuVramGb: 24, maxConcurrentJobs: 1, lastHeartbeatAt: new Date() },
+        { id: "node-2", nodeInstanceId: "gpu-gone", inferenceEndpoint: "http://127.0.0.1:9", emaScore: 0.5,
+          immunityExpiresAt: null, gpuModel: "RTX 3090", gpuVramGb: 24, maxConcurrentJobs: 1, lastHeartbeatAt: longAgo },
+      ],
+      updateNodeEmaScore: async () => {},
+    }, registry);
+    await router.refreshNodes();
+    // Health checks need live endpoints; mark both healthy so only the TTL differs
+    for (const node of (router as any).nodes.values()) node.healthy = true;
+
+    expect(router.selectNodes().map(n => n.nodeInstanceId)).toEqual(["gpu-live"]);
+    const stats = router.getStats().nodes;
+    expect(stats.find(n => n.instanceId === "gpu-gone")?.stale).toBe(true);
+    expect(router.getPressure().nodes.find(n => n.instanceId === "gpu-gone")?.canAccept).toBe(false);
+
+    registry.recordHeartbeat("gpu-gone", "node-2", { inFlight: 0 });
+    expect(router.selectNodes().map(n => n.nodeInstanceId).sort()).toEqual(["gpu-gone", "gpu-live"]);
+  });
+});
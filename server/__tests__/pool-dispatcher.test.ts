@@ -0,0 +1,82 @@
+/**
+ * Pool dispatcher: sliding-window batch dispatch (POST /api/compute/inference/batch)
+ *
+ * Freed slots pull the next item, so faster nodes complete more of the batch;
+ * items stuck past a node's timeout go to another node.
+ */
+import { describe, it, expect } from "vitest";
+import { dispatchSlidingWindow, type DispatchRunner } from "../services/pool-dispatcher";
+
+const sleep = (ms: number) => new Promise(resolve => setTimeout(resolve, ms));
+
+/** Resolves after `ms`, rejects promptly if aborted. */
+function delayed<R>(ms: number, value: R, signal: AbortSignal): Promise<R> {
+  return new Promise((resolve, reject) => {
+    const timer = setTimeout(() => resolve(value), ms);
+    signal.addEventListener("abort", () => { clearTimeout(timer); reject(new Error("aborted")); });
+  });
+}
+
+describe("dispatchSlidingWindow", () => {
+  it("lets the fast node absorb more of the batch without starving the slow one", async () => {
+    const items = Array.from({ length: 30 }, (_v, i) => i);
+    const latency: Record<string, number> = { fast: 2, slow: 40 };
+    const run: DispatchRunner<number, string> = (item, nodeId, signal) => delayed(latency[nodeId], `${nodeId}:${item}`, signal);
+
+    const report = await dispatchSlidingWindow(items, [
+      { nodeId: "fast", window: 2, timeoutMs: 1000 },
+      { nodeId: "slow", window: 2, timeoutMs: 1000 },
+    ], run, 3);
+
+    expect(report.outcomes.every(o => o.ok)).toBe(true);
+    expect(report.outcomes.map(o => o.index)).toEqual(items);
+    expect(report.nodes.fast.completed).toBeGreaterThan(report.nodes.slow.completed);
+    expect(report.nodes.slow.completed).toBeGreaterThan(0);
+  });
+
+  it("never exceeds a node's window", async () => {
+    const active: Record<string, number> = { a: 0, b: 0 };
+    const peak: Record<string, number> = { a: 0, b: 0 };
+    const run: DispatchRunner<number, number> = async (item, nodeId) => {
+      active[nodeId]++;
+      peak[nodeId] = Math.max(peak[nodeId], active[nodeId]);
+      await sleep(3);
+      active[nodeId]--;
+      return item;
+    };
+
+    await dispatchSlidingWindow(Array.from({ length: 20 }, (_v, i) => i), [
+      { nodeId: "a", window: 3, timeoutMs: 1000 },
+      { nodeId: "b", window: 1, timeoutMs: 1000 },
+    ], run, 3);
+
+    expect(peak.a).toBe(3);
+    expect(peak.b).toBe(1);
+  });
+
+  it("re-dispatches an item stuck past the node's timeout to another node", async () => {
+    const run: DispatchRunner<number, string> = (item, nodeId, signal) =>
+      nodeId === "stuck" ? delayed(60_000, "never", signal) : delayed(2, `ok:${item}`, signal);
+
+    const report = await dispatchSlidingWindow([0, 1, 2], [
+      { nodeId: "stuck", window: 1, timeoutMs: 20 },
+      { nodeId: "good", window: 1, timeoutMs: 1000 },
+    ], run, 3);
+
+    expect(report.outcomes.every(o => o.ok)).toBe(true);
+    expect(report.nodes.stuck.timedOut).toBeGreaterThanOrEqual(1);
+    expect(report.redispatched).toBeGreaterThanOrEqual(1);
+  });
+
+  it("gives up on an item after maxAttempts", async () => {
+    const run: DispatchRunner<number, number> = async () => { throw new Error("boom"); };
+
+    const report = await dispatchSlidingWindow([0], [
+      { nodeId: "a", window: 1, timeoutMs: 1000 },
+      { nodeId: "b", window: 1, timeoutMs: 1000 },
+    ], run, 2);
+
+    expect(report.outcomes[0]).toMatchObject({ ok: false, error: "boom", attempts: 2 });
+    expect(report.nodes.a.failed + report.nodes.b.failed).toBe(2);
+  });
+});
@@ -0,0 +1,25 @@
+/**
+ * Pool Router Fixture
+ * A PoolRouterService over one pool-ready GPU node (node-1 / gpu-computer-b),
+ * with nodes already loaded. EMA scores the router persists land in `persisted`.
+ */
+import { PoolRouterService } from "../services/pool-router-service";
+
+export async function makeRouter(): Promise<{ router: PoolRouterService; persisted: Record<string, number> }> {
+  const persisted: Record<string, number> = {};
+  const router = new PoolRouterService({
+    getPoolReadyNodes: async () => [{
+      id: "node-1",
+      nodeInstanceId: "gpu-computer-b",
+      inferenceEndpoint: "http://127.0.0.1:9",
+      emaScore: 0.5,
+      immunityExpiresAt: null,
+      gpuModel: "RTX 4090",
+      gpuVramGb: 24,
+      maxConcurrentJobs: 1,
+    }],
+    updateNodeEmaScore: async (id: string, score: number) => { persisted[id] = score; },
+  });
+  await router.refreshNodes();
+  return { router, persisted };
+}
@@ -0,0 +1,56 @@
+/**
+ * Pool router: latency tiering of routing weights
+ *
+ * A node 1+ pool stdDevs slower than the pool mean gets latencyFactor 0.5,
+ * 2+ gets 0.25; the applied factor is reported by getStats() and getPressure().
+ */
+import { describe, it, expect, beforeEach, afterEach } from "vitest";
+import type { PoolRouterService } from "../services/pool-router-service";
+import { latencyStats } from "../services/health-score";
+import { makeRouter } from "./pool-router-fixture";
+
+const FILLER_NODES = ["filler-a", "filler-b"];
+
+function record(nodeId: string, latencyMs: number, count: number): void {
+  for (let i = 0; i < count; i++) latencyStats.addMeasurement(nodeId, latencyMs);
+}
+
+describe("PoolRouterService latency tiers", () => {
+  let router: PoolRouterService;
+
+  beforeEach(async () => {
+    ({ router } = await makeRouter());
+  });
+
+  afterEach(() => {
+    for (const id of ["node-1", ...FILLER_NODES]) latencyStats.clearNode(id);
+  });
+
+  it("applies no latency factor until the node has enough samples", () => {
+    record("filler-a", 100, 20);
+    record("node-1", 5000, 2);
+    const node = router.getStats().nodes[0];
+    expect(node.latencyFactor).toBe(1.0);
+    expect(node.latencyZScore).toBeNull();
+  });
+
+  it("halves the weight of a node 1-2 stdDevs slower than the pool", () => {
+    record("filler-a", 100, 15);
+    record("filler-b", 100, 15);
+    record("node-1", 400, 10); // z ≈ 1.73
+    const node = router.getStats().nodes[0];
+    expect(node.latencyZScore).toBeGreaterThanOrEqual(1);
+    expect(node.latencyZScore).toBeLessThan(2);
+    expect(node.latencyFactor).toBe(0.5);
+    expect(router.getPressure().nodes[0].latencyFactor).toBe(0.5);
+  });
+
+  it("quarters the weight of a node 2+ stdDevs slower than the pool", () => {
+    record("filler-a", 100, 25);
+    record("filler-b", 100, 25);
+    record("node-1", 1000, 5); // z ≈ 3.2
+    const node = router.getStats().nodes[0];
+    expect(node.latencyZScore).toBeGreaterThanOrEqual(2);
+    expect(node.latencyFactor).toBe(0.25);
+  });
+});
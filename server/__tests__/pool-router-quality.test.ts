@@ -0,0 +1,53 @@
+/**
+ * Pool router: Best-of-N quality reports (POST /api/compute/quality-report)
+ *
+ * Reports feed a per-node quality average and nudge the GPU EMA that routing
+ * weights read; unknown node ids are skipped.
+ */
+import { describe, it, expect, beforeEach } from "vitest";
+import type { PoolRouterService } from "../services/pool-router-service";
+import { makeRouter } from "./pool-router-fixture";
+
+describe("PoolRouterService.handleQualityReport", () => {
+  let router: PoolRouterService;
+  let persisted: Record<string, number>;
+
+  beforeEach(async () => {
+    ({ router, persisted } = await makeRouter());
+  });
+
+  it("matches pool:-prefixed instance ids and records a quality average", () => {
+    const result = router.handleQualityReport([
+      { node_id: "pool:gpu-computer-b", score: 0.9, verified: true, latency_ms: 1200 },
+      { node_id: "pool:unknown-node", score: 0.9, verified: true, latency_ms: 800 },
+    ]);
+    expect(result).toEqual({ matched: 1, unknown: 1 });
+
+    const node = router.getStats().nodes[0];
+    expect(node.qualityScore).toBe(0.9);
+    expect(node.qualityReports).toBe(1);
+  });
+
+  it("raises the GPU EMA for verified high scores and persists it", () => {
+    const before = router.getStats().nodes[0].emaScore;
+    router.handleQualityReport([{ node_id: "gpu-computer-b", score: 0.95, verified: true, latency_ms: 500 }]);
+    expect(router.getStats().nodes[0].emaScore).toBeGreaterThan(before);
+    expect(persisted["node-1"]).toBeDefined();
+  });
+
+  it("lowers the GPU EMA for unverified low scores", () => {
+    const before = router.getStats().nodes[0].emaScore;
+    // The penalty is deliberately small, so apply a few before comparing rounded stats
+    for (let i = 0; i < 5; i++) {
+      router.handleQualityReport([{ node_id: "gpu-computer-b", score: 0.2, verified: false, latency_ms: 500 }]);
+    }
+    expect(router.getStats().nodes[0].emaScore).toBeLessThan(before);
+  });
+
+  it("leaves the EMA alone for middling scores", () => {
+    const before = router.getStats().nodes[0].emaScore;
+    router.handleQualityReport([{ node_id: "gpu-computer-b", score: 0.6, verified: true, latency_ms: 500 }]);
+    expect(router.getStats().nodes[0].emaScore).toBe(before);
+    expect(router.getStats().nodes[0].qualityScore).toBe(0.6);
+  });
+});
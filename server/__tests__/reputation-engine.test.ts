@@ -0,0 +1,74 @@
+/**
+ * Reputation engine: per-node EMA, bonus/penalty rules, decay, persistence
+ *
+ * A coordinator restart reloads standing from REPUTATION_FILE; idle scores
+ * decay halfway to neutral every decayHalfLifeMs.
+ */
+import { describe, it, expect, beforeEach, afterEach } from "vitest";
+import * as fs from "fs";
+import * as os from "os";
+import * as path from "path";
+import { ReputationEngine, NEUTRAL_SCORE } from "../services/reputation-engine";
+
+const HOUR = 3600_000;
+
+describe("ReputationEngine", () => {
+  let dir: string;
+  let file: string;
+
+  beforeEach(() => {
+    dir = fs.mkdtempSync(path.join(os.tmpdir(), "reputation-"));
+    file = path.join(dir, "reputation.json");
+  });
+
+  afterEach(() => {
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+
+  it("moves the class EMA toward each sample", () => {
+    const engine = new ReputationEngine({ alpha: 0.5 });
+    engine.recordSample("node-1", "gpu", 1.0, 800, 0);
+    const rep = engine.get("node-1", 0)!;
+    expect(rep.scores.gpu).toBeCloseTo(0.75);
+    expect(rep.scores.cpu).toBe(NEUTRAL_SCORE);
+    expect(rep.latencyEmaMs).toBe(800);
+  });
+
+  it("applies verification and eval penalties to every class, never below the floor", () => {
+    const engine = new ReputationEngine({ floor: 0.47 });
+    expect(engine.recordVerification("node-1", 1, 3, 0, 0)).toBe(true);
+    expect(engine.get("node-1", 0)!.scores.cpu).toBeCloseTo(0.475);
+    expect(engine.recordVerification("node-1", 1, 1, 0, 0)).toBe(false); // too few samples
+    expect(engine.recordEval("node-1", 0.9, 0)).toBe(false);
+    expect(engine.recordEval("node-1", 0.2, 0)).toBe(true);
+    expect(engine.get("node-1", 0)!.scores.gpu).toBe(0.47);
+  });
+
+  it("decays idle scores halfway to neutral per half-life", () => {
+    const engine = new ReputationEngine({ decayHalfLifeMs: 10 * HOUR });
+    engine.seed("node-1", 0.9, 0);
+    expect(engine.get("node-1", 10 * HOUR)!.scores.gpu).toBeCloseTo(0.7);
+    expect(engine.get("node-1", 20 * HOUR)!.scores.gpu).toBeCloseTo(0.6);
+  });
+
+  it("restores standing after a restart", () => {
+    const before = new ReputationEngine({ decayHalfLifeMs: 0 }, file);
+    before.seed("node-1", 0.5);
+    for (let i = 0; i < 5; i++) before.recordQuality("node-1", 0.95, true);
+    before.flush();
+
+    const after = new ReputationEngine({ decayHalfLifeMs: 0 }, file);
+    after.load();
+    const rep = after.get("node-1")!;
+    expect(rep.qualityReports).toBe(5);
+    expect(rep.scores.gpu).toBeCloseTo(before.get("node-1")!.scores.gpu);
+    expect(rep.scores.gpu).toBeGreaterThan(0.5);
+  });
+
+  it("starts fresh on a corrupt file", () => {
+    fs.writeFileSync(file, "{not json");
+    const engine = new ReputationEngine({}, file);
+    engine.load();
+    expect(engine.has("node-1")).toBe(false);
+  });
+});
@@ -68,6 +68,7 @@ export function createSQLiteTables(dbPath: string) {
       failed_proofs INTEGER NOT NULL DEFAULT 0,
       consecutive_fails INTEGER NOT NULL DEFAULT 0,
       total_earned_hbd REAL NOT NULL DEFAULT 0,
+      seal_commitment TEXT,
       last_seen TEXT NOT NULL DEFAULT (datetime('now')),
       created_at TEXT NOT NULL DEFAULT (datetime('now'))
     );
@@ -691,5 +692,11 @@ export function createSQLiteTables(dbPath: string) {
     );
   `);
 
+  // Columns added after a table was first created — CREATE TABLE IF NOT EXISTS won't add them
+  const nodeColumns = raw.prepare("PRAGMA table_info(storage_nodes)").all() as { name: string }[];
+  if (!nodeColumns.some((c) => c.name === "seal_commitment")) {
+    raw.exec("ALTER TABLE storage_nodes ADD COLUMN seal_commitment TEXT");
+  }
+
   return db;
 }
@@ -198,6 +198,10 @@ app.use((req, res, next) => {
       poaEngine.stop();
       log("PoA engine stopped, pending batches flushed", "shutdown");
     } catch (err) { logger.error({ err }, "PoA flush error during shutdown"); }
+    try {
+      const { reputationEngine } = await import("./services/reputation-engine");
+      reputationEngine.flush();
+    } catch (err) { logger.error({ err }, "Reputation flush error during shutdown"); }
     try { await ipfsManager.stop(); } catch (err) { logger.error({ err }, "IPFS stop error during shutdown"); }
     const { pool } = await import("./db");
     try { await pool.end(); log("Database pool closed", "shutdown"); } catch (err) { logger.error({ err }, "DB pool close error during shutdown"); }
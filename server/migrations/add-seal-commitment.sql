@@ -0,0 +1,4 @@
+-- Add seal_commitment to storage_nodes
+-- Proof-of-replication key commitment, pinned at a node's first sealing registration
+ALTER TABLE storage_nodes
+  ADD COLUMN IF NOT EXISTS seal_commitment TEXT;
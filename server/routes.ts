@@ -32,6 +32,7 @@ import type { Phase2AChallengeStorage } from "./services/phase2a-challenge-servi
 import { Phase2APrecomputeWorker } from "./services/phase2a-precompute-worker";
 import type { PrecomputeStorage } from "./services/phase2a-precompute-worker";
 import { TrustRegistryService } from "./services/trust-registry";
+import { nodeRegistry } from "./services/node-registry";
 import { SpiritBombService } from "./services/spirit-bomb-service";
 import { hiveSimulator as hiveClientForTrust } from "./services/hive-simulator";
 import { createProofHash } from "./services/poa-crypto";
@@ -229,7 +230,8 @@ export async function registerRoutes(
 
   // Pool Router — load-balanced inference routing across GPU nodes
   const { PoolRouterService } = await import("./services/pool-router-service");
-  const poolRouter = new PoolRouterService(storage);
+  const { reputationEngine } = await import("./services/reputation-engine");
+  const poolRouter = new PoolRouterService(storage, nodeRegistry, reputationEngine);
   poolRouter.start().catch(err => logRoutes.error({ err }, "Pool router failed to start"));
 
   // WebSocket for real-time updates (using noServer mode for proper multi-path support)
@@ -5112,6 +5114,17 @@ export async function registerRoutes(
       }).parse(req.body);
       await computeService.heartbeat(node.id, heartbeat.jobsInProgress);
 
+      // Liveness + capability snapshot — stale nodes (no beat within the TTL) are not routed to
+      nodeRegistry.recordHeartbeat(heartbeat.nodeInstanceId, node.id, {
+        gpuModel: node.gpuModel,
+        vramFreeMb: heartbeat.vramTotalMb !== undefined && heartbeat.vramUsedMb !== undefined
+          ? Math.max(0, heartbeat.vramTotalMb - heartbeat.vramUsedMb) : undefined,
+        cpuPct: heartbeat.cpuPct,
+        inFlight: heartbeat.jobsInProgress,
+        queueDepth: heartbeat.queueDepth,
+        contributionTypes: heartbeat.contributionTypes,
+      });
+
       // Persist static fields if sent (nodes self-upgrade via heartbeat)
       const hasStaticUpdates = heartbeat.cpuCores !== undefined || heartbeat.ramGb !== undefined
         || heartbeat.contributionTypes !== undefined || heartbeat.encryptionPublicKey !== undefined
@@ -5237,6 +5250,18 @@ export async function registerRoutes(
     }
   });
 
+  // GET /api/compute/nodes/registry — Heartbeat registry: liveness + last reported capabilities (public)
+  // (/api/nodes is the storage-node registry, so compute liveness lives under /api/compute)
+  app.get("/api/compute/nodes/registry", (_req, res) => {
+    const nodes = nodeRegistry.list();
+    res.json({
+      ttlMs: nodeRegistry.ttl,
+      live: nodes.filter(n => !n.stale).length,
+      stale: nodes.filter(n => n.stale).length,
+      nodes,
+    });
+  });
+
   // GET /api/compute/nodes/:id — Get specific node details (public)
   app.get("/api/compute/nodes/:id", async (req, res) => {
     try {
@@ -6222,6 +6247,47 @@ export async function registerRoutes(
     }
   });
 
+  // POST /api/compute/inference/batch — Many prompts across the pool with per-node sliding windows
+  // Fast nodes pull more items; stuck items are re-dispatched after the node's timeout.
+  app.post("/api/compute/inference/batch", requireAnyAuth, async (req, res) => {
+    const parsed = z.object({
+      items: z.array(z.object({
+        prompt: z.string().min(1).max(100000).optional(),
+        messages: z.array(z.object({
+          role: z.enum(["system", "user", "assistant"]),
+          content: z.string(),
+        })).optional(),
+        max_tokens: z.number().int().min(1).max(16384).default(2048),
+        temperature: z.number().min(0).max(2).default(0.7),
+      }).refine(d => d.prompt || d.messages, { message: "Either prompt or messages is required" })).min(1).max(256),
+      window: z.number().int().min(1).max(16).optional(),
+    }).safeParse(req.body);
+    if (!parsed.success) {
+      res.status(400).json({ error: parsed.error.issues[0]?.message || "Invalid batch" });
+      return;
+    }
+    if (!poolRouter.isAvailable()) {
+      res.status(503).json({ error: { code: "POOL_UNAVAILABLE", message: "No healthy pool nodes available" } });
+      return;
+    }
+    try {
+      const start = Date.now();
+      const items = parsed.data.items.map(item => ({ ...item, mode: "pool" }));
+      const report = await poolRouter.dispatchBatch(items, parsed.data.window);
+      res.json({
+        results: report.outcomes.map(o => o.ok
+          ? { index: o.index, ok: true, text: o.result!.response.text ?? o.result!.response, routed_to: o.result!.nodeInstanceId, latency_ms: o.result!.latencyMs, attempts: o.attempts }
+          : { index: o.index, ok: false, error: o.error, attempts: o.attempts }),
+        nodes: report.nodes,
+        redispatched: report.redispatched,
+        latency_ms: Date.now() - start,
+      });
+    } catch (err: any) {
+      logCompute.warn({ err }, "Batch inference failed");
+      res.status(err.message?.includes("No healthy") ? 503 : 500).json({ error: err.message });
+    }
+  });
+
   // POST /api/compute/inference/stream — SSE streaming inference via pool
   app.post("/api/compute/inference/stream", async (req, res) => {
     try {
@@ -6347,22 +6413,29 @@ export async function registerRoutes(
 
   // Sprint 2: POST /api/compute/quality-report — Receive Best-of-N quality scores from Hive-AI
   // Level 2: API key auth. Level 3: optional Hive signature verification (if _auth present).
+  // Fire-and-forget: validated, acknowledged with 202, then applied to the router's EMA store.
   app.post("/api/compute/quality-report", requireAnyAuth, optionalHiveSignature, async (req: any, res: any) => {
-    try {
-      const schema = z.object({
-        candidates: z.array(z.object({
-          node_id: z.string(),
-          score: z.number().min(0).max(1),
-          verified: z.boolean(),
-          latency_ms: z.number().nonnegative(),
-        })),
-      });
-      const data = schema.parse(req.body);
-      if (poolRouter) poolRouter.handleQualityReport(data.candidates);
-      res.json({ ok: true, processed: data.candidates.length });
-    } catch (err: any) {
-      res.status(400).json({ error: err.message });
+    const schema = z.object({
+      candidates: z.array(z.object({
+        node_id: z.string().min(1),
+        score: z.number().min(0).max(1),
+        verified: z.boolean(),
+        latency_ms: z.number().nonnegative(),
+      })).max(100),
+    });
+    const parsed = schema.safeParse(req.body);
+    if (!parsed.success) {
+      return res.status(400).json({ error: parsed.error.message });
     }
+    res.status(202).json({ ok: true, accepted: parsed.data.candidates.length });
+
+    setImmediate(() => {
+      try {
+        poolRouter.handleQualityReport(parsed.data.candidates);
+      } catch (err: any) {
+        logCompute.warn({ err }, "Quality report processing failed");
+      }
+    });
   });
 
   // Sprint 2: POST /api/compute/verification-report — Receive sandbox verification results
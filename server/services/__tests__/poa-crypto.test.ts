@@ -1,4 +1,5 @@
 import { describe, it, expect } from "vitest";
+import crypto from "crypto";
 import {
   hashFile,
   hashString,
@@ -9,6 +10,9 @@ import {
   createProofRequest,
   verifyProofResponse,
   computeBlockListHash,
+  sealBlock,
+  sealReplicaKey,
+  sealKeyCommitment,
 } from "../poa-crypto";
 import { MockIPFSClient } from "../ipfs-client";
 
@@ -161,6 +165,55 @@ describe("createProofHash", () => {
   });
 });
 
+describe("sealBlock", () => {
+  const secret = Buffer.alloc(32, 7);
+
+  it("round-trips: sealing twice with the same key restores the block", async () => {
+    const key = sealReplicaKey(secret, "alice");
+    const block = Buffer.from("replica test block".repeat(50));
+    const sealed = await sealBlock(key, "bafyblock", block);
+    expect(sealed.equals(block)).toBe(false);
+    expect((await sealBlock(key, "bafyblock", sealed)).equals(block)).toBe(true);
+  });
+
+  it("gives each account and each block its own sealed bytes", async () => {
+    const block = Buffer.from("same bytes on one disk");
+    const alice = await sealBlock(sealReplicaKey(secret, "alice"), "bafyblock", block);
+    const bob = await sealBlock(sealReplicaKey(secret, "bob"), "bafyblock", block);
+    const otherCid = await sealBlock(sealReplicaKey(secret, "alice"), "bafyother", block);
+    expect(alice.equals(bob)).toBe(false);
+    expect(alice.equals(otherCid)).toBe(false);
+  });
+
+  it("can't be keyed from the account name alone", () => {
+    const key = sealReplicaKey(secret, "alice");
+    expect(key.equals(sealReplicaKey(Buffer.alloc(32, 8), "alice"))).toBe(false);
+    expect(key.equals(crypto.createHash("sha256").update("hivepoa-seal-v1:alice").digest())).toBe(false);
+  });
+
+  it("publishes a commitment that differs from the key and pins it", () => {
+    const key = sealReplicaKey(secret, "alice");
+    const commitment = sealKeyCommitment(key);
+    expect(commitment).toMatch(/^[0-9a-f]{64}$/);
+    expect(commitment).not.toBe(key.toString("hex"));
+    expect(sealKeyCommitment(sealReplicaKey(secret, "alice"))).toBe(commitment);
+    expect(sealKeyCommitment(sealReplicaKey(Buffer.alloc(32, 8), "alice"))).not.toBe(commitment);
+  });
+
+  it("makes createProofHash prove the sealed replica instead of the plain file", async () => {
+    const ipfs = new MockIPFSClient();
+    const content = Buffer.from("small sealed file");
+    const cid = await ipfs.add(content);
+    const key = sealReplicaKey(secret, "alice");
+
+    const plain = await createProofHash(ipfs, "salt", cid, []);
+    const sealed = await createProofHash(ipfs, "salt", cid, [], key);
+    const expected = hashFile(Buffer.concat([await sealBlock(key, cid, content), Buffer.from("salt")]));
+    expect(sealed).not.toBe(plain);
+    expect(sealed).toBe(expected);
+  });
+});
+
 describe("createProofRequest", () => {
   it("creates a well-formed proof request", () => {
     const req = createProofRequest("hash123", "QmCid123", "testuser");
@@ -1,7 +1,9 @@
 import { WebSocket } from "ws";
 import { storage } from "../storage";
 import { logWS } from "../logger";
+import { sealKeyCommitment } from "./poa-crypto";
 import type { TreasuryCoordinator } from "./treasury-coordinator";
+import type { StorageNode } from "@shared/schema";
 
 interface ConnectedAgent {
   ws: WebSocket;
@@ -10,6 +12,7 @@ interface ConnectedAgent {
   nodeId: string;
   connectedAt: number;
   lastPong: number;
+  sealKey: Buffer | null; // replica key of an agent that keeps sealed replicas (never stored or published)
 }
 
 const MAX_PENDING_CHALLENGES = 5000;
@@ -104,7 +107,7 @@ class AgentWSManager {
   }
 
   private async handleRegister(ws: WebSocket, message: any): Promise<void> {
-    const { peerId, hiveUsername, version, storageMaxGB } = message;
+    const { peerId, hiveUsername, version, storageMaxGB, sealing, sealKey } = message;
 
     if (!peerId || !hiveUsername) {
       ws.send(JSON.stringify({ type: "error", message: "Missing peerId or hiveUsername" }));
@@ -176,6 +179,8 @@ class AgentWSManager {
       existing.ws.close(4005, "Replaced by new connection");
     }
 
+    const replicaKey = await this.acceptSealKey(node, sealing, sealKey);
+
     this.agents.set(node.id, {
       ws,
       peerId,
@@ -183,6 +188,7 @@ class AgentWSManager {
       nodeId: node.id,
       connectedAt: Date.now(),
       lastPong: Date.now(),
+      sealKey: replicaKey,
     });
     this.peerToNode.set(peerId, node.id);
 
@@ -285,7 +291,8 @@ class AgentWSManager {
     cid: string,
     salt: string,
     validatorUsername: string,
-    timeoutMs: number = 30_000
+    timeoutMs: number = 30_000,
+    sealed: boolean = false
   ): Promise<{ status: "success" | "fail" | "timeout"; proofHash?: string; elapsed: number; error?: string }> {
     const agent = this.agents.get(nodeId);
     if (!agent || agent.ws.readyState !== WebSocket.OPEN) {
@@ -321,10 +328,46 @@ class AgentWSManager {
         CID: cid,
         User: validatorUsername,
         Status: "Pending",
+        ...(sealed && { Sealed: true }),
       }));
     });
   }
 
+  /** True if the connected agent registered with sealing enabled and an accepted replica key. */
+  supportsSealing(nodeId: string): boolean {
+    return !!this.agents.get(nodeId)?.sealKey;
+  }
+
+  /** Replica key to re-derive the agent's sealed proofs with, or null. */
+  getSealKey(nodeId: string): Buffer | null {
+    return this.agents.get(nodeId)?.sealKey ?? null;
+  }
+
+  /**
+   * Proof-of-replication: the key's commitment is pinned to the node the first
+   * time it registers with sealing, and that is the only key accepted afterwards
+   * — otherwise a node could re-key onto a replica it doesn't hold.
+   */
+  private async acceptSealKey(node: StorageNode, sealing: unknown, sealKeyHex: unknown): Promise<Buffer | null> {
+    if (sealing !== true || typeof sealKeyHex !== "string" || !/^[0-9a-f]{64}$/.test(sealKeyHex)) return null;
+    const key = Buffer.from(sealKeyHex, "hex");
+    const commitment = sealKeyCommitment(key);
+    if (!node.sealCommitment) {
+      try {
+        await storage.setStorageNodeSealCommitment(node.id, commitment);
+      } catch (err) {
+        logWS.error({ err, nodeId: node.id }, "Failed to store seal key commitment");
+        return null;
+      }
+      logWS.info({ nodeId: node.id, commitment }, "Seal key commitment pinned");
+    } else if (node.sealCommitment !== commitment) {
+      logWS.warn({ nodeId: node.id, expected: node.sealCommitment, got: commitment },
+        "Seal key does not match the pinned commitment — no sealed challenges for this node");
+      return null;
+    }
+    return key;
+  }
+
   isAgentConnected(nodeId: string): boolean {
     const agent = this.agents.get(nodeId);
     return !!agent && agent.ws.readyState === WebSocket.OPEN;
@@ -0,0 +1,125 @@
+/**
+ * Node Registry — Heartbeat liveness and capability metadata for compute nodes.
+ *
+ * Every POST /api/compute/nodes/heartbeat lands here with the node's latest
+ * capabilities (GPU model, free VRAM, CPU load, in-flight jobs, queue depth).
+ * A node whose last heartbeat is older than HEARTBEAT_TTL_MS is stale: the
+ * pool router stops routing to it until it beats again. Workers beat every
+ * 20-30s (docs/COMPUTE_API_CONTRACT.md), so the TTL tolerates two missed beats.
+ *
+ * Nodes with no heartbeat on record (nothing seen since startup and no
+ * lastHeartbeatAt in the DB) are not considered stale — health checks decide.
+ */
+
+export const HEARTBEAT_TTL_MS = 90_000;
+
+export interface NodeCapabilities {
+  gpuModel: string | null;
+  vramFreeMb: number | null;
+  cpuPct: number | null;
+  inFlight: number;
+  queueDepth: number | null;
+  contributionTypes: string | null;
+}
+
+export interface RegistryEntry {
+  nodeId: string;
+  nodeInstanceId: string;
+  lastHeartbeatAt: number;
+  heartbeats: number;
+  capabilities: NodeCapabilities;
+  stale: boolean;
+}
+
+const EMPTY_CAPABILITIES: NodeCapabilities = {
+  gpuModel: null,
+  vramFreeMb: null,
+  cpuPct: null,
+  inFlight: 0,
+  queueDepth: null,
+  contributionTypes: null,
+};
+
+export class NodeRegistry {
+  private entries = new Map<string, RegistryEntry>(); // keyed by nodeInstanceId
+  private ttlMs: number;
+
+  constructor(ttlMs: number = HEARTBEAT_TTL_MS) {
+    this.ttlMs = ttlMs;
+  }
+
+  /** Record a heartbeat. Capabilities not sent in this beat keep their last known value. */
+  recordHeartbeat(nodeInstanceId: string, nodeId: string, capabilities: Partial<NodeCapabilities>, now = Date.now()): RegistryEntry {
+    const existing = this.entries.get(nodeInstanceId);
+    const merged = { ...(existing?.capabilities ?? EMPTY_CAPABILITIES) };
+    for (const [key, value] of Object.entries(capabilities)) {
+      if (value !== undefined) (merged as any)[key] = value;
+    }
+    const entry: RegistryEntry = {
+      nodeId,
+      nodeInstanceId,
+      lastHeartbeatAt: now,
+      heartbeats: (existing?.heartbeats ?? 0) + 1,
+      capabilities: merged,
+      stale: false,
+    };
+    if (existing?.stale) console.log(`[node-registry] ${nodeInstanceId} is live again`);
+    this.entries.set(nodeInstanceId, entry);
+    return entry;
+  }
+
+  /**
+   * Seed a node from its persisted lastHeartbeatAt so a server restart doesn't
+   * forget who was alive. No-op once the node has beaten in this process.
+   */
+  seed(nodeInstanceId: string, nodeId: string, lastHeartbeatAt: Date | null | undefined): void {
+    if (this.entries.has(nodeInstanceId) || !lastHeartbeatAt) return;
+    this.entries.set(nodeInstanceId, {
+      nodeId,
+      nodeInstanceId,
+      lastHeartbeatAt: lastHeartbeatAt.getTime(),
+      heartbeats: 0,
+      capabilities: { ...EMPTY_CAPABILITIES },
+      stale: false,
+    });
+  }
+
+  /** True if the node has a heartbeat on record and it is older than the TTL. */
+  isStale(nodeInstanceId: string, now = Date.now()): boolean {
+    const entry = this.entries.get(nodeInstanceId);
+    return entry !== undefined && now - entry.lastHeartbeatAt > this.ttlMs;
+  }
+
+  /** Flag nodes that have crossed the TTL since the last sweep. Returns their instance ids. */
+  sweep(now = Date.now()): string[] {
+    const newlyStale: string[] = [];
+    this.entries.forEach((entry) => {
+      const stale = now - entry.lastHeartbeatAt > this.ttlMs;
+      if (stale && !entry.stale) newlyStale.push(entry.nodeInstanceId);
+      entry.stale = stale;
+    });
+    return newlyStale;
+  }
+
+  /** Every known node, most recent heartbeat first, with staleness computed at call time. */
+  list(now = Date.now()): (RegistryEntry & { ageMs: number })[] {
+    return Array.from(this.entries.values())
+      .map(entry => ({
+        ...entry,
+        capabilities: { ...entry.capabilities },
+        stale: now - entry.lastHeartbeatAt > this.ttlMs,
+        ageMs: now - entry.lastHeartbeatAt,
+      }))
+      .sort((a, b) => b.lastHeartbeatAt - a.lastHeartbeatAt);
+  }
+
+  remove(nodeInstanceId: string): void {
+    this.entries.delete(nodeInstanceId);
+  }
+
+  get ttl(): number {
+    return this.ttlMs;
+  }
+}
+
+export const nodeRegistry = new NodeRegistry();
@@ -31,6 +31,37 @@ export function createSaltWithEntropy(hiveBlockHash: string): string {
   return crypto.createHash("sha256").update(combined).digest("hex");
 }
 
+/**
+ * Proof-of-replication sealing — must match desktop-agent/src/main/poa-crypto.ts.
+ * A sealed block is the block XOR scrypt(replicaKey, "seal:<blockCid>"), with
+ * the replica key an HMAC of the Hive account under a secret only the node
+ * holds: each node has to store its own sealed copy, and re-sealing on demand
+ * costs a memory-hard scrypt run per challenged block. Agents send the key
+ * when they register; only its commitment is stored and published.
+ */
+export const SEAL_COST_LOG2 = 17;
+const SEAL_SCRYPT_R = 8;
+
+export function sealReplicaKey(secret: Buffer, account: string): Buffer {
+  return crypto.createHmac("sha256", secret).update(`hivepoa-seal-v1:${account}`).digest();
+}
+
+export function sealKeyCommitment(replicaKey: Buffer): string {
+  return hashString(`hivepoa-seal-commit-v1:${replicaKey.toString("hex")}`);
+}
+
+export async function sealBlock(replicaKey: Buffer, blockCid: string, data: Buffer): Promise<Buffer> {
+  const N = 2 ** SEAL_COST_LOG2;
+  const keystream = await new Promise<Buffer>((resolve, reject) => {
+    crypto.scrypt(replicaKey, `seal:${blockCid}`, Math.max(1, data.length), {
+      N, r: SEAL_SCRYPT_R, p: 1, maxmem: 256 * N * SEAL_SCRYPT_R,
+    }, (err, key) => (err ? reject(err) : resolve(key)));
+  });
+  const sealed = Buffer.allocUnsafe(data.length);
+  for (let i = 0; i < data.length; i++) sealed[i] = data[i] ^ keystream[i];
+  return sealed;
+}
+
 export function getIntFromHash(hash: string, length: number): number {
   if (length <= 1) {
     return 0;
@@ -56,11 +87,13 @@ export async function appendHashToBlock(
   return combined;
 }
 
+/** `replicaKey` computes the proof over the node's sealed replica instead of the plain blocks. */
 export async function createProofHash(
   ipfs: IPFSClient,
   hash: string,
   cid: string,
-  blockCids: string[]
+  blockCids: string[],
+  replicaKey?: Buffer
 ): Promise<string> {
   logPoA.info(`[PoA Crypto] Proof CID: ${cid}`);
   
@@ -68,7 +101,8 @@ export async function createProofHash(
   logPoA.info(`[PoA Crypto] Block count: ${length}`);
   
   if (length === 0) {
-    const fileBuffer = await ipfs.cat(cid);
+    let fileBuffer = await ipfs.cat(cid);
+    if (replicaKey) fileBuffer = await sealBlock(replicaKey, cid, fileBuffer);
     const combined = Buffer.concat([fileBuffer, Buffer.from(hash)]);
     return hashFile(combined);
   }
@@ -94,7 +128,8 @@ export async function createProofHash(
   
   try {
     const blockPromises = blocksToFetch.map(async (blockIndex) => {
-      const blockBuffer = await ipfs.cat(blockCids[blockIndex]);
+      let blockBuffer = await ipfs.cat(blockCids[blockIndex]);
+      if (replicaKey) blockBuffer = await sealBlock(replicaKey, blockCids[blockIndex], blockBuffer);
       return { index: blockIndex, buffer: blockBuffer };
     });
     
@@ -74,6 +74,15 @@ export const POA_CONFIG = {
 
   // Timeouts — must exceed anti-cheat window (25s) to give agents time to compute proofs
   CHALLENGE_TIMEOUT_MS: 30_000,
+
+  // Proof-of-replication: "off", "prefer" (sealed challenges for agents that seal,
+  // plain fallback while a pin is still being sealed) or "require" (unsealed = fail)
+  SEALED_PROOFS: (process.env.POA_SEALED_PROOFS || "off") as "off" | "prefer" | "require",
+  // A stored replica answers fast; re-sealing 5 blocks on demand takes seconds
+  SEALED_DEADLINE_MS: envInt("POA_SEALED_DEADLINE_MS", 3_000, 3_000),
+  // "prefer" only: plain fallbacks per node per 24h for pins it hasn't sealed yet.
+  // Past this, NOT_SEALED fails — otherwise a node could just never seal anything.
+  SEALED_FALLBACKS_PER_DAY: envInt("POA_SEALED_FALLBACKS_PER_DAY", 5, 5),
 };
 
 // LRU Cache with TTL for block CIDs
@@ -154,6 +163,8 @@ export class PoAEngine {
   private nodeFileCooldowns: Map<string, number> = new Map();
   // Key: nodeId, Value: timestamp of last challenge (any file)
   private nodeCooldowns: Map<string, number> = new Map();
+  // Key: nodeId, Value: times of plain fallbacks after NOT_SEALED in the last 24h
+  private sealedFallbacks: Map<string, number[]> = new Map();
 
   // Micropayment batching: accumulate rewards until threshold, then issue one payout
   // SPK 1.0 spec: 10 proofs = 1 combined transaction (reduces chain bloat)
@@ -699,14 +710,38 @@ export class PoAEngine {
       }
 
       // ── Phase 2: Challenge (same as v1) ───────────────────────────────
-      const phase2Start = Date.now();
-      const result = await agentWSManager.challengeAgent(
+      // Proof-of-replication: ask for a proof over the agent's sealed replica
+      let sealed = POA_CONFIG.SEALED_PROOFS === "require" ||
+        (POA_CONFIG.SEALED_PROOFS === "prefer" && agentWSManager.supportsSealing(node.id));
+      const sealKey = sealed ? agentWSManager.getSealKey(node.id) : null;
+      if (sealed && !sealKey) {
+        // "require" and the agent registered no accepted replica key — its sealed proof can't be checked
+        logPoA.info(`[PoA] AGENT-WS FAILED: ${node.hiveUsername} has no accepted seal key (sealed proofs required)`);
+        await this.recordChallengeResult(challengeId, node.id, file.id, "NOT_SEALED", "fail", Date.now() - startTime);
+        return;
+      }
+      let phase2Start = Date.now();
+      let result = await agentWSManager.challengeAgent(
         node.id,
         file.cid,
         salt,
         this.config.validatorUsername,
-        POA_CONFIG.CHALLENGE_TIMEOUT_MS
+        POA_CONFIG.CHALLENGE_TIMEOUT_MS,
+        sealed
       );
+      if (sealed && POA_CONFIG.SEALED_PROOFS === "prefer" && result.status === "fail" && result.error === "NOT_SEALED" &&
+          this.takeSealedFallback(node.id)) {
+        // Pin still being sealed on the agent — plain proof this round
+        sealed = false;
+        phase2Start = Date.now();
+        result = await agentWSManager.challengeAgent(
+          node.id,
+          file.cid,
+          salt,
+          this.config.validatorUsername,
+          POA_CONFIG.CHALLENGE_TIMEOUT_MS
+        );
+      }
 
       // Use server-measured elapsed time (don't trust agent-reported timing)
       const serverElapsed = Date.now() - phase2Start;
@@ -722,9 +757,11 @@ export class PoAEngine {
         return;
       }
 
-      // Anti-cheat timing check (25s max — proves data was pre-stored)
-      if (serverElapsed >= 25_000) {
-        logPoA.info(`[PoA] TIMING FAIL: ${node.hiveUsername} took ${serverElapsed}ms server-side (>25s limit)`);
+      // Anti-cheat timing check (25s max — proves data was pre-stored; sealed proofs
+      // get a much tighter deadline so the replica can't be sealed on demand)
+      const deadlineMs = sealed ? POA_CONFIG.SEALED_DEADLINE_MS : 25_000;
+      if (serverElapsed >= deadlineMs) {
+        logPoA.info(`[PoA] TIMING FAIL: ${node.hiveUsername} took ${serverElapsed}ms server-side (>${deadlineMs}ms limit${sealed ? ", sealed" : ""})`);
         await this.recordChallengeResult(challengeId, node.id, file.id, "TOO_SLOW", "fail", totalElapsed);
         return;
       }
@@ -746,10 +783,11 @@ export class PoAEngine {
         }
       }
 
-      const expectedProofHash = await createProofHash(this.ipfsClient, salt, file.cid, blockCids || []);
+      const expectedProofHash = await createProofHash(
+        this.ipfsClient, salt, file.cid, blockCids || [], sealed ? sealKey! : undefined);
 
       if (result.proofHash && result.proofHash === expectedProofHash) {
-        const phase = commitmentVerified ? 'v2' : 'v1-fallback';
+        const phase = (commitmentVerified ? 'v2' : 'v1-fallback') + (sealed ? '+sealed' : '');
         logPoA.info(`[PoA] AGENT-WS PASSED [${phase}]: ${node.hiveUsername} (${totalElapsed}ms)`);
         await this.recordChallengeResult(challengeId, node.id, file.id, result.proofHash, "success", totalElapsed);
       } else {
@@ -762,6 +800,20 @@ export class PoAEngine {
     }
   }
 
+  /** Use up one of the node's daily plain fallbacks; false once they are gone. */
+  private takeSealedFallback(nodeId: string): boolean {
+    const since = Date.now() - 86_400_000;
+    const recent = (this.sealedFallbacks.get(nodeId) || []).filter((at) => at > since);
+    if (recent.length >= POA_CONFIG.SEALED_FALLBACKS_PER_DAY) {
+      logPoA.info(`[PoA] ${nodeId}: ${recent.length} NOT_SEALED fallbacks in 24h — failing this one`);
+      this.sealedFallbacks.set(nodeId, recent);
+      return false;
+    }
+    recent.push(Date.now());
+    this.sealedFallbacks.set(nodeId, recent);
+    return true;
+  }
+
   private async processSimulatedChallenge(
     challengeId: string, 
     nodeId: string, 
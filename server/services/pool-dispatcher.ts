@@ -0,0 +1,140 @@
+/**
+ * Pool Dispatcher — Sliding-window, work-stealing dispatch of a batch across nodes.
+ *
+ * Instead of splitting a batch up front and waiting for the slowest node,
+ * every node gets a window of W in-flight items (W sized by its routing
+ * weight, at least 1 so slow contributors still get work). Whenever an item
+ * finishes, that node's freed slot pulls the next item from the shared
+ * queue — fast nodes naturally absorb more of the batch.
+ *
+ * An item running longer than its node's timeout is aborted and put back at
+ * the front of the queue for a node that hasn't tried it yet; failures are
+ * retried the same way, up to maxAttempts.
+ */
+
+export interface DispatchSlot {
+  nodeId: string;
+  /** Items this node may have in flight at once. */
+  window: number;
+  /** Per-item timeout on this node before the item is re-dispatched. */
+  timeoutMs: number;
+}
+
+export interface DispatchOutcome<R> {
+  index: number;
+  ok: boolean;
+  nodeId: string | null; // node that produced the result (or last tried)
+  result?: R;
+  error?: string;
+  attempts: number;
+}
+
+export interface DispatchNodeSummary {
+  window: number;
+  completed: number;
+  failed: number;
+  timedOut: number;
+}
+
+export interface DispatchReport<R> {
+  outcomes: DispatchOutcome<R>[]; // same order as the input items
+  nodes: Record<string, DispatchNodeSummary>;
+  redispatched: number;
+}
+
+export type DispatchRunner<T, R> = (item: T, nodeId: string, signal: AbortSignal) => Promise<R>;
+
+export async function dispatchSlidingWindow<T, R>(
+  items: T[],
+  slots: DispatchSlot[],
+  run: DispatchRunner<T, R>,
+  maxAttempts: number,
+): Promise<DispatchReport<R>> {
+  const outcomes: DispatchOutcome<R>[] = new Array(items.length);
+  const nodes: Record<string, DispatchNodeSummary> = {};
+  for (const slot of slots) nodes[slot.nodeId] = { window: slot.window, completed: 0, failed: 0, timedOut: 0 };
+  const report: DispatchReport<R> = { outcomes, nodes, redispatched: 0 };
+
+  if (items.length === 0) return report;
+  if (slots.length === 0) {
+    items.forEach((_item, index) => {
+      outcomes[index] = { index, ok: false, nodeId: null, error: "No nodes available", attempts: 0 };
+    });
+    return report;
+  }
+
+  const queue = items.map((_item, index) => index);
+  const tried = items.map(() => new Set<string>());
+  const attempts = items.map(() => 0);
+  const inFlight = new Map<string, number>(slots.map(s => [s.nodeId, 0]));
+  let remaining = items.length;
+
+  return new Promise(resolve => {
+    // Next queued item this node may take: one it hasn't tried, or any once every node has tried it
+    const nextFor = (nodeId: string): number => {
+      const pos = queue.findIndex(i => !tried[i].has(nodeId) || tried[i].size >= slots.length);
+      return pos === -1 ? -1 : queue.splice(pos, 1)[0];
+    };
+
+    const settle = (index: number, slot: DispatchSlot, outcome: { ok: true; result: R } | { ok: false; error: string }) => {
+      inFlight.set(slot.nodeId, inFlight.get(slot.nodeId)! - 1);
+      if (outcome.ok) {
+        nodes[slot.nodeId].completed++;
+        outcomes[index] = { index, ok: true, nodeId: slot.nodeId, result: outcome.result, attempts: attempts[index] };
+        remaining--;
+      } else if (attempts[index] < maxAttempts) {
+        queue.unshift(index); // retried before fresh items so stragglers don't hold up the tail
+        report.redispatched++;
+      } else {
+        outcomes[index] = { index, ok: false, nodeId: slot.nodeId, error: outcome.error, attempts: attempts[index] };
+        remaining--;
+      }
+      if (remaining === 0) resolve(report);
+      else fill();
+    };
+
+    const start = (index: number, slot: DispatchSlot) => {
+      attempts[index]++;
+      tried[index].add(slot.nodeId);
+      inFlight.set(slot.nodeId, inFlight.get(slot.nodeId)! + 1);
+      const controller = new AbortController();
+      let done = false;
+
+      const timer = setTimeout(() => {
+        if (done) return;
+        done = true;
+        controller.abort();
+        nodes[slot.nodeId].timedOut++;
+        settle(index, slot, { ok: false, error: `Timed out after ${slot.timeoutMs}ms on ${slot.nodeId}` });
+      }, slot.timeoutMs);
+
+      run(items[index], slot.nodeId, controller.signal).then(
+        (result) => {
+          if (done) return;
+          done = true;
+          clearTimeout(timer);
+          settle(index, slot, { ok: true, result });
+        },
+        (err: any) => {
+          if (done) return;
+          done = true;
+          clearTimeout(timer);
+          nodes[slot.nodeId].failed++;
+          settle(index, slot, { ok: false, error: err?.message ?? String(err) });
+        },
+      );
+    };
+
+    const fill = () => {
+      for (const slot of slots) {
+        while (inFlight.get(slot.nodeId)! < slot.window) {
+          const index = nextFor(slot.nodeId);
+          if (index === -1) break;
+          start(index, slot);
+        }
+      }
+    };
+
+    fill();
+  });
+}
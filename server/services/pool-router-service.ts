@@ -3,14 +3,19 @@
  *
  * Health-checks registered nodes, maintains EMA quality scores,
  * selects the best node for each inference request, and handles failover.
+ * Nodes whose heartbeats have lapsed (see node-registry.ts) are skipped.
+ * Scores live in the ReputationEngine (reputation-engine.ts), which persists
+ * them across restarts; node state holds a synced copy for routing.
  *
  * Routing weight formula:
  *   weight = emaScore × utilFactor × vramFactor × loadFactor × stakeFactor × latencyFactor
  */
 import { type ComputeNode } from "@shared/schema";
 import { latencyStats } from "./health-score";
+import { nodeRegistry, type NodeRegistry } from "./node-registry";
+import { ReputationEngine } from "./reputation-engine";
+import { dispatchSlidingWindow, type DispatchReport } from "./pool-dispatcher";
 
-const EMA_ALPHA = 0.1; // Slow-moving average (~10 request half-life)
 const MAX_WEIGHT_CAP = 0.3; // No single node gets >30% of traffic
 const IMMUNITY_FLOOR = 0.3; // Immune nodes get at least this weight
 const HEALTH_CHECK_INTERVAL_MS = 10_000; // 10 seconds
@@ -20,7 +25,12 @@ const INFERENCE_TIMEOUT_MS = 120_000; // 2 min
 const MAX_CONSECUTIVE_FAILURES = 3;
 const MAX_FAILOVER_ATTEMPTS = 3;
 const MAX_EXPECTED_LATENCY_MS = 60_000; // For EMA score calculation
+const LATENCY_MIN_SAMPLES = 5; // Per-node samples before latency affects routing weight
 const INFLIGHT_TTL_MS = 180_000; // 3 min — auto-reap stale inFlight entries
+const DISPATCH_MAX_WINDOW = 4; // Batch items in flight on the highest-weighted node
+const DISPATCH_MAX_ATTEMPTS = 3; // Per batch item, across nodes
+const DISPATCH_TIMEOUT_P95_MULTIPLIER = 3; // Re-dispatch an item after 3× the node's p95 latency
+const DISPATCH_MIN_TIMEOUT_MS = 15_000;
 // Allocatable scheduling: reserve resources for OS/desktop, never schedule against total
 const VRAM_HOST_RESERVE_MB = 2048; // 2GB reserved for Windows/display/OS
 const RAM_HOST_RESERVE_MB = 4096;  // 4GB reserved for OS/apps
@@ -68,6 +78,19 @@ interface PoolNodeState {
   // Percentage of time stalled in last 10s (0-100). Only available on Linux with cgroup v2.
   psiCpuSome: number;    // CPU pressure: % time at least one task stalled on CPU
   psiMemorySome: number; // Memory pressure: % time at least one task stalled on memory
+  // Best-of-N quality reports from Hive-AI (null until the first report)
+  qualityScore: number | null;
+  qualityReports: number;
+}
+
+interface InferenceBody {
+  prompt?: string;
+  messages?: { role: string; content: string }[];
+  max_tokens?: number;
+  temperature?: number;
+  mode?: string;
+  encrypted?: any;
+  targetNodeId?: string;
 }
 
 interface RoutingResult {
@@ -86,6 +109,7 @@ interface PoolStats {
     gpu: string;
     vramGb: number;
     healthy: boolean;
+    stale: boolean;
     emaScore: number;
     utilization: number;
     inFlight: number;
@@ -95,6 +119,9 @@ interface PoolStats {
     vramTotalMb: number;
     vramPressurePct: number;
     latency?: { mean: number; stdDev: number; p50: number; p95: number; count: number };
+    // Routing weight multiplier from latency tiering, and the z-score behind it
+    latencyFactor: number;
+    latencyZScore: number | null;
     gpuTempC: number;
     pressure: "low" | "medium" | "high" | "critical";
     // CPU+RAM pooling
@@ -107,6 +134,8 @@ interface PoolStats {
     maxConcurrentCpuJobs: number;
     encryptionPublicKey: string | null;
     encryptionKeyVersion: number;
+    qualityScore: number | null;
+    qualityReports: number;
   }[];
   healthyCount: number;
   totalVramGb: number;
@@ -129,9 +158,13 @@ export class PoolRouterService {
   private running = false;
   // Phase 2: Track inFlight requests with timestamps for TTL reaping
   private inFlightTracking = new Map<string, Map<string, number>>();
+  private registry: NodeRegistry;
+  private reputation: ReputationEngine;
 
-  constructor(storage: any) {
+  constructor(storage: any, registry: NodeRegistry = nodeRegistry, reputation: ReputationEngine = new ReputationEngine()) {
     this.storage = storage;
+    this.registry = registry;
+    this.reputation = reputation;
   }
 
   /** Start background health-check loops. */
@@ -140,6 +173,9 @@ export class PoolRouterService {
     this.running = true;
     log.info("Starting pool router service");
 
+    // Restore node standing from the last run before nodes are seeded from the DB
+    this.reputation.load();
+
     // Initial load of nodes from DB
     await this.refreshNodes();
 
@@ -157,6 +193,7 @@ export class PoolRouterService {
     this.running = false;
     if (this.healthTimer) clearInterval(this.healthTimer);
     if (this.statusTimer) clearInterval(this.statusTimer);
+    this.reputation.flush();
     log.info("Pool router stopped");
   }
 
@@ -168,12 +205,13 @@ export class PoolRouterService {
 
       for (const node of dbNodes) {
         if (!node.inferenceEndpoint) continue;
+        this.registry.seed(node.nodeInstanceId, node.id, node.lastHeartbeatAt);
 
         const existing = this.nodes.get(node.id);
         if (existing) {
           // Update from DB but keep runtime state (telemetry, inFlight, etc.)
           existing.inferenceEndpoint = node.inferenceEndpoint;
-          existing.emaScore = node.emaScore;
+          this.applyReputation(existing); // picks up idle decay
           existing.immuneUntil = node.immunityExpiresAt?.getTime() ?? 0;
           // Re-read CPU/RAM/contribution fields from DB (may have changed via registration or heartbeat)
           existing.cpuCores = (node as any).cpuCores ?? existing.cpuCores;
@@ -183,7 +221,8 @@ export class PoolRouterService {
           if (dbContrib) existing.contributionTypes = this.parseContributionTypes(dbContrib);
           existing.maxConcurrentCpuJobs = Math.max(1, Math.floor(existing.cpuCores / 2));
         } else {
-          // New node
+          // New node — the DB emaScore only seeds nodes the reputation engine has never seen
+          const rep = this.reputation.seed(node.id, node.emaScore, now);
           this.nodes.set(node.id, {
             nodeId: node.id,
             nodeInstanceId: node.nodeInstanceId,
@@ -191,8 +230,8 @@ export class PoolRouterService {
             healthy: false, // unknown until first health check
             lastHealthCheck: 0,
             gpuUtilizationPct: 0,
-            emaScore: node.emaScore,
-            emaScores: { gpu: node.emaScore, cpu: 0.5, ram: 0.5 },
+            emaScore: this.reputation.aggregate(rep),
+            emaScores: { ...rep.scores },
             immuneUntil: node.immunityExpiresAt?.getTime() ?? 0,
             consecutiveFailures: 0,
             inFlightRequests: 0,
@@ -221,6 +260,8 @@ export class PoolRouterService {
             cpuEndpointUrl: (node as any).cpuEndpointUrl ?? null,
             psiCpuSome: 0,
             psiMemorySome: 0,
+            qualityScore: rep.qualityScore,
+            qualityReports: rep.qualityReports,
           });
           // Phase 2: initialize inFlight tracking for this node
           this.inFlightTracking.set(node.id, new Map());
@@ -273,6 +314,10 @@ export class PoolRouterService {
 
     await Promise.allSettled(checks);
 
+    for (const instanceId of this.registry.sweep()) {
+      log.warn(`Node ${instanceId} missed heartbeats for ${this.registry.ttl / 1000}s — excluded from routing`);
+    }
+
     // Phase 2: Reap stale inFlight entries and self-heal counters
     this.reapStaleInFlight();
   }
@@ -326,9 +371,46 @@ export class PoolRouterService {
    *  @param resourceType — filter by contribution type: "gpu" (default), "cpu", or "ram"
    */
   selectNodes(resourceType: ResourceType = "gpu"): PoolNodeState[] {
+    const weighted = this.weighNodes(resourceType);
+    if (weighted.length === 0) return [];
+
+    // Cap max weight
+    const totalWeight = weighted.reduce((sum, w) => sum + w.weight, 0);
+    if (totalWeight > 0) {
+      for (const w of weighted) {
+        if (w.weight / totalWeight > MAX_WEIGHT_CAP) {
+          w.weight = totalWeight * MAX_WEIGHT_CAP;
+        }
+      }
+    }
+
+    // Weighted random selection — probabilistic, not deterministic
+    // This ensures all healthy nodes get SOME traffic proportional to their weight
+    const totalW = weighted.reduce((sum, w) => sum + w.weight, 0);
+    if (totalW > 0) {
+      const rand = Math.random() * totalW;
+      let cumulative = 0;
+      let selectedIdx = 0;
+      for (let i = 0; i < weighted.length; i++) {
+        cumulative += weighted[i].weight;
+        if (rand <= cumulative) { selectedIdx = i; break; }
+      }
+      // Put selected first, rest as fallbacks sorted by weight
+      const selected = weighted.splice(selectedIdx, 1)[0];
+      weighted.sort((a, b) => b.weight - a.weight);
+      weighted.unshift(selected);
+    }
+
+    return weighted.map(w => w.node);
+  }
+
+  /** Eligible nodes (healthy, live, with spare capacity) and their uncapped routing weights. */
+  private weighNodes(resourceType: ResourceType): { node: PoolNodeState; weight: number }[] {
     const now = Date.now();
     const healthy = Array.from(this.nodes.values()).filter(n => {
       if (!n.healthy) return false;
+      // Heartbeat lapsed: the node may be gone even if its endpoint still answers
+      if (this.registry.isStale(n.nodeInstanceId, now)) return false;
       // Filter by resource contribution type
       if (!n.contributionTypes.includes(resourceType)) return false;
       // CPU nodes must have a cpuEndpointUrl to actually serve CPU workloads
@@ -339,10 +421,8 @@ export class PoolRouterService {
       return true;
     });
 
-    if (healthy.length === 0) return [];
-
     // Compute weights — formula varies by resource type
-    const weighted = healthy.map(node => {
+    return healthy.map(node => {
       const isImmune = now < node.immuneUntil;
       const stakeFactor = 1 + Math.log2(Math.max(node.hivePower, 100) / 100) * 0.1;
 
@@ -383,17 +463,8 @@ export class PoolRouterService {
                          : vramPressure > 0.8 ? 0.4
                          : 1 - (vramPressure * 0.5);
 
-        // Latency — aggressive dampening for slow nodes
-        let latencyFactor = 1.0;
-        const nodeStats = latencyStats.getStatistics(node.nodeId);
-        const globalStats = latencyStats.getGlobalStatistics();
-        if (nodeStats && nodeStats.count >= 5 && globalStats && globalStats.stdDev > 0) {
-          const nodeVsGlobal = (nodeStats.mean - globalStats.mean) / globalStats.stdDev;
-          if (nodeVsGlobal > 2.0) latencyFactor = 0.15;
-          else if (nodeVsGlobal > 1.0) latencyFactor = 0.35;
-          else if (nodeVsGlobal > 0.5) latencyFactor = 0.7;
-          else if (nodeVsGlobal < -0.5) latencyFactor = 1.2;
-        }
+        // Latency — stdDev tiers against the pool mean
+        const { factor: latencyFactor } = this.latencyFactor(node);
 
         // Thermal throttling
         const thermalFactor = node.gpuTempC > 85 ? 0.3
@@ -417,39 +488,10 @@ export class PoolRouterService {
 
       return { node, weight };
     });
-
-    // Cap max weight
-    const totalWeight = weighted.reduce((sum, w) => sum + w.weight, 0);
-    if (totalWeight > 0) {
-      for (const w of weighted) {
-        if (w.weight / totalWeight > MAX_WEIGHT_CAP) {
-          w.weight = totalWeight * MAX_WEIGHT_CAP;
-        }
-      }
-    }
-
-    // Weighted random selection — probabilistic, not deterministic
-    // This ensures all healthy nodes get SOME traffic proportional to their weight
-    const totalW = weighted.reduce((sum, w) => sum + w.weight, 0);
-    if (totalW > 0) {
-      const rand = Math.random() * totalW;
-      let cumulative = 0;
-      let selectedIdx = 0;
-      for (let i = 0; i < weighted.length; i++) {
-        cumulative += weighted[i].weight;
-        if (rand <= cumulative) { selectedIdx = i; break; }
-      }
-      // Put selected first, rest as fallbacks sorted by weight
-      const selected = weighted.splice(selectedIdx, 1)[0];
-      weighted.sort((a, b) => b.weight - a.weight);
-      weighted.unshift(selected);
-    }
-
-    return weighted.map(w => w.node);
   }
 
   /** Route an inference request with failover. */
-  async routeInference(body: { prompt?: string; messages?: { role: string; content: string }[]; max_tokens?: number; temperature?: number; mode?: string; encrypted?: any; targetNodeId?: string }): Promise<RoutingResult> {
+  async routeInference(body: InferenceBody): Promise<RoutingResult> {
     const candidates = this.selectNodes();
     if (candidates.length === 0) {
       throw new Error("No healthy pool nodes available");
@@ -476,59 +518,7 @@ export class PoolRouterService {
       }
 
       try {
-        const controller = new AbortController();
-        const timeout = setTimeout(() => controller.abort(), INFERENCE_TIMEOUT_MS);
-
-        // Try Hive-AI format first, then OpenAI-compatible (llama-server/vLLM)
-        let res: Response | null = null;
-        let data: any = null;
-
-        // Attempt 1: Hive-AI /api/compute/inference
-        try {
-          res = await fetch(`${node.inferenceEndpoint}/api/compute/inference`, {
-            method: "POST",
-            headers: { "Content-Type": "application/json" },
-            body: JSON.stringify(body),
-            signal: controller.signal,
-          });
-          if (res.ok) {
-            data = await res.json();
-          }
-        } catch { /* try next format */ }
-
-        // Attempt 2: OpenAI-compatible /v1/chat/completions (llama-server, vLLM)
-        // Skip for encrypted envelopes — those are Hive-AI format only
-        if (!data && !body.encrypted) {
-          try {
-            const controller2 = new AbortController();
-            const timeout2 = setTimeout(() => controller2.abort(), INFERENCE_TIMEOUT_MS);
-            // Use messages array if provided, otherwise wrap prompt as single user message
-            const messages = body.messages || [{ role: "user", content: body.prompt || "" }];
-            res = await fetch(`${node.inferenceEndpoint}/v1/chat/completions`, {
-              method: "POST",
-              headers: { "Content-Type": "application/json" },
-              body: JSON.stringify({
-                messages,
-                max_tokens: body.max_tokens || 2048,
-                temperature: body.temperature ?? 0.7,
-                stream: false,
-              }),
-              signal: controller2.signal,
-            });
-            clearTimeout(timeout2);
-            if (res.ok) {
-              const oaiData = await res.json();
-              // Normalize OpenAI format to our format
-              data = {
-                text: oaiData.choices?.[0]?.message?.content || "",
-                tokens: oaiData.usage?.completion_tokens || 0,
-                model: oaiData.model || "llama-server",
-              };
-            }
-          } catch { /* both formats failed */ }
-        }
-
-        clearTimeout(timeout);
+        const { data, res } = await this.callNode(node, body);
         node.inFlightRequests = Math.max(0, node.inFlightRequests - 1);
         // Phase 2: Clear inFlight tracking entry
         const trackingMap = this.inFlightTracking.get(node.nodeId);
@@ -544,7 +534,7 @@ export class PoolRouterService {
           // Skip EMA update for cached responses (< 50ms = Hive-AI cache hit, not real GPU work)
           if (latencyMs >= 50) {
             const sampleScore = Math.max(0.1, Math.min(1.0, 1.0 - (latencyMs / MAX_EXPECTED_LATENCY_MS)));
-            this.updateEma(node, sampleScore);
+            this.updateEma(node, sampleScore, "gpu", latencyMs);
             // Phase 3: Record latency for percentile tracking
             latencyStats.addMeasurement(node.nodeId, latencyMs);
           }
@@ -585,17 +575,174 @@ export class PoolRouterService {
     throw new Error(`All pool nodes failed: ${errors.join("; ")}`);
   }
 
-  /** Update EMA score for a node, per workload class. */
-  private updateEma(node: PoolNodeState, sampleScore: number, workloadClass: ResourceType = "gpu"): void {
-    // Update per-class EMA
-    const oldScore = node.emaScores[workloadClass] ?? node.emaScore;
-    node.emaScores[workloadClass] = EMA_ALPHA * sampleScore + (1 - EMA_ALPHA) * oldScore;
-    // Keep legacy aggregate in sync (weighted average across classes)
-    const classes = Object.keys(node.emaScores);
-    node.emaScore = classes.reduce((sum, k) => sum + node.emaScores[k], 0) / classes.length;
-
-    // Persist to DB periodically (fire-and-forget)
-    this.storage.updateNodeEmaScore(node.nodeId, node.emaScore).catch(() => {});
+  /**
+   * Run a batch of inference requests across the pool with a sliding window
+   * per node (see pool-dispatcher.ts). Each node's window is DISPATCH_MAX_WINDOW
+   * scaled by its routing weight relative to the best node, capped by its free
+   * inference slots; its timeout is a multiple of its p95 latency.
+   */
+  async dispatchBatch(items: InferenceBody[], maxWindow: number = DISPATCH_MAX_WINDOW): Promise<DispatchReport<{ response: any; latencyMs: number; nodeInstanceId: string }>> {
+    const weighted = this.weighNodes("gpu");
+    if (weighted.length === 0) {
+      throw new Error("No healthy pool nodes available");
+    }
+    const topWeight = Math.max(...weighted.map(w => w.weight));
+    const slots = weighted.map(({ node, weight }) => ({
+      nodeId: node.nodeId,
+      window: Math.max(1, Math.min(
+        node.maxConcurrentInference - node.inFlightRequests,
+        Math.round(maxWindow * (topWeight > 0 ? weight / topWeight : 1)),
+      )),
+      timeoutMs: this.dispatchTimeout(node),
+    }));
+
+    const report = await dispatchSlidingWindow(items, slots, async (body, nodeId, signal) => {
+      const node = this.nodes.get(nodeId);
+      if (!node) throw new Error(`Node ${nodeId} left the pool`);
+      const requestId = Math.random().toString(36).slice(2);
+      this.inFlightTracking.get(nodeId)?.set(requestId, Date.now());
+      node.inFlightRequests++;
+      const startTime = Date.now();
+      try {
+        const { data, res } = await this.callNode(node, body, signal);
+        if (!data) throw new Error(res ? `HTTP ${res.status}` : "No response");
+        const latencyMs = Date.now() - startTime;
+        // Skip EMA update for cached responses (< 50ms = Hive-AI cache hit, not real GPU work)
+        if (latencyMs >= 50) {
+          this.updateEma(node, Math.max(0.1, Math.min(1.0, 1.0 - (latencyMs / MAX_EXPECTED_LATENCY_MS))), "gpu", latencyMs);
+          latencyStats.addMeasurement(node.nodeId, latencyMs);
+        }
+        return { response: data, latencyMs, nodeInstanceId: node.nodeInstanceId };
+      } catch (err) {
+        this.updateEma(node, 0); // failure or timed out
+        throw err;
+      } finally {
+        node.inFlightRequests = Math.max(0, node.inFlightRequests - 1);
+        this.inFlightTracking.get(nodeId)?.delete(requestId);
+      }
+    }, DISPATCH_MAX_ATTEMPTS);
+
+    const failed = report.outcomes.filter(o => !o.ok).length;
+    log.info(`Batch of ${items.length} dispatched over ${slots.length} nodes: ${items.length - failed} ok, ${failed} failed, ${report.redispatched} re-dispatched`);
+    return report;
+  }
+
+  /** Per-item batch timeout: a multiple of the node's p95 latency once known. */
+  private dispatchTimeout(node: PoolNodeState): number {
+    const p95 = this.getPercentile(node.nodeId, 95);
+    if (p95 === null) return INFERENCE_TIMEOUT_MS;
+    return Math.round(Math.min(INFERENCE_TIMEOUT_MS, Math.max(DISPATCH_MIN_TIMEOUT_MS, p95 * DISPATCH_TIMEOUT_P95_MULTIPLIER)));
+  }
+
+  /**
+   * One inference call to one node — Hive-AI format first, then OpenAI-compatible
+   * (llama-server/vLLM). `data` is null if neither format answered; `res` is the
+   * last response seen. Aborting `signal` cancels whichever attempt is running.
+   */
+  private async callNode(node: PoolNodeState, body: InferenceBody, signal?: AbortSignal): Promise<{ data: any; res: Response | null }> {
+    const controller = new AbortController();
+    const timeout = setTimeout(() => controller.abort(), INFERENCE_TIMEOUT_MS);
+    const onAbort = () => controller.abort();
+    signal?.addEventListener("abort", onAbort);
+
+    // Try Hive-AI format first, then OpenAI-compatible (llama-server/vLLM)
+    let res: Response | null = null;
+    let data: any = null;
+
+    try {
+      // Attempt 1: Hive-AI /api/compute/inference
+      try {
+        res = await fetch(`${node.inferenceEndpoint}/api/compute/inference`, {
+          method: "POST",
+          headers: { "Content-Type": "application/json" },
+          body: JSON.stringify(body),
+          signal: controller.signal,
+        });
+        if (res.ok) {
+          data = await res.json();
+        }
+      } catch { /* try next format */ }
+
+      // Attempt 2: OpenAI-compatible /v1/chat/completions (llama-server, vLLM)
+      // Skip for encrypted envelopes — those are Hive-AI format only
+      if (!data && !body.encrypted && !signal?.aborted) {
+        const controller2 = new AbortController();
+        const timeout2 = setTimeout(() => controller2.abort(), INFERENCE_TIMEOUT_MS);
+        const onAbort2 = () => controller2.abort();
+        signal?.addEventListener("abort", onAbort2);
+        try {
+          // Use messages array if provided, otherwise wrap prompt as single user message
+          const messages = body.messages || [{ role: "user", content: body.prompt || "" }];
+          res = await fetch(`${node.inferenceEndpoint}/v1/chat/completions`, {
+            method: "POST",
+            headers: { "Content-Type": "application/json" },
+            body: JSON.stringify({
+              messages,
+              max_tokens: body.max_tokens || 2048,
+              temperature: body.temperature ?? 0.7,
+              stream: false,
+            }),
+            signal: controller2.signal,
+          });
+          if (res.ok) {
+            const oaiData = await res.json();
+            // Normalize OpenAI format to our format
+            data = {
+              text: oaiData.choices?.[0]?.message?.content || "",
+              tokens: oaiData.usage?.completion_tokens || 0,
+              model: oaiData.model || "llama-server",
+            };
+          }
+        } catch { /* both formats failed */ } finally {
+          clearTimeout(timeout2);
+          signal?.removeEventListener("abort", onAbort2);
+        }
+      }
+    } finally {
+      clearTimeout(timeout);
+      signal?.removeEventListener("abort", onAbort);
+    }
+    return { data, res };
+  }
+
+  /**
+   * Latency weight from how many pool stdDevs the node's mean latency sits
+   * above the pool mean: 2+ → 0.25, 1+ → 0.5, 0.5+ → 0.7, 0.5 below → 1.2.
+   * 1.0 (and a null z-score) until both have enough samples.
+   */
+  private latencyFactor(node: PoolNodeState): { factor: number; zScore: number | null } {
+    const nodeStats = latencyStats.getStatistics(node.nodeId);
+    const globalStats = latencyStats.getGlobalStatistics();
+    if (!nodeStats || nodeStats.count < LATENCY_MIN_SAMPLES || !globalStats || globalStats.stdDev <= 0) {
+      return { factor: 1.0, zScore: null };
+    }
+    const zScore = (nodeStats.mean - globalStats.mean) / globalStats.stdDev;
+    const factor = zScore >= 2.0 ? 0.25
+                 : zScore >= 1.0 ? 0.5
+                 : zScore > 0.5 ? 0.7
+                 : zScore < -0.5 ? 1.2
+                 : 1.0;
+    return { factor, zScore };
+  }
+
+  /** Record a request outcome in the node's per-class EMA. */
+  private updateEma(node: PoolNodeState, sampleScore: number, workloadClass: ResourceType = "gpu", latencyMs?: number): void {
+    this.reputation.recordSample(node.nodeId, workloadClass, sampleScore, latencyMs);
+    this.applyReputation(node, true);
+  }
+
+  /**
+   * Copy the node's reputation into its routing state; with `persist`, also
+   * write the legacy aggregate emaScore to the DB (fire-and-forget).
+   */
+  private applyReputation(node: PoolNodeState, persist = false): void {
+    const rep = this.reputation.get(node.nodeId);
+    if (!rep) return;
+    node.emaScores = { ...rep.scores };
+    node.emaScore = this.reputation.aggregate(rep);
+    node.qualityScore = rep.qualityScore;
+    node.qualityReports = rep.qualityReports;
+    if (persist) this.storage.updateNodeEmaScore(node.nodeId, node.emaScore).catch(() => {});
   }
 
   /** Log a routing decision (fire-and-forget). */
@@ -630,12 +777,15 @@ export class PoolRouterService {
           };
         }
 
+        const { factor: latencyFactor, zScore: latencyZScore } = this.latencyFactor(n);
+
         return {
           id: n.nodeId,
           instanceId: n.nodeInstanceId,
           gpu: n.gpuModel,
           vramGb: n.gpuVramGb,
           healthy: n.healthy,
+          stale: this.registry.isStale(n.nodeInstanceId, now),
           emaScore: Math.round(n.emaScore * 100) / 100,
           utilization: n.gpuUtilizationPct,
           inFlight: n.inFlightRequests,
@@ -651,6 +801,8 @@ export class PoolRouterService {
           })(),
           // Phase 3: latency percentiles
           latency,
+          latencyFactor,
+          latencyZScore: latencyZScore === null ? null : Math.round(latencyZScore * 100) / 100,
           // Phase 4: thermal + pressure level
           gpuTempC: n.gpuTempC,
           pressure: this.computePressure(n),
@@ -664,6 +816,8 @@ export class PoolRouterService {
           maxConcurrentCpuJobs: n.maxConcurrentCpuJobs,
           encryptionPublicKey: n.encryptionPublicKey,
           encryptionKeyVersion: n.encryptionKeyVersion,
+          qualityScore: n.qualityScore === null ? null : Math.round(n.qualityScore * 1000) / 1000,
+          qualityReports: n.qualityReports,
         };
       }),
       healthyCount: nodeList.filter(n => n.healthy).length,
@@ -680,9 +834,9 @@ export class PoolRouterService {
     };
   }
 
-  /** Check if pool routing is available (at least 1 healthy node). */
+  /** Check if pool routing is available (at least 1 healthy, non-stale node). */
   isAvailable(): boolean {
-    return Array.from(this.nodes.values()).some(n => n.healthy);
+    return Array.from(this.nodes.values()).some(n => n.healthy && !this.registry.isStale(n.nodeInstanceId));
   }
 
   /** Phase 4: Update node state from rich heartbeat data (sub-second visibility). */
@@ -718,8 +872,9 @@ export class PoolRouterService {
 
   /** Phase 5: Get pressure summary for Hive-AI decision-making. */
   getPressure(): {
-    nodes: { instanceId: string; healthy: boolean; pressure: string; vramFreeMb: number;
-             inFlight: number; maxInference: number; canAccept: boolean; latencyP50Ms: number | null }[];
+    nodes: { instanceId: string; healthy: boolean; stale: boolean; pressure: string; vramFreeMb: number;
+             inFlight: number; maxInference: number; canAccept: boolean; latencyP50Ms: number | null;
+             latencyFactor: number }[];
     recommendation: { bestNode: string | null; poolCapacity: string; estimatedWaitMs: number };
   } {
     const candidates = this.selectNodes();
@@ -727,15 +882,18 @@ export class PoolRouterService {
 
     const nodes = nodeList.map(n => {
       const pressure = this.computePressure(n);
+      const stale = this.registry.isStale(n.nodeInstanceId);
       return {
         instanceId: n.nodeInstanceId,
         healthy: n.healthy,
+        stale,
         pressure,
         vramFreeMb: Math.max(0, n.vramTotalMb - n.vramUsedMb),
         inFlight: n.inFlightRequests,
         maxInference: n.maxConcurrentInference,
-        canAccept: n.healthy && pressure !== "critical" && n.inFlightRequests < n.maxConcurrentInference,
+        canAccept: n.healthy && !stale && pressure !== "critical" && n.inFlightRequests < n.maxConcurrentInference,
         latencyP50Ms: this.getPercentile(n.nodeId, 50),
+        latencyFactor: this.latencyFactor(n).factor,
       };
     });
 
@@ -756,25 +914,29 @@ export class PoolRouterService {
 
   // --- Sprint 2: Quality feedback receivers ---
 
-  /** Receive quality scores from Best-of-N selection. Nudge EMA based on verified quality. */
-  handleQualityReport(candidates: { node_id: string; score: number; verified: boolean; latency_ms: number }[]): void {
+  /**
+   * Receive quality scores from Best-of-N selection. Each matched node's quality
+   * average is updated and its GPU EMA (the one routing reads) is nudged per
+   * the reputation rules: verified + high score = small bonus, unverified +
+   * low score = penalty.
+   * Unknown node ids (deregistered or not yet loaded) are counted and skipped.
+   */
+  handleQualityReport(candidates: { node_id: string; score: number; verified: boolean; latency_ms: number }[]): { matched: number; unknown: number } {
+    let matched = 0;
     for (const c of candidates) {
       // node_id format from Hive-AI: "pool:gpu-computer-b" — strip prefix
       const instanceId = c.node_id.replace(/^pool:/, "");
-      const node = Array.from(this.nodes.values()).find(n => n.nodeInstanceId === instanceId);
+      const node = this.nodes.get(instanceId)
+        ?? Array.from(this.nodes.values()).find(n => n.nodeInstanceId === instanceId);
       if (!node) continue;
-
-      // Nudge EMA: verified + high score = small bonus, unverified + low score = penalty
-      // Use a gentle alpha (0.05) so quality signals don't override latency-based EMA too aggressively
-      const qualityAlpha = 0.05;
-      if (c.verified && c.score >= 0.8) {
-        node.emaScore = Math.min(1.0, node.emaScore + qualityAlpha * (1.0 - node.emaScore));
-      } else if (!c.verified && c.score < 0.5) {
-        node.emaScore = Math.max(0.1, node.emaScore - qualityAlpha * 0.3);
-      }
-      // Persist fire-and-forget
-      this.storage.updateNodeEmaScore(node.nodeId, node.emaScore).catch(() => {});
+      matched++;
+      this.reputation.recordQuality(node.nodeId, c.score, c.verified);
+      this.applyReputation(node, true);
     }
+    if (matched < candidates.length) {
+      log.warn(`Quality report: ${candidates.length - matched} of ${candidates.length} candidates matched no pool node`);
+    }
+    return { matched, unknown: candidates.length - matched };
   }
 
   /** Receive sandbox verification results per node. Track pass rates. */
@@ -782,14 +944,11 @@ export class PoolRouterService {
     const node = Array.from(this.nodes.values()).find(n => n.nodeInstanceId === nodeInstanceId);
     if (!node) return;
 
-    const total = report.passed + report.failed + report.timed_out;
-    if (total === 0) return;
-
-    const passRate = report.passed / total;
     // Penalize nodes with consistently failing code — gentle EMA nudge
-    if (passRate < 0.5 && total >= 3) {
-      node.emaScore = Math.max(0.1, node.emaScore * 0.95); // 5% penalty
-      this.storage.updateNodeEmaScore(node.nodeId, node.emaScore).catch(() => {});
+    const penalized = this.reputation.recordVerification(node.nodeId, report.passed, report.failed, report.timed_out);
+    this.applyReputation(node, penalized);
+    if (penalized) {
+      const total = report.passed + report.failed + report.timed_out;
       log.warn(`Verification penalty for ${nodeInstanceId}: ${report.passed}/${total} passed`);
     }
   }
@@ -800,11 +959,9 @@ export class PoolRouterService {
       const node = Array.from(this.nodes.values()).find(n => n.nodeInstanceId === instanceId);
       if (!node) continue;
 
-      const overall = scores.overall ?? 0;
       // Nodes scoring significantly below average get a small penalty
-      if (overall < 0.6) {
-        node.emaScore = Math.max(0.1, node.emaScore * 0.97);
-        this.storage.updateNodeEmaScore(node.nodeId, node.emaScore).catch(() => {});
+      if (this.reputation.recordEval(node.nodeId, scores.overall ?? 0)) {
+        this.applyReputation(node, true);
       }
     }
     log.info(`Eval breakdown received for ${modelVersion}: ${Object.keys(nodeScores).length} nodes`);
@@ -832,12 +989,8 @@ export class PoolRouterService {
     // Phase 5: Self-healing — reset EMA floor for recovered nodes
     for (const node of Array.from(this.nodes.values())) {
       if (node.healthy && node.consecutiveFailures === 0 && node.emaScore < 0.1) {
-        node.emaScore = 0.3;
-        // Reset all per-class EMAs too
-        for (const cls of Object.keys(node.emaScores)) {
-          if (node.emaScores[cls] < 0.1) node.emaScores[cls] = 0.3;
-        }
-        this.storage.updateNodeEmaScore(node.nodeId, 0.3).catch(() => {});
+        this.reputation.liftFloor(node.nodeId, 0.1, 0.3);
+        this.applyReputation(node, true);
         log.info(`Self-heal: EMA floor reset for ${node.nodeInstanceId}`);
       }
       // Reset unhealthy nodes after 5 minutes to give them another chance
@@ -920,7 +1073,7 @@ export class PoolRouterService {
         const data = await res.json();
         const latencyMs = Date.now() - startTime;
         if (latencyMs >= 50) {
-          this.updateEma(node, Math.max(0.1, Math.min(1.0, 1.0 - (latencyMs / MAX_EXPECTED_LATENCY_MS))), "cpu");
+          this.updateEma(node, Math.max(0.1, Math.min(1.0, 1.0 - (latencyMs / MAX_EXPECTED_LATENCY_MS))), "cpu", latencyMs);
           latencyStats.addMeasurement(node.nodeId, latencyMs);
         }
         return {
@@ -0,0 +1,282 @@
+/**
+ * Reputation Engine — Per-node standing for pool routing, persisted to disk.
+ *
+ * Every signal the pool router receives about a node lands here:
+ *   - request outcomes (latency-derived sample score, 0 on failure) → per-class EMA
+ *   - Best-of-N quality reports → quality average + small GPU bonus/penalty
+ *   - sandbox verification reports → penalty when most submissions fail
+ *   - eval breakdowns → penalty for low overall scores
+ *
+ * Scores decay toward NEUTRAL with a configurable half-life while a node is
+ * idle, so old standing (good or bad) fades rather than sticking forever.
+ *
+ * State is written to REPUTATION_FILE (default ./data/reputation.json) with a
+ * debounced tmp-file + rename, and reloaded on start — a coordinator restart
+ * keeps every node's standing. Decay covers the downtime on load.
+ */
+import * as fs from "fs";
+import * as path from "path";
+
+export type ReputationClass = "gpu" | "cpu" | "ram";
+
+export interface ReputationRules {
+  /** EMA weight of each request sample. */
+  alpha: number;
+  /** EMA weight of each quality report in the quality average. */
+  qualityAlpha: number;
+  /** Fraction of the gap to 1.0 (bonus) / fixed step × 0.3 (penalty) applied per quality report. */
+  qualityNudge: number;
+  qualityBonusMin: number;   // verified and score >= this → bonus
+  qualityPenaltyMax: number; // unverified and score < this → penalty
+  /** Multiplier applied when a verification report passes less than verificationMinPassRate. */
+  verificationPenalty: number;
+  verificationMinPassRate: number;
+  verificationMinSamples: number;
+  /** Multiplier applied when an eval breakdown's overall score is below evalMinScore. */
+  evalPenalty: number;
+  evalMinScore: number;
+  /** Penalties never push a score below this. */
+  floor: number;
+  /** Idle half-life of the distance from NEUTRAL, in ms. 0 disables decay. */
+  decayHalfLifeMs: number;
+}
+
+export const DEFAULT_REPUTATION_RULES: ReputationRules = {
+  alpha: 0.1, // Slow-moving average (~10 request half-life)
+  qualityAlpha: 0.2, // Quality reports arrive far less often than requests
+  qualityNudge: 0.05, // Gentle, so quality signals don't override latency-based EMA
+  qualityBonusMin: 0.8,
+  qualityPenaltyMax: 0.5,
+  verificationPenalty: 0.95,
+  verificationMinPassRate: 0.5,
+  verificationMinSamples: 3,
+  evalPenalty: 0.97,
+  evalMinScore: 0.6,
+  floor: 0.1,
+  decayHalfLifeMs: 72 * 3600_000, // 3 days
+};
+
+export const NEUTRAL_SCORE = 0.5;
+const SAVE_DEBOUNCE_MS = 1_000;
+const FILE_VERSION = 1;
+
+export interface NodeReputation {
+  scores: Record<ReputationClass, number>;
+  qualityScore: number | null;
+  qualityReports: number;
+  verification: { passed: number; failed: number; timedOut: number };
+  latencyEmaMs: number | null;
+  samples: number;
+  updatedAt: number;
+}
+
+/** Rules overridden from REPUTATION_ALPHA / REPUTATION_DECAY_HALF_LIFE_HOURS when set. */
+export function reputationRulesFromEnv(env: NodeJS.ProcessEnv = process.env): Partial<ReputationRules> {
+  const rules: Partial<ReputationRules> = {};
+  const alpha = Number(env.REPUTATION_ALPHA);
+  if (env.REPUTATION_ALPHA && alpha > 0 && alpha <= 1) rules.alpha = alpha;
+  const halfLifeHours = Number(env.REPUTATION_DECAY_HALF_LIFE_HOURS);
+  if (env.REPUTATION_DECAY_HALF_LIFE_HOURS && halfLifeHours >= 0) rules.decayHalfLifeMs = halfLifeHours * 3600_000;
+  return rules;
+}
+
+const log = {
+  info: (msg: string) => console.log(`[reputation] ${msg}`),
+  warn: (msg: string) => console.warn(`[reputation] ${msg}`),
+};
+
+export class ReputationEngine {
+  readonly rules: ReputationRules;
+  private nodes = new Map<string, NodeReputation>();
+  private filePath: string | null;
+  private saveTimer: ReturnType<typeof setTimeout> | null = null;
+
+  /** `filePath` null keeps reputation in memory only (tests, ephemeral coordinators). */
+  constructor(rules: Partial<ReputationRules> = {}, filePath: string | null = null) {
+    this.rules = { ...DEFAULT_REPUTATION_RULES, ...rules };
+    this.filePath = filePath;
+  }
+
+  /** Load persisted state. A missing file is a fresh start; a corrupt one is logged and ignored. */
+  load(): void {
+    if (!this.filePath) return;
+    let raw: string;
+    try {
+      raw = fs.readFileSync(this.filePath, "utf-8");
+    } catch {
+      return;
+    }
+    try {
+      const parsed = JSON.parse(raw);
+      if (parsed?.version !== FILE_VERSION || typeof parsed.nodes !== "object") {
+        log.warn(`Ignoring ${this.filePath}: unsupported format`);
+        return;
+      }
+      for (const [nodeId, rep] of Object.entries<NodeReputation>(parsed.nodes)) {
+        this.nodes.set(nodeId, rep);
+      }
+      log.info(`Loaded reputation for ${this.nodes.size} nodes from ${this.filePath}`);
+    } catch (err: any) {
+      log.warn(`Ignoring ${this.filePath}: ${err.message}`);
+    }
+  }
+
+  has(nodeId: string): boolean {
+    return this.nodes.has(nodeId);
+  }
+
+  /** Current reputation (decayed to `now`), or undefined for an unknown node. */
+  get(nodeId: string, now = Date.now()): NodeReputation | undefined {
+    const rep = this.nodes.get(nodeId);
+    if (!rep) return undefined;
+    this.decay(rep, now);
+    return rep;
+  }
+
+  /** Start tracking a node not yet on record, e.g. from its DB emaScore. No-op if known. */
+  seed(nodeId: string, gpuScore: number = NEUTRAL_SCORE, now = Date.now()): NodeReputation {
+    const existing = this.get(nodeId, now);
+    if (existing) return existing;
+    const rep: NodeReputation = {
+      scores: { gpu: gpuScore, cpu: NEUTRAL_SCORE, ram: NEUTRAL_SCORE },
+      qualityScore: null,
+      qualityReports: 0,
+      verification: { passed: 0, failed: 0, timedOut: 0 },
+      latencyEmaMs: null,
+      samples: 0,
+      updatedAt: now,
+    };
+    this.nodes.set(nodeId, rep);
+    this.scheduleSave();
+    return rep;
+  }
+
+  /** Mean of the per-class scores — the legacy emaScore persisted to the DB. */
+  aggregate(rep: NodeReputation): number {
+    const scores = Object.values(rep.scores);
+    return scores.reduce((sum, s) => sum + s, 0) / scores.length;
+  }
+
+  /** One routed request: `sampleScore` in [0, 1] (0 for a failure), latency if it completed. */
+  recordSample(nodeId: string, workloadClass: ReputationClass, sampleScore: number, latencyMs?: number, now = Date.now()): NodeReputation {
+    const rep = this.seed(nodeId, NEUTRAL_SCORE, now);
+    const { alpha } = this.rules;
+    rep.scores[workloadClass] = alpha * sampleScore + (1 - alpha) * rep.scores[workloadClass];
+    if (latencyMs !== undefined) {
+      rep.latencyEmaMs = rep.latencyEmaMs === null ? latencyMs : alpha * latencyMs + (1 - alpha) * rep.latencyEmaMs;
+    }
+    rep.samples++;
+    this.scheduleSave();
+    return rep;
+  }
+
+  /** Best-of-N quality report: updates the quality average and bonuses/penalizes the GPU score. */
+  recordQuality(nodeId: string, score: number, verified: boolean, now = Date.now()): NodeReputation {
+    const rep = this.seed(nodeId, NEUTRAL_SCORE, now);
+    const r = this.rules;
+    rep.qualityScore = rep.qualityScore === null ? score : r.qualityAlpha * score + (1 - r.qualityAlpha) * rep.qualityScore;
+    rep.qualityReports++;
+    const gpu = rep.scores.gpu;
+    if (verified && score >= r.qualityBonusMin) {
+      rep.scores.gpu = Math.min(1.0, gpu + r.qualityNudge * (1.0 - gpu));
+    } else if (!verified && score < r.qualityPenaltyMax) {
+      rep.scores.gpu = Math.max(r.floor, gpu - r.qualityNudge * 0.3);
+    }
+    this.scheduleSave();
+    return rep;
+  }
+
+  /** Sandbox verification counts. Returns true if the penalty applied. */
+  recordVerification(nodeId: string, passed: number, failed: number, timedOut: number, now = Date.now()): boolean {
+    const total = passed + failed + timedOut;
+    if (total === 0) return false;
+    const rep = this.seed(nodeId, NEUTRAL_SCORE, now);
+    rep.verification.passed += passed;
+    rep.verification.failed += failed;
+    rep.verification.timedOut += timedOut;
+    const r = this.rules;
+    const penalized = passed / total < r.verificationMinPassRate && total >= r.verificationMinSamples;
+    if (penalized) this.penalizeAll(rep, r.verificationPenalty);
+    this.scheduleSave();
+    return penalized;
+  }
+
+  /** Eval breakdown overall score. Returns true if the penalty applied. */
+  recordEval(nodeId: string, overall: number, now = Date.now()): boolean {
+    if (overall >= this.rules.evalMinScore) return false;
+    const rep = this.seed(nodeId, NEUTRAL_SCORE, now);
+    this.penalizeAll(rep, this.rules.evalPenalty);
+    this.scheduleSave();
+    return true;
+  }
+
+  /** Lift any class score under `below` to `to` (router self-heal for recovered nodes). */
+  liftFloor(nodeId: string, below: number, to: number): boolean {
+    const rep = this.nodes.get(nodeId);
+    if (!rep) return false;
+    let lifted = false;
+    for (const cls of Object.keys(rep.scores) as ReputationClass[]) {
+      if (rep.scores[cls] < below) {
+        rep.scores[cls] = to;
+        lifted = true;
+      }
+    }
+    if (lifted) this.scheduleSave();
+    return lifted;
+  }
+
+  /** Write any pending state now (call on shutdown). */
+  flush(): void {
+    if (!this.saveTimer) return;
+    clearTimeout(this.saveTimer);
+    this.saveTimer = null;
+    this.save();
+  }
+
+  private penalizeAll(rep: NodeReputation, multiplier: number): void {
+    for (const cls of Object.keys(rep.scores) as ReputationClass[]) {
+      rep.scores[cls] = Math.max(this.rules.floor, rep.scores[cls] * multiplier);
+    }
+  }
+
+  /** Pull scores toward NEUTRAL for the time since the last update. Compounds correctly when applied piecewise. */
+  private decay(rep: NodeReputation, now: number): void {
+    const elapsed = now - rep.updatedAt;
+    if (elapsed <= 0) return;
+    rep.updatedAt = now;
+    if (this.rules.decayHalfLifeMs <= 0) return;
+    const keep = Math.pow(0.5, elapsed / this.rules.decayHalfLifeMs);
+    for (const cls of Object.keys(rep.scores) as ReputationClass[]) {
+      rep.scores[cls] = NEUTRAL_SCORE + (rep.scores[cls] - NEUTRAL_SCORE) * keep;
+    }
+  }
+
+  private scheduleSave(): void {
+    if (!this.filePath) return;
+    if (this.saveTimer) clearTimeout(this.saveTimer);
+    this.saveTimer = setTimeout(() => {
+      this.saveTimer = null;
+      this.save();
+    }, SAVE_DEBOUNCE_MS);
+    this.saveTimer.unref?.();
+  }
+
+  private save(): void {
+    if (!this.filePath) return;
+    try {
+      fs.mkdirSync(path.dirname(this.filePath), { recursive: true });
+      const tmpPath = `${this.filePath}.tmp`;
+      const body = { version: FILE_VERSION, savedAt: Date.now(), nodes: Object.fromEntries(this.nodes) };
+      fs.writeFileSync(tmpPath, JSON.stringify(body));
+      fs.renameSync(tmpPath, this.filePath);
+    } catch (err: any) {
+      log.warn(`Failed to write ${this.filePath}: ${err.message}`);
+    }
+  }
+}
+
+/** The coordinator's engine — persisted to REPUTATION_FILE, default ./data/reputation.json. */
+export const reputationEngine = new ReputationEngine(
+  reputationRulesFromEnv(),
+  process.env.REPUTATION_FILE || path.join(process.cwd(), "data", "reputation.json"),
+);
@@ -202,6 +202,12 @@ export class SQLiteStorage implements IStorage {
       .where(eq(S.storageNodes.id, id));
   }
 
+  async setStorageNodeSealCommitment(id: string, commitment: string): Promise<void> {
+    await db().update(S.storageNodes)
+      .set({ sealCommitment: commitment })
+      .where(eq(S.storageNodes.id, id));
+  }
+
   async decayInactiveNodeReputation(inactiveDays: number, decayPerDay: number): Promise<number> {
     const cutoff = new Date(Date.now() - inactiveDays * 24 * 60 * 60 * 1000);
     const result = await db().update(S.storageNodes)
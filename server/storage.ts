@@ -214,6 +214,7 @@ export interface IStorage {
   updateStorageNodeReputation(id: string, reputation: number, status: string, consecutiveFails?: number): Promise<void>;
   updateNodeEarnings(id: string, hbdAmount: number): Promise<void>;
   updateStorageNodeLastSeen(id: string): Promise<void>;
+  setStorageNodeSealCommitment(id: string, commitment: string): Promise<void>;
   decayInactiveNodeReputation(inactiveDays: number, decayPerDay: number): Promise<number>;
 
   // Files
@@ -773,6 +774,12 @@ export class DatabaseStorage implements IStorage {
       .where(eq(storageNodes.id, id));
   }
 
+  async setStorageNodeSealCommitment(id: string, commitment: string): Promise<void> {
+    await db.update(storageNodes)
+      .set({ sealCommitment: commitment })
+      .where(eq(storageNodes.id, id));
+  }
+
   /**
    * Decay reputation for nodes inactive longer than `inactiveDays`.
    * Each day of inactivity costs `decayPerDay` rep points.
@@ -5,7 +5,7 @@
  */
 declare module 'better-sqlite3' {
   interface Database {
-    pragma(pragma: string): any;
+    pragma(pragma: string, options?: { simple?: boolean }): any;
     exec(sql: string): void;
     prepare(sql: string): Statement;
     close(): void;
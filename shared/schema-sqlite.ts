@@ -30,6 +30,7 @@ export const storageNodes = sqliteTable("storage_nodes", {
   failedProofs: integer("failed_proofs").notNull().default(0),
   consecutiveFails: integer("consecutive_fails").notNull().default(0),
   totalEarnedHbd: real("total_earned_hbd").notNull().default(0),
+  sealCommitment: text("seal_commitment"),
   lastSeen: text("last_seen").notNull().default(sql`(datetime('now'))`),
   createdAt: text("created_at").notNull().default(sql`(datetime('now'))`),
 });
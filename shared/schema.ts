@@ -19,6 +19,7 @@ export const storageNodes = pgTable("storage_nodes", {
   failedProofs: integer("failed_proofs").notNull().default(0),
   consecutiveFails: integer("consecutive_fails").notNull().default(0), // 3 consecutive = instant ban
   totalEarnedHbd: real("total_earned_hbd").notNull().default(0), // Track earnings
+  sealCommitment: text("seal_commitment"), // Proof-of-replication key commitment, pinned at first sealing registration
   lastSeen: timestamp("last_seen").notNull().defaultNow(),
   createdAt: timestamp("created_at").notNull().defaultNow(),
 });